@@ -5,9 +5,50 @@
 
 use serde_json::{Map, Value as JsonValue};
 use std::collections::HashMap;
-use stratadb::{Output, Value, VersionedValue};
+use stratadb::{Output, TimeRangeInput, Value, VersionedValue};
 
 use crate::error::{McpError, Result};
+use crate::session::{is_reserved_space, RESERVED_SPACE_PREFIX};
+
+/// How non-finite floats (NaN, Infinity) are rendered in JSON output.
+///
+/// JSON has no representation for these values, so they must be mapped
+/// to something serde_json can serialize.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum NonFiniteFloat {
+    /// Render as `null` (default).
+    #[default]
+    Null,
+    /// Render as the string `"NaN"`, `"Infinity"`, or `"-Infinity"`.
+    String,
+}
+
+/// How byte values are encoded in JSON output.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum BytesEncoding {
+    /// stratadb's built-in base64 encoding (default).
+    #[default]
+    Base64,
+    /// Lowercase hex string.
+    Hex,
+    /// Array of byte values (0-255).
+    Array,
+}
+
+/// Options controlling how stratadb values are converted to JSON.
+#[derive(Debug, Clone, Default)]
+pub struct ConvertOptions {
+    /// Rendering of non-finite floats.
+    pub non_finite: NonFiniteFloat,
+    /// Encoding of byte values.
+    pub bytes: BytesEncoding,
+    /// Return bare values for versioned reads, omitting the
+    /// `{value, version, timestamp}` wrapper.
+    pub raw: bool,
+    /// Wrap single-value reads in `{found, value}` so an absent entry
+    /// (`{found: false}`) differs from a stored null.
+    pub distinguish_missing: bool,
+}
 
 /// Convert a JSON value to a stratadb Value.
 pub fn json_to_value(json: JsonValue) -> Result<Value> {
@@ -18,7 +59,7 @@ pub fn json_to_value(json: JsonValue) -> Result<Value> {
             if let Some(i) = n.as_i64() {
                 Ok(Value::Int(i))
             } else if let Some(f) = n.as_f64() {
-                Ok(Value::Float(f))
+                float_to_value(f)
             } else {
                 Err(McpError::InvalidArg {
                     name: "value".to_string(),
@@ -41,38 +82,141 @@ pub fn json_to_value(json: JsonValue) -> Result<Value> {
     }
 }
 
+/// Convert a float to a stratadb Value, rejecting NaN and Infinity.
+fn float_to_value(f: f64) -> Result<Value> {
+    if !f.is_finite() {
+        return Err(McpError::InvalidArg {
+            name: "value".to_string(),
+            reason: format!("Non-finite number '{}' is not supported", f),
+        });
+    }
+    Ok(Value::Float(f))
+}
+
 /// Convert a stratadb Value to a JSON value.
 /// Uses stratadb's built-in conversion which handles base64 encoding for bytes.
+///
+/// Exposed for library consumers; tools convert via `value_to_json_with`.
+#[allow(dead_code)]
 pub fn value_to_json(value: Value) -> JsonValue {
-    // stratadb::Value implements Into<serde_json::Value>
-    value.into()
+    value_to_json_with(value, &ConvertOptions::default())
+}
+
+/// Convert a stratadb Value to a JSON value using the given options.
+pub fn value_to_json_with(value: Value, opts: &ConvertOptions) -> JsonValue {
+    match value {
+        Value::Float(f) if !f.is_finite() => non_finite_to_json(f, opts.non_finite),
+        Value::Bytes(b) if opts.bytes != BytesEncoding::Base64 => bytes_to_json(b, opts.bytes),
+        Value::Array(arr) => JsonValue::Array(
+            arr.into_iter()
+                .map(|v| value_to_json_with(v, opts))
+                .collect(),
+        ),
+        Value::Object(obj) => JsonValue::Object(
+            obj.into_iter()
+                .map(|(k, v)| (k, value_to_json_with(v, opts)))
+                .collect(),
+        ),
+        // stratadb::Value implements Into<serde_json::Value>
+        other => other.into(),
+    }
+}
+
+/// Render a non-finite float according to the configured policy.
+fn non_finite_to_json(f: f64, policy: NonFiniteFloat) -> JsonValue {
+    match policy {
+        NonFiniteFloat::Null => JsonValue::Null,
+        NonFiniteFloat::String => {
+            let s = if f.is_nan() {
+                "NaN"
+            } else if f.is_sign_positive() {
+                "Infinity"
+            } else {
+                "-Infinity"
+            };
+            JsonValue::String(s.to_string())
+        }
+    }
+}
+
+/// Encode bytes as hex or a numeric array. Base64 is left to stratadb.
+fn bytes_to_json(bytes: Vec<u8>, encoding: BytesEncoding) -> JsonValue {
+    match encoding {
+        BytesEncoding::Hex => {
+            JsonValue::String(bytes.iter().map(|b| format!("{:02x}", b)).collect())
+        }
+        BytesEncoding::Array => {
+            JsonValue::Array(bytes.into_iter().map(|b| JsonValue::Number(b.into())).collect())
+        }
+        BytesEncoding::Base64 => Value::Bytes(bytes).into(),
+    }
 }
 
 /// Convert a VersionedValue to JSON.
-pub fn versioned_to_json(vv: VersionedValue) -> JsonValue {
+pub fn versioned_to_json(vv: VersionedValue, opts: &ConvertOptions) -> JsonValue {
     serde_json::json!({
-        "value": value_to_json(vv.value),
+        "value": value_to_json_with(vv.value, opts),
         "version": vv.version,
         "timestamp": vv.timestamp,
     })
 }
 
 /// Convert an Output to JSON for MCP response.
+///
+/// Exposed for library consumers; tools convert with the session's options
+/// via `output_to_json_with`.
+#[allow(dead_code)]
 pub fn output_to_json(output: Output) -> JsonValue {
+    output_to_json_with(output, &ConvertOptions::default())
+}
+
+/// Convert an Output to JSON for MCP response using the given options.
+pub fn output_to_json_with(output: Output, opts: &ConvertOptions) -> JsonValue {
     match output {
         Output::Unit => JsonValue::Null,
-        Output::Maybe(opt) => opt.map_or(JsonValue::Null, value_to_json),
-        Output::MaybeVersioned(opt) => opt.map_or(JsonValue::Null, versioned_to_json),
+        Output::Maybe(opt) if opts.distinguish_missing => match opt {
+            Some(v) => serde_json::json!({ "found": true, "value": value_to_json_with(v, opts) }),
+            None => serde_json::json!({ "found": false }),
+        },
+        Output::Maybe(opt) => opt.map_or(JsonValue::Null, |v| value_to_json_with(v, opts)),
+        Output::MaybeVersioned(opt) if opts.distinguish_missing => match opt {
+            Some(vv) if opts.raw => {
+                serde_json::json!({ "found": true, "value": value_to_json_with(vv.value, opts) })
+            }
+            Some(vv) => {
+                let mut found = versioned_to_json(vv, opts);
+                if let JsonValue::Object(obj) = &mut found {
+                    obj.insert("found".to_string(), JsonValue::Bool(true));
+                }
+                found
+            }
+            None => serde_json::json!({ "found": false }),
+        },
+        Output::MaybeVersioned(opt) => opt.map_or(JsonValue::Null, |vv| {
+            if opts.raw {
+                value_to_json_with(vv.value, opts)
+            } else {
+                versioned_to_json(vv, opts)
+            }
+        }),
         Output::MaybeVersion(opt) => opt.map_or(JsonValue::Null, |v| JsonValue::Number(v.into())),
         Output::Version(v) => serde_json::json!({ "version": v }),
         Output::Bool(b) => JsonValue::Bool(b),
         Output::Uint(n) => JsonValue::Number(n.into()),
 
-        Output::VersionedValues(values) => {
-            JsonValue::Array(values.into_iter().map(versioned_to_json).collect())
-        }
+        Output::VersionedValues(values) => JsonValue::Array(
+            values
+                .into_iter()
+                .map(|vv| versioned_to_json(vv, opts))
+                .collect(),
+        ),
         Output::VersionHistory(opt) => opt.map_or(JsonValue::Null, |values| {
-            JsonValue::Array(values.into_iter().map(versioned_to_json).collect())
+            JsonValue::Array(
+                values
+                    .into_iter()
+                    .map(|vv| versioned_to_json(vv, opts))
+                    .collect(),
+            )
         }),
         Output::Keys(keys) => JsonValue::Array(keys.into_iter().map(JsonValue::String).collect()),
 
@@ -95,7 +239,7 @@ pub fn output_to_json(output: Output) -> JsonValue {
                     serde_json::json!({
                         "key": m.key,
                         "score": m.score,
-                        "metadata": m.metadata.map(value_to_json),
+                        "metadata": m.metadata.map(|v| value_to_json_with(v, opts)),
                     })
                 })
                 .collect();
@@ -106,7 +250,7 @@ pub fn output_to_json(output: Output) -> JsonValue {
             serde_json::json!({
                 "key": vd.key,
                 "embedding": vd.data.embedding,
-                "metadata": vd.data.metadata.map(value_to_json),
+                "metadata": vd.data.metadata.map(|v| value_to_json_with(v, opts)),
                 "version": vd.version,
                 "timestamp": vd.timestamp,
             })
@@ -254,6 +398,255 @@ pub fn output_to_json(output: Output) -> JsonValue {
     }
 }
 
+/// Cap the size of arrays in a tool result.
+///
+/// A top-level array, or an array directly under a top-level object field,
+/// longer than `max_items` is replaced by
+/// `{"truncated": true, "total": N, "returned": M, "items": [...]}`.
+pub fn truncate_result(result: JsonValue, max_items: usize) -> JsonValue {
+    match result {
+        JsonValue::Array(items) => truncate_array(items, max_items),
+        JsonValue::Object(obj) => JsonValue::Object(
+            obj.into_iter()
+                .map(|(k, v)| match v {
+                    JsonValue::Array(items) => (k, truncate_array(items, max_items)),
+                    other => (k, other),
+                })
+                .collect(),
+        ),
+        other => other,
+    }
+}
+
+fn truncate_array(mut items: Vec<JsonValue>, max_items: usize) -> JsonValue {
+    if items.len() <= max_items {
+        return JsonValue::Array(items);
+    }
+    let total = items.len();
+    items.truncate(max_items);
+    serde_json::json!({
+        "truncated": true,
+        "total": total,
+        "returned": items.len(),
+        "items": items,
+    })
+}
+
+/// Narrow a `*Getv` history to versions newer than `since_version`, keeping
+/// at most the `limit` most recent, newest first.
+///
+/// The output is returned unchanged if neither bound is given.
+pub fn history_window(output: Output, since_version: Option<u64>, limit: Option<u64>) -> Output {
+    if since_version.is_none() && limit.is_none() {
+        return output;
+    }
+    match output {
+        Output::VersionHistory(Some(mut values)) => {
+            if let Some(since) = since_version {
+                values.retain(|vv| vv.version > since);
+            }
+            values.sort_by_key(|vv| std::cmp::Reverse(vv.version));
+            if let Some(limit) = limit {
+                values.truncate(limit as usize);
+            }
+            Output::VersionHistory(Some(values))
+        }
+        other => other,
+    }
+}
+
+/// Parse the ISO-8601 bounds of a time range into microsecond timestamps.
+pub fn time_range_micros(range: &TimeRangeInput) -> Result<(u64, u64)> {
+    let start = parse_timestamp("start", &range.start)?;
+    let end = parse_timestamp("end", &range.end)?;
+    if start > end {
+        return Err(McpError::InvalidArg {
+            name: "end".to_string(),
+            reason: "Must not be before start".to_string(),
+        });
+    }
+    Ok((start, end))
+}
+
+/// Parse an ISO-8601 timestamp into microseconds since the Unix epoch.
+///
+/// Accepts a date (`2024-01-31`) or a date and time (`2024-01-31T12:00:00`)
+/// with optional fractional seconds and a `Z` or `+HH:MM` offset; times
+/// without an offset are UTC.
+pub fn parse_timestamp(name: &str, s: &str) -> Result<u64> {
+    let invalid = || McpError::InvalidArg {
+        name: name.to_string(),
+        reason: format!(
+            "Expected an ISO-8601 timestamp such as 2024-01-31T12:00:00Z, got '{}'",
+            s
+        ),
+    };
+    let digits = |part: &str| -> Result<i64> {
+        if part.is_empty() || !part.bytes().all(|b| b.is_ascii_digit()) {
+            return Err(invalid());
+        }
+        part.parse().map_err(|_| invalid())
+    };
+    let field = |start: usize, len: usize| s.get(start..start + len).ok_or_else(invalid);
+
+    if s.get(4..5) != Some("-") || s.get(7..8) != Some("-") {
+        return Err(invalid());
+    }
+    let (year, month, day) = (digits(field(0, 4)?)?, digits(field(5, 2)?)?, digits(field(8, 2)?)?);
+    if !(1..=12).contains(&month) || day < 1 || day > days_in_month(year, month) {
+        return Err(invalid());
+    }
+
+    let mut seconds = days_from_civil(year, month, day) * 86_400;
+    let mut micros = 0;
+    let mut rest = s.get(10..).ok_or_else(invalid)?;
+    if !rest.is_empty() {
+        if !matches!(rest.as_bytes()[0], b'T' | b't' | b' ')
+            || rest.get(3..4) != Some(":")
+            || rest.get(6..7) != Some(":")
+        {
+            return Err(invalid());
+        }
+        let part = |start: usize| rest.get(start..start + 2).ok_or_else(invalid);
+        let (hour, minute, second) = (digits(part(1)?)?, digits(part(4)?)?, digits(part(7)?)?);
+        if hour > 23 || minute > 59 || second > 60 {
+            return Err(invalid());
+        }
+        seconds += hour * 3_600 + minute * 60 + second;
+        rest = &rest[9..];
+
+        if let Some(frac) = rest.strip_prefix('.') {
+            let len = frac.bytes().take_while(|b| b.is_ascii_digit()).count();
+            if len == 0 {
+                return Err(invalid());
+            }
+            let padded = format!("{:0<6}", &frac[..len]);
+            micros = digits(&padded[..6])?;
+            rest = &frac[len..];
+        }
+
+        match rest {
+            "" | "Z" | "z" => {}
+            offset if offset.len() == 6 && offset.get(3..4) == Some(":") => {
+                let sign = match offset.as_bytes()[0] {
+                    b'+' => 1,
+                    b'-' => -1,
+                    _ => return Err(invalid()),
+                };
+                let (hours, minutes) = (digits(&offset[1..3])?, digits(&offset[4..6])?);
+                seconds -= sign * (hours * 3_600 + minutes * 60);
+            }
+            _ => return Err(invalid()),
+        }
+    }
+
+    if seconds < 0 {
+        return Err(invalid());
+    }
+    Ok(seconds as u64 * 1_000_000 + micros as u64)
+}
+
+/// Days from 1970-01-01 to a proleptic Gregorian date.
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let day_of_year = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146_097 + day_of_era - 719_468
+}
+
+fn days_in_month(year: i64, month: i64) -> i64 {
+    match month {
+        2 if year % 4 == 0 && (year % 100 != 0 || year % 400 == 0) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+/// Arguments that name a branch or space in every tool that takes them.
+const NAME_ARGS: &[&str] = &[
+    "branch",
+    "branch_id",
+    "branch_a",
+    "branch_b",
+    "source",
+    "destination",
+    "to_branch",
+    "space",
+    "to_space",
+];
+
+/// Arguments in [`NAME_ARGS`] that name a space.
+const SPACE_ARGS: &[&str] = &["space", "to_space"];
+
+/// Reject a space name reserved for the server's own records.
+pub fn check_space_name(arg: &str, space: &str) -> Result<()> {
+    if is_reserved_space(space) {
+        return Err(McpError::InvalidArg {
+            name: arg.to_string(),
+            reason: format!(
+                "Space names starting with '{}' are reserved",
+                RESERVED_SPACE_PREFIX
+            ),
+        });
+    }
+    Ok(())
+}
+
+/// Normalize a branch or space name.
+///
+/// Surrounding whitespace is trimmed and, if `fold_case` is set, the name is
+/// lowercased. Empty names and names containing control characters are
+/// rejected.
+pub fn normalize_name(arg: &str, name: &str, fold_case: bool) -> Result<String> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err(McpError::InvalidArg {
+            name: arg.to_string(),
+            reason: "Name must not be empty".to_string(),
+        });
+    }
+    if name.chars().any(char::is_control) {
+        return Err(McpError::InvalidArg {
+            name: arg.to_string(),
+            reason: "Name must not contain control characters".to_string(),
+        });
+    }
+    Ok(if fold_case {
+        name.to_lowercase()
+    } else {
+        name.to_string()
+    })
+}
+
+/// Normalize the branch and space names in a tool's arguments in place.
+///
+/// `from` and `to` name spaces only in `strata_space_copy`; elsewhere they
+/// are keys or collections and are left alone. Reserved space names are
+/// rejected.
+pub fn normalize_name_args(
+    tool: &str,
+    args: &mut Map<String, JsonValue>,
+    fold_case: bool,
+) -> Result<()> {
+    let space_pair: &[&str] = if tool == "strata_space_copy" {
+        &["from", "to"]
+    } else {
+        &[]
+    };
+    for &arg in NAME_ARGS.iter().chain(space_pair) {
+        if let Some(JsonValue::String(name)) = args.get_mut(arg) {
+            *name = normalize_name(arg, name, fold_case)?;
+            if SPACE_ARGS.contains(&arg) || space_pair.contains(&arg) {
+                check_space_name(arg, name)?;
+            }
+        }
+    }
+    Ok(())
+}
+
 /// Helper to get a required string argument from JSON arguments.
 pub fn get_string_arg(args: &Map<String, JsonValue>, name: &str) -> Result<String> {
     args.get(name)
@@ -279,6 +672,22 @@ pub fn get_optional_u64(args: &Map<String, JsonValue>, name: &str) -> Option<u64
     args.get(name).and_then(|v| v.as_u64())
 }
 
+/// Helper to apply optional per-call `raw` and `distinguish_missing`
+/// arguments on top of base options.
+pub fn get_convert_options(
+    args: &Map<String, JsonValue>,
+    base: &ConvertOptions,
+) -> ConvertOptions {
+    let mut opts = base.clone();
+    if let Some(raw) = get_optional_bool(args, "raw") {
+        opts.raw = raw;
+    }
+    if let Some(distinguish) = get_optional_bool(args, "distinguish_missing") {
+        opts.distinguish_missing = distinguish;
+    }
+    opts
+}
+
 /// Helper to get a required value argument and convert it to stratadb Value.
 pub fn get_value_arg(args: &Map<String, JsonValue>, name: &str) -> Result<Value> {
     let json = args
@@ -309,3 +718,171 @@ pub fn get_vector_arg(args: &Map<String, JsonValue>, name: &str) -> Result<Vec<f
 pub fn get_optional_bool(args: &Map<String, JsonValue>, name: &str) -> Option<bool> {
     args.get(name).and_then(|v| v.as_bool())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_timestamp() {
+        assert_eq!(parse_timestamp("t", "1970-01-01").unwrap(), 0);
+        assert_eq!(parse_timestamp("t", "1970-01-02T00:00:00Z").unwrap(), 86_400_000_000);
+        assert_eq!(
+            parse_timestamp("t", "2024-02-29T12:30:15.25Z").unwrap(),
+            1_709_209_815_250_000
+        );
+        assert_eq!(
+            parse_timestamp("t", "2024-02-29T14:30:15.25+02:00").unwrap(),
+            parse_timestamp("t", "2024-02-29T12:30:15.250Z").unwrap()
+        );
+        assert!(parse_timestamp("t", "2023-02-29").is_err());
+        assert!(parse_timestamp("t", "2024-01-31T25:00:00Z").is_err());
+        assert!(parse_timestamp("t", "yesterday").is_err());
+        assert!(parse_timestamp("t", "1969-12-31T23:59:59Z").is_err());
+    }
+
+    #[test]
+    fn test_float_to_value_rejects_nan() {
+        let err = float_to_value(f64::NAN).unwrap_err();
+        assert!(matches!(err, McpError::InvalidArg { .. }));
+    }
+
+    #[test]
+    fn test_float_to_value_rejects_infinity() {
+        assert!(float_to_value(f64::INFINITY).is_err());
+        assert!(float_to_value(f64::NEG_INFINITY).is_err());
+        assert!(float_to_value(1.5).is_ok());
+    }
+
+    #[test]
+    fn test_value_to_json_non_finite_null() {
+        assert_eq!(value_to_json(Value::Float(f64::NAN)), JsonValue::Null);
+        assert_eq!(value_to_json(Value::Float(f64::INFINITY)), JsonValue::Null);
+    }
+
+    #[test]
+    fn test_value_to_json_non_finite_string() {
+        let opts = ConvertOptions {
+            non_finite: NonFiniteFloat::String,
+            ..Default::default()
+        };
+        assert_eq!(
+            value_to_json_with(Value::Float(f64::NAN), &opts),
+            serde_json::json!("NaN")
+        );
+        assert_eq!(
+            value_to_json_with(Value::Float(f64::INFINITY), &opts),
+            serde_json::json!("Infinity")
+        );
+        assert_eq!(
+            value_to_json_with(Value::Float(f64::NEG_INFINITY), &opts),
+            serde_json::json!("-Infinity")
+        );
+    }
+
+    #[test]
+    fn test_bytes_to_json_hex() {
+        let opts = ConvertOptions {
+            bytes: BytesEncoding::Hex,
+            ..Default::default()
+        };
+        assert_eq!(
+            value_to_json_with(Value::Bytes(vec![0x00, 0xab, 0xff]), &opts),
+            serde_json::json!("00abff")
+        );
+    }
+
+    #[test]
+    fn test_bytes_to_json_array() {
+        let opts = ConvertOptions {
+            bytes: BytesEncoding::Array,
+            ..Default::default()
+        };
+        assert_eq!(
+            value_to_json_with(Value::Bytes(vec![1, 2, 255]), &opts),
+            serde_json::json!([1, 2, 255])
+        );
+    }
+
+    #[test]
+    fn test_normalize_name() {
+        assert_eq!(normalize_name("branch", "  main\t", false).unwrap(), "main");
+        assert_eq!(normalize_name("branch", " Main ", true).unwrap(), "main");
+        assert!(normalize_name("branch", "   ", false).is_err());
+        assert!(normalize_name("space", "a\nb", false).is_err());
+    }
+
+    #[test]
+    fn test_normalize_name_args_space_copy() {
+        let mut args = Map::new();
+        args.insert("from".to_string(), JsonValue::from(" a "));
+        args.insert("to".to_string(), JsonValue::from(" b "));
+        let mut renamed = args.clone();
+        normalize_name_args("strata_kv_rename", &mut renamed, false).unwrap();
+        assert_eq!(renamed["from"], " a ");
+        normalize_name_args("strata_space_copy", &mut args, false).unwrap();
+        assert_eq!(args["from"], "a");
+        assert_eq!(args["to"], "b");
+    }
+
+    #[test]
+    fn test_normalize_name_args_rejects_reserved_space() {
+        let mut args = Map::new();
+        args.insert("space".to_string(), JsonValue::from(" _mcp_kv_expiry"));
+        assert!(normalize_name_args("strata_kv_list", &mut args, false).is_err());
+        let mut args = Map::new();
+        args.insert("to".to_string(), JsonValue::from("_mcp_x"));
+        assert!(normalize_name_args("strata_space_copy", &mut args, false).is_err());
+        assert!(normalize_name_args("strata_kv_copy", &mut args, false).is_ok());
+    }
+
+    #[test]
+    fn test_truncate_result_wraps_long_arrays() {
+        let result = truncate_result(serde_json::json!([1, 2, 3, 4]), 2);
+        assert_eq!(
+            result,
+            serde_json::json!({"truncated": true, "total": 4, "returned": 2, "items": [1, 2]})
+        );
+        assert_eq!(truncate_result(serde_json::json!([1, 2]), 2), serde_json::json!([1, 2]));
+    }
+
+    #[test]
+    fn test_truncate_result_object_fields() {
+        let result = truncate_result(serde_json::json!({"keys": ["a", "b", "c"], "cursor": "c"}), 1);
+        assert_eq!(result["keys"]["total"], serde_json::json!(3));
+        assert_eq!(result["keys"]["items"], serde_json::json!(["a"]));
+        assert_eq!(result["cursor"], serde_json::json!("c"));
+    }
+
+    #[test]
+    fn test_distinguish_missing_maybe() {
+        let opts = ConvertOptions {
+            distinguish_missing: true,
+            ..Default::default()
+        };
+        assert_eq!(
+            output_to_json_with(Output::Maybe(None), &opts),
+            serde_json::json!({"found": false})
+        );
+        assert_eq!(
+            output_to_json_with(Output::Maybe(Some(Value::Null)), &opts),
+            serde_json::json!({"found": true, "value": null})
+        );
+        let plain = ConvertOptions::default();
+        assert_eq!(output_to_json_with(Output::Maybe(None), &plain), JsonValue::Null);
+        assert_eq!(output_to_json_with(Output::Maybe(Some(Value::Null)), &plain), JsonValue::Null);
+    }
+
+    #[test]
+    fn test_bytes_to_json_base64_default() {
+        let bytes = Value::Bytes(vec![1, 2, 3]);
+        let expected: JsonValue = bytes.clone().into();
+        assert_eq!(value_to_json(bytes), expected);
+    }
+
+    #[test]
+    fn test_value_to_json_non_finite_nested() {
+        let value = Value::Array(vec![Value::Int(1), Value::Float(f64::NAN)]);
+        assert_eq!(value_to_json(value), serde_json::json!([1, null]));
+    }
+}
@@ -105,6 +105,9 @@ impl From<serde_json::Error> for McpError {
 }
 
 /// JSON-RPC error codes.
+///
+/// The standard codes are from the JSON-RPC 2.0 specification. Codes from
+/// -32000 to -32099 are reserved for server-defined errors.
 pub mod rpc_codes {
     /// Parse error - Invalid JSON was received.
     pub const PARSE_ERROR: i32 = -32700;
@@ -116,6 +119,10 @@ pub mod rpc_codes {
     pub const INVALID_PARAMS: i32 = -32602;
     /// Internal error - Internal JSON-RPC error.
     pub const INTERNAL_ERROR: i32 = -32603;
+    /// Conflict - A concurrent change or merge conflict stopped the operation
+    /// (strata codes `VERSION_CONFLICT`, `TXN_CONFLICT`, and `CONFLICT`), or an
+    /// insert found its key already present (`KEY_EXISTS`).
+    pub const CONFLICT: i32 = -32001;
 }
 
 impl McpError {
@@ -135,12 +142,47 @@ impl McpError {
                     "INVALID_KEY" | "INVALID_PATH" | "INVALID_INPUT" | "WRONG_TYPE" => {
                         rpc_codes::INVALID_PARAMS
                     }
+                    "VERSION_CONFLICT" | "TXN_CONFLICT" | "CONFLICT" | "KEY_EXISTS" => {
+                        rpc_codes::CONFLICT
+                    }
                     _ => rpc_codes::INTERNAL_ERROR,
                 }
             }
             _ => rpc_codes::INTERNAL_ERROR,
         }
     }
+
+    /// Replace the message of a strata error with its code and a generic note.
+    ///
+    /// Strata messages can name paths and storage internals. The code, and so
+    /// the RPC code and retryability, are kept; other errors are unchanged.
+    pub fn redacted(self) -> Self {
+        match self {
+            McpError::Strata { code, .. } => McpError::Strata {
+                message: format!("{} (details redacted)", code),
+                code,
+            },
+            other => other,
+        }
+    }
+
+    /// Whether retrying the same request may succeed.
+    ///
+    /// True for conflicts and storage I/O errors reported by the engine;
+    /// false for errors in the request itself, which fail the same way every
+    /// time. `TXN_TIMEOUT` is not retryable: the transaction
+    /// was already rolled back, so the whole transaction must be restarted.
+    /// Neither is `Io`, which comes from the server's own file access (backup
+    /// and bundle paths) and is usually permanent, such as a missing file.
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            McpError::Strata { code, .. } => matches!(
+                code.as_str(),
+                "VERSION_CONFLICT" | "TXN_CONFLICT" | "CONFLICT" | "IO_ERROR"
+            ),
+            _ => false,
+        }
+    }
 }
 
 /// Result type for MCP operations.
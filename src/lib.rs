@@ -7,7 +7,7 @@
 //!
 //! ## Features
 //!
-//! - **61 tools** covering all Strata primitives: KV, JSON, Event, State, Vector, Branch, Space, Transaction, Search, Bundle, Retention
+//! - **109 tools** covering all Strata primitives: KV, JSON, Event, State, Vector, Branch, Space, Transaction, Search, Bundle, Retention
 //! - **Session state**: Tracks current branch and space context across tool calls
 //! - **Transaction support**: ACID transactions via begin/commit/rollback tools
 //! - **Branch operations**: Fork, diff, and merge branches for data isolation
@@ -51,8 +51,11 @@ mod server;
 mod session;
 mod tools;
 
-pub use convert::{json_to_value, output_to_json, value_to_json};
-pub use error::{McpError, Result};
-pub use server::{JsonRpcRequest, JsonRpcResponse, McpServer};
-pub use session::McpSession;
+pub use convert::{
+    json_to_value, output_to_json, output_to_json_with, value_to_json, value_to_json_with,
+    BytesEncoding, ConvertOptions, NonFiniteFloat,
+};
+pub use error::{rpc_codes, McpError, Result};
+pub use server::{JsonRpcRequest, JsonRpcResponse, McpServer, ShutdownHandle};
+pub use session::{McpSession, MergeResolution, RuntimeConfig};
 pub use tools::{ToolDef, ToolRegistry};
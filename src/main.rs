@@ -2,6 +2,8 @@
 //!
 //! Run with `strata-mcp --db /path/to/data` or `strata-mcp --cache` for in-memory mode.
 
+use std::time::Duration;
+
 use clap::Parser;
 use stratadb::{AccessMode, OpenOptions, Strata};
 use tracing_subscriber::EnvFilter;
@@ -12,8 +14,10 @@ mod server;
 mod session;
 mod tools;
 
-use server::McpServer;
-use session::McpSession;
+use convert::{BytesEncoding, ConvertOptions, NonFiniteFloat};
+use server::{McpServer, DEFAULT_MAX_MESSAGE_BYTES};
+use session::{McpSession, RuntimeConfig};
+use tools::cache;
 
 /// MCP server for Strata database.
 ///
@@ -43,6 +47,79 @@ struct Args {
     #[arg(long)]
     auto_embed: bool,
 
+    /// How NaN and Infinity float values are rendered in output.
+    /// "null" (default) or "string" ("NaN", "Infinity", "-Infinity").
+    #[arg(long, value_name = "MODE", default_value = "null", value_parser = ["null", "string"])]
+    non_finite_floats: String,
+
+    /// Encoding for byte values in output: "base64" (default), "hex", or "array".
+    #[arg(
+        long,
+        value_name = "ENCODING",
+        default_value = "base64",
+        value_parser = ["base64", "hex", "array"]
+    )]
+    bytes_encoding: String,
+
+    /// Hide the database path from strata_db_config output.
+    #[arg(long)]
+    redact_db_path: bool,
+
+    /// Return bare values from read tools by default, omitting the
+    /// version/timestamp wrapper. Tools still accept a per-call `raw` argument.
+    #[arg(long)]
+    raw: bool,
+
+    /// Wrap single-value reads in `{found, value}` by default, so a missing
+    /// entry differs from a stored null. Tools still accept a per-call
+    /// `distinguish_missing` argument.
+    #[arg(long)]
+    distinguish_missing: bool,
+
+    /// Lowercase branch and space names in tool arguments, so names that
+    /// differ only in case refer to the same branch or space.
+    #[arg(long)]
+    fold_name_case: bool,
+
+    /// Maximum size of a single JSON-RPC request line, in bytes.
+    #[arg(long, value_name = "BYTES", default_value_t = DEFAULT_MAX_MESSAGE_BYTES)]
+    max_message_bytes: usize,
+
+    /// Maximum number of items in any array of a tool result.
+    /// Longer arrays are truncated and marked with truncation metadata.
+    #[arg(long, value_name = "N")]
+    max_result_items: Option<usize>,
+
+    /// Replace strata error details in responses with the error code and a
+    /// generic message. Full errors are still logged.
+    #[arg(long)]
+    redact_errors: bool,
+
+    /// Add `_meta.elapsed_us` timing to every tool call result.
+    #[arg(long)]
+    report_timing: bool,
+
+    /// Cache results of metadata read tools (e.g. strata_db_info,
+    /// strata_branch_list) for this many milliseconds. Any other tool call
+    /// clears the cache. Disabled by default.
+    #[arg(long, value_name = "MS")]
+    result_cache_ttl_ms: Option<u64>,
+
+    /// Prefix prepended to every tool name, e.g. "acme_" for
+    /// `acme_strata_kv_put`. Avoids collisions behind an aggregator.
+    #[arg(long, value_name = "PREFIX")]
+    tool_prefix: Option<String>,
+
+    /// Flush the database and exit after this many seconds without a request.
+    /// Any request, including a ping, restarts the countdown.
+    #[arg(long, value_name = "SECS")]
+    idle_timeout_secs: Option<u64>,
+
+    /// Do not install SIGINT/SIGTERM handlers.
+    /// By default, these signals flush the database and exit cleanly.
+    #[arg(long)]
+    no_signal_handler: bool,
+
     /// Enable debug logging to stderr.
     #[arg(long, short)]
     verbose: bool,
@@ -112,8 +189,44 @@ fn main() {
     };
 
     // Create session and server
-    let session = McpSession::new(db);
-    let mut server = McpServer::new(session);
+    let mut session = McpSession::new(db);
+    session.set_runtime_config(RuntimeConfig {
+        cache: args.cache,
+        db_path: args.db.clone(),
+        auto_embed: args.auto_embed && !args.cache,
+        redact_path: args.redact_db_path,
+    });
+    session.set_convert_options(ConvertOptions {
+        non_finite: match args.non_finite_floats.as_str() {
+            "string" => NonFiniteFloat::String,
+            _ => NonFiniteFloat::Null,
+        },
+        bytes: match args.bytes_encoding.as_str() {
+            "hex" => BytesEncoding::Hex,
+            "array" => BytesEncoding::Array,
+            _ => BytesEncoding::Base64,
+        },
+        raw: args.raw,
+        distinguish_missing: args.distinguish_missing,
+    });
+    session.set_fold_name_case(args.fold_name_case);
+    if let Some(ttl_ms) = args.result_cache_ttl_ms {
+        session.enable_result_cache(Duration::from_millis(ttl_ms), cache::DEFAULT_CAPACITY);
+    }
+    let mut server = McpServer::new(session)
+        .with_signal_handling(!args.no_signal_handler)
+        .with_max_message_bytes(args.max_message_bytes)
+        .with_timing(args.report_timing)
+        .with_redacted_errors(args.redact_errors);
+    if let Some(max_items) = args.max_result_items {
+        server = server.with_max_result_items(max_items);
+    }
+    if let Some(prefix) = &args.tool_prefix {
+        server = server.with_tool_prefix(prefix);
+    }
+    if let Some(secs) = args.idle_timeout_secs {
+        server = server.with_idle_timeout(Duration::from_secs(secs));
+    }
 
     // Run the server
     if let Err(e) = server.run_sync() {
@@ -4,18 +4,37 @@
 
 use serde::{Deserialize, Serialize};
 use serde_json::{Map, Value as JsonValue};
-use std::io::{BufRead, Write};
-
+use std::io::{BufRead, Read, Write};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use stratadb::Command;
+
+use crate::convert::truncate_result;
 use crate::error::{rpc_codes, McpError, Result};
 use crate::session::McpSession;
+use crate::tools::completion::MAX_COMPLETIONS;
 use crate::tools::ToolRegistry;
 
-/// MCP protocol version we support.
-const PROTOCOL_VERSION: &str = "2024-11-05";
+/// MCP protocol version we prefer, used when a client's version can't be matched.
+pub(crate) const PROTOCOL_VERSION: &str = "2024-11-05";
+
+/// MCP protocol versions we support, newest first.
+pub(crate) const SUPPORTED_PROTOCOL_VERSIONS: &[&str] = &["2025-06-18", "2025-03-26", "2024-11-05"];
 
 /// Server information.
-const SERVER_NAME: &str = "strata-mcp";
-const SERVER_VERSION: &str = env!("CARGO_PKG_VERSION");
+pub(crate) const SERVER_NAME: &str = "strata-mcp";
+pub(crate) const SERVER_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+/// How often the router checks for a shutdown request while idle.
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Items sent per notification when a tool result is streamed.
+pub const STREAM_CHUNK_ITEMS: usize = 100;
+
+/// Default maximum size of a single request line, in bytes.
+pub const DEFAULT_MAX_MESSAGE_BYTES: usize = 4 * 1024 * 1024;
 
 /// JSON-RPC 2.0 request.
 #[derive(Debug, Deserialize)]
@@ -85,16 +104,68 @@ impl JsonRpcResponse {
     }
 
     /// Create an error response from an McpError.
+    ///
+    /// The error `data` carries `retryable` so clients know when backing off
+    /// and retrying can help.
     pub fn from_error(id: Option<JsonValue>, err: McpError) -> Self {
-        Self::error(id, err.rpc_code(), err.to_string())
+        let mut response = Self::error(id, err.rpc_code(), err.to_string());
+        if let Some(error) = response.error.as_mut() {
+            error.data = Some(serde_json::json!({ "retryable": err.is_retryable() }));
+        }
+        response
     }
 }
 
+/// Handle for requesting a graceful server shutdown from another thread.
+///
+/// Once requested, the server finishes any requests it has already received,
+/// flushes the database, and returns from its run loop.
+#[derive(Debug, Clone, Default)]
+pub struct ShutdownHandle(Arc<AtomicBool>);
+
+impl ShutdownHandle {
+    /// Request a graceful shutdown.
+    pub fn shutdown(&self) {
+        self.0.store(true, Ordering::SeqCst);
+    }
+
+    /// Whether a shutdown has been requested.
+    pub fn is_shutdown(&self) -> bool {
+        self.0.load(Ordering::SeqCst)
+    }
+}
+
+/// Input delivered from the reader thread to the router.
+enum Incoming {
+    Line(String),
+    TooLarge,
+    Eof,
+    Error(std::io::Error),
+}
+
+/// Work forwarded from the router to the session-owning request loop.
+enum Routed {
+    Request(JsonRpcRequest),
+    /// A request the router answered itself, such as `ping`
+    Activity,
+    Eof,
+    Shutdown,
+    Error(McpError),
+}
+
 /// MCP server.
 pub struct McpServer {
     session: McpSession,
     registry: ToolRegistry,
     initialized: bool,
+    shutdown: ShutdownHandle,
+    handle_signals: bool,
+    max_message_bytes: usize,
+    max_result_items: Option<usize>,
+    idle_timeout: Option<Duration>,
+    report_timing: bool,
+    /// Notifications to write before the response being handled
+    notifications: Vec<JsonValue>,
 }
 
 impl McpServer {
@@ -104,51 +175,193 @@ impl McpServer {
             session,
             registry: ToolRegistry::new(),
             initialized: false,
+            shutdown: ShutdownHandle::default(),
+            handle_signals: true,
+            max_message_bytes: DEFAULT_MAX_MESSAGE_BYTES,
+            max_result_items: None,
+            idle_timeout: None,
+            report_timing: false,
+            notifications: Vec::new(),
         }
     }
 
-    /// Run the server synchronously, reading from stdin and writing to stdout.
-    pub fn run_sync(&mut self) -> Result<()> {
-        let stdin = std::io::stdin();
-        let mut stdout = std::io::stdout();
-        let mut line = String::new();
+    /// Set the maximum size of a single request line, in bytes.
+    ///
+    /// Longer lines are discarded without being buffered and answered
+    /// with an `INVALID_REQUEST` error.
+    pub fn with_max_message_bytes(mut self, max_bytes: usize) -> Self {
+        self.max_message_bytes = max_bytes;
+        self
+    }
 
-        let stdin_lock = stdin.lock();
-        let mut reader = std::io::BufReader::new(stdin_lock);
+    /// Cap the number of items returned in any array of a tool result.
+    ///
+    /// Longer arrays are cut to `max_items` and wrapped with `truncated`,
+    /// `total`, and `returned` fields. Unlimited by default.
+    pub fn with_max_result_items(mut self, max_items: usize) -> Self {
+        self.max_result_items = Some(max_items);
+        self
+    }
 
-        loop {
-            line.clear();
-            let bytes_read = reader.read_line(&mut line)?;
+    /// Shut down after `timeout` passes with no request to handle.
+    ///
+    /// The database is flushed before the run loop returns, as on EOF. The
+    /// window restarts after each request is answered, including `ping`s,
+    /// which the router answers without reaching the session. Disabled by
+    /// default.
+    pub fn with_idle_timeout(mut self, timeout: Duration) -> Self {
+        self.idle_timeout = Some(timeout);
+        self
+    }
 
-            if bytes_read == 0 {
-                // EOF - client disconnected
-                break;
-            }
+    /// Report how long each tool call took (disabled by default).
+    ///
+    /// When enabled, every successful `tools/call` result carries
+    /// `_meta.elapsed_us`, the microseconds spent dispatching the tool.
+    pub fn with_timing(mut self, enabled: bool) -> Self {
+        self.report_timing = enabled;
+        self
+    }
 
-            let line = line.trim();
-            if line.is_empty() {
-                continue;
-            }
+    /// Hide strata error details from clients (disabled by default).
+    ///
+    /// When enabled, strata errors in responses keep their code but their
+    /// message is replaced with a generic one; the full error is logged.
+    pub fn with_redacted_errors(mut self, enabled: bool) -> Self {
+        self.session.set_redact_errors(enabled);
+        self
+    }
 
-            // Parse the request
-            let response = match serde_json::from_str::<JsonRpcRequest>(line) {
-                Ok(request) => self.handle_request(request),
-                Err(e) => JsonRpcResponse::error(
-                    None,
-                    rpc_codes::PARSE_ERROR,
-                    format!("Parse error: {}", e),
-                ),
+    /// Prefix every tool name with `prefix` (none by default).
+    ///
+    /// Tools are listed and must be called by their prefixed names, e.g.
+    /// `acme_strata_kv_put` for a prefix of `acme_`.
+    pub fn with_tool_prefix(mut self, prefix: &str) -> Self {
+        self.registry = ToolRegistry::new().with_prefix(prefix);
+        self
+    }
+
+    /// Enable or disable SIGINT/SIGTERM handling (enabled by default).
+    ///
+    /// When enabled, the run loop installs signal handlers that trigger a
+    /// graceful shutdown. Embedders that manage signals themselves should
+    /// disable this and use [`McpServer::shutdown_handle`] instead.
+    pub fn with_signal_handling(mut self, enabled: bool) -> Self {
+        self.handle_signals = enabled;
+        self
+    }
+
+    /// Get a handle that can request a graceful shutdown from another thread.
+    ///
+    /// Exposed for library consumers; the binary relies on signal handling.
+    #[allow(dead_code)]
+    pub fn shutdown_handle(&self) -> ShutdownHandle {
+        self.shutdown.clone()
+    }
+
+    /// Run the server synchronously, reading from stdin and writing to stdout.
+    pub fn run_sync(&mut self) -> Result<()> {
+        self.run_with(std::io::stdin(), std::io::stdout())
+    }
+
+    /// Run the server over the given reader and writer.
+    ///
+    /// Concurrency model: the session is owned by the calling thread, which
+    /// handles session-bound requests one at a time in arrival order. A
+    /// reader thread reads lines so that a shutdown request is noticed even
+    /// while waiting for input, and a router thread answers `ping`, parse
+    /// errors, and oversized messages immediately so they are never blocked
+    /// behind a slow tool call. Responses may therefore be written out of
+    /// request order; clients correlate them by id.
+    ///
+    /// Shutdown takes effect after the requests already routed to the
+    /// request loop are answered, even if the client keeps sending.
+    pub fn run_with<R, W>(&mut self, reader: R, writer: W) -> Result<()>
+    where
+        R: Read + Send + 'static,
+        W: Write + Send,
+    {
+        if self.handle_signals {
+            spawn_signal_listener(self.shutdown.clone());
+        }
+        let incoming = spawn_reader(reader, self.max_message_bytes);
+        let writer = Mutex::new(writer);
+        let stopped = AtomicBool::new(false);
+        let (work_tx, work_rx) = mpsc::channel();
+
+        let max_bytes = self.max_message_bytes;
+        let shutdown = self.shutdown.clone();
+        let writer_ref = &writer;
+        let stopped_ref = &stopped;
+        std::thread::scope(|scope| {
+            scope.spawn(move || {
+                let router = Router {
+                    work: work_tx,
+                    writer: writer_ref,
+                    stopped: stopped_ref,
+                    shutdown,
+                    max_bytes,
+                };
+                router.run(incoming)
+            });
+            let result = self.process_requests(&work_rx, writer_ref);
+            stopped_ref.store(true, Ordering::SeqCst);
+            result
+        })
+    }
+
+    /// Handle routed requests until EOF, shutdown, or an I/O error.
+    fn process_requests<W: Write>(
+        &mut self,
+        work: &Receiver<Routed>,
+        writer: &Mutex<W>,
+    ) -> Result<()> {
+        loop {
+            let received = match self.idle_timeout {
+                Some(timeout) => work.recv_timeout(timeout),
+                None => work.recv().map_err(RecvTimeoutError::from),
+            };
+            let request = match received {
+                Ok(Routed::Request(request)) => request,
+                Ok(Routed::Activity) => continue,
+                Err(RecvTimeoutError::Timeout) => {
+                    tracing::info!("Idle timeout reached, flushing database");
+                    self.flush();
+                    break;
+                }
+                Ok(Routed::Eof) | Err(RecvTimeoutError::Disconnected) => {
+                    // EOF - client disconnected; persist buffered writes
+                    self.flush();
+                    break;
+                }
+                Ok(Routed::Shutdown) => {
+                    tracing::info!("Shutdown requested, flushing database");
+                    self.flush();
+                    break;
+                }
+                Ok(Routed::Error(e)) => return Err(e),
             };
 
-            // Send response
-            let response_json = serde_json::to_string(&response)?;
-            writeln!(stdout, "{}", response_json)?;
-            stdout.flush()?;
+            let response = self.handle_request(request);
+            for notification in std::mem::take(&mut self.notifications) {
+                write_response(writer, &notification)?;
+            }
+            write_response(writer, &response)?;
         }
 
         Ok(())
     }
 
+    /// Flush the database, logging rather than failing on error.
+    fn flush(&mut self) {
+        if self.session.is_read_only() {
+            return;
+        }
+        if let Err(e) = self.session.execute(Command::Flush) {
+            tracing::warn!("Failed to flush database: {}", e);
+        }
+    }
+
     /// Handle a single JSON-RPC request.
     fn handle_request(&mut self, request: JsonRpcRequest) -> JsonRpcResponse {
         // Validate JSON-RPC version
@@ -162,6 +375,13 @@ impl McpServer {
 
         // Route to appropriate handler
         match request.method.as_str() {
+            "tools/list" | "tools/call" | "completion/complete" if !self.initialized => {
+                JsonRpcResponse::error(
+                    request.id,
+                    rpc_codes::INVALID_REQUEST,
+                    format!("Server not initialized; send initialize before {}", request.method),
+                )
+            }
             "initialize" => self.handle_initialize(request),
             "initialized" => {
                 // Client acknowledgment - no response needed for notifications
@@ -170,6 +390,7 @@ impl McpServer {
             }
             "tools/list" => self.handle_tools_list(request),
             "tools/call" => self.handle_tools_call(request),
+            "completion/complete" => self.handle_completion(request),
             "ping" => JsonRpcResponse::success(request.id, serde_json::json!({})),
             _ => JsonRpcResponse::error(
                 request.id,
@@ -182,13 +403,20 @@ impl McpServer {
     /// Handle the initialize request.
     fn handle_initialize(&mut self, request: JsonRpcRequest) -> JsonRpcResponse {
         self.initialized = true;
+        let requested = request
+            .params
+            .as_ref()
+            .and_then(|p| p.get("protocolVersion"))
+            .and_then(|v| v.as_str());
+        let version = negotiate_protocol_version(requested);
 
         JsonRpcResponse::success(
             request.id,
             serde_json::json!({
-                "protocolVersion": PROTOCOL_VERSION,
+                "protocolVersion": version,
                 "capabilities": {
-                    "tools": {}
+                    "tools": {},
+                    "completions": {}
                 },
                 "serverInfo": {
                     "name": SERVER_NAME,
@@ -199,17 +427,32 @@ impl McpServer {
     }
 
     /// Handle the tools/list request.
+    ///
+    /// An optional `category` param (e.g. `"kv"`, `"vector"`) lists only the
+    /// tools in that category.
     fn handle_tools_list(&self, request: JsonRpcRequest) -> JsonRpcResponse {
-        let tools: Vec<JsonValue> = self
-            .registry
-            .tools()
-            .iter()
+        let category = request
+            .params
+            .as_ref()
+            .and_then(|p| p.get("category"))
+            .and_then(|v| v.as_str());
+        let listed = match category {
+            Some(category) => self.registry.tools_in_category(category),
+            None => self.registry.tools().iter().collect(),
+        };
+        let tools: Vec<JsonValue> = listed
+            .into_iter()
             .map(|t| {
-                serde_json::json!({
+                let mut tool = serde_json::json!({
                     "name": t.name,
                     "description": t.description,
                     "inputSchema": t.input_schema
-                })
+                });
+                if t.deprecated {
+                    tool["deprecated"] = JsonValue::Bool(true);
+                    tool["replacement"] = serde_json::json!(t.replacement);
+                }
+                tool
             })
             .collect();
 
@@ -253,25 +496,363 @@ impl McpServer {
             }
         };
 
+        let stream_token = params
+            .get("_meta")
+            .and_then(|m| m.get("streamToken"))
+            .cloned();
+
         // Dispatch the tool call
-        match self.registry.dispatch(&mut self.session, &name, arguments) {
+        let started = Instant::now();
+        let dispatched = self.registry.dispatch(&mut self.session, &name, arguments);
+        let elapsed = started.elapsed();
+        match dispatched {
             Ok(result) => {
+                let result = match (stream_token, result) {
+                    (Some(token), JsonValue::Array(items)) => self.stream_items(token, items),
+                    (_, result) => match self.max_result_items {
+                        Some(max_items) => truncate_result(result, max_items),
+                        None => result,
+                    },
+                };
                 // MCP tool responses are wrapped in content array
-                JsonRpcResponse::success(
+                let mut response = serde_json::json!({
+                    "content": [{
+                        "type": "text",
+                        "text": serde_json::to_string(&result).unwrap_or_else(|_| "null".to_string())
+                    }]
+                });
+                if self.report_timing {
+                    response["_meta"] = serde_json::json!({
+                        "elapsed_us": elapsed.as_micros() as u64,
+                    });
+                }
+                JsonRpcResponse::success(request.id, response)
+            }
+            Err(err) => self.error_response(request.id, err),
+        }
+    }
+
+    /// Queue an array result as `notifications/progress` chunks.
+    ///
+    /// Each notification carries the client's `streamToken` as its
+    /// `progressToken`, up to [`STREAM_CHUNK_ITEMS`] items, and the running
+    /// and total item counts. The response itself then holds an empty array.
+    fn stream_items(&mut self, token: JsonValue, items: Vec<JsonValue>) -> JsonValue {
+        let total = items.len();
+        let mut sent = 0;
+        for chunk in items.chunks(STREAM_CHUNK_ITEMS) {
+            sent += chunk.len();
+            self.notifications.push(serde_json::json!({
+                "jsonrpc": "2.0",
+                "method": "notifications/progress",
+                "params": {
+                    "progressToken": token,
+                    "progress": sent,
+                    "total": total,
+                    "items": chunk,
+                },
+            }));
+        }
+        JsonValue::Array(Vec::new())
+    }
+
+    /// Build the response for a failed request, redacting strata details if set.
+    fn error_response(&self, id: Option<JsonValue>, err: McpError) -> JsonRpcResponse {
+        JsonRpcResponse::from_error(id, self.session.client_error(err))
+    }
+
+    /// Handle the completion/complete request.
+    ///
+    /// Completes tool arguments, referenced as `{"type": "ref/tool", "name": ...}`.
+    /// Other reference types have nothing to complete and get no values.
+    fn handle_completion(&mut self, request: JsonRpcRequest) -> JsonRpcResponse {
+        let params = match &request.params {
+            Some(JsonValue::Object(obj)) => obj,
+            _ => {
+                return JsonRpcResponse::error(
+                    request.id,
+                    rpc_codes::INVALID_PARAMS,
+                    "Missing params object".to_string(),
+                )
+            }
+        };
+
+        let reference = params.get("ref");
+        let argument = params.get("argument");
+        let arg_name = argument.and_then(|a| a.get("name")).and_then(|v| v.as_str());
+        let (ref_type, arg_name) = match (
+            reference.and_then(|r| r.get("type")).and_then(|v| v.as_str()),
+            arg_name,
+        ) {
+            (Some(t), Some(a)) => (t, a),
+            _ => {
+                return JsonRpcResponse::error(
                     request.id,
-                    serde_json::json!({
-                        "content": [{
-                            "type": "text",
-                            "text": serde_json::to_string(&result).unwrap_or_else(|_| "null".to_string())
-                        }]
-                    }),
+                    rpc_codes::INVALID_PARAMS,
+                    "Missing 'ref.type' or 'argument.name' in params".to_string(),
                 )
             }
-            Err(err) => JsonRpcResponse::from_error(request.id, err),
+        };
+        let partial = argument
+            .and_then(|a| a.get("value"))
+            .and_then(|v| v.as_str())
+            .unwrap_or("");
+
+        let values = if ref_type == "ref/tool" {
+            let name = reference
+                .and_then(|r| r.get("name"))
+                .and_then(|v| v.as_str())
+                .unwrap_or("");
+            match self
+                .registry
+                .complete(&mut self.session, name, arg_name, partial)
+            {
+                Ok(values) => values,
+                Err(err) => return self.error_response(request.id, err),
+            }
+        } else {
+            Vec::new()
+        };
+
+        let total = values.len();
+        let values: Vec<String> = values.into_iter().take(MAX_COMPLETIONS).collect();
+        JsonRpcResponse::success(
+            request.id,
+            serde_json::json!({
+                "completion": {
+                    "values": values,
+                    "total": total,
+                    "hasMore": total > MAX_COMPLETIONS,
+                }
+            }),
+        )
+    }
+}
+
+/// Pick the protocol version to answer a client's `initialize` with.
+///
+/// Versions are dates, so they order as strings. Returns the client's version
+/// if supported, else the newest supported version older than it, else
+/// [`PROTOCOL_VERSION`].
+fn negotiate_protocol_version(requested: Option<&str>) -> &'static str {
+    match requested {
+        Some(requested) => SUPPORTED_PROTOCOL_VERSIONS
+            .iter()
+            .find(|v| **v <= requested)
+            .copied()
+            .unwrap_or(PROTOCOL_VERSION),
+        None => PROTOCOL_VERSION,
+    }
+}
+
+/// Serialize a response or notification and write it as a single line.
+fn write_response<W: Write>(writer: &Mutex<W>, response: &impl Serialize) -> Result<()> {
+    let response_json = serde_json::to_string(response)?;
+    let mut writer = writer
+        .lock()
+        .map_err(|_| McpError::Internal("response writer lock poisoned".to_string()))?;
+    writeln!(writer, "{}", response_json)?;
+    writer.flush()?;
+    Ok(())
+}
+
+/// Routes input from the reader thread to the request loop.
+///
+/// Answers session-independent input directly and forwards the rest. Runs
+/// until EOF, an I/O error, a shutdown request, or `stopped` is set by the
+/// request loop. Shutdown is forwarded behind the requests already routed,
+/// so those are answered first; input not yet routed is dropped.
+struct Router<'a, W> {
+    work: mpsc::Sender<Routed>,
+    writer: &'a Mutex<W>,
+    stopped: &'a AtomicBool,
+    shutdown: ShutdownHandle,
+    max_bytes: usize,
+}
+
+impl<W: Write> Router<'_, W> {
+    fn run(&self, incoming: Receiver<Incoming>) {
+        loop {
+            // Checked before every read, so a client that never goes quiet
+            // cannot hold off a shutdown
+            if self.shutdown.is_shutdown() {
+                let _ = self.work.send(Routed::Shutdown);
+                break;
+            }
+            if self.stopped.load(Ordering::SeqCst) {
+                break;
+            }
+            let response = match incoming.recv_timeout(POLL_INTERVAL) {
+                Ok(Incoming::Line(line)) => match self.route_line(line) {
+                    Some(response) => response,
+                    None => continue,
+                },
+                Ok(Incoming::TooLarge) => JsonRpcResponse::error(
+                    None,
+                    rpc_codes::INVALID_REQUEST,
+                    format!(
+                        "Request exceeds maximum message size of {} bytes",
+                        self.max_bytes
+                    ),
+                ),
+                Ok(Incoming::Eof) | Err(RecvTimeoutError::Disconnected) => {
+                    let _ = self.work.send(Routed::Eof);
+                    break;
+                }
+                Ok(Incoming::Error(e)) => {
+                    let _ = self.work.send(Routed::Error(e.into()));
+                    break;
+                }
+                Err(RecvTimeoutError::Timeout) => continue,
+            };
+
+            if let Err(e) = write_response(self.writer, &response) {
+                let _ = self.work.send(Routed::Error(e));
+                break;
+            }
+        }
+    }
+
+    /// Answer a line directly, or forward it and return `None`.
+    fn route_line(&self, line: String) -> Option<JsonRpcResponse> {
+        let line = line.trim();
+        if line.is_empty() {
+            return None;
+        }
+        match serde_json::from_str::<JsonRpcRequest>(line) {
+            Ok(request) if request.jsonrpc == "2.0" && request.method == "ping" => {
+                // Keeps the idle timeout from closing a keep-alive-only client
+                let _ = self.work.send(Routed::Activity);
+                Some(JsonRpcResponse::success(request.id, serde_json::json!({})))
+            }
+            Ok(request) => {
+                // If the request loop has exited, the run is ending anyway
+                let _ = self.work.send(Routed::Request(request));
+                None
+            }
+            Err(e) => Some(JsonRpcResponse::error(
+                None,
+                rpc_codes::PARSE_ERROR,
+                format!("Parse error: {}", e),
+            )),
         }
     }
 }
 
+/// Read lines on a background thread and deliver them over a channel.
+fn spawn_reader<R: Read + Send + 'static>(reader: R, max_bytes: usize) -> Receiver<Incoming> {
+    let (tx, rx) = mpsc::channel();
+    std::thread::spawn(move || {
+        let mut reader = std::io::BufReader::new(reader);
+        loop {
+            let msg = match read_bounded_line(&mut reader, max_bytes) {
+                Ok(msg) => msg,
+                Err(e) => Incoming::Error(e),
+            };
+            let done = matches!(msg, Incoming::Eof | Incoming::Error(_));
+            if tx.send(msg).is_err() || done {
+                break;
+            }
+        }
+    });
+    rx
+}
+
+/// Read a single line, discarding it instead of buffering if it exceeds `max_bytes`.
+fn read_bounded_line<R: BufRead>(reader: &mut R, max_bytes: usize) -> std::io::Result<Incoming> {
+    let mut line = Vec::new();
+    let mut too_large = false;
+
+    loop {
+        let available = reader.fill_buf()?;
+        if available.is_empty() {
+            break;
+        }
+
+        let (len, found_newline) = match available.iter().position(|&b| b == b'\n') {
+            Some(i) => (i + 1, true),
+            None => (available.len(), false),
+        };
+        if !too_large {
+            if line.len() + len > max_bytes {
+                too_large = true;
+                line = Vec::new();
+            } else {
+                line.extend_from_slice(&available[..len]);
+            }
+        }
+        reader.consume(len);
+
+        if found_newline {
+            break;
+        }
+    }
+
+    if too_large {
+        Ok(Incoming::TooLarge)
+    } else if line.is_empty() {
+        Ok(Incoming::Eof)
+    } else {
+        Ok(Incoming::Line(String::from_utf8_lossy(&line).into_owned()))
+    }
+}
+
+/// Listen for SIGINT/SIGTERM on a background thread and request shutdown.
+fn spawn_signal_listener(shutdown: ShutdownHandle) {
+    let spawned = std::thread::Builder::new()
+        .name("strata-mcp-signals".to_string())
+        .spawn(move || {
+            let runtime = match tokio::runtime::Builder::new_current_thread()
+                .enable_all()
+                .build()
+            {
+                Ok(rt) => rt,
+                Err(e) => {
+                    tracing::warn!("Failed to start signal listener: {}", e);
+                    return;
+                }
+            };
+            runtime.block_on(wait_for_signal());
+            tracing::info!("Received shutdown signal");
+            shutdown.shutdown();
+        });
+    if let Err(e) = spawned {
+        tracing::warn!("Failed to spawn signal listener: {}", e);
+    }
+}
+
+/// Wait until SIGINT or (on Unix) SIGTERM is received.
+async fn wait_for_signal() {
+    let ctrl_c = async {
+        if let Err(e) = tokio::signal::ctrl_c().await {
+            tracing::warn!("Failed to listen for SIGINT: {}", e);
+            std::future::pending::<()>().await;
+        }
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        use tokio::signal::unix::{signal, SignalKind};
+        match signal(SignalKind::terminate()) {
+            Ok(mut sig) => {
+                sig.recv().await;
+            }
+            Err(e) => {
+                tracing::warn!("Failed to listen for SIGTERM: {}", e);
+                std::future::pending::<()>().await;
+            }
+        }
+    };
+
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = ctrl_c => {}
+        _ = terminate => {}
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -284,6 +865,33 @@ mod tests {
         assert!(!json.contains("\"error\""));
     }
 
+    #[test]
+    fn test_read_bounded_line() {
+        let mut reader = std::io::Cursor::new(b"short\nthis line is too long\nok\n".to_vec());
+        assert!(matches!(
+            read_bounded_line(&mut reader, 10).unwrap(),
+            Incoming::Line(l) if l == "short\n"
+        ));
+        assert!(matches!(read_bounded_line(&mut reader, 10).unwrap(), Incoming::TooLarge));
+        assert!(matches!(
+            read_bounded_line(&mut reader, 10).unwrap(),
+            Incoming::Line(l) if l == "ok\n"
+        ));
+        assert!(matches!(read_bounded_line(&mut reader, 10).unwrap(), Incoming::Eof));
+    }
+
+    #[test]
+    fn test_negotiate_protocol_version() {
+        assert_eq!(negotiate_protocol_version(Some("2025-03-26")), "2025-03-26");
+        assert_eq!(negotiate_protocol_version(Some("2024-11-05")), "2024-11-05");
+        // Newer than anything supported: answer with our newest older version
+        assert_eq!(negotiate_protocol_version(Some("2099-01-01")), "2025-06-18");
+        assert_eq!(negotiate_protocol_version(Some("2025-04-01")), "2025-03-26");
+        // Older than anything supported, or missing: fall back to the preferred version
+        assert_eq!(negotiate_protocol_version(Some("2024-01-01")), PROTOCOL_VERSION);
+        assert_eq!(negotiate_protocol_version(None), PROTOCOL_VERSION);
+    }
+
     #[test]
     fn test_json_rpc_response_error() {
         let response = JsonRpcResponse::error(Some(JsonValue::Number(1.into())), -32600, "Invalid".to_string());
@@ -291,4 +899,72 @@ mod tests {
         assert!(json.contains("\"error\""));
         assert!(!json.contains("\"result\""));
     }
+
+    #[test]
+    fn test_from_error_marks_conflicts_retryable() {
+        for code in ["VERSION_CONFLICT", "TXN_CONFLICT", "CONFLICT"] {
+            let err = McpError::Strata {
+                code: code.to_string(),
+                message: "conflict".to_string(),
+            };
+            let response = JsonRpcResponse::from_error(None, err);
+            let data = response.error.and_then(|e| e.data).unwrap();
+            assert_eq!(data["retryable"], serde_json::json!(true), "{}", code);
+        }
+    }
+
+    #[test]
+    fn test_from_error_conflicts_use_conflict_code() {
+        for code in ["VERSION_CONFLICT", "TXN_CONFLICT", "CONFLICT"] {
+            let err = McpError::Strata {
+                code: code.to_string(),
+                message: "conflict".to_string(),
+            };
+            let response = JsonRpcResponse::from_error(None, err);
+            assert_eq!(response.error.unwrap().code, rpc_codes::CONFLICT, "{}", code);
+        }
+    }
+
+    #[test]
+    fn test_redacted_error_keeps_code() {
+        let err = McpError::Strata {
+            code: "TXN_CONFLICT".to_string(),
+            message: "conflict writing /var/lib/strata/wal/000042.log".to_string(),
+        };
+        let response = JsonRpcResponse::from_error(None, err.redacted());
+        let error = response.error.unwrap();
+        assert!(!error.message.contains("/var/lib"));
+        assert!(error.message.contains("TXN_CONFLICT"));
+        assert_eq!(error.code, rpc_codes::CONFLICT);
+        assert_eq!(error.data.unwrap()["retryable"], serde_json::json!(true));
+    }
+
+    #[test]
+    fn test_from_error_validation_not_retryable() {
+        let errors = [
+            McpError::InvalidArg {
+                name: "key".to_string(),
+                reason: "bad".to_string(),
+            },
+            McpError::MissingArg("key".to_string()),
+            McpError::Strata {
+                code: "WRONG_TYPE".to_string(),
+                message: "wrong type".to_string(),
+            },
+            McpError::Strata {
+                code: "INVALID_KEY".to_string(),
+                message: "invalid key".to_string(),
+            },
+            McpError::Strata {
+                code: "TXN_TIMEOUT".to_string(),
+                message: "transaction timed out and was rolled back".to_string(),
+            },
+            McpError::from(std::io::Error::from(std::io::ErrorKind::NotFound)),
+        ];
+        for err in errors {
+            let response = JsonRpcResponse::from_error(None, err);
+            let data = response.error.and_then(|e| e.data).unwrap();
+            assert_eq!(data["retryable"], serde_json::json!(false));
+        }
+    }
 }
@@ -2,12 +2,52 @@
 //!
 //! Wraps a stratadb Session with branch/space context, similar to the CLI's SessionState.
 
+use std::collections::HashMap;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
 use stratadb::{
-    AccessMode, BranchDiffResult, Command, ForkInfo, MergeInfo, MergeStrategy, Output, Session,
-    Strata,
+    AccessMode, BranchDiffResult, BranchId, Command, ForkInfo, MergeInfo, MergeStrategy, Output,
+    Session, Strata, TxnOptions, Value,
 };
 
+use crate::convert::ConvertOptions;
 use crate::error::{McpError, Result};
+use crate::tools::cache::ResultCache;
+
+/// Prefix of spaces that hold the server's own records.
+///
+/// Reserved spaces are left out of space listings, and tool arguments that
+/// name a space cannot name one.
+pub const RESERVED_SPACE_PREFIX: &str = "_mcp_";
+
+/// Returns `true` if `space` is reserved for the server's own records.
+pub fn is_reserved_space(space: &str) -> bool {
+    space.starts_with(RESERVED_SPACE_PREFIX)
+}
+
+/// Options the database was opened with, as reported by `strata_db_config`.
+#[derive(Debug, Clone, Default)]
+pub struct RuntimeConfig {
+    /// Whether the database is an in-memory cache database
+    pub cache: bool,
+    /// Database directory, if opened from disk
+    pub db_path: Option<String>,
+    /// Whether automatic text embedding is enabled
+    pub auto_embed: bool,
+    /// Hide the database path from tool output
+    pub redact_path: bool,
+}
+
+/// How to settle a merge conflict on one key.
+#[derive(Debug, Clone)]
+pub enum MergeResolution {
+    /// Keep the source branch's value
+    Source,
+    /// Keep the current branch's value
+    Target,
+    /// Write an explicit value
+    Value(Value),
+}
 
 /// MCP session state.
 ///
@@ -25,6 +65,26 @@ pub struct McpSession {
     space: String,
     /// Whether a transaction is active
     in_transaction: bool,
+    /// Write commands buffered in the active transaction
+    txn_operations: u64,
+    /// Whether the active transaction was begun read-only
+    txn_read_only: bool,
+    /// When the active transaction is rolled back if still open
+    txn_deadline: Option<Instant>,
+    /// Branch the active transaction was begun on
+    txn_branch: Option<String>,
+    /// Options for converting outputs to JSON
+    convert_options: ConvertOptions,
+    /// Options the database was opened with
+    runtime_config: RuntimeConfig,
+    /// Inference model endpoint configured in this session
+    model_endpoint: Option<String>,
+    /// Whether branch and space names in tool arguments are lowercased
+    fold_name_case: bool,
+    /// Cached results of read tools, if enabled
+    result_cache: Option<ResultCache>,
+    /// Whether strata error details are hidden from clients
+    redact_errors: bool,
 }
 
 impl McpSession {
@@ -37,6 +97,16 @@ impl McpSession {
             branch: "default".to_string(),
             space: "default".to_string(),
             in_transaction: false,
+            txn_operations: 0,
+            txn_read_only: false,
+            txn_deadline: None,
+            txn_branch: None,
+            convert_options: ConvertOptions::default(),
+            runtime_config: RuntimeConfig::default(),
+            model_endpoint: None,
+            fold_name_case: false,
+            result_cache: None,
+            redact_errors: false,
         }
     }
 
@@ -46,7 +116,7 @@ impl McpSession {
     }
 
     /// Reject write operations when the database is read-only.
-    fn check_write_access(&self, operation: &str) -> Result<()> {
+    pub(crate) fn check_write_access(&self, operation: &str) -> Result<()> {
         if self.is_read_only() {
             return Err(McpError::Strata {
                 code: "ACCESS_DENIED".to_string(),
@@ -71,13 +141,106 @@ impl McpSession {
 
     /// Whether a transaction is currently active.
     ///
-    /// Exposed for library consumers; the MCP server itself tracks transactions
-    /// via the `execute()` method's output matching.
-    #[allow(dead_code)]
+    /// Tracked via the `execute()` method's output matching.
     pub fn in_transaction(&self) -> bool {
         self.in_transaction
     }
 
+    /// Number of write commands executed in the active transaction.
+    pub fn txn_operations(&self) -> u64 {
+        self.txn_operations
+    }
+
+    /// Whether the active transaction is read-only.
+    pub fn txn_read_only(&self) -> bool {
+        self.txn_read_only
+    }
+
+    /// Branch the active transaction was begun on, if one is active.
+    pub fn txn_branch(&self) -> Option<&str> {
+        self.txn_branch.as_deref()
+    }
+
+    /// Roll back the active transaction if it is still open after `timeout`.
+    ///
+    /// Checked on the next `execute()`; there is no background timer.
+    pub fn set_txn_timeout(&mut self, timeout: Duration) {
+        if self.in_transaction {
+            self.txn_deadline = Some(Instant::now() + timeout);
+        }
+    }
+
+    /// Get the options used to convert outputs to JSON.
+    pub fn convert_options(&self) -> &ConvertOptions {
+        &self.convert_options
+    }
+
+    /// Set the options used to convert outputs to JSON.
+    pub fn set_convert_options(&mut self, opts: ConvertOptions) {
+        self.convert_options = opts;
+    }
+
+    /// Get the options the database was opened with.
+    pub fn runtime_config(&self) -> &RuntimeConfig {
+        &self.runtime_config
+    }
+
+    /// Record the options the database was opened with.
+    pub fn set_runtime_config(&mut self, config: RuntimeConfig) {
+        self.runtime_config = config;
+    }
+
+    /// Get the inference model endpoint configured in this session, if any.
+    pub fn model_endpoint(&self) -> Option<&str> {
+        self.model_endpoint.as_deref()
+    }
+
+    /// Record the inference model endpoint.
+    pub fn set_model_endpoint(&mut self, endpoint: String) {
+        self.model_endpoint = Some(endpoint);
+    }
+
+    /// Returns `true` if branch and space names in tool arguments are lowercased.
+    pub fn fold_name_case(&self) -> bool {
+        self.fold_name_case
+    }
+
+    /// Set whether branch and space names in tool arguments are lowercased.
+    pub fn set_fold_name_case(&mut self, fold: bool) {
+        self.fold_name_case = fold;
+    }
+
+    /// Set whether strata error details are hidden from clients.
+    pub fn set_redact_errors(&mut self, redact: bool) {
+        self.redact_errors = redact;
+    }
+
+    /// Prepare an error for a client, redacting strata details if enabled.
+    ///
+    /// Used for errors in responses and for per-operation errors that batch
+    /// tools report inside a successful result.
+    pub fn client_error(&self, err: McpError) -> McpError {
+        if !self.redact_errors {
+            return err;
+        }
+        if let McpError::Strata { code, message } = &err {
+            tracing::warn!("Redacted strata error {}: {}", code, message);
+        }
+        err.redacted()
+    }
+
+    /// Cache results of read tools for `ttl`, keeping at most `capacity`.
+    ///
+    /// Any non-cacheable tool call clears the cache. Disabled by default.
+    pub fn enable_result_cache(&mut self, ttl: Duration, capacity: usize) {
+        self.result_cache = Some(ResultCache::new(ttl, capacity));
+    }
+
+    /// Get the result cache, if enabled.
+    pub(crate) fn result_cache_mut(&mut self) -> Option<&mut ResultCache> {
+        self.result_cache.as_mut()
+    }
+
     /// Switch to a different branch.
     ///
     /// Verifies the branch exists before switching.
@@ -112,21 +275,131 @@ impl McpSession {
     /// Rejects write commands when the database is read-only.
     /// Updates transaction state tracking based on output.
     pub fn execute(&mut self, cmd: Command) -> Result<Output> {
-        if cmd.is_write() {
+        let expired = self.txn_deadline.is_some_and(|d| Instant::now() >= d);
+        if self.in_transaction && expired && !matches!(cmd, Command::TxnRollback) {
+            if let Err(e) = self.session.execute(Command::TxnRollback) {
+                tracing::warn!("Failed to roll back timed out transaction: {}", e);
+            }
+            self.end_transaction();
+            return Err(McpError::Strata {
+                code: "TXN_TIMEOUT".to_string(),
+                message: format!(
+                    "transaction timed out and was rolled back; {} was not applied",
+                    cmd.name()
+                ),
+            });
+        }
+
+        let is_write = cmd.is_write();
+        if is_write {
             self.check_write_access(cmd.name())?;
         }
-        let output = self.session.execute(cmd)?;
+        let (begin_read_only, begin_branch) = match &cmd {
+            Command::TxnBegin { branch, options } => (
+                options.as_ref().is_some_and(|o| o.read_only),
+                branch
+                    .as_ref()
+                    .map_or_else(|| self.branch.clone(), |b| b.as_str().to_string()),
+            ),
+            _ => (false, String::new()),
+        };
+        let mut output = self.session.execute(cmd)?;
+        if let Output::SpaceList(spaces) = &mut output {
+            spaces.retain(|space| !is_reserved_space(space));
+        }
 
         // Track transaction state changes
         match &output {
-            Output::TxnBegun => self.in_transaction = true,
-            Output::TxnCommitted { .. } | Output::TxnAborted => self.in_transaction = false,
+            Output::TxnBegun => {
+                self.in_transaction = true;
+                self.txn_operations = 0;
+                self.txn_read_only = begin_read_only;
+                self.txn_branch = Some(begin_branch);
+            }
+            Output::TxnCommitted { .. } | Output::TxnAborted => self.end_transaction(),
+            _ if self.in_transaction && is_write => self.txn_operations += 1,
             _ => {}
         }
 
         Ok(output)
     }
 
+    /// Clear transaction tracking after a commit or rollback.
+    fn end_transaction(&mut self) {
+        self.in_transaction = false;
+        self.txn_operations = 0;
+        self.txn_read_only = false;
+        self.txn_deadline = None;
+        self.txn_branch = None;
+    }
+
+    /// Run `f` atomically.
+    ///
+    /// Wraps `f` in a transaction that is committed on success and rolled back
+    /// on error. If a transaction is already active, `f` runs inside it.
+    pub fn atomically<T>(&mut self, f: impl FnOnce(&mut Self) -> Result<T>) -> Result<T> {
+        if self.in_transaction {
+            return f(self);
+        }
+
+        let cmd = Command::TxnBegin {
+            branch: self.branch_id(),
+            options: Some(TxnOptions { read_only: false }),
+        };
+        self.execute(cmd)?;
+
+        match f(self) {
+            Ok(value) => {
+                self.execute(Command::TxnCommit)?;
+                Ok(value)
+            }
+            Err(err) => {
+                if let Err(rollback_err) = self.execute(Command::TxnRollback) {
+                    tracing::warn!("Failed to roll back transaction: {}", rollback_err);
+                }
+                Err(err)
+            }
+        }
+    }
+
+    /// Run `f` atomically in a transaction begun on `branch`.
+    ///
+    /// The session is switched to `branch` for the duration, so writes there
+    /// are covered by the transaction, and switched back afterwards, even on
+    /// error. Inside an active transaction `f` simply runs in it; callers
+    /// must check that `branch` is the transaction's branch.
+    pub fn atomically_on<T>(
+        &mut self,
+        branch: &str,
+        f: impl FnOnce(&mut Self) -> Result<T>,
+    ) -> Result<T> {
+        if self.in_transaction || branch == self.branch {
+            return self.atomically(f);
+        }
+        let previous = std::mem::replace(&mut self.branch, branch.to_string());
+        let result = self.atomically(f);
+        self.branch = previous;
+        result
+    }
+
+    /// List the ids of all branches in the database.
+    pub fn branch_ids(&mut self) -> Result<Vec<String>> {
+        let cmd = Command::BranchList {
+            state: None,
+            limit: None,
+            offset: None,
+        };
+        match self.execute(cmd)? {
+            Output::BranchInfoList(branches) => Ok(branches
+                .into_iter()
+                .map(|bi| bi.info.id.as_str().to_string())
+                .collect()),
+            _ => Err(McpError::Internal(
+                "Unexpected output for BranchList".to_string(),
+            )),
+        }
+    }
+
     /// Fork the current branch to a new branch.
     pub fn fork_branch(&self, destination: &str) -> Result<ForkInfo> {
         self.check_write_access("BranchFork")?;
@@ -153,6 +426,225 @@ impl McpSession {
             .map_err(McpError::from)
     }
 
+    /// Merge a source branch into the current branch, settling listed conflicts.
+    ///
+    /// Conflicts are found first by merging into a scratch fork of the current
+    /// branch. With `MergeStrategy::Strict`, any conflict without a resolution
+    /// fails the merge before the current branch is touched. Otherwise the
+    /// merge runs last-writer-wins and resolved keys are then overwritten with
+    /// the chosen value in one transaction. Only KV, state, and JSON conflicts
+    /// can be resolved, and only on keys that conflict once, since resolutions
+    /// are keyed by key alone.
+    pub fn merge_branch_resolved(
+        &mut self,
+        source: &str,
+        strategy: MergeStrategy,
+        resolutions: &HashMap<String, MergeResolution>,
+    ) -> Result<MergeInfo> {
+        self.check_write_access("BranchMerge")?;
+        let check = self.trial_merge(source)?;
+
+        for key in resolutions.keys() {
+            let matches = check
+                .conflicts
+                .iter()
+                .filter(|c| c.key.as_str() == key)
+                .count();
+            if matches == 0 {
+                return Err(McpError::InvalidArg {
+                    name: format!("resolutions.{}", key),
+                    reason: "No merge conflict on this key".to_string(),
+                });
+            }
+            if matches > 1 {
+                return Err(McpError::InvalidArg {
+                    name: format!("resolutions.{}", key),
+                    reason: "Key conflicts in more than one space or primitive; \
+                             resolve it by hand after merging"
+                        .to_string(),
+                });
+            }
+        }
+        if matches!(strategy, MergeStrategy::Strict) {
+            let unresolved: Vec<String> = check
+                .conflicts
+                .iter()
+                .filter(|c| !resolutions.contains_key(c.key.as_str()))
+                .map(|c| c.key.to_string())
+                .collect();
+            if !unresolved.is_empty() {
+                return Err(McpError::Strata {
+                    code: "CONFLICT".to_string(),
+                    message: format!("unresolved merge conflicts: {}", unresolved.join(", ")),
+                });
+            }
+        }
+
+        // Pick values before merging, while the current branch still holds its own
+        let target = self.branch.clone();
+        let mut writes = Vec::new();
+        for c in &check.conflicts {
+            let resolution = match resolutions.get(c.key.as_str()) {
+                Some(resolution) => resolution,
+                None => continue,
+            };
+            let primitive = format!("{:?}", c.primitive).to_lowercase();
+            let space = c.space.to_string();
+            let key = c.key.to_string();
+            if !["kv", "state", "json"].contains(&primitive.as_str()) {
+                return Err(McpError::InvalidArg {
+                    name: format!("resolutions.{}", key),
+                    reason: format!("Conflicts on {} entries cannot be resolved", primitive),
+                });
+            }
+            let value = match resolution {
+                MergeResolution::Source => self.read_entry(source, &primitive, &space, &key)?,
+                MergeResolution::Target => self.read_entry(&target, &primitive, &space, &key)?,
+                MergeResolution::Value(v) => Some(v.clone()),
+            };
+            writes.push((primitive, space, key, value));
+        }
+
+        let info = self
+            .strata
+            .branches()
+            .merge(source, &self.branch, MergeStrategy::LastWriterWins)
+            .map_err(McpError::from)?;
+        self.atomically(|session| {
+            for (primitive, space, key, value) in writes {
+                session.write_entry(&primitive, space, key, value)?;
+            }
+            Ok(())
+        })
+        .map_err(|e| McpError::Strata {
+            code: "PARTIAL_MERGE".to_string(),
+            message: format!(
+                "Merged '{}' but its conflict resolutions were not applied: {}",
+                source, e
+            ),
+        })?;
+        Ok(info)
+    }
+
+    /// Merge `source` into a throwaway fork of the current branch to learn
+    /// which keys conflict.
+    fn trial_merge(&mut self, source: &str) -> Result<MergeInfo> {
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_nanos())
+            .unwrap_or(0);
+        let scratch = format!("{}-merge-check-{}", self.branch, nanos);
+        self.strata
+            .branches()
+            .fork(&self.branch, &scratch)
+            .map_err(McpError::from)?;
+
+        let result = self
+            .strata
+            .branches()
+            .merge(source, &scratch, MergeStrategy::LastWriterWins)
+            .map_err(McpError::from);
+        if let Err(e) = self.execute(Command::BranchDelete {
+            branch: BranchId::from(scratch),
+        }) {
+            tracing::warn!("Failed to delete merge check branch: {}", e);
+        }
+        result
+    }
+
+    /// Read the current value of a KV, state, or JSON entry on a branch.
+    pub(crate) fn read_entry(
+        &mut self,
+        branch: &str,
+        primitive: &str,
+        space: &str,
+        key: &str,
+    ) -> Result<Option<Value>> {
+        let (branch, space, key) = (
+            Some(BranchId::from(branch.to_string())),
+            Some(space.to_string()),
+            key.to_string(),
+        );
+        let cmd = match primitive {
+            "kv" => Command::KvGet {
+                branch,
+                space,
+                key,
+                as_of: None,
+            },
+            "state" => Command::StateGet {
+                branch,
+                space,
+                cell: key,
+                as_of: None,
+            },
+            "json" => Command::JsonGet {
+                branch,
+                space,
+                key,
+                path: "$".to_string(),
+                as_of: None,
+            },
+            other => {
+                return Err(McpError::Internal(format!("Cannot read {} entries", other)))
+            }
+        };
+        match self.execute(cmd)? {
+            Output::MaybeVersioned(opt) => Ok(opt.map(|vv| vv.value)),
+            Output::Maybe(opt) => Ok(opt),
+            _ => Ok(None),
+        }
+    }
+
+    /// Write or delete a KV, state, or JSON entry on the current branch.
+    pub(crate) fn write_entry(
+        &mut self,
+        primitive: &str,
+        space: String,
+        key: String,
+        value: Option<Value>,
+    ) -> Result<()> {
+        let (branch, space) = (self.branch_id(), Some(space));
+        let cmd = match (primitive, value) {
+            ("kv", Some(value)) => Command::KvPut {
+                branch,
+                space,
+                key,
+                value,
+            },
+            ("kv", None) => Command::KvDelete { branch, space, key },
+            ("state", Some(value)) => Command::StateSet {
+                branch,
+                space,
+                cell: key,
+                value,
+            },
+            ("state", None) => Command::StateDelete {
+                branch,
+                space,
+                cell: key,
+            },
+            ("json", Some(value)) => Command::JsonSet {
+                branch,
+                space,
+                key,
+                path: "$".to_string(),
+                value,
+            },
+            ("json", None) => Command::JsonDelete {
+                branch,
+                space,
+                key,
+                path: "$".to_string(),
+            },
+            (other, _) => {
+                return Err(McpError::Internal(format!("Cannot write {} entries", other)))
+            }
+        };
+        self.execute(cmd)?;
+        Ok(())
+    }
+
     /// Get the current branch ID for use in commands.
     pub fn branch_id(&self) -> Option<stratadb::BranchId> {
         Some(self.branch().to_string().into())
@@ -0,0 +1,453 @@
+//! Batch execution tools.
+//!
+//! Tools: strata_batch, strata_with_transaction, strata_event_replay
+
+use serde_json::{Map, Value as JsonValue};
+use stratadb::{Command, Output, TxnOptions, VersionedValue};
+
+use crate::convert::{get_optional_bool, get_optional_string, get_optional_u64, value_to_json};
+use crate::error::{McpError, Result};
+use crate::session::McpSession;
+use crate::tools::{ToolDef, ToolRegistry};
+
+/// Get all batch tool definitions.
+pub fn tools() -> Vec<ToolDef> {
+    vec![
+        ToolDef::new(
+            "strata_batch",
+            "Run multiple tool calls in order in a single request. Each operation has a tool \
+             name and arguments. Returns an array of per-operation results or errors. \
+             Set stop_on_error to stop after the first failure. Set atomic to run the whole \
+             batch in a transaction that is rolled back if any operation fails.",
+            serde_json::json!({
+                "type": "object",
+                "properties": {
+                    "operations": operations_schema(),
+                    "stop_on_error": {"type": "boolean"},
+                    "atomic": {"type": "boolean"}
+                },
+                "required": ["operations"],
+                "additionalProperties": false
+            }),
+        ),
+        ToolDef::new(
+            "strata_with_transaction",
+            "Run tool calls inside a transaction that is managed for you: it begins, runs each \
+             operation in order, commits if all succeed, and rolls back on the first error. \
+             Returns committed=true with the results, or rolled_back=true with the error and \
+             the results of the operations that ran. Set read_only for a read-only transaction.",
+            serde_json::json!({
+                "type": "object",
+                "properties": {
+                    "operations": operations_schema(),
+                    "read_only": {"type": "boolean"}
+                },
+                "required": ["operations"],
+                "additionalProperties": false
+            }),
+        ),
+        ToolDef::new(
+            "strata_event_replay",
+            "Re-run the commands recorded in the event log against the current branch and \
+             space, in one transaction that is rolled back if any command fails. An event \
+             describes a command when its payload is {tool, arguments}, as in strata_batch; \
+             other events are skipped. Limit the replay with from_sequence and to_sequence \
+             (inclusive) and event_type. Requires confirm=true because it writes. Returns the \
+             replayed and skipped counts with per-command results.",
+            serde_json::json!({
+                "type": "object",
+                "properties": {
+                    "from_sequence": {"type": "integer"},
+                    "to_sequence": {"type": "integer"},
+                    "event_type": {"type": "string"},
+                    "confirm": {"type": "boolean"}
+                },
+                "required": ["confirm"],
+                "additionalProperties": false
+            }),
+        ),
+    ]
+}
+
+/// Schema for an array of `{tool, arguments}` operations.
+fn operations_schema() -> JsonValue {
+    serde_json::json!({
+        "type": "array",
+        "items": {
+            "type": "object",
+            "properties": {
+                "tool": {"type": "string", "description": "Tool name to call"},
+                "arguments": {"type": "object", "description": "Tool arguments"}
+            },
+            "required": ["tool"]
+        }
+    })
+}
+
+/// A single parsed batch operation.
+struct BatchOp {
+    tool: String,
+    arguments: Map<String, JsonValue>,
+}
+
+/// Parse the operations array, validating every entry before anything runs.
+fn parse_operations(
+    registry: &ToolRegistry,
+    args: &Map<String, JsonValue>,
+    atomic: bool,
+) -> Result<Vec<BatchOp>> {
+    let arr = args
+        .get("operations")
+        .and_then(|v| v.as_array())
+        .ok_or_else(|| McpError::MissingArg("operations".to_string()))?;
+
+    let mut ops = Vec::new();
+    for (i, item) in arr.iter().enumerate() {
+        let obj = item.as_object().ok_or_else(|| McpError::InvalidArg {
+            name: format!("operations[{}]", i),
+            reason: "Expected object with tool and optional arguments".to_string(),
+        })?;
+
+        let tool = obj
+            .get("tool")
+            .and_then(|v| v.as_str())
+            .ok_or_else(|| McpError::InvalidArg {
+                name: format!("operations[{}].tool", i),
+                reason: "Missing or invalid tool".to_string(),
+            })?
+            .to_string();
+
+        let unprefixed = registry.unprefixed(&tool);
+        check_nested_tool(unprefixed, atomic, || format!("operations[{}].tool", i))?;
+
+        let arguments = match obj.get("arguments") {
+            Some(JsonValue::Object(m)) => m.clone(),
+            Some(JsonValue::Null) | None => Map::new(),
+            _ => {
+                return Err(McpError::InvalidArg {
+                    name: format!("operations[{}].arguments", i),
+                    reason: "Expected object".to_string(),
+                })
+            }
+        };
+
+        ops.push(BatchOp { tool, arguments });
+    }
+
+    Ok(ops)
+}
+
+/// Reject tools that cannot run inside a batch.
+fn check_nested_tool(tool: &str, atomic: bool, name: impl Fn() -> String) -> Result<()> {
+    if matches!(
+        tool,
+        "strata_batch" | "strata_with_transaction" | "strata_event_replay"
+    ) {
+        return Err(McpError::InvalidArg {
+            name: name(),
+            reason: format!("Nested {} calls are not supported", tool),
+        });
+    }
+    if atomic && tool.starts_with("strata_txn_") {
+        return Err(McpError::InvalidArg {
+            name: name(),
+            reason: "Transaction tools cannot be used in an atomic batch".to_string(),
+        });
+    }
+    Ok(())
+}
+
+/// Dispatch a batch tool call.
+pub fn dispatch(
+    registry: &ToolRegistry,
+    session: &mut McpSession,
+    name: &str,
+    args: Map<String, JsonValue>,
+) -> Result<JsonValue> {
+    match name {
+        "strata_batch" => {
+            let atomic = get_optional_bool(&args, "atomic").unwrap_or(false);
+            let stop_on_error = get_optional_bool(&args, "stop_on_error").unwrap_or(false);
+            let ops = parse_operations(registry, &args, atomic)?;
+
+            if atomic {
+                run_atomic(registry, session, ops)
+            } else {
+                Ok(run_sequential(registry, session, ops, stop_on_error))
+            }
+        }
+
+        "strata_with_transaction" => {
+            let read_only = get_optional_bool(&args, "read_only").unwrap_or(false);
+            let ops = parse_operations(registry, &args, true)?;
+            run_in_transaction(registry, session, ops, read_only)
+        }
+
+        "strata_event_replay" => {
+            if !get_optional_bool(&args, "confirm").unwrap_or(false) {
+                return Err(McpError::InvalidArg {
+                    name: "confirm".to_string(),
+                    reason: "Replaying events writes to the current branch; pass confirm=true"
+                        .to_string(),
+                });
+            }
+            session.check_write_access("EventReplay")?;
+            let from = get_optional_u64(&args, "from_sequence").unwrap_or(0);
+            let to = get_optional_u64(&args, "to_sequence").unwrap_or(u64::MAX);
+            if from > to {
+                return Err(McpError::InvalidArg {
+                    name: "to_sequence".to_string(),
+                    reason: "Must not be less than from_sequence".to_string(),
+                });
+            }
+            let event_type = get_optional_string(&args, "event_type");
+            replay_events(registry, session, from, to, event_type)
+        }
+
+        _ => Err(McpError::UnknownTool(name.to_string())),
+    }
+}
+
+/// Run operations in order, recording a result or error for each.
+fn run_sequential(
+    registry: &ToolRegistry,
+    session: &mut McpSession,
+    ops: Vec<BatchOp>,
+    stop_on_error: bool,
+) -> JsonValue {
+    let mut results = Vec::new();
+    for op in ops {
+        match registry.dispatch(session, &op.tool, op.arguments) {
+            Ok(result) => results.push(serde_json::json!({
+                "tool": op.tool,
+                "result": result,
+            })),
+            Err(err) => {
+                let err = session.client_error(err);
+                results.push(serde_json::json!({
+                    "tool": op.tool,
+                    "error": {
+                        "code": err.rpc_code(),
+                        "message": err.to_string(),
+                    },
+                }));
+                if stop_on_error {
+                    break;
+                }
+            }
+        }
+    }
+    JsonValue::Array(results)
+}
+
+/// Run operations inside a transaction, rolling back on the first error.
+fn run_atomic(
+    registry: &ToolRegistry,
+    session: &mut McpSession,
+    ops: Vec<BatchOp>,
+) -> Result<JsonValue> {
+    if session.in_transaction() {
+        return Err(McpError::InvalidArg {
+            name: "atomic".to_string(),
+            reason: "An atomic batch cannot run inside an active transaction".to_string(),
+        });
+    }
+
+    let cmd = Command::TxnBegin {
+        branch: session.branch_id(),
+        options: Some(TxnOptions { read_only: false }),
+    };
+    session.execute(cmd)?;
+
+    let mut results = Vec::new();
+    for op in ops {
+        match registry.dispatch(session, &op.tool, op.arguments) {
+            Ok(result) => results.push(serde_json::json!({
+                "tool": op.tool,
+                "result": result,
+            })),
+            Err(err) => {
+                if let Err(rollback_err) = session.execute(Command::TxnRollback) {
+                    tracing::warn!("Failed to roll back atomic batch: {}", rollback_err);
+                }
+                return Err(err);
+            }
+        }
+    }
+
+    session.execute(Command::TxnCommit)?;
+    Ok(JsonValue::Array(results))
+}
+
+/// Run operations in a transaction, reporting a rollback as a result.
+fn run_in_transaction(
+    registry: &ToolRegistry,
+    session: &mut McpSession,
+    ops: Vec<BatchOp>,
+    read_only: bool,
+) -> Result<JsonValue> {
+    if session.in_transaction() {
+        return Err(McpError::InvalidArg {
+            name: "operations".to_string(),
+            reason: "A transaction is already active; commit or roll it back first".to_string(),
+        });
+    }
+
+    let cmd = Command::TxnBegin {
+        branch: session.branch_id(),
+        options: Some(TxnOptions { read_only }),
+    };
+    session.execute(cmd)?;
+
+    let mut results = Vec::new();
+    for (i, op) in ops.into_iter().enumerate() {
+        match registry.dispatch(session, &op.tool, op.arguments) {
+            Ok(result) => results.push(serde_json::json!({
+                "tool": op.tool,
+                "result": result,
+            })),
+            Err(err) => {
+                if let Err(rollback_err) = session.execute(Command::TxnRollback) {
+                    tracing::warn!("Failed to roll back transaction: {}", rollback_err);
+                }
+                let err = session.client_error(err);
+                return Ok(serde_json::json!({
+                    "rolled_back": true,
+                    "results": results,
+                    "error": {
+                        "index": i,
+                        "tool": op.tool,
+                        "code": err.rpc_code(),
+                        "message": err.to_string(),
+                    },
+                }));
+            }
+        }
+    }
+
+    let version = match session.execute(Command::TxnCommit)? {
+        Output::TxnCommitted { version } => serde_json::json!(version),
+        _ => JsonValue::Null,
+    };
+    Ok(serde_json::json!({
+        "committed": true,
+        "version": version,
+        "results": results,
+    }))
+}
+
+/// Read events in `from..=to`, optionally of one type, in sequence order.
+fn read_events(
+    session: &mut McpSession,
+    from: u64,
+    to: u64,
+    event_type: Option<String>,
+) -> Result<Vec<(u64, VersionedValue)>> {
+    let mut events: Vec<(u64, VersionedValue)> = match event_type {
+        // Typed reads carry no sequence numbers, so this relies on an event's
+        // version being its sequence, as strata_event_append reports it
+        Some(event_type) => match session.execute(Command::EventGetByType {
+            branch: session.branch_id(),
+            space: session.space_id(),
+            event_type,
+            limit: None,
+            after_sequence: from.checked_sub(1),
+            as_of: None,
+        })? {
+            Output::VersionedValues(events) => events
+                .into_iter()
+                .map(|vv| (vv.version, vv))
+                .filter(|(seq, _)| (from..=to).contains(seq))
+                .collect(),
+            _ => Vec::new(),
+        },
+        None => {
+            let len = match session.execute(Command::EventLen {
+                branch: session.branch_id(),
+                space: session.space_id(),
+            })? {
+                Output::Uint(n) => n,
+                _ => 0,
+            };
+            let mut events = Vec::new();
+            for sequence in from..len.min(to.saturating_add(1)) {
+                let cmd = Command::EventGet {
+                    branch: session.branch_id(),
+                    space: session.space_id(),
+                    sequence,
+                    as_of: None,
+                };
+                if let Output::MaybeVersioned(Some(vv)) = session.execute(cmd)? {
+                    events.push((sequence, vv));
+                }
+            }
+            events
+        }
+    };
+    events.sort_by_key(|(seq, _)| *seq);
+    Ok(events)
+}
+
+/// Replay the command events in a sequence range in one transaction.
+///
+/// Events are all read before the transaction begins, so commands that
+/// append events do not feed back into the replay.
+fn replay_events(
+    registry: &ToolRegistry,
+    session: &mut McpSession,
+    from: u64,
+    to: u64,
+    event_type: Option<String>,
+) -> Result<JsonValue> {
+    let mut ops = Vec::new();
+    let mut sequences = Vec::new();
+    let mut skipped = 0;
+    for (sequence, event) in read_events(session, from, to, event_type)? {
+        let payload = value_to_json(event.value);
+        let tool = match payload.get("tool").and_then(|t| t.as_str()) {
+            Some(tool) => tool.to_string(),
+            None => {
+                skipped += 1;
+                continue;
+            }
+        };
+        check_nested_tool(registry.unprefixed(&tool), true, || {
+            format!("event {} tool", sequence)
+        })?;
+        let arguments = match payload.get("arguments") {
+            Some(JsonValue::Object(m)) => m.clone(),
+            Some(JsonValue::Null) | None => Map::new(),
+            _ => {
+                return Err(McpError::InvalidArg {
+                    name: format!("event {} arguments", sequence),
+                    reason: "Expected object".to_string(),
+                })
+            }
+        };
+        ops.push(BatchOp { tool, arguments });
+        sequences.push(sequence);
+    }
+
+    let results = if ops.is_empty() {
+        Vec::new()
+    } else {
+        match run_atomic(registry, session, ops)? {
+            JsonValue::Array(results) => results,
+            _ => Vec::new(),
+        }
+    };
+    let results: Vec<JsonValue> = sequences
+        .into_iter()
+        .zip(results)
+        .map(|(sequence, mut result)| {
+            result["sequence"] = serde_json::json!(sequence);
+            result
+        })
+        .collect();
+
+    Ok(serde_json::json!({
+        "replayed": results.len(),
+        "skipped": skipped,
+        "results": results,
+    }))
+}
@@ -2,17 +2,20 @@
 //!
 //! Tools: strata_branch_create, strata_branch_get, strata_branch_list, strata_branch_exists,
 //!        strata_branch_delete, strata_branch_fork, strata_branch_diff, strata_branch_merge,
-//!        strata_branch_switch
+//!        strata_branch_switch, strata_diff_values
+
+use std::collections::HashMap;
 
 use serde_json::{Map, Value as JsonValue};
-use stratadb::{BranchId, Command, MergeStrategy};
+use stratadb::{BranchDiffEntry, BranchId, Command, MergeStrategy, Output};
 
 use crate::convert::{
-    get_optional_string, get_optional_u64, get_string_arg, json_to_value, output_to_json,
+    get_optional_bool, get_optional_string, get_optional_u64, get_string_arg, json_to_value,
+    output_to_json_with, value_to_json_with,
 };
 use crate::error::{McpError, Result};
 use crate::schema;
-use crate::session::McpSession;
+use crate::session::{McpSession, MergeResolution};
 use crate::tools::ToolDef;
 
 /// Get all branch tool definitions.
@@ -21,9 +24,11 @@ pub fn tools() -> Vec<ToolDef> {
         ToolDef::new(
             "strata_branch_create",
             "Create a new empty branch for isolated development or experimentation. \
-             Optionally specify branch_id (name or UUID) and metadata. Returns the new branch info.",
+             Optionally specify branch_id (name or UUID) and metadata. Returns the new branch info. \
+             Pass if_not_exists=true to return the existing branch info instead of a \
+             BRANCH_EXISTS error; the info then includes created (false if it already existed).",
             schema!(object {
-                optional: { "branch_id": string, "metadata": any }
+                optional: { "branch_id": string, "metadata": any, "if_not_exists": boolean }
             }),
         ),
         ToolDef::new(
@@ -36,10 +41,13 @@ pub fn tools() -> Vec<ToolDef> {
         ),
         ToolDef::new(
             "strata_branch_list",
-            "List all branches in the database. Returns array of branch info objects. \
-             Use limit and offset for pagination.",
+            "List branches in the database, sorted by id. Returns array of branch info objects. \
+             Pass state to list only branches whose status (as reported by strata_branch_get) \
+             matches it. \
+             For pagination pass limit, then the id of the last branch returned as cursor to \
+             get the next page. offset is deprecated; it uses the database's own ordering.",
             schema!(object {
-                optional: { "limit": integer, "offset": integer }
+                optional: { "state": string, "cursor": string, "limit": integer, "offset": integer }
             }),
         ),
         ToolDef::new(
@@ -53,9 +61,12 @@ pub fn tools() -> Vec<ToolDef> {
         ToolDef::new(
             "strata_branch_delete",
             "Permanently delete a branch and all its data. Cannot delete the 'default' branch. \
-             This action cannot be undone.",
+             This action cannot be undone. Fails if a transaction is active on the branch \
+             unless force=true, which rolls it back first. Deleting the current branch \
+             switches the session to 'default'.",
             schema!(object {
-                required: { "branch": string }
+                required: { "branch": string },
+                optional: { "force": boolean }
             }),
         ),
         ToolDef::new(
@@ -69,18 +80,42 @@ pub fn tools() -> Vec<ToolDef> {
         ToolDef::new(
             "strata_branch_diff",
             "Compare two branches and see what's different. Returns added, removed, and \
-             modified entries. Useful before merging to preview changes.",
+             modified entries. Useful before merging to preview changes. Entries include \
+             value_a and value_b by default; pass values=false to omit them, or hash=true to \
+             return hash_a and hash_b content hashes instead, which are equal when the values \
+             are.",
             schema!(object {
-                required: { "branch_a": string, "branch_b": string }
+                required: { "branch_a": string, "branch_b": string },
+                optional: { "values": boolean, "hash": boolean }
+            }),
+        ),
+        ToolDef::new(
+            "strata_diff_values",
+            "Compare one KV, state, or JSON entry between two branches without switching. \
+             Reads key from branch_a and branch_b in space (default: the current space). \
+             Returns {value_a, value_b, equal}; a value is null where the key is missing.",
+            serde_json::json!({
+                "type": "object",
+                "properties": {
+                    "key": {"type": "string"},
+                    "primitive": {"type": "string", "enum": ["kv", "state", "json"]},
+                    "branch_a": {"type": "string"},
+                    "branch_b": {"type": "string"},
+                    "space": {"type": "string"}
+                },
+                "required": ["key", "primitive", "branch_a", "branch_b"],
+                "additionalProperties": false
             }),
         ),
         ToolDef::new(
             "strata_branch_merge",
             "Merge changes from source branch into the current branch. Strategy 'last_writer_wins' \
-             (default) resolves conflicts by timestamp; 'strict' fails on any conflict.",
+             (default) resolves conflicts by timestamp; 'strict' fails on any conflict. \
+             resolutions maps conflicting keys to \"source\", \"target\", or {\"value\": ...} \
+             to settle them explicitly; a strict merge succeeds if every conflict is resolved.",
             schema!(object {
                 required: { "source": string },
-                optional: { "strategy": string }
+                optional: { "strategy": string, "resolutions": any }
             }),
         ),
         ToolDef::new(
@@ -108,12 +143,36 @@ pub fn dispatch(
                 Some(v) => Some(json_to_value(v.clone())?),
             };
 
+            let if_not_exists = get_optional_bool(&args, "if_not_exists").unwrap_or(false);
+
+            if if_not_exists {
+                if let Some(branch) = &branch_id {
+                    let exists = session.execute(Command::BranchExists {
+                        branch: BranchId::from(branch.clone()),
+                    })?;
+                    if matches!(exists, Output::Bool(true)) {
+                        let output = session.execute(Command::BranchGet {
+                            branch: BranchId::from(branch.clone()),
+                        })?;
+                        return Ok(with_created(
+                            output_to_json_with(output, session.convert_options()),
+                            false,
+                        ));
+                    }
+                }
+            }
+
             let cmd = Command::BranchCreate {
                 branch_id,
                 metadata,
             };
             let output = session.execute(cmd)?;
-            Ok(output_to_json(output))
+            let info = output_to_json_with(output, session.convert_options());
+            Ok(if if_not_exists {
+                with_created(info, true)
+            } else {
+                info
+            })
         }
 
         "strata_branch_get" => {
@@ -123,20 +182,65 @@ pub fn dispatch(
                 branch: BranchId::from(branch),
             };
             let output = session.execute(cmd)?;
-            Ok(output_to_json(output))
+            Ok(output_to_json_with(output, session.convert_options()))
         }
 
         "strata_branch_list" => {
             let limit = get_optional_u64(&args, "limit");
             let offset = get_optional_u64(&args, "offset");
+            let cursor = get_optional_string(&args, "cursor");
+            let state = get_optional_string(&args, "state");
 
+            if offset.is_some() && cursor.is_some() {
+                return Err(McpError::InvalidArg {
+                    name: "offset".to_string(),
+                    reason: "Cannot be combined with cursor".to_string(),
+                });
+            }
+            if offset.is_some() && state.is_none() {
+                let cmd = Command::BranchList {
+                    state: None,
+                    limit,
+                    offset,
+                };
+                let output = session.execute(cmd)?;
+                return Ok(output_to_json_with(output, session.convert_options()));
+            }
+
+            // The state filter matches the status string branch info reports
             let cmd = Command::BranchList {
                 state: None,
-                limit,
-                offset,
+                limit: None,
+                offset: None,
             };
-            let output = session.execute(cmd)?;
-            Ok(output_to_json(output))
+            let mut branches = match session.execute(cmd)? {
+                Output::BranchInfoList(branches) => branches,
+                _ => {
+                    return Err(McpError::Internal(
+                        "Unexpected output for BranchList".to_string(),
+                    ))
+                }
+            };
+            if let Some(state) = &state {
+                branches.retain(|bi| format!("{:?}", bi.info.status).eq_ignore_ascii_case(state));
+            }
+            if let Some(offset) = offset {
+                // Deprecated offset paging keeps the database's own ordering
+                branches.drain(..(offset as usize).min(branches.len()));
+            } else {
+                // Paging by id is stable while branches are created or deleted
+                branches.sort_by(|a, b| a.info.id.as_str().cmp(b.info.id.as_str()));
+                if let Some(cursor) = cursor {
+                    branches.retain(|bi| bi.info.id.as_str() > cursor.as_str());
+                }
+            }
+            if let Some(limit) = limit {
+                branches.truncate(limit as usize);
+            }
+            Ok(output_to_json_with(
+                Output::BranchInfoList(branches),
+                session.convert_options(),
+            ))
         }
 
         "strata_branch_exists" => {
@@ -146,17 +250,35 @@ pub fn dispatch(
                 branch: BranchId::from(branch),
             };
             let output = session.execute(cmd)?;
-            Ok(output_to_json(output))
+            Ok(output_to_json_with(output, session.convert_options()))
         }
 
         "strata_branch_delete" => {
             let branch = get_string_arg(&args, "branch")?;
+            let force = get_optional_bool(&args, "force").unwrap_or(false);
+
+            if session.txn_branch() == Some(branch.as_str()) {
+                if !force {
+                    return Err(McpError::InvalidArg {
+                        name: "branch".to_string(),
+                        reason: format!(
+                            "A transaction is active on branch '{}'; commit or roll it back \
+                             first, or pass force=true to roll it back",
+                            branch
+                        ),
+                    });
+                }
+                session.execute(Command::TxnRollback)?;
+            }
 
             let cmd = Command::BranchDelete {
-                branch: BranchId::from(branch),
+                branch: BranchId::from(branch.clone()),
             };
             let output = session.execute(cmd)?;
-            Ok(output_to_json(output))
+            if session.branch() == branch {
+                session.switch_branch("default")?;
+            }
+            Ok(output_to_json_with(output, session.convert_options()))
         }
 
         "strata_branch_fork" => {
@@ -173,6 +295,7 @@ pub fn dispatch(
         "strata_branch_diff" => {
             let branch_a = get_string_arg(&args, "branch_a")?;
             let branch_b = get_string_arg(&args, "branch_b")?;
+            let values = DiffValues::from_args(&args)?;
 
             let diff = session.diff_branches(&branch_a, &branch_b)?;
 
@@ -181,27 +304,12 @@ pub fn dispatch(
                 .spaces
                 .into_iter()
                 .map(|s| {
-                    let added: Vec<JsonValue> = s.added.into_iter().map(|e| serde_json::json!({
-                        "key": e.key,
-                        "primitive": format!("{:?}", e.primitive),
-                        "space": e.space,
-                        "value_a": e.value_a,
-                        "value_b": e.value_b,
-                    })).collect();
-                    let removed: Vec<JsonValue> = s.removed.into_iter().map(|e| serde_json::json!({
-                        "key": e.key,
-                        "primitive": format!("{:?}", e.primitive),
-                        "space": e.space,
-                        "value_a": e.value_a,
-                        "value_b": e.value_b,
-                    })).collect();
-                    let modified: Vec<JsonValue> = s.modified.into_iter().map(|e| serde_json::json!({
-                        "key": e.key,
-                        "primitive": format!("{:?}", e.primitive),
-                        "space": e.space,
-                        "value_a": e.value_a,
-                        "value_b": e.value_b,
-                    })).collect();
+                    let entries = |list: Vec<BranchDiffEntry>| -> Vec<JsonValue> {
+                        list.into_iter().map(|e| diff_entry_to_json(e, values)).collect()
+                    };
+                    let added = entries(s.added);
+                    let removed = entries(s.removed);
+                    let modified = entries(s.modified);
                     serde_json::json!({
                         "space": s.space,
                         "added": added,
@@ -241,19 +349,30 @@ pub fn dispatch(
                 }
             };
 
-            let info = session.merge_branch(&source, strategy)?;
+            let resolutions = get_resolutions(&args)?;
+            let info = if resolutions.is_empty() {
+                session.merge_branch(&source, strategy)?
+            } else {
+                session.merge_branch_resolved(&source, strategy, &resolutions)?
+            };
 
             // Convert conflicts to JSON
             let conflicts: Vec<JsonValue> = info
                 .conflicts
                 .into_iter()
                 .map(|c| {
+                    let resolution = resolutions.get(c.key.as_str()).map(|r| match r {
+                        MergeResolution::Source => "source",
+                        MergeResolution::Target => "target",
+                        MergeResolution::Value(_) => "value",
+                    });
                     serde_json::json!({
                         "key": c.key,
                         "primitive": format!("{:?}", c.primitive),
                         "space": c.space,
                         "source_value": c.source_value,
                         "target_value": c.target_value,
+                        "resolution": resolution,
                     })
                 })
                 .collect();
@@ -274,6 +393,128 @@ pub fn dispatch(
             }))
         }
 
+        "strata_diff_values" => {
+            let key = get_string_arg(&args, "key")?;
+            let primitive = get_string_arg(&args, "primitive")?;
+            let branch_a = get_string_arg(&args, "branch_a")?;
+            let branch_b = get_string_arg(&args, "branch_b")?;
+            let space =
+                get_optional_string(&args, "space").unwrap_or_else(|| session.space().to_string());
+
+            let mut read = |branch: &str| -> Result<JsonValue> {
+                let value = session.read_entry(branch, &primitive, &space, &key)?;
+                Ok(value.map_or(JsonValue::Null, |v| {
+                    value_to_json_with(v, session.convert_options())
+                }))
+            };
+            let value_a = read(&branch_a)?;
+            let value_b = read(&branch_b)?;
+            Ok(serde_json::json!({
+                "value_a": value_a,
+                "value_b": value_b,
+                "equal": value_a == value_b,
+            }))
+        }
+
         _ => Err(McpError::UnknownTool(name.to_string())),
     }
 }
+
+/// Add `created` to branch info returned by strata_branch_create.
+fn with_created(mut info: JsonValue, created: bool) -> JsonValue {
+    if let JsonValue::Object(map) = &mut info {
+        map.insert("created".to_string(), JsonValue::Bool(created));
+    }
+    info
+}
+
+/// How `strata_branch_diff` reports entry values.
+#[derive(Clone, Copy)]
+enum DiffValues {
+    Inline,
+    Omit,
+    Hash,
+}
+
+impl DiffValues {
+    /// Read the `values` (default `true`) and `hash` (default `false`) arguments.
+    fn from_args(args: &Map<String, JsonValue>) -> Result<Self> {
+        match (get_optional_bool(args, "values"), get_optional_bool(args, "hash")) {
+            (Some(true), Some(true)) => Err(McpError::InvalidArg {
+                name: "hash".to_string(),
+                reason: "Cannot be combined with values=true".to_string(),
+            }),
+            (_, Some(true)) => Ok(Self::Hash),
+            (Some(false), _) => Ok(Self::Omit),
+            _ => Ok(Self::Inline),
+        }
+    }
+}
+
+/// Convert a diff entry to JSON, reporting its values as `mode` asks.
+fn diff_entry_to_json(e: BranchDiffEntry, mode: DiffValues) -> JsonValue {
+    let mut entry = serde_json::json!({
+        "key": e.key,
+        "primitive": format!("{:?}", e.primitive),
+        "space": e.space,
+    });
+    match mode {
+        DiffValues::Inline => {
+            entry["value_a"] = serde_json::json!(e.value_a);
+            entry["value_b"] = serde_json::json!(e.value_b);
+        }
+        DiffValues::Hash => {
+            entry["hash_a"] = content_hash(&serde_json::json!(e.value_a));
+            entry["hash_b"] = content_hash(&serde_json::json!(e.value_b));
+        }
+        DiffValues::Omit => {}
+    }
+    entry
+}
+
+/// Hash a value's JSON form with 64-bit FNV-1a, as 16 hex digits.
+///
+/// Object keys serialize in sorted order, so equal values hash equally
+/// across calls. A missing value has no hash.
+fn content_hash(value: &JsonValue) -> JsonValue {
+    if value.is_null() {
+        return JsonValue::Null;
+    }
+    let hash = value.to_string().bytes().fold(0xcbf2_9ce4_8422_2325_u64, |h, b| {
+        (h ^ u64::from(b)).wrapping_mul(0x0100_0000_01b3)
+    });
+    JsonValue::String(format!("{:016x}", hash))
+}
+
+/// Parse the optional `resolutions` argument of strata_branch_merge.
+fn get_resolutions(args: &Map<String, JsonValue>) -> Result<HashMap<String, MergeResolution>> {
+    let map = match args.get("resolutions") {
+        None | Some(JsonValue::Null) => return Ok(HashMap::new()),
+        Some(JsonValue::Object(map)) => map,
+        Some(_) => {
+            return Err(McpError::InvalidArg {
+                name: "resolutions".to_string(),
+                reason: "Expected object mapping keys to resolutions".to_string(),
+            })
+        }
+    };
+
+    let mut resolutions = HashMap::new();
+    for (key, value) in map {
+        let resolution = match value {
+            JsonValue::String(s) if s == "source" => MergeResolution::Source,
+            JsonValue::String(s) if s == "target" => MergeResolution::Target,
+            JsonValue::Object(obj) if obj.len() == 1 && obj.contains_key("value") => {
+                MergeResolution::Value(json_to_value(obj["value"].clone())?)
+            }
+            _ => {
+                return Err(McpError::InvalidArg {
+                    name: format!("resolutions.{}", key),
+                    reason: "Expected \"source\", \"target\", or {\"value\": ...}".to_string(),
+                })
+            }
+        };
+        resolutions.insert(key.clone(), resolution);
+    }
+    Ok(resolutions)
+}
@@ -1,11 +1,18 @@
 //! Branch bundle tools for data portability.
 //!
-//! Tools: strata_bundle_export, strata_bundle_import, strata_bundle_validate
+//! Tools: strata_bundle_export, strata_bundle_import, strata_bundle_validate,
+//!        strata_bundle_list, strata_export_jsonl, strata_import_jsonl
 
 use serde_json::{Map, Value as JsonValue};
-use stratadb::Command;
+use std::collections::BTreeMap;
+use std::fs::OpenOptions;
+use std::io::{BufWriter, Write};
+use stratadb::{BranchId, Command, Output, Strata, Value};
 
-use crate::convert::{get_string_arg, output_to_json};
+use crate::convert::{
+    check_space_name, get_optional_bool, get_optional_string, get_string_arg, json_to_value,
+    output_to_json_with, value_to_json_with,
+};
 use crate::error::{McpError, Result};
 use crate::schema;
 use crate::session::McpSession;
@@ -17,9 +24,23 @@ pub fn tools() -> Vec<ToolDef> {
         ToolDef::new(
             "strata_bundle_export",
             "Export a branch to a portable bundle file. The bundle contains all data \
-             and can be imported into another database. Returns the file path and statistics.",
-            schema!(object {
-                required: { "branch": string, "path": string }
+             and can be imported into another database. Returns the file path and statistics. \
+             Pass key_prefix and/or primitives (kv, state, json) to export only matching \
+             entries; a filtered bundle holds just their current values, without version \
+             history, events, or vectors.",
+            serde_json::json!({
+                "type": "object",
+                "properties": {
+                    "branch": {"type": "string"},
+                    "path": {"type": "string"},
+                    "key_prefix": {"type": "string"},
+                    "primitives": {
+                        "type": "array",
+                        "items": {"type": "string", "enum": ["kv", "state", "json"]}
+                    }
+                },
+                "required": ["branch", "path"],
+                "additionalProperties": false
             }),
         ),
         ToolDef::new(
@@ -38,6 +59,38 @@ pub fn tools() -> Vec<ToolDef> {
                 required: { "path": string }
             }),
         ),
+        ToolDef::new(
+            "strata_bundle_list",
+            "List the bundle files in a directory (not its subdirectories). Each file is \
+             validated; files that are not readable bundles are skipped. Returns path, \
+             branch_id, entry_count, and bundle_size (bytes) for each bundle, sorted by path.",
+            schema!(object {
+                required: { "directory": string }
+            }),
+        ),
+        ToolDef::new(
+            "strata_export_jsonl",
+            "Export a branch as plain-text NDJSON: one line per entry with primitive (kv, \
+             state, json, or event), space, key, value, and version, covering every space. \
+             Event keys are sequence numbers. Vectors are not exported; use \
+             strata_bundle_export for a complete copy. The file must not already exist. \
+             Returns the path and line count.",
+            schema!(object {
+                required: { "branch": string, "path": string }
+            }),
+        ),
+        ToolDef::new(
+            "strata_import_jsonl",
+            "Import an NDJSON file, such as one written by strata_export_jsonl, into the \
+             current branch. Each line is {primitive, space, key, value}, where primitive is \
+             kv, state, or json and space defaults to the current space. All writes run in one \
+             transaction. Malformed lines and event lines are skipped and reported. Set \
+             dry_run to validate without writing. Returns counts per primitive and skipped.",
+            schema!(object {
+                required: { "path": string },
+                optional: { "dry_run": boolean }
+            }),
+        ),
     ]
 }
 
@@ -51,10 +104,23 @@ pub fn dispatch(
         "strata_bundle_export" => {
             let branch_id = get_string_arg(&args, "branch")?;
             let path = get_string_arg(&args, "path")?;
+            let key_prefix = get_optional_string(&args, "key_prefix");
+            let primitives = args.get("primitives").and_then(|v| v.as_array()).map(|arr| {
+                arr.iter()
+                    .filter_map(|v| v.as_str().map(String::from))
+                    .collect::<Vec<_>>()
+            });
+
+            if key_prefix.is_some() || primitives.is_some() {
+                let primitives = primitives.unwrap_or_else(|| {
+                    ["kv", "state", "json"].map(String::from).to_vec()
+                });
+                return export_filtered(session, &branch_id, &path, key_prefix, &primitives);
+            }
 
             let cmd = Command::BranchExport { branch_id, path };
             let output = session.execute(cmd)?;
-            Ok(output_to_json(output))
+            Ok(output_to_json_with(output, session.convert_options()))
         }
 
         "strata_bundle_import" => {
@@ -62,7 +128,7 @@ pub fn dispatch(
 
             let cmd = Command::BranchImport { path };
             let output = session.execute(cmd)?;
-            Ok(output_to_json(output))
+            Ok(output_to_json_with(output, session.convert_options()))
         }
 
         "strata_bundle_validate" => {
@@ -70,9 +136,412 @@ pub fn dispatch(
 
             let cmd = Command::BranchBundleValidate { path };
             let output = session.execute(cmd)?;
-            Ok(output_to_json(output))
+            Ok(output_to_json_with(output, session.convert_options()))
+        }
+
+        "strata_bundle_list" => {
+            let directory = get_string_arg(&args, "directory")?;
+            list_bundles(session, &directory)
+        }
+
+        "strata_export_jsonl" => {
+            let branch = get_string_arg(&args, "branch")?;
+            let path = get_string_arg(&args, "path")?;
+            export_jsonl(session, &branch, &path)
+        }
+
+        "strata_import_jsonl" => {
+            let path = get_string_arg(&args, "path")?;
+            let dry_run = get_optional_bool(&args, "dry_run").unwrap_or(false);
+            import_jsonl(session, &path, dry_run)
         }
 
         _ => Err(McpError::UnknownTool(name.to_string())),
     }
 }
+
+/// Describe every bundle file directly inside `directory`.
+fn list_bundles(session: &mut McpSession, directory: &str) -> Result<JsonValue> {
+    let mut paths = Vec::new();
+    for entry in std::fs::read_dir(directory)? {
+        let entry = entry?;
+        if entry.file_type()?.is_file() {
+            paths.push(entry.path());
+        }
+    }
+    paths.sort();
+
+    let mut bundles = Vec::new();
+    for path in paths {
+        let path_str = path.to_string_lossy().into_owned();
+        let cmd = Command::BranchBundleValidate {
+            path: path_str.clone(),
+        };
+        let result = match session.execute(cmd) {
+            Ok(Output::BundleValidated(result)) => result,
+            Ok(_) => continue,
+            Err(e) => {
+                tracing::debug!("Skipping {}: {}", path_str, e);
+                continue;
+            }
+        };
+        bundles.push(serde_json::json!({
+            "path": path_str,
+            "branch_id": result.branch_id,
+            "entry_count": result.entry_count,
+            "bundle_size": std::fs::metadata(&path)?.len(),
+        }));
+    }
+    Ok(JsonValue::Array(bundles))
+}
+
+/// Export the matching KV, state, and JSON entries of a branch.
+///
+/// The export command always writes a whole branch, so the matching entries
+/// are copied into a branch of the same name in a scratch in-memory database
+/// and that branch is exported instead.
+fn export_filtered(
+    session: &mut McpSession,
+    branch: &str,
+    path: &str,
+    key_prefix: Option<String>,
+    primitives: &[String],
+) -> Result<JsonValue> {
+    let branch_id = || Some(BranchId::from(branch.to_string()));
+    let spaces = match session.execute(Command::SpaceList {
+        branch: branch_id(),
+    })? {
+        Output::SpaceList(spaces) => spaces,
+        _ => Vec::new(),
+    };
+
+    let mut entries = Vec::new();
+    for space in &spaces {
+        let space_id = || Some(space.clone());
+        for primitive in primitives {
+            let keys = match primitive.as_str() {
+                "kv" => keys_of(session.execute(Command::KvList {
+                    branch: branch_id(),
+                    space: space_id(),
+                    prefix: key_prefix.clone(),
+                    cursor: None,
+                    limit: None,
+                    as_of: None,
+                })?),
+                "state" => keys_of(session.execute(Command::StateList {
+                    branch: branch_id(),
+                    space: space_id(),
+                    prefix: key_prefix.clone(),
+                    as_of: None,
+                })?),
+                "json" => json_keys(session, branch, space, key_prefix.clone())?,
+                other => {
+                    return Err(McpError::InvalidArg {
+                        name: "primitives".to_string(),
+                        reason: format!("Cannot filter {} entries; use kv, state, or json", other),
+                    })
+                }
+            };
+            for key in keys {
+                if let Some(value) = session.read_entry(branch, primitive, space, &key)? {
+                    entries.push((primitive.clone(), space.clone(), key, value));
+                }
+            }
+        }
+    }
+
+    let mut scratch = McpSession::new(Strata::cache()?);
+    if branch != scratch.branch() {
+        scratch.execute(Command::BranchCreate {
+            branch_id: Some(branch.to_string()),
+            metadata: None,
+        })?;
+        scratch.switch_branch(branch)?;
+    }
+    for space in spaces.iter().filter(|s| s.as_str() != scratch.space()) {
+        scratch.execute(Command::SpaceCreate {
+            branch: scratch.branch_id(),
+            space: space.clone(),
+        })?;
+    }
+    scratch.atomically(|scratch| {
+        for (primitive, space, key, value) in entries {
+            scratch.write_entry(&primitive, space, key, Some(value))?;
+        }
+        Ok(())
+    })?;
+
+    let cmd = Command::BranchExport {
+        branch_id: branch.to_string(),
+        path: path.to_string(),
+    };
+    let output = scratch.execute(cmd)?;
+    Ok(output_to_json_with(output, session.convert_options()))
+}
+
+/// List every JSON document key in a space of a branch.
+fn json_keys(
+    session: &mut McpSession,
+    branch: &str,
+    space: &str,
+    prefix: Option<String>,
+) -> Result<Vec<String>> {
+    let mut all = Vec::new();
+    let mut cursor = None;
+    loop {
+        let cmd = Command::JsonList {
+            branch: Some(BranchId::from(branch.to_string())),
+            space: Some(space.to_string()),
+            prefix: prefix.clone(),
+            cursor: cursor.take(),
+            limit: 1000,
+            as_of: None,
+        };
+        match session.execute(cmd)? {
+            Output::JsonListResult { keys, cursor: next } => {
+                all.extend(keys);
+                match next {
+                    Some(next) => cursor = Some(next),
+                    None => break,
+                }
+            }
+            _ => break,
+        }
+    }
+    Ok(all)
+}
+
+/// Write every KV, state, JSON, and event entry of a branch as NDJSON.
+fn export_jsonl(session: &mut McpSession, branch: &str, path: &str) -> Result<JsonValue> {
+    let branch_id = || Some(BranchId::from(branch.to_string()));
+    let exists = matches!(
+        session.execute(Command::BranchExists {
+            branch: BranchId::from(branch.to_string()),
+        })?,
+        Output::Bool(true)
+    );
+    if !exists {
+        return Err(McpError::InvalidArg {
+            name: "branch".to_string(),
+            reason: format!("Branch '{}' does not exist", branch),
+        });
+    }
+    let spaces = match session.execute(Command::SpaceList {
+        branch: branch_id(),
+    })? {
+        Output::SpaceList(spaces) => spaces,
+        _ => Vec::new(),
+    };
+
+    let file = OpenOptions::new().write(true).create_new(true).open(path)?;
+    let mut out = BufWriter::new(file);
+    let mut lines = 0u64;
+    let mut write_line = |session: &McpSession, primitive, space: &str, key, entry: Entry| {
+        let (value, version) = entry;
+        let line = serde_json::json!({
+            "primitive": primitive,
+            "space": space,
+            "key": key,
+            "value": value_to_json_with(value, session.convert_options()),
+            "version": version,
+        });
+        writeln!(out, "{}", line)?;
+        lines += 1;
+        Ok::<(), McpError>(())
+    };
+
+    for space in spaces {
+        let space_id = || Some(space.clone());
+
+        let cmd = Command::KvList {
+            branch: branch_id(),
+            space: space_id(),
+            prefix: None,
+            cursor: None,
+            limit: None,
+            as_of: None,
+        };
+        for key in keys_of(session.execute(cmd)?) {
+            let cmd = Command::KvGet {
+                branch: branch_id(),
+                space: space_id(),
+                key: key.clone(),
+                as_of: None,
+            };
+            if let Some(entry) = entry_of(session.execute(cmd)?) {
+                write_line(session, "kv", &space, JsonValue::String(key), entry)?;
+            }
+        }
+
+        let cmd = Command::StateList {
+            branch: branch_id(),
+            space: space_id(),
+            prefix: None,
+            as_of: None,
+        };
+        for cell in keys_of(session.execute(cmd)?) {
+            let cmd = Command::StateGet {
+                branch: branch_id(),
+                space: space_id(),
+                cell: cell.clone(),
+                as_of: None,
+            };
+            if let Some(entry) = entry_of(session.execute(cmd)?) {
+                write_line(session, "state", &space, JsonValue::String(cell), entry)?;
+            }
+        }
+
+        let mut cursor = None;
+        loop {
+            let cmd = Command::JsonList {
+                branch: branch_id(),
+                space: space_id(),
+                prefix: None,
+                cursor: cursor.take(),
+                limit: 1000,
+                as_of: None,
+            };
+            let (keys, next) = match session.execute(cmd)? {
+                Output::JsonListResult { keys, cursor } => (keys, cursor),
+                _ => break,
+            };
+            for key in keys {
+                let cmd = Command::JsonGet {
+                    branch: branch_id(),
+                    space: space_id(),
+                    key: key.clone(),
+                    path: "$".to_string(),
+                    as_of: None,
+                };
+                if let Some(entry) = entry_of(session.execute(cmd)?) {
+                    write_line(session, "json", &space, JsonValue::String(key), entry)?;
+                }
+            }
+            match next {
+                Some(next) => cursor = Some(next),
+                None => break,
+            }
+        }
+
+        let len = match session.execute(Command::EventLen {
+            branch: branch_id(),
+            space: space_id(),
+        })? {
+            Output::Uint(n) => n,
+            _ => 0,
+        };
+        for sequence in 0..len {
+            let cmd = Command::EventGet {
+                branch: branch_id(),
+                space: space_id(),
+                sequence,
+                as_of: None,
+            };
+            if let Some(entry) = entry_of(session.execute(cmd)?) {
+                write_line(session, "event", &space, JsonValue::from(sequence), entry)?;
+            }
+        }
+    }
+
+    out.flush()?;
+    Ok(serde_json::json!({
+        "path": path,
+        "branch": branch,
+        "lines": lines,
+    }))
+}
+
+/// Import NDJSON entries into the current branch in one transaction.
+fn import_jsonl(session: &mut McpSession, path: &str, dry_run: bool) -> Result<JsonValue> {
+    if !dry_run {
+        session.check_write_access("ImportJsonl")?;
+    }
+    let contents = std::fs::read_to_string(path)?;
+
+    let mut entries = Vec::new();
+    let mut skipped = Vec::new();
+    for (i, line) in contents.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        match parse_line(line, session.space()) {
+            Ok(entry) => entries.push(entry),
+            Err(reason) => skipped.push(serde_json::json!({
+                "line": i + 1,
+                "reason": reason,
+            })),
+        }
+    }
+
+    let mut imported: BTreeMap<&str, u64> = ["kv", "state", "json"].map(|p| (p, 0)).into();
+    for (primitive, ..) in &entries {
+        if let Some(count) = imported.get_mut(primitive.as_str()) {
+            *count += 1;
+        }
+    }
+
+    if !dry_run {
+        session.atomically(|session| {
+            for (primitive, space, key, value) in entries {
+                session.write_entry(&primitive, space, key, Some(value))?;
+            }
+            Ok(())
+        })?;
+    }
+
+    Ok(serde_json::json!({
+        "dry_run": dry_run,
+        "imported": imported,
+        "skipped": skipped,
+    }))
+}
+
+/// Parse one NDJSON line into `(primitive, space, key, value)`.
+fn parse_line(
+    line: &str,
+    default_space: &str,
+) -> std::result::Result<(String, String, String, Value), String> {
+    let entry: JsonValue = serde_json::from_str(line).map_err(|e| format!("Invalid JSON: {}", e))?;
+    let obj = entry.as_object().ok_or("Expected an object")?;
+
+    let primitive = match obj.get("primitive").and_then(|v| v.as_str()) {
+        Some(p @ ("kv" | "state" | "json")) => p.to_string(),
+        Some("event") => return Err("Events cannot be imported".to_string()),
+        Some(other) => return Err(format!("Unknown primitive '{}'", other)),
+        None => return Err("Missing primitive".to_string()),
+    };
+    let space = match obj.get("space") {
+        Some(JsonValue::String(space)) => space.clone(),
+        Some(JsonValue::Null) | None => default_space.to_string(),
+        Some(_) => return Err("Expected space to be a string".to_string()),
+    };
+    check_space_name("space", &space).map_err(|e| e.to_string())?;
+    let key = obj
+        .get("key")
+        .and_then(|v| v.as_str())
+        .filter(|k| !k.is_empty())
+        .ok_or("Missing or invalid key")?
+        .to_string();
+    let value = obj.get("value").cloned().ok_or("Missing value")?;
+    let value = json_to_value(value).map_err(|e| e.to_string())?;
+
+    Ok((primitive, space, key, value))
+}
+
+fn keys_of(output: Output) -> Vec<String> {
+    match output {
+        Output::Keys(keys) => keys,
+        _ => Vec::new(),
+    }
+}
+
+/// A value read for export and its version, if the read reported one.
+type Entry = (Value, JsonValue);
+
+fn entry_of(output: Output) -> Option<Entry> {
+    match output {
+        Output::MaybeVersioned(vv) => vv.map(|vv| (vv.value, serde_json::json!(vv.version))),
+        Output::Maybe(value) => value.map(|v| (v, JsonValue::Null)),
+        _ => None,
+    }
+}
@@ -0,0 +1,151 @@
+//! Result cache for idempotent read tools.
+//!
+//! Agents often repeat the same metadata read, such as `strata_db_info` or
+//! `strata_branch_list`, several times in a row. When enabled, results of
+//! the tools in [`CACHEABLE`] are kept for a short TTL, keyed by tool,
+//! arguments, branch, and space. Any other tool call clears the cache, so a
+//! write is never hidden behind a stale entry. Reads inside a transaction
+//! bypass the cache, since they see the transaction's own snapshot.
+
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+use serde_json::{Map, Value as JsonValue};
+
+/// Entries kept when no capacity is given.
+pub const DEFAULT_CAPACITY: usize = 128;
+
+/// Read-only tools whose results may be cached.
+pub const CACHEABLE: &[&str] = &[
+    "strata_db_info",
+    "strata_db_time_range",
+    "strata_db_branches_summary",
+    "strata_list_all",
+    "strata_branch_list",
+    "strata_branch_get",
+    "strata_branch_exists",
+    "strata_space_list",
+    "strata_space_exists",
+    "strata_vector_list_collections",
+    "strata_vector_stats",
+];
+
+struct Entry {
+    value: JsonValue,
+    stored: Instant,
+    last_used: u64,
+}
+
+/// Size-bounded LRU cache of tool results with a per-entry TTL.
+pub struct ResultCache {
+    ttl: Duration,
+    capacity: usize,
+    entries: HashMap<String, Entry>,
+    clock: u64,
+}
+
+impl ResultCache {
+    /// Create a cache holding at most `capacity` results for `ttl` each.
+    pub fn new(ttl: Duration, capacity: usize) -> Self {
+        Self {
+            ttl,
+            capacity,
+            entries: HashMap::new(),
+            clock: 0,
+        }
+    }
+
+    /// Cache key for a call, or `None` if the tool is not cacheable.
+    pub fn key(
+        tool: &str,
+        branch: &str,
+        space: &str,
+        args: &Map<String, JsonValue>,
+    ) -> Option<String> {
+        if !CACHEABLE.contains(&tool) {
+            return None;
+        }
+        let args = JsonValue::Object(args.clone());
+        Some(format!("{}\0{}\0{}\0{}", tool, branch, space, args))
+    }
+
+    /// Get a live cached result, dropping it if its TTL has passed.
+    pub fn get(&mut self, key: &str) -> Option<JsonValue> {
+        self.clock += 1;
+        let entry = self.entries.get_mut(key)?;
+        if entry.stored.elapsed() >= self.ttl {
+            self.entries.remove(key);
+            return None;
+        }
+        entry.last_used = self.clock;
+        Some(entry.value.clone())
+    }
+
+    /// Store a result, evicting the least recently used entry if full.
+    pub fn insert(&mut self, key: String, value: JsonValue) {
+        if self.capacity == 0 {
+            return;
+        }
+        self.clock += 1;
+        if self.entries.len() >= self.capacity && !self.entries.contains_key(&key) {
+            let oldest = self
+                .entries
+                .iter()
+                .min_by_key(|(_, e)| e.last_used)
+                .map(|(k, _)| k.clone());
+            if let Some(oldest) = oldest {
+                self.entries.remove(&oldest);
+            }
+        }
+        self.entries.insert(
+            key,
+            Entry {
+                value,
+                stored: Instant::now(),
+                last_used: self.clock,
+            },
+        );
+    }
+
+    /// Drop every cached result.
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lru_eviction() {
+        let mut cache = ResultCache::new(Duration::from_secs(60), 2);
+        cache.insert("a".to_string(), JsonValue::from(1));
+        cache.insert("b".to_string(), JsonValue::from(2));
+        assert_eq!(cache.get("a"), Some(JsonValue::from(1)));
+
+        // "b" is now the least recently used
+        cache.insert("c".to_string(), JsonValue::from(3));
+        assert_eq!(cache.get("b"), None);
+        assert_eq!(cache.get("a"), Some(JsonValue::from(1)));
+        assert_eq!(cache.get("c"), Some(JsonValue::from(3)));
+    }
+
+    #[test]
+    fn test_ttl_expiry() {
+        let mut cache = ResultCache::new(Duration::ZERO, 4);
+        cache.insert("a".to_string(), JsonValue::from(1));
+        assert_eq!(cache.get("a"), None);
+    }
+
+    #[test]
+    fn test_key_only_for_cacheable_tools() {
+        let args = Map::new();
+        assert!(ResultCache::key("strata_db_info", "default", "default", &args).is_some());
+        assert!(ResultCache::key("strata_kv_put", "default", "default", &args).is_none());
+        assert_ne!(
+            ResultCache::key("strata_space_list", "a", "default", &args),
+            ResultCache::key("strata_space_list", "b", "default", &args)
+        );
+    }
+}
@@ -0,0 +1,131 @@
+//! Argument completion for the MCP `completion/complete` method.
+//!
+//! Suggests values for a tool argument from what already exists in the
+//! session: branches, spaces, vector collections, keys, and state cells.
+//! Arguments with a schema `enum` complete from its values.
+
+use stratadb::{Command, Output};
+
+use crate::error::Result;
+use crate::session::McpSession;
+use crate::tools::ToolDef;
+
+/// Maximum number of values returned by one completion request.
+pub const MAX_COMPLETIONS: usize = 100;
+
+/// Where candidate values for an argument come from.
+enum Provider {
+    Branch,
+    Space,
+    Collection,
+    KvKey,
+    JsonKey,
+    StateCell,
+}
+
+/// Pick the provider for an argument of a tool, if it names existing data.
+fn provider(tool: &str, argument: &str) -> Option<Provider> {
+    match argument {
+        "branch" | "branch_a" | "branch_b" | "source" => Some(Provider::Branch),
+        "space" if tool != "strata_space_create" => Some(Provider::Space),
+        "collection" if tool != "strata_vector_create_collection" => Some(Provider::Collection),
+        "key" if tool.starts_with("strata_kv_") => Some(Provider::KvKey),
+        "key" if tool.starts_with("strata_json_") => Some(Provider::JsonKey),
+        "cell" => Some(Provider::StateCell),
+        _ => None,
+    }
+}
+
+/// Complete a partial value for one of a tool's arguments.
+///
+/// `name` is the tool's unprefixed name. Returns matching candidates in
+/// sorted order, or an empty list if the argument is unknown or has no
+/// provider.
+pub fn complete(
+    session: &mut McpSession,
+    name: &str,
+    tool: &ToolDef,
+    argument: &str,
+    partial: &str,
+) -> Result<Vec<String>> {
+    let prop = match tool
+        .input_schema
+        .get("properties")
+        .and_then(|p| p.get(argument))
+    {
+        Some(prop) => prop,
+        None => return Ok(Vec::new()),
+    };
+
+    let mut values = if let Some(options) = prop.get("enum").and_then(|e| e.as_array()) {
+        options
+            .iter()
+            .filter_map(|v| v.as_str().map(String::from))
+            .collect()
+    } else {
+        match provider(name, argument) {
+            Some(provider) => candidates(session, provider, partial)?,
+            None => Vec::new(),
+        }
+    };
+
+    values.retain(|v| v.starts_with(partial));
+    values.sort();
+    values.dedup();
+    Ok(values)
+}
+
+/// Query the session for candidate values.
+fn candidates(session: &mut McpSession, provider: Provider, partial: &str) -> Result<Vec<String>> {
+    let prefix = Some(partial.to_string()).filter(|p| !p.is_empty());
+    let values = match provider {
+        Provider::Branch => session.branch_ids()?,
+        Provider::Space => match session.execute(Command::SpaceList {
+            branch: session.branch_id(),
+        })? {
+            Output::SpaceList(spaces) => spaces,
+            _ => Vec::new(),
+        },
+        Provider::Collection => match session.execute(Command::VectorListCollections {
+            branch: session.branch_id(),
+            space: session.space_id(),
+        })? {
+            Output::VectorCollectionList(collections) => {
+                collections.into_iter().map(|c| c.name).collect()
+            }
+            _ => Vec::new(),
+        },
+        Provider::KvKey => match session.execute(Command::KvList {
+            branch: session.branch_id(),
+            space: session.space_id(),
+            prefix,
+            cursor: None,
+            limit: Some(MAX_COMPLETIONS as u64 + 1),
+            as_of: None,
+        })? {
+            Output::Keys(keys) => keys,
+            _ => Vec::new(),
+        },
+        Provider::JsonKey => match session.execute(Command::JsonList {
+            branch: session.branch_id(),
+            space: session.space_id(),
+            prefix,
+            cursor: None,
+            limit: MAX_COMPLETIONS as u64 + 1,
+            as_of: None,
+        })? {
+            Output::JsonListResult { keys, .. } => keys,
+            _ => Vec::new(),
+        },
+        Provider::StateCell => match session.execute(Command::StateList {
+            branch: session.branch_id(),
+            space: session.space_id(),
+            prefix,
+            as_of: None,
+        })? {
+            Output::Keys(keys) => keys,
+            _ => Vec::new(),
+        },
+    };
+    Ok(values)
+}
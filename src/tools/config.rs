@@ -39,12 +39,13 @@ pub fn dispatch(
             let timeout_ms = get_optional_u64(&args, "timeout_ms");
 
             let cmd = Command::ConfigureModel {
-                endpoint,
+                endpoint: endpoint.clone(),
                 model,
                 api_key,
                 timeout_ms,
             };
             session.execute(cmd)?;
+            session.set_model_endpoint(endpoint);
             Ok(serde_json::json!({ "status": "ok" }))
         }
 
@@ -1,14 +1,24 @@
 //! Database-level tools.
 //!
-//! Tools: strata_db_ping, strata_db_info, strata_db_flush, strata_db_compact
+//! Tools: strata_db_ping, strata_db_info, strata_db_flush, strata_db_compact,
+//!        strata_db_time_range, strata_db_backup, strata_db_restore, strata_db_config,
+//!        strata_db_benchmark, strata_db_branches_summary, strata_db_health, strata_list_all
 
 use serde_json::{Map, Value as JsonValue};
-use stratadb::Command;
+use std::collections::BTreeMap;
+use std::net::{TcpStream, ToSocketAddrs};
+use std::path::{Component, Path};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use stratadb::{BranchId, Command, Output, Value};
 
-use crate::convert::output_to_json;
-use crate::error::Result;
+use crate::convert::{
+    get_optional_bool, get_optional_string, get_optional_u64, get_string_arg, output_to_json_with,
+    BytesEncoding, NonFiniteFloat,
+};
+use crate::error::{McpError, Result};
 use crate::schema;
 use crate::session::McpSession;
+use crate::tools::retention::history_timestamps;
 use crate::tools::ToolDef;
 
 /// Get all database tool definitions.
@@ -20,6 +30,16 @@ pub fn tools() -> Vec<ToolDef> {
              Use this as a health check before starting work.",
             schema!(object {}),
         ),
+        ToolDef::new(
+            "strata_db_health",
+            "Run readiness checks for liveness probes: ping, storage (database info can be \
+             read), write (a temporary branch can be created and deleted), and model (the \
+             endpoint set by strata_configure_model accepts connections). Each check reports \
+             status ok, error, or skipped; write is skipped on a read-only database or inside \
+             a transaction, and model is skipped if no endpoint is configured. Returns the \
+             checks and healthy=true if none failed.",
+            schema!(object {}),
+        ),
         ToolDef::new(
             "strata_db_info",
             "Get database statistics including version, uptime in seconds, branch count, \
@@ -38,33 +58,956 @@ pub fn tools() -> Vec<ToolDef> {
              This is done automatically but can be triggered manually if needed.",
             schema!(object {}),
         ),
+        ToolDef::new(
+            "strata_db_config",
+            "Get the server's runtime configuration: read-only mode, storage (cache or \
+             persistent), database path, auto-embed, and output encoding options. \
+             The path is null if the server was started with --redact-db-path.",
+            schema!(object {}),
+        ),
         ToolDef::new(
             "strata_db_time_range",
             "Get the available time range for the current branch. Returns oldest_ts and latest_ts \
              (microsecond timestamps) for use with as_of time-travel reads. Returns null timestamps \
-             if the branch has no data.",
-            schema!(object {}),
+             if the branch has no data. Pass space and/or primitive (kv, state, json, or event) \
+             to narrow the range to that scope; narrowed ranges scan every entry in scope.",
+            serde_json::json!({
+                "type": "object",
+                "properties": {
+                    "space": {"type": "string"},
+                    "primitive": {"type": "string", "enum": ["kv", "state", "json", "event"]}
+                },
+                "additionalProperties": false
+            }),
+        ),
+        ToolDef::new(
+            "strata_db_backup",
+            "Back up the entire database (all branches and spaces) to a directory. Each branch \
+             is exported as a bundle alongside a manifest. The directory must be empty or not \
+             exist. Returns the path, branch count, and total entry count.",
+            schema!(object {
+                required: { "path": string }
+            }),
+        ),
+        ToolDef::new(
+            "strata_db_restore",
+            "Restore a backup created by strata_db_backup into the current database. \
+             Every bundle is validated before anything is written. Existing branches are \
+             skipped unless overwrite=true, which replaces them (the 'default' branch is never \
+             replaced); a branch whose import fails is put back as it was. Returns counts of \
+             branches and keys restored and the list of skipped branches.",
+            schema!(object {
+                required: { "path": string },
+                optional: { "overwrite": boolean }
+            }),
+        ),
+        ToolDef::new(
+            "strata_db_benchmark",
+            "Measure database latency with a small fixed workload: ping, KV puts, and KV gets, \
+             repeated `iterations` times (default 20, max 100). Writes go to a temporary branch \
+             that is deleted afterwards. On a read-only database or inside a transaction only \
+             reads are measured. Returns min/p50/p95/p99/max/mean in microseconds per operation.",
+            schema!(object {
+                optional: { "iterations": integer }
+            }),
+        ),
+        ToolDef::new(
+            "strata_db_branches_summary",
+            "Get a compact overview of all branches: total_branches, by_status (count per \
+             status), and branches with each id and key_count (KV, state, and JSON keys across \
+             all spaces). Key counts are paged by branch id: pass limit (default 20, max 100) \
+             and the returned cursor to fetch the next page; cursor is null on the last page.",
+            schema!(object {
+                optional: { "limit": integer, "cursor": string }
+            }),
+        ),
+        ToolDef::new(
+            "strata_list_all",
+            "Get an overview of what is stored in the current branch/space. Returns kv, json, \
+             and state key lists, vector_collections names, and the event count (events are \
+             not listed by type). Pass prefix to match keys and collection names, primitives \
+             (kv, json, state, vector, event) to list only some, and limit to cap each list \
+             (default 100). truncated names the lists that hit the limit.",
+            serde_json::json!({
+                "type": "object",
+                "properties": {
+                    "prefix": {"type": "string"},
+                    "primitives": {
+                        "type": "array",
+                        "items": {
+                            "type": "string",
+                            "enum": ["kv", "json", "state", "vector", "event"]
+                        }
+                    },
+                    "limit": {"type": "integer"}
+                },
+                "additionalProperties": false
+            }),
         ),
     ]
 }
 
+/// Entries listed per primitive by strata_list_all by default.
+const LIST_ALL_DEFAULT_LIMIT: u64 = 100;
+
+/// Branches counted per strata_db_branches_summary page by default.
+const SUMMARY_DEFAULT_LIMIT: u64 = 20;
+
+/// Upper bound on branches counted per strata_db_branches_summary page.
+const SUMMARY_MAX_LIMIT: u64 = 100;
+
+/// Iterations run by strata_db_benchmark when none are requested.
+const BENCHMARK_DEFAULT_ITERATIONS: u64 = 20;
+
+/// Upper bound on strata_db_benchmark iterations.
+const BENCHMARK_MAX_ITERATIONS: u64 = 100;
+
+/// How long strata_db_health waits to connect to the model endpoint.
+const MODEL_CHECK_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Manifest file written at the root of a backup directory.
+const BACKUP_MANIFEST: &str = "manifest.json";
+
+/// Format identifier stored in backup manifests.
+const BACKUP_FORMAT: &str = "strata-mcp-backup";
+
 /// Dispatch a database tool call.
 pub fn dispatch(
     session: &mut McpSession,
     name: &str,
-    _args: Map<String, JsonValue>,
+    args: Map<String, JsonValue>,
 ) -> Result<JsonValue> {
     let cmd = match name {
         "strata_db_ping" => Command::Ping,
         "strata_db_info" => Command::Info,
         "strata_db_flush" => Command::Flush,
         "strata_db_compact" => Command::Compact,
-        "strata_db_time_range" => Command::TimeRange {
+        "strata_db_time_range" => {
+            let space = get_optional_string(&args, "space");
+            let primitive = get_optional_string(&args, "primitive");
+            if space.is_some() || primitive.is_some() {
+                return scoped_time_range(session, space, primitive);
+            }
+            Command::TimeRange {
+                branch: session.branch_id(),
+            }
+        }
+        "strata_db_backup" => return backup(session, &args),
+        "strata_db_restore" => return restore(session, &args),
+        "strata_db_config" => return Ok(config(session)),
+        "strata_db_benchmark" => return benchmark(session, &args),
+        "strata_db_health" => return Ok(health(session)),
+        "strata_db_branches_summary" => return branches_summary(session, &args),
+        "strata_list_all" => return list_all(session, &args),
+        _ => return Err(McpError::UnknownTool(name.to_string())),
+    };
+
+    let output = session.execute(cmd)?;
+    Ok(output_to_json_with(output, session.convert_options()))
+}
+
+/// List keys and collections of every primitive in the current branch and space.
+///
+/// Each list is capped at `limit` on its own; lists cut short are named in
+/// `truncated`. Events have no type index to list, so only their count is
+/// reported.
+fn list_all(session: &mut McpSession, args: &Map<String, JsonValue>) -> Result<JsonValue> {
+    let prefix = get_optional_string(args, "prefix");
+    let limit = get_optional_u64(args, "limit").unwrap_or(LIST_ALL_DEFAULT_LIMIT);
+    if limit == 0 {
+        return Err(McpError::InvalidArg {
+            name: "limit".to_string(),
+            reason: "Must be at least 1".to_string(),
+        });
+    }
+    let primitives: Option<Vec<&str>> = args
+        .get("primitives")
+        .and_then(|v| v.as_array())
+        .map(|arr| arr.iter().filter_map(|v| v.as_str()).collect());
+    let wanted = |p: &str| primitives.as_ref().is_none_or(|ps| ps.contains(&p));
+
+    let mut result = Map::new();
+    let mut truncated = Vec::new();
+    let mut add = |name: &str, mut names: Vec<String>| {
+        if names.len() as u64 > limit {
+            names.truncate(limit as usize);
+            truncated.push(name.to_string());
+        }
+        result.insert(name.to_string(), JsonValue::from(names));
+    };
+
+    if wanted("kv") {
+        let keys = match session.execute(Command::KvList {
+            branch: session.branch_id(),
+            space: session.space_id(),
+            prefix: prefix.clone(),
+            cursor: None,
+            limit: Some(limit + 1),
+            as_of: None,
+        })? {
+            Output::Keys(keys) => keys,
+            _ => Vec::new(),
+        };
+        add("kv", keys);
+    }
+    if wanted("json") {
+        let keys = match session.execute(Command::JsonList {
+            branch: session.branch_id(),
+            space: session.space_id(),
+            prefix: prefix.clone(),
+            cursor: None,
+            limit: limit + 1,
+            as_of: None,
+        })? {
+            Output::JsonListResult { keys, .. } => keys,
+            _ => Vec::new(),
+        };
+        add("json", keys);
+    }
+    if wanted("state") {
+        let cells = match session.execute(Command::StateList {
+            branch: session.branch_id(),
+            space: session.space_id(),
+            prefix: prefix.clone(),
+            as_of: None,
+        })? {
+            Output::Keys(cells) => cells,
+            _ => Vec::new(),
+        };
+        add("state", cells);
+    }
+    if wanted("vector") {
+        let names = match session.execute(Command::VectorListCollections {
             branch: session.branch_id(),
+            space: session.space_id(),
+        })? {
+            Output::VectorCollectionList(collections) => collections
+                .into_iter()
+                .map(|c| c.name)
+                .filter(|name| prefix.as_ref().is_none_or(|p| name.starts_with(p.as_str())))
+                .collect(),
+            _ => Vec::new(),
+        };
+        add("vector_collections", names);
+    }
+    if wanted("event") {
+        let count = match session.execute(Command::EventLen {
+            branch: session.branch_id(),
+            space: session.space_id(),
+        })? {
+            Output::Uint(n) => n,
+            _ => 0,
+        };
+        result.insert("events".to_string(), JsonValue::from(count));
+    }
+
+    result.insert("truncated".to_string(), JsonValue::from(truncated));
+    Ok(JsonValue::Object(result))
+}
+
+/// Oldest and latest timestamps within one space and/or primitive.
+///
+/// `TimeRange` only covers a whole branch, so a narrowed range is computed
+/// from the version histories and event timestamps of every entry in scope.
+/// Without a space, every space in the branch is scanned. Vectors keep no
+/// history and are not covered.
+fn scoped_time_range(
+    session: &mut McpSession,
+    space: Option<String>,
+    primitive: Option<String>,
+) -> Result<JsonValue> {
+    let branch = session.branch_id();
+    let spaces = match &space {
+        Some(space) => vec![space.clone()],
+        None => match session.execute(Command::SpaceList {
+            branch: branch.clone(),
+        })? {
+            Output::SpaceList(spaces) => spaces,
+            _ => vec![session.space().to_string()],
         },
-        _ => return Err(crate::error::McpError::UnknownTool(name.to_string())),
     };
+    let wanted = |p: &str| primitive.as_deref().is_none_or(|want| want == p);
 
-    let output = session.execute(cmd)?;
-    Ok(output_to_json(output))
+    let mut timestamps = Vec::new();
+    for space in spaces {
+        let space = Some(space);
+
+        if wanted("kv") {
+            let keys = match session.execute(Command::KvList {
+                branch: branch.clone(),
+                space: space.clone(),
+                prefix: None,
+                cursor: None,
+                limit: None,
+                as_of: None,
+            })? {
+                Output::Keys(keys) => keys,
+                _ => Vec::new(),
+            };
+            for key in keys {
+                timestamps.extend(history_timestamps(session.execute(Command::KvGetv {
+                    branch: branch.clone(),
+                    space: space.clone(),
+                    key,
+                    as_of: None,
+                })?));
+            }
+        }
+
+        if wanted("state") {
+            let cells = match session.execute(Command::StateList {
+                branch: branch.clone(),
+                space: space.clone(),
+                prefix: None,
+                as_of: None,
+            })? {
+                Output::Keys(keys) => keys,
+                _ => Vec::new(),
+            };
+            for cell in cells {
+                timestamps.extend(history_timestamps(session.execute(Command::StateGetv {
+                    branch: branch.clone(),
+                    space: space.clone(),
+                    cell,
+                    as_of: None,
+                })?));
+            }
+        }
+
+        if wanted("json") {
+            let mut cursor = None;
+            loop {
+                let (keys, next) = match session.execute(Command::JsonList {
+                    branch: branch.clone(),
+                    space: space.clone(),
+                    prefix: None,
+                    cursor: cursor.take(),
+                    limit: 1000,
+                    as_of: None,
+                })? {
+                    Output::JsonListResult { keys, cursor } => (keys, cursor),
+                    _ => (Vec::new(), None),
+                };
+                for key in keys {
+                    timestamps.extend(history_timestamps(session.execute(Command::JsonGetv {
+                        branch: branch.clone(),
+                        space: space.clone(),
+                        key,
+                        as_of: None,
+                    })?));
+                }
+                match next {
+                    Some(next) => cursor = Some(next),
+                    None => break,
+                }
+            }
+        }
+
+        if wanted("event") {
+            let len = match session.execute(Command::EventLen {
+                branch: branch.clone(),
+                space: space.clone(),
+            })? {
+                Output::Uint(n) => n,
+                _ => 0,
+            };
+            for sequence in 0..len {
+                let output = session.execute(Command::EventGet {
+                    branch: branch.clone(),
+                    space: space.clone(),
+                    sequence,
+                    as_of: None,
+                })?;
+                if let Output::MaybeVersioned(Some(vv)) = output {
+                    timestamps.push(vv.timestamp);
+                }
+            }
+        }
+    }
+
+    Ok(serde_json::json!({
+        "oldest_ts": timestamps.iter().min(),
+        "latest_ts": timestamps.iter().max(),
+        "space": space,
+        "primitive": primitive,
+    }))
+}
+
+/// Export every branch into a backup directory and write its manifest.
+fn backup(session: &mut McpSession, args: &Map<String, JsonValue>) -> Result<JsonValue> {
+    let path = get_string_arg(args, "path")?;
+    let dir = Path::new(&path);
+    if dir.exists() && dir.read_dir()?.next().is_some() {
+        return Err(McpError::InvalidArg {
+            name: "path".to_string(),
+            reason: "Backup directory must be empty or not exist".to_string(),
+        });
+    }
+    std::fs::create_dir_all(dir)?;
+
+    let mut branches = Vec::new();
+    let mut total_entries: u64 = 0;
+    for (i, branch_id) in session.branch_ids()?.into_iter().enumerate() {
+        let file = format!("branch-{}.bundle", i);
+        let cmd = Command::BranchExport {
+            branch_id: branch_id.clone(),
+            path: dir.join(&file).to_string_lossy().into_owned(),
+        };
+        let entry_count = match session.execute(cmd)? {
+            Output::BranchExported(result) => result.entry_count,
+            _ => {
+                return Err(McpError::Internal(
+                    "Unexpected output for BranchExport".to_string(),
+                ))
+            }
+        };
+        total_entries += entry_count;
+        branches.push(serde_json::json!({
+            "branch": branch_id,
+            "file": file,
+            "entry_count": entry_count,
+        }));
+    }
+
+    let branch_count = branches.len();
+    let manifest = serde_json::json!({
+        "format": BACKUP_FORMAT,
+        "version": 1,
+        "branches": branches,
+    });
+    std::fs::write(dir.join(BACKUP_MANIFEST), serde_json::to_vec_pretty(&manifest)?)?;
+
+    Ok(serde_json::json!({
+        "path": path,
+        "branch_count": branch_count,
+        "entry_count": total_entries,
+    }))
+}
+
+/// Import every branch listed in a backup manifest.
+fn restore(session: &mut McpSession, args: &Map<String, JsonValue>) -> Result<JsonValue> {
+    session.check_write_access("DbRestore")?;
+    let path = get_string_arg(args, "path")?;
+    let overwrite = get_optional_bool(args, "overwrite").unwrap_or(false);
+    let dir = Path::new(&path);
+
+    let raw = std::fs::read(dir.join(BACKUP_MANIFEST))?;
+    let manifest: JsonValue = serde_json::from_slice(&raw)?;
+    if manifest.get("format").and_then(|v| v.as_str()) != Some(BACKUP_FORMAT) {
+        return Err(McpError::InvalidArg {
+            name: "path".to_string(),
+            reason: "Not a strata_db_backup directory".to_string(),
+        });
+    }
+    let entries = manifest
+        .get("branches")
+        .and_then(|v| v.as_array())
+        .ok_or_else(|| McpError::InvalidArg {
+            name: "path".to_string(),
+            reason: "Backup manifest has no branch list".to_string(),
+        })?;
+
+    // Check every entry before touching the database
+    let mut bundles = Vec::with_capacity(entries.len());
+    for entry in entries {
+        let (branch, file) = match (
+            entry.get("branch").and_then(|v| v.as_str()),
+            entry.get("file").and_then(|v| v.as_str()),
+        ) {
+            (Some(branch), Some(file)) => (branch.to_string(), file.to_string()),
+            _ => {
+                return Err(McpError::InvalidArg {
+                    name: "path".to_string(),
+                    reason: "Backup manifest entry is missing branch or file".to_string(),
+                })
+            }
+        };
+        let bundle = bundle_path(dir, &file)?;
+        validate_bundle(session, &bundle)?;
+        bundles.push((branch, bundle));
+    }
+
+    let mut branches_restored = 0;
+    let mut keys_restored: u64 = 0;
+    let mut skipped = Vec::new();
+    for (branch, bundle) in bundles {
+        let exists = matches!(
+            session.execute(Command::BranchExists {
+                branch: BranchId::from(branch.clone()),
+            })?,
+            Output::Bool(true)
+        );
+        let keys_written = if exists {
+            if !overwrite || branch == "default" {
+                skipped.push(JsonValue::String(branch));
+                continue;
+            }
+            replace_branch(session, &branch, &bundle)?
+        } else {
+            import_bundle(session, &bundle)?
+        };
+        branches_restored += 1;
+        keys_restored += keys_written;
+    }
+
+    Ok(serde_json::json!({
+        "branches_restored": branches_restored,
+        "keys_restored": keys_restored,
+        "skipped": skipped,
+    }))
+}
+
+/// Resolve a manifest `file` to a bundle path inside the backup directory.
+///
+/// Only plain file names are accepted, so a manifest cannot point the
+/// import at files outside the backup.
+fn bundle_path(dir: &Path, file: &str) -> Result<String> {
+    let mut components = Path::new(file).components();
+    match (components.next(), components.next()) {
+        (Some(Component::Normal(_)), None) => Ok(dir.join(file).to_string_lossy().into_owned()),
+        _ => Err(McpError::InvalidArg {
+            name: "path".to_string(),
+            reason: format!("Backup manifest file '{}' is not a plain file name", file),
+        }),
+    }
+}
+
+/// Fail unless `bundle` is a readable bundle with valid checksums.
+fn validate_bundle(session: &mut McpSession, bundle: &str) -> Result<()> {
+    let cmd = Command::BranchBundleValidate {
+        path: bundle.to_string(),
+    };
+    match session.execute(cmd)? {
+        Output::BundleValidated(result) if result.checksums_valid => Ok(()),
+        Output::BundleValidated(_) => Err(McpError::InvalidArg {
+            name: "path".to_string(),
+            reason: format!("Backup bundle '{}' failed checksum validation", bundle),
+        }),
+        _ => Err(McpError::Internal(
+            "Unexpected output for BranchBundleValidate".to_string(),
+        )),
+    }
+}
+
+/// Import a bundle, returning the number of keys written.
+fn import_bundle(session: &mut McpSession, bundle: &str) -> Result<u64> {
+    let cmd = Command::BranchImport {
+        path: bundle.to_string(),
+    };
+    match session.execute(cmd)? {
+        Output::BranchImported(result) => Ok(result.keys_written),
+        _ => Err(McpError::Internal(
+            "Unexpected output for BranchImport".to_string(),
+        )),
+    }
+}
+
+/// Replace an existing branch with the contents of a bundle.
+///
+/// A bundle import recreates the branch it was exported from, so the branch
+/// has to be deleted first. It is exported to a temporary bundle beforehand
+/// and imported again if the restore fails, leaving the branch as it was.
+fn replace_branch(session: &mut McpSession, branch: &str, bundle: &str) -> Result<u64> {
+    let stamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_nanos())
+        .unwrap_or(0);
+    let saved = std::env::temp_dir()
+        .join(format!("strata-restore-{}-{}.bundle", std::process::id(), stamp))
+        .to_string_lossy()
+        .into_owned();
+    session.execute(Command::BranchExport {
+        branch_id: branch.to_string(),
+        path: saved.clone(),
+    })?;
+    session.execute(Command::BranchDelete {
+        branch: BranchId::from(branch.to_string()),
+    })?;
+
+    let result = import_bundle(session, bundle);
+    if let Err(e) = &result {
+        tracing::warn!("Restoring branch '{}' failed, putting it back: {}", branch, e);
+        if let Err(e) = import_bundle(session, &saved) {
+            tracing::error!(
+                "Could not put back branch '{}'; its data is in {}: {}",
+                branch,
+                saved,
+                e
+            );
+            return result;
+        }
+    }
+    let _ = std::fs::remove_file(&saved);
+    result
+}
+
+/// Describe the runtime configuration of the session.
+fn config(session: &McpSession) -> JsonValue {
+    let runtime = session.runtime_config();
+    let opts = session.convert_options();
+    let db_path = if runtime.redact_path {
+        None
+    } else {
+        runtime.db_path.clone()
+    };
+
+    serde_json::json!({
+        "read_only": session.is_read_only(),
+        "storage": if runtime.cache { "cache" } else { "persistent" },
+        "db_path": db_path,
+        "path_redacted": runtime.redact_path,
+        "auto_embed": runtime.auto_embed,
+        "output": {
+            "non_finite_floats": match opts.non_finite {
+                NonFiniteFloat::Null => "null",
+                NonFiniteFloat::String => "string",
+            },
+            "bytes_encoding": match opts.bytes {
+                BytesEncoding::Base64 => "base64",
+                BytesEncoding::Hex => "hex",
+                BytesEncoding::Array => "array",
+            },
+            "raw": opts.raw,
+            "distinguish_missing": opts.distinguish_missing,
+        },
+    })
+}
+
+/// Run the benchmark workload and summarize per-operation latencies.
+fn benchmark(session: &mut McpSession, args: &Map<String, JsonValue>) -> Result<JsonValue> {
+    let iterations = get_optional_u64(args, "iterations").unwrap_or(BENCHMARK_DEFAULT_ITERATIONS);
+    if iterations == 0 || iterations > BENCHMARK_MAX_ITERATIONS {
+        return Err(McpError::InvalidArg {
+            name: "iterations".to_string(),
+            reason: format!("Must be between 1 and {}", BENCHMARK_MAX_ITERATIONS),
+        });
+    }
+
+    let mut ping = Vec::new();
+    for _ in 0..iterations {
+        ping.push(timed(|| session.execute(Command::Ping))?);
+    }
+
+    // Writes would land in the open transaction, so only reads run there
+    let writes = !session.is_read_only() && !session.in_transaction();
+    let (put, get) = if writes {
+        let scratch = scratch_branch("mcp-benchmark");
+        session.execute(Command::BranchCreate {
+            branch_id: Some(scratch.clone()),
+            metadata: None,
+        })?;
+        let result = benchmark_kv(session, &scratch, iterations);
+        if let Err(e) = session.execute(Command::BranchDelete {
+            branch: BranchId::from(scratch),
+        }) {
+            tracing::warn!("Failed to delete benchmark branch: {}", e);
+        }
+        let (put, get) = result?;
+        (Some(put), get)
+    } else {
+        let mut get = Vec::new();
+        for i in 0..iterations {
+            let cmd = Command::KvGet {
+                branch: session.branch_id(),
+                space: session.space_id(),
+                key: format!("mcp-benchmark-{}", i),
+                as_of: None,
+            };
+            get.push(timed(|| session.execute(cmd))?);
+        }
+        (None, get)
+    };
+
+    Ok(serde_json::json!({
+        "iterations": iterations,
+        "writes": writes,
+        "ping": latency_summary(ping),
+        "put": put.map(latency_summary),
+        "get": latency_summary(get),
+    }))
+}
+
+/// Name a temporary branch that will not collide with existing ones.
+fn scratch_branch(prefix: &str) -> String {
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_nanos())
+        .unwrap_or(0);
+    format!("{}-{}", prefix, nanos)
+}
+
+/// Run every readiness check and report whether all of them passed.
+fn health(session: &mut McpSession) -> JsonValue {
+    let ping = check(session, |s| s.execute(Command::Ping).map(|_| ()));
+    let storage = check(session, |s| s.execute(Command::Info).map(|_| ()));
+
+    let write = if session.is_read_only() {
+        skipped("database is read-only")
+    } else if session.in_transaction() {
+        // Writes would land in the open transaction
+        skipped("a transaction is active")
+    } else {
+        check(session, |s| {
+            let scratch = scratch_branch("mcp-health");
+            s.execute(Command::BranchCreate {
+                branch_id: Some(scratch.clone()),
+                metadata: None,
+            })?;
+            s.execute(Command::BranchDelete {
+                branch: BranchId::from(scratch),
+            })?;
+            Ok(())
+        })
+    };
+
+    let model = match session.model_endpoint().map(str::to_string) {
+        Some(endpoint) => check(session, |_| connect(&endpoint)),
+        None => skipped("no model endpoint configured"),
+    };
+
+    let checks = serde_json::json!({
+        "ping": ping,
+        "storage": storage,
+        "write": write,
+        "model": model,
+    });
+    let healthy = checks
+        .as_object()
+        .map(|c| c.values().all(|v| v["status"] != "error"))
+        .unwrap_or(false);
+    serde_json::json!({
+        "healthy": healthy,
+        "checks": checks,
+    })
+}
+
+/// Run one health check, reporting its latency or error.
+///
+/// Errors are reported inside a successful result, so they are redacted here
+/// the same way error responses are.
+fn check(
+    session: &mut McpSession,
+    f: impl FnOnce(&mut McpSession) -> Result<()>,
+) -> JsonValue {
+    let start = Instant::now();
+    match f(session) {
+        Ok(()) => serde_json::json!({
+            "status": "ok",
+            "latency_us": start.elapsed().as_micros() as u64,
+        }),
+        Err(e) => serde_json::json!({
+            "status": "error",
+            "message": session.client_error(e).to_string(),
+        }),
+    }
+}
+
+/// Report a health check that did not run.
+fn skipped(reason: &str) -> JsonValue {
+    serde_json::json!({
+        "status": "skipped",
+        "reason": reason,
+    })
+}
+
+/// Open a TCP connection to the host and port of an endpoint URL.
+fn connect(endpoint: &str) -> Result<()> {
+    let (rest, default_port) = if let Some(rest) = endpoint.strip_prefix("https://") {
+        (rest, 443)
+    } else {
+        (endpoint.strip_prefix("http://").unwrap_or(endpoint), 80)
+    };
+    let authority = rest.split('/').next().unwrap_or(rest);
+    let authority = authority.rsplit('@').next().unwrap_or(authority);
+    let (host, port) = match authority.rsplit_once(':') {
+        Some((host, port)) if !authority.ends_with(']') => match port.parse::<u16>() {
+            Ok(port) => (host, port),
+            Err(_) => (authority, default_port),
+        },
+        _ => (authority, default_port),
+    };
+    let host = host.trim_start_matches('[').trim_end_matches(']');
+
+    let addr = (host, port)
+        .to_socket_addrs()?
+        .next()
+        .ok_or_else(|| McpError::Io(format!("no address found for {}", host)))?;
+    TcpStream::connect_timeout(&addr, MODEL_CHECK_TIMEOUT)?;
+    Ok(())
+}
+
+/// Time KV puts and then gets of the same keys on a scratch branch.
+fn benchmark_kv(
+    session: &mut McpSession,
+    branch: &str,
+    iterations: u64,
+) -> Result<(Vec<u64>, Vec<u64>)> {
+    let key = |i: u64| format!("mcp-benchmark-{}", i);
+    let mut put = Vec::new();
+    for i in 0..iterations {
+        let cmd = Command::KvPut {
+            branch: Some(BranchId::from(branch.to_string())),
+            space: None,
+            key: key(i),
+            value: Value::Int(i as i64),
+        };
+        put.push(timed(|| session.execute(cmd))?);
+    }
+
+    let mut get = Vec::new();
+    for i in 0..iterations {
+        let cmd = Command::KvGet {
+            branch: Some(BranchId::from(branch.to_string())),
+            space: None,
+            key: key(i),
+            as_of: None,
+        };
+        get.push(timed(|| session.execute(cmd))?);
+    }
+    Ok((put, get))
+}
+
+/// Run an operation and return its duration in microseconds.
+fn timed(f: impl FnOnce() -> Result<Output>) -> Result<u64> {
+    let start = Instant::now();
+    f()?;
+    Ok(start.elapsed().as_micros() as u64)
+}
+
+/// Summarize latencies (microseconds) with nearest-rank percentiles.
+fn latency_summary(mut samples: Vec<u64>) -> JsonValue {
+    samples.sort_unstable();
+    let percentile = |p: f64| {
+        let rank = ((p / 100.0) * samples.len() as f64).ceil() as usize;
+        samples[rank.clamp(1, samples.len()) - 1]
+    };
+    let mean = samples.iter().sum::<u64>() as f64 / samples.len() as f64;
+    serde_json::json!({
+        "min_us": samples[0],
+        "p50_us": percentile(50.0),
+        "p95_us": percentile(95.0),
+        "p99_us": percentile(99.0),
+        "max_us": samples[samples.len() - 1],
+        "mean_us": mean,
+    })
+}
+
+/// Summarize branch statuses and count keys for one page of branches.
+fn branches_summary(session: &mut McpSession, args: &Map<String, JsonValue>) -> Result<JsonValue> {
+    let limit = get_optional_u64(args, "limit").unwrap_or(SUMMARY_DEFAULT_LIMIT);
+    if limit == 0 || limit > SUMMARY_MAX_LIMIT {
+        return Err(McpError::InvalidArg {
+            name: "limit".to_string(),
+            reason: format!("Must be between 1 and {}", SUMMARY_MAX_LIMIT),
+        });
+    }
+    let cursor = get_optional_string(args, "cursor");
+
+    let cmd = Command::BranchList {
+        state: None,
+        limit: None,
+        offset: None,
+    };
+    let mut branches: Vec<(String, String)> = match session.execute(cmd)? {
+        Output::BranchInfoList(list) => list
+            .into_iter()
+            .map(|bi| {
+                let status = format!("{:?}", bi.info.status).to_lowercase();
+                (bi.info.id.as_str().to_string(), status)
+            })
+            .collect(),
+        _ => {
+            return Err(McpError::Internal(
+                "Unexpected output for BranchList".to_string(),
+            ))
+        }
+    };
+    branches.sort();
+
+    let mut by_status: BTreeMap<String, u64> = BTreeMap::new();
+    for (_, status) in &branches {
+        *by_status.entry(status.clone()).or_insert(0) += 1;
+    }
+
+    let start = cursor.map_or(0, |c| branches.partition_point(|(id, _)| *id <= c));
+    let end = (start + limit as usize).min(branches.len());
+    let mut page = Vec::new();
+    for (id, _) in &branches[start..end] {
+        page.push(serde_json::json!({
+            "id": id,
+            "key_count": branch_key_count(session, id)?,
+        }));
+    }
+    let next = if end < branches.len() {
+        Some(branches[end - 1].0.clone())
+    } else {
+        None
+    };
+
+    Ok(serde_json::json!({
+        "total_branches": branches.len(),
+        "by_status": by_status,
+        "branches": page,
+        "cursor": next,
+    }))
+}
+
+/// Count KV, state, and JSON keys in every space of a branch.
+fn branch_key_count(session: &mut McpSession, branch: &str) -> Result<u64> {
+    let branch_id = || Some(BranchId::from(branch.to_string()));
+    let spaces = match session.execute(Command::SpaceList {
+        branch: branch_id(),
+    })? {
+        Output::SpaceList(spaces) => spaces,
+        _ => Vec::new(),
+    };
+
+    let mut count = 0;
+    for space in spaces {
+        let space = Some(space);
+        let cmd = Command::KvList {
+            branch: branch_id(),
+            space: space.clone(),
+            prefix: None,
+            cursor: None,
+            limit: None,
+            as_of: None,
+        };
+        if let Output::Keys(keys) = session.execute(cmd)? {
+            count += keys.len() as u64;
+        }
+
+        let cmd = Command::StateList {
+            branch: branch_id(),
+            space: space.clone(),
+            prefix: None,
+            as_of: None,
+        };
+        if let Output::Keys(keys) = session.execute(cmd)? {
+            count += keys.len() as u64;
+        }
+
+        let mut cursor = None;
+        loop {
+            let cmd = Command::JsonList {
+                branch: branch_id(),
+                space: space.clone(),
+                prefix: None,
+                cursor: cursor.take(),
+                limit: 1000,
+                as_of: None,
+            };
+            match session.execute(cmd)? {
+                Output::JsonListResult { keys, cursor: next } => {
+                    count += keys.len() as u64;
+                    match next {
+                        Some(next) => cursor = Some(next),
+                        None => break,
+                    }
+                }
+                _ => break,
+            }
+        }
+    }
+    Ok(count)
 }
@@ -1,16 +1,20 @@
 //! Event log tools.
 //!
-//! Tools: strata_event_append, strata_event_get, strata_event_list, strata_event_len
+//! Tools: strata_event_append, strata_event_get, strata_event_list, strata_event_len,
+//!        strata_event_get_by_time, strata_event_count
 
 use serde_json::{Map, Value as JsonValue};
-use stratadb::Command;
+use std::cmp::Ordering;
+use stratadb::{Command, FilterOp, MetadataFilter, Output, TimeRangeInput, VersionedValue};
 
 use crate::convert::{
-    get_optional_u64, get_string_arg, get_u64_arg, get_value_arg, output_to_json,
+    get_convert_options, get_optional_string, get_optional_u64, get_string_arg, get_u64_arg,
+    get_value_arg, output_to_json_with, time_range_micros, value_to_json_with, ConvertOptions,
 };
 use crate::error::{McpError, Result};
 use crate::schema;
 use crate::session::McpSession;
+use crate::tools::vector::{filter_schema, parse_filters};
 use crate::tools::ToolDef;
 
 /// Get all event tool definitions.
@@ -26,26 +30,58 @@ pub fn tools() -> Vec<ToolDef> {
         ToolDef::new(
             "strata_event_get",
             "Get an event by its sequence number. Returns null if not found. \
-             Pass as_of (microsecond timestamp) for time-travel reads.",
+             Pass as_of (microsecond timestamp) for time-travel reads. \
+             Pass raw=true to return the bare payload without version info. \
+             Pass distinguish_missing=true to get {found: false} for a missing event.",
             schema!(object {
                 required: { "sequence": integer },
-                optional: { "as_of": integer }
+                optional: { "as_of": integer, "raw": boolean, "distinguish_missing": boolean }
             }),
         ),
         ToolDef::new(
             "strata_event_list",
             "List events of a specific type with optional pagination. \
-             Pass as_of (microsecond timestamp) for time-travel reads.",
+             Pass as_of (microsecond timestamp) for time-travel reads. \
+             Pass filter (array of {field, op, value}, as in strata_vector_search) to keep only \
+             events whose payload matches every condition; field names a payload field, with \
+             dots for nested fields. Filtering happens after listing, before limit is applied.",
+            serde_json::json!({
+                "type": "object",
+                "properties": {
+                    "event_type": {"type": "string"},
+                    "limit": {"type": "integer"},
+                    "after_sequence": {"type": "integer"},
+                    "as_of": {"type": "integer"},
+                    "filter": filter_schema()
+                },
+                "required": ["event_type"],
+                "additionalProperties": false
+            }),
+        ),
+        ToolDef::new(
+            "strata_event_get_by_time",
+            "Get events whose timestamps fall between start and end (inclusive ISO-8601 \
+             timestamps such as 2024-01-31T12:00:00Z), oldest first. Pass event_type to only \
+             return events of that type and limit to cap the number returned.",
             schema!(object {
-                required: { "event_type": string },
-                optional: { "limit": integer, "after_sequence": integer, "as_of": integer }
+                required: { "start": string, "end": string },
+                optional: { "event_type": string, "limit": integer }
             }),
         ),
         ToolDef::new(
             "strata_event_len",
-            "Get the total count of events in the log.",
+            "Get the total count of events in the log. Deprecated: use strata_event_count.",
             schema!(object {}),
         ),
+        ToolDef::new(
+            "strata_event_count",
+            "Count events without returning them. Pass event_type to count events of that \
+             type (this reads the type's listing); without it, returns the total like \
+             strata_event_len. Returns an integer.",
+            schema!(object {
+                optional: { "event_type": string }
+            }),
+        ),
     ]
 }
 
@@ -67,12 +103,13 @@ pub fn dispatch(
                 payload,
             };
             let output = session.execute(cmd)?;
-            Ok(output_to_json(output))
+            Ok(output_to_json_with(output, session.convert_options()))
         }
 
         "strata_event_get" => {
             let sequence = get_u64_arg(&args, "sequence")?;
             let as_of = get_optional_u64(&args, "as_of");
+            let opts = get_convert_options(&args, session.convert_options());
 
             let cmd = Command::EventGet {
                 branch: session.branch_id(),
@@ -81,7 +118,7 @@ pub fn dispatch(
                 as_of,
             };
             let output = session.execute(cmd)?;
-            Ok(output_to_json(output))
+            Ok(output_to_json_with(output, &opts))
         }
 
         "strata_event_list" => {
@@ -89,17 +126,55 @@ pub fn dispatch(
             let limit = get_optional_u64(&args, "limit");
             let after_sequence = get_optional_u64(&args, "after_sequence");
             let as_of = get_optional_u64(&args, "as_of");
+            let filters = parse_filters(&args)?;
 
             let cmd = Command::EventGetByType {
                 branch: session.branch_id(),
                 space: session.space_id(),
                 event_type,
-                limit,
+                // The limit applies to matching events, so list them all first
+                limit: if filters.is_some() { None } else { limit },
                 after_sequence,
                 as_of,
             };
-            let output = session.execute(cmd)?;
-            Ok(output_to_json(output))
+            let output = match (session.execute(cmd)?, filters) {
+                (Output::VersionedValues(events), Some(filters)) => {
+                    let opts = ConvertOptions::default();
+                    let mut events: Vec<_> = events
+                        .into_iter()
+                        .filter(|vv| {
+                            let payload = value_to_json_with(vv.value.clone(), &opts);
+                            filters.iter().all(|f| payload_matches(&payload, f, &opts))
+                        })
+                        .collect();
+                    if let Some(limit) = limit {
+                        events.truncate(limit as usize);
+                    }
+                    Output::VersionedValues(events)
+                }
+                (output, _) => output,
+            };
+            Ok(output_to_json_with(output, session.convert_options()))
+        }
+
+        "strata_event_get_by_time" => {
+            let range = TimeRangeInput {
+                start: get_string_arg(&args, "start")?,
+                end: get_string_arg(&args, "end")?,
+            };
+            let (start, end) = time_range_micros(&range)?;
+            let event_type = get_optional_string(&args, "event_type");
+            let limit = get_optional_u64(&args, "limit");
+
+            let mut events: Vec<VersionedValue> = all_events(session, event_type)?
+                .into_iter()
+                .filter(|vv| vv.timestamp >= start && vv.timestamp <= end)
+                .collect();
+            if let Some(limit) = limit {
+                events.truncate(limit as usize);
+            }
+            let output = Output::VersionedValues(events);
+            Ok(output_to_json_with(output, session.convert_options()))
         }
 
         "strata_event_len" => {
@@ -108,9 +183,114 @@ pub fn dispatch(
                 space: session.space_id(),
             };
             let output = session.execute(cmd)?;
-            Ok(output_to_json(output))
+            Ok(output_to_json_with(output, session.convert_options()))
+        }
+
+        "strata_event_count" => {
+            let count = match get_optional_string(&args, "event_type") {
+                Some(event_type) => all_events(session, Some(event_type))?.len() as u64,
+                None => match session.execute(Command::EventLen {
+                    branch: session.branch_id(),
+                    space: session.space_id(),
+                })? {
+                    Output::Uint(n) => n,
+                    _ => 0,
+                },
+            };
+            Ok(serde_json::json!(count))
         }
 
         _ => Err(McpError::UnknownTool(name.to_string())),
     }
 }
+
+/// Read every event in the current space, or every event of one type, in sequence order.
+fn all_events(session: &mut McpSession, event_type: Option<String>) -> Result<Vec<VersionedValue>> {
+    if let Some(event_type) = event_type {
+        let cmd = Command::EventGetByType {
+            branch: session.branch_id(),
+            space: session.space_id(),
+            event_type,
+            limit: None,
+            after_sequence: None,
+            as_of: None,
+        };
+        return match session.execute(cmd)? {
+            Output::VersionedValues(events) => Ok(events),
+            _ => Ok(Vec::new()),
+        };
+    }
+
+    let len = match session.execute(Command::EventLen {
+        branch: session.branch_id(),
+        space: session.space_id(),
+    })? {
+        Output::Uint(n) => n,
+        _ => 0,
+    };
+    let mut events = Vec::new();
+    for sequence in 0..len {
+        let cmd = Command::EventGet {
+            branch: session.branch_id(),
+            space: session.space_id(),
+            sequence,
+            as_of: None,
+        };
+        if let Output::MaybeVersioned(Some(vv)) = session.execute(cmd)? {
+            events.push(vv);
+        }
+    }
+    Ok(events)
+}
+
+/// Returns `true` if the payload field named by the filter passes its condition.
+///
+/// A missing field never matches.
+fn payload_matches(payload: &JsonValue, filter: &MetadataFilter, opts: &ConvertOptions) -> bool {
+    let actual = match filter
+        .field
+        .split('.')
+        .try_fold(payload, |v, name| v.get(name))
+    {
+        Some(actual) => actual,
+        None => return false,
+    };
+    let expected = value_to_json_with(filter.value.clone(), opts);
+
+    match filter.op {
+        FilterOp::Eq => json_eq(actual, &expected),
+        FilterOp::Ne => !json_eq(actual, &expected),
+        FilterOp::Gt => compare(actual, &expected) == Some(Ordering::Greater),
+        FilterOp::Gte => matches!(
+            compare(actual, &expected),
+            Some(Ordering::Greater | Ordering::Equal)
+        ),
+        FilterOp::Lt => compare(actual, &expected) == Some(Ordering::Less),
+        FilterOp::Lte => matches!(
+            compare(actual, &expected),
+            Some(Ordering::Less | Ordering::Equal)
+        ),
+        FilterOp::In => expected
+            .as_array()
+            .is_some_and(|options| options.iter().any(|o| json_eq(actual, o))),
+        FilterOp::Contains => match actual {
+            JsonValue::String(s) => expected.as_str().is_some_and(|e| s.contains(e)),
+            JsonValue::Array(items) => items.iter().any(|item| json_eq(item, &expected)),
+            _ => false,
+        },
+    }
+}
+
+/// Compare JSON values, treating integers and floats of the same value as equal.
+fn json_eq(a: &JsonValue, b: &JsonValue) -> bool {
+    a == b || compare(a, b) == Some(Ordering::Equal)
+}
+
+/// Order two numbers or two strings; other pairs are not comparable.
+fn compare(a: &JsonValue, b: &JsonValue) -> Option<Ordering> {
+    match (a, b) {
+        (JsonValue::Number(a), JsonValue::Number(b)) => a.as_f64()?.partial_cmp(&b.as_f64()?),
+        (JsonValue::String(a), JsonValue::String(b)) => Some(a.cmp(b)),
+        _ => None,
+    }
+}
@@ -0,0 +1,260 @@
+//! Expiry of KV keys written with `ttl_secs`.
+//!
+//! stratadb has no native TTL. `strata_kv_put` records which version it
+//! wrote and when that version expires in [`EXPIRY_SPACE`], a reserved space
+//! that space listings leave out and tool arguments cannot name. KV read
+//! tools hide a key whose current version is due; nothing is deleted on
+//! read, so reads stay idempotent and work on read-only sessions.
+//! `strata_kv_sweep_expired` deletes expired keys and their records. A later
+//! write without `ttl_secs` creates a new version, leaving the record stale
+//! until the next sweep.
+
+use std::collections::HashMap;
+
+use serde_json::Value as JsonValue;
+use stratadb::{BranchId, Command, Output};
+
+use crate::convert::{json_to_value, value_to_json};
+use crate::error::Result;
+use crate::session::McpSession;
+use crate::tools::retention::now_micros;
+
+/// Reserved space holding expiry records, one per key written with a TTL.
+pub const EXPIRY_SPACE: &str = "_mcp_kv_expiry";
+
+/// When one version of a key expires.
+struct ExpiryRecord {
+    space: String,
+    key: String,
+    version: u64,
+    expires_at: u64,
+}
+
+impl ExpiryRecord {
+    fn from_json(record: &JsonValue) -> Option<Self> {
+        Some(Self {
+            space: record.get("space")?.as_str()?.to_string(),
+            key: record.get("key")?.as_str()?.to_string(),
+            version: record.get("version")?.as_u64()?,
+            expires_at: record.get("expires_at")?.as_u64()?,
+        })
+    }
+
+    /// Whether `version` is the recorded one and has expired by `at`.
+    fn expires(&self, version: u64, at: u64) -> bool {
+        self.version == version && self.expires_at <= at
+    }
+}
+
+/// Key of the expiry record for `key` in `space`.
+fn record_key(space: &str, key: &str) -> String {
+    format!("{}/{}", space, key)
+}
+
+/// Record that `version` of `key` in the current space expires in `ttl_secs`.
+pub fn set_expiry(session: &mut McpSession, key: &str, version: u64, ttl_secs: u64) -> Result<()> {
+    let expires_at = now_micros().saturating_add(ttl_secs.saturating_mul(1_000_000));
+    let record = serde_json::json!({
+        "space": session.space(),
+        "key": key,
+        "version": version,
+        "expires_at": expires_at,
+    });
+    session.execute(Command::KvPut {
+        branch: session.branch_id(),
+        space: Some(EXPIRY_SPACE.to_string()),
+        key: record_key(session.space(), key),
+        value: json_to_value(record)?,
+    })?;
+    Ok(())
+}
+
+/// Read the expiry record for `key` in `space` of `branch`, if any.
+fn read_record(
+    session: &mut McpSession,
+    branch: Option<BranchId>,
+    space: &str,
+    key: &str,
+) -> Result<Option<ExpiryRecord>> {
+    let cmd = Command::KvGet {
+        branch,
+        space: Some(EXPIRY_SPACE.to_string()),
+        key: record_key(space, key),
+        as_of: None,
+    };
+    Ok(match session.execute(cmd)? {
+        Output::MaybeVersioned(Some(vv)) => ExpiryRecord::from_json(&value_to_json(vv.value)),
+        _ => None,
+    })
+}
+
+/// Replace a `KvGet` output with "not found" if the version it read has
+/// expired by `as_of`, or by now when `as_of` is not given.
+pub fn filter_get(
+    session: &mut McpSession,
+    branch: Option<BranchId>,
+    space: &str,
+    key: &str,
+    as_of: Option<u64>,
+    output: Output,
+) -> Result<Output> {
+    let version = match &output {
+        Output::MaybeVersioned(Some(vv)) => vv.version,
+        _ => return Ok(output),
+    };
+    match read_record(session, branch, space, key)? {
+        Some(record) if record.expires(version, as_of.unwrap_or_else(now_micros)) => {
+            Ok(Output::MaybeVersioned(None))
+        }
+        _ => Ok(output),
+    }
+}
+
+/// Drop an expired version from a `KvGetv` output of a key in the current space.
+pub fn filter_history(
+    session: &mut McpSession,
+    key: &str,
+    as_of: Option<u64>,
+    output: Output,
+) -> Result<Output> {
+    let Output::VersionHistory(Some(mut values)) = output else {
+        return Ok(output);
+    };
+    let (branch, space) = (session.branch_id(), session.space().to_string());
+    if let Some(record) = read_record(session, branch, &space, key)? {
+        let at = as_of.unwrap_or_else(now_micros);
+        values.retain(|vv| !record.expires(vv.version, at));
+    }
+    Ok(Output::VersionHistory(Some(values)))
+}
+
+/// Keep the keys of the current space whose listed version has not expired.
+///
+/// Only keys with an expiry record are read again, so a space without TTL
+/// keys costs one extra list.
+pub fn live_keys(
+    session: &mut McpSession,
+    keys: Vec<String>,
+    as_of: Option<u64>,
+) -> Result<Vec<String>> {
+    let records = space_records(session)?;
+    if records.is_empty() {
+        return Ok(keys);
+    }
+    let at = as_of.unwrap_or_else(now_micros);
+    let mut live = Vec::with_capacity(keys.len());
+    for key in keys {
+        if let Some(record) = records.get(&key) {
+            let cmd = Command::KvGet {
+                branch: session.branch_id(),
+                space: session.space_id(),
+                key: key.clone(),
+                as_of,
+            };
+            if let Output::MaybeVersioned(Some(vv)) = session.execute(cmd)? {
+                if record.expires(vv.version, at) {
+                    continue;
+                }
+            }
+        }
+        live.push(key);
+    }
+    Ok(live)
+}
+
+/// Expiry records of the current space, keyed by the key they cover.
+fn space_records(session: &mut McpSession) -> Result<HashMap<String, ExpiryRecord>> {
+    let space = session.space().to_string();
+    Ok(all_records(session, Some(record_key(&space, "")))?
+        .into_iter()
+        .filter(|record| record.space == space)
+        .map(|record| (record.key.clone(), record))
+        .collect())
+}
+
+/// Every expiry record in the current branch whose record key has `prefix`.
+fn all_records(session: &mut McpSession, prefix: Option<String>) -> Result<Vec<ExpiryRecord>> {
+    let cmd = Command::KvList {
+        branch: session.branch_id(),
+        space: Some(EXPIRY_SPACE.to_string()),
+        prefix,
+        cursor: None,
+        limit: None,
+        as_of: None,
+    };
+    let record_keys = match session.execute(cmd)? {
+        Output::Keys(keys) => keys,
+        _ => return Ok(Vec::new()),
+    };
+    let mut records = Vec::with_capacity(record_keys.len());
+    for record_key in record_keys {
+        let cmd = Command::KvGet {
+            branch: session.branch_id(),
+            space: Some(EXPIRY_SPACE.to_string()),
+            key: record_key,
+            as_of: None,
+        };
+        if let Output::MaybeVersioned(Some(vv)) = session.execute(cmd)? {
+            records.extend(ExpiryRecord::from_json(&value_to_json(vv.value)));
+        }
+    }
+    Ok(records)
+}
+
+/// Delete expired keys in every space of the current branch, and drop
+/// records whose key was rewritten or deleted since.
+///
+/// Returns `{keys_deleted, records_removed}`.
+pub fn sweep(session: &mut McpSession) -> Result<JsonValue> {
+    session.check_write_access("KvDelete")?;
+    session.atomically(|session| {
+        let now = now_micros();
+        let (mut keys_deleted, mut records_removed) = (0u64, 0u64);
+        for record in all_records(session, None)? {
+            let current = session.execute(Command::KvGet {
+                branch: session.branch_id(),
+                space: Some(record.space.clone()),
+                key: record.key.clone(),
+                as_of: None,
+            })?;
+            let version = match current {
+                Output::MaybeVersioned(Some(vv)) => Some(vv.version),
+                _ => None,
+            };
+            match version {
+                // Still the recorded version and not yet due
+                Some(v) if v == record.version && record.expires_at > now => continue,
+                Some(v) if v == record.version => {
+                    session.execute(Command::KvDelete {
+                        branch: session.branch_id(),
+                        space: Some(record.space.clone()),
+                        key: record.key.clone(),
+                    })?;
+                    keys_deleted += 1;
+                }
+                _ => {}
+            }
+            session.execute(Command::KvDelete {
+                branch: session.branch_id(),
+                space: Some(EXPIRY_SPACE.to_string()),
+                key: record_key(&record.space, &record.key),
+            })?;
+            records_removed += 1;
+        }
+        Ok(serde_json::json!({
+            "keys_deleted": keys_deleted,
+            "records_removed": records_removed,
+        }))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::session::is_reserved_space;
+
+    #[test]
+    fn test_expiry_space_is_reserved() {
+        assert!(is_reserved_space(EXPIRY_SPACE));
+    }
+}
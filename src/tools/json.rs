@@ -1,16 +1,20 @@
 //! JSON document store tools.
 //!
-//! Tools: strata_json_set, strata_json_get, strata_json_delete, strata_json_list, strata_json_history
+//! Tools: strata_json_set, strata_json_get, strata_json_delete, strata_json_list, strata_json_history,
+//!        strata_json_numincr, strata_json_get_many, strata_json_set_many, strata_json_exists,
+//!        strata_json_path_count, strata_json_delete_many
 
 use serde_json::{Map, Value as JsonValue};
-use stratadb::Command;
+use stratadb::{Command, Output, Value};
 
 use crate::convert::{
-    get_optional_string, get_optional_u64, get_string_arg, get_value_arg, output_to_json,
+    get_convert_options, get_optional_bool, get_optional_string, get_optional_u64, get_string_arg,
+    get_value_arg, history_window, json_to_value, output_to_json_with, value_to_json_with,
 };
 use crate::error::{McpError, Result};
 use crate::schema;
 use crate::session::McpSession;
+use crate::tools::key_filter::KeyFilter;
 use crate::tools::ToolDef;
 
 /// Get all JSON tool definitions.
@@ -26,6 +30,47 @@ pub fn tools() -> Vec<ToolDef> {
         ToolDef::new(
             "strata_json_get",
             "Get a value at a JSONPath from a document. Use '$' for the entire document. Returns null if not found. \
+             Pass as_of (microsecond timestamp) for time-travel reads. \
+             Pass raw=true to return the bare value without version info. \
+             Pass multi=true to return an array of every match, for paths with wildcards \
+             such as $.items[*].name (supports .field, ['field'], [index], .* and [*]). \
+             Pass distinguish_missing=true to get {found: false} when nothing is at the path \
+             and found: true alongside the value otherwise.",
+            schema!(object {
+                required: { "key": string, "path": string },
+                optional: {
+                    "as_of": integer,
+                    "raw": boolean,
+                    "multi": boolean,
+                    "distinguish_missing": boolean
+                }
+            }),
+        ),
+        ToolDef::new(
+            "strata_json_get_many",
+            "Get several JSONPaths from one document in a single call. Returns an object \
+             mapping each path to its value (null for missing paths). \
+             Pass as_of (microsecond timestamp) for time-travel reads.",
+            schema!(object {
+                required: { "key": string, "paths": array_string },
+                optional: { "as_of": integer }
+            }),
+        ),
+        ToolDef::new(
+            "strata_json_exists",
+            "Check whether a JSONPath exists in a document without returning its value. \
+             A path holding JSON null exists. Returns true/false. \
+             Pass as_of (microsecond timestamp) for time-travel reads.",
+            schema!(object {
+                required: { "key": string, "path": string },
+                optional: { "as_of": integer }
+            }),
+        ),
+        ToolDef::new(
+            "strata_json_path_count",
+            "Count the elements at a JSONPath without returning them: the length of an array \
+             or the number of keys in an object. Returns null if the path does not exist and \
+             fails with WRONG_TYPE if the value is not an array or object. \
              Pass as_of (microsecond timestamp) for time-travel reads.",
             schema!(object {
                 required: { "key": string, "path": string },
@@ -39,21 +84,55 @@ pub fn tools() -> Vec<ToolDef> {
                 required: { "key": string, "path": string }
             }),
         ),
+        ToolDef::new(
+            "strata_json_delete_many",
+            "Delete multiple JSON documents (each at path $) in one transaction. Returns \
+             array of counts of elements removed (0 or 1 per key).",
+            schema!(object {
+                required: { "keys": array_string }
+            }),
+        ),
         ToolDef::new(
             "strata_json_list",
             "List JSON document keys with optional prefix filter and cursor-based pagination. \
-             Pass as_of (microsecond timestamp) for time-travel reads.",
+             Pass as_of (microsecond timestamp) for time-travel reads. \
+             Set case_insensitive to match the prefix ignoring case, or pass a glob \
+             (* matches any characters, ? one character); these filter after listing, \
+             so they read every key under the prefix and are slower on large keyspaces.",
             schema!(object {
-                optional: { "prefix": string, "cursor": string, "limit": integer, "as_of": integer }
+                optional: {
+                    "prefix": string, "cursor": string, "limit": integer, "as_of": integer,
+                    "case_insensitive": boolean, "glob": string
+                }
             }),
         ),
         ToolDef::new(
             "strata_json_history",
             "Get the full version history for a JSON document. \
-             Pass as_of (microsecond timestamp) to get history up to that point.",
+             Pass as_of (microsecond timestamp) to get history up to that point. \
+             Pass limit to return only the most recent N versions, newest first, and \
+             since_version to return only versions newer than one already seen.",
             schema!(object {
                 required: { "key": string },
-                optional: { "as_of": integer }
+                optional: { "as_of": integer, "limit": integer, "since_version": integer }
+            }),
+        ),
+        ToolDef::new(
+            "strata_json_numincr",
+            "Atomically add `by` to the number at a JSONPath in a document. Use a negative \
+             `by` to decrement. A missing path starts from 0. Returns the new number. \
+             Fails with WRONG_TYPE if the existing value is not a number.",
+            schema!(object {
+                required: { "key": string, "path": string, "by": number }
+            }),
+        ),
+        ToolDef::new(
+            "strata_json_set_many",
+            "Set values in multiple JSON documents atomically. Each item is {key, path, value}. \
+             All items are validated before anything is written, and the writes run in \
+             a transaction. Returns array of version numbers.",
+            schema!(object {
+                required: { "items": array_object }
             }),
         ),
     ]
@@ -68,7 +147,7 @@ pub fn dispatch(
     match name {
         "strata_json_set" => {
             let key = get_string_arg(&args, "key")?;
-            let path = get_string_arg(&args, "path")?;
+            let path = get_path_arg(&args, "path")?;
             let value = get_value_arg(&args, "value")?;
 
             let cmd = Command::JsonSet {
@@ -79,13 +158,32 @@ pub fn dispatch(
                 value,
             };
             let output = session.execute(cmd)?;
-            Ok(output_to_json(output))
+            Ok(output_to_json_with(output, session.convert_options()))
         }
 
         "strata_json_get" => {
             let key = get_string_arg(&args, "key")?;
-            let path = get_string_arg(&args, "path")?;
+            let path = get_path_arg(&args, "path")?;
             let as_of = get_optional_u64(&args, "as_of");
+            let opts = get_convert_options(&args, session.convert_options());
+
+            if get_optional_bool(&args, "multi").unwrap_or(false) {
+                let steps = parse_path(&path)?;
+                let cmd = Command::JsonGet {
+                    branch: session.branch_id(),
+                    space: session.space_id(),
+                    key,
+                    path: "$".to_string(),
+                    as_of,
+                };
+                let matches = match session.execute(cmd)? {
+                    Output::MaybeVersioned(Some(vv)) => {
+                        select_all(value_to_json_with(vv.value, &opts), &steps)
+                    }
+                    _ => Vec::new(),
+                };
+                return Ok(JsonValue::Array(matches));
+            }
 
             let cmd = Command::JsonGet {
                 branch: session.branch_id(),
@@ -95,12 +193,91 @@ pub fn dispatch(
                 as_of,
             };
             let output = session.execute(cmd)?;
-            Ok(output_to_json(output))
+            Ok(output_to_json_with(output, &opts))
+        }
+
+        "strata_json_exists" => {
+            let key = get_string_arg(&args, "key")?;
+            let path = get_path_arg(&args, "path")?;
+            let as_of = get_optional_u64(&args, "as_of");
+
+            let cmd = Command::JsonGet {
+                branch: session.branch_id(),
+                space: session.space_id(),
+                key,
+                path,
+                as_of,
+            };
+            // A stored JSON null comes back as Some(Value::Null), distinct from a missing path
+            let exists = matches!(session.execute(cmd)?, Output::MaybeVersioned(Some(_)));
+            Ok(JsonValue::Bool(exists))
+        }
+
+        "strata_json_path_count" => {
+            let key = get_string_arg(&args, "key")?;
+            let path = get_path_arg(&args, "path")?;
+            let as_of = get_optional_u64(&args, "as_of");
+
+            let cmd = Command::JsonGet {
+                branch: session.branch_id(),
+                space: session.space_id(),
+                key,
+                path: path.clone(),
+                as_of,
+            };
+            match session.execute(cmd)? {
+                Output::MaybeVersioned(Some(vv)) => match vv.value {
+                    Value::Array(items) => Ok(serde_json::json!(items.len())),
+                    Value::Object(fields) => Ok(serde_json::json!(fields.len())),
+                    _ => Err(McpError::Strata {
+                        code: "WRONG_TYPE".to_string(),
+                        message: format!("value at {} is not an array or object", path),
+                    }),
+                },
+                _ => Ok(JsonValue::Null),
+            }
+        }
+
+        "strata_json_get_many" => {
+            let key = get_string_arg(&args, "key")?;
+            let paths = args
+                .get("paths")
+                .and_then(|v| v.as_array())
+                .ok_or_else(|| McpError::MissingArg("paths".to_string()))?;
+            let as_of = get_optional_u64(&args, "as_of");
+
+            let mut results = Map::new();
+            for path_value in paths {
+                let path = path_value
+                    .as_str()
+                    .ok_or_else(|| McpError::InvalidArg {
+                        name: "paths".to_string(),
+                        reason: "Paths must be strings".to_string(),
+                    })?
+                    .to_string();
+                validate_path("paths", &path)?;
+
+                let cmd = Command::JsonGet {
+                    branch: session.branch_id(),
+                    space: session.space_id(),
+                    key: key.clone(),
+                    path: path.clone(),
+                    as_of,
+                };
+                let value = match session.execute(cmd)? {
+                    Output::MaybeVersioned(Some(vv)) => {
+                        value_to_json_with(vv.value, session.convert_options())
+                    }
+                    _ => JsonValue::Null,
+                };
+                results.insert(path, value);
+            }
+            Ok(JsonValue::Object(results))
         }
 
         "strata_json_delete" => {
             let key = get_string_arg(&args, "key")?;
-            let path = get_string_arg(&args, "path")?;
+            let path = get_path_arg(&args, "path")?;
 
             let cmd = Command::JsonDelete {
                 branch: session.branch_id(),
@@ -109,30 +286,68 @@ pub fn dispatch(
                 path,
             };
             let output = session.execute(cmd)?;
-            Ok(output_to_json(output))
+            Ok(output_to_json_with(output, session.convert_options()))
+        }
+
+        "strata_json_delete_many" => {
+            let keys = args
+                .get("keys")
+                .and_then(|v| v.as_array())
+                .ok_or_else(|| McpError::MissingArg("keys".to_string()))?;
+            let keys = keys
+                .iter()
+                .map(|v| {
+                    v.as_str().map(String::from).ok_or_else(|| McpError::InvalidArg {
+                        name: "keys".to_string(),
+                        reason: "Keys must be strings".to_string(),
+                    })
+                })
+                .collect::<Result<Vec<_>>>()?;
+
+            session.check_write_access("JsonDeleteMany")?;
+            session.atomically(|session| {
+                let mut results = Vec::new();
+                for key in keys {
+                    let cmd = Command::JsonDelete {
+                        branch: session.branch_id(),
+                        space: session.space_id(),
+                        key,
+                        path: "$".to_string(),
+                    };
+                    let output = session.execute(cmd)?;
+                    results.push(output_to_json_with(output, session.convert_options()));
+                }
+                Ok(JsonValue::Array(results))
+            })
         }
 
         "strata_json_list" => {
-            let prefix = get_optional_string(&args, "prefix");
+            let filter = KeyFilter::from_args(&args);
             let cursor = get_optional_string(&args, "cursor");
             let limit = get_optional_u64(&args, "limit").unwrap_or(100);
             let as_of = get_optional_u64(&args, "as_of");
 
+            if filter.is_active() {
+                return json_list_filtered(session, &filter, cursor, limit, as_of);
+            }
+
             let cmd = Command::JsonList {
                 branch: session.branch_id(),
                 space: session.space_id(),
-                prefix,
+                prefix: filter.native_prefix(),
                 cursor,
                 limit,
                 as_of,
             };
             let output = session.execute(cmd)?;
-            Ok(output_to_json(output))
+            Ok(output_to_json_with(output, session.convert_options()))
         }
 
         "strata_json_history" => {
             let key = get_string_arg(&args, "key")?;
             let as_of = get_optional_u64(&args, "as_of");
+            let limit = get_optional_u64(&args, "limit");
+            let since_version = get_optional_u64(&args, "since_version");
 
             let cmd = Command::JsonGetv {
                 branch: session.branch_id(),
@@ -140,10 +355,466 @@ pub fn dispatch(
                 key,
                 as_of,
             };
-            let output = session.execute(cmd)?;
-            Ok(output_to_json(output))
+            let output = history_window(session.execute(cmd)?, since_version, limit);
+            Ok(output_to_json_with(output, session.convert_options()))
+        }
+
+        "strata_json_numincr" => {
+            let key = get_string_arg(&args, "key")?;
+            let path = get_path_arg(&args, "path")?;
+            let by = args
+                .get("by")
+                .and_then(|v| v.as_number())
+                .cloned()
+                .ok_or_else(|| McpError::MissingArg("by".to_string()))?;
+
+            session.check_write_access("JsonNumIncr")?;
+            session.atomically(|session| {
+                let cmd = Command::JsonGet {
+                    branch: session.branch_id(),
+                    space: session.space_id(),
+                    key: key.clone(),
+                    path: path.clone(),
+                    as_of: None,
+                };
+                let current = match session.execute(cmd)? {
+                    Output::MaybeVersioned(Some(vv)) => vv.value,
+                    _ => Value::Int(0),
+                };
+                let value = increment(current, &by, &path)?;
+
+                let cmd = Command::JsonSet {
+                    branch: session.branch_id(),
+                    space: session.space_id(),
+                    key,
+                    path,
+                    value: value.clone(),
+                };
+                session.execute(cmd)?;
+                Ok(value_to_json_with(value, session.convert_options()))
+            })
+        }
+
+        "strata_json_set_many" => {
+            let items = args
+                .get("items")
+                .and_then(|v| v.as_array())
+                .ok_or_else(|| McpError::MissingArg("items".to_string()))?;
+
+            let mut writes = Vec::with_capacity(items.len());
+            for (i, item) in items.iter().enumerate() {
+                let field = |name: &str| {
+                    item
+                        .get(name)
+                        .and_then(|v| v.as_str())
+                        .map(String::from)
+                        .ok_or_else(|| McpError::InvalidArg {
+                            name: format!("items[{}].{}", i, name),
+                            reason: format!("Each item must have a '{}' string field", name),
+                        })
+                };
+                let key = field("key")?;
+                let path = field("path")?;
+                validate_path(&format!("items[{}].path", i), &path)?;
+                let value_json = item.get("value").cloned().ok_or_else(|| McpError::InvalidArg {
+                    name: format!("items[{}].value", i),
+                    reason: "Each item must have a 'value' field".to_string(),
+                })?;
+                writes.push((key, path, json_to_value(value_json)?));
+            }
+
+            session.check_write_access("JsonSetMany")?;
+            session.atomically(|session| {
+                let mut versions = Vec::new();
+                for (key, path, value) in writes {
+                    let cmd = Command::JsonSet {
+                        branch: session.branch_id(),
+                        space: session.space_id(),
+                        key,
+                        path,
+                        value,
+                    };
+                    let output = session.execute(cmd)?;
+                    versions.push(output_to_json_with(output, session.convert_options()));
+                }
+                Ok(JsonValue::Array(versions))
+            })
         }
 
         _ => Err(McpError::UnknownTool(name.to_string())),
     }
 }
+
+/// Documents read per JsonList call when listing every key for a filter.
+const FILTER_PAGE_SIZE: u64 = 1000;
+
+/// List JSON keys matching a post-listing filter, one page at a time.
+///
+/// The returned cursor is the last key of the page, or absent on the last page.
+fn json_list_filtered(
+    session: &mut McpSession,
+    filter: &KeyFilter,
+    mut cursor: Option<String>,
+    limit: u64,
+    as_of: Option<u64>,
+) -> Result<JsonValue> {
+    let mut keys = Vec::new();
+    loop {
+        let cmd = Command::JsonList {
+            branch: session.branch_id(),
+            space: session.space_id(),
+            prefix: filter.native_prefix(),
+            cursor: cursor.take(),
+            limit: FILTER_PAGE_SIZE,
+            as_of,
+        };
+        match session.execute(cmd)? {
+            Output::JsonListResult {
+                keys: page,
+                cursor: next,
+            } => {
+                keys.extend(filter.apply(page));
+                match next {
+                    Some(next) if (keys.len() as u64) <= limit => cursor = Some(next),
+                    _ => break,
+                }
+            }
+            _ => {
+                return Err(McpError::Internal(
+                    "Unexpected output for JsonList".to_string(),
+                ))
+            }
+        }
+    }
+
+    let more = keys.len() as u64 > limit;
+    keys.truncate(limit as usize);
+    let cursor = if more { keys.last().cloned() } else { None };
+    let output = Output::JsonListResult { keys, cursor };
+    Ok(output_to_json_with(output, session.convert_options()))
+}
+
+/// Get a required JSONPath argument, checking its syntax.
+fn get_path_arg(args: &Map<String, JsonValue>, name: &str) -> Result<String> {
+    let path = get_string_arg(args, name)?;
+    validate_path(name, &path)?;
+    Ok(path)
+}
+
+/// Check JSONPath syntax before a path is sent to the engine.
+///
+/// Accepts dot and bracket field access, wildcards, recursive descent,
+/// indexes, unions, slices like `[1:3]`, and filters like `[?(@.x > 1)]`.
+/// Whether the engine evaluates a given form is still up to the engine; this
+/// only rejects malformed paths early, naming the offending token.
+fn validate_path(name: &str, path: &str) -> Result<()> {
+    let invalid = |pos: usize, token: &str, reason: &str| McpError::InvalidArg {
+        name: name.to_string(),
+        reason: format!("{} at position {} ('{}') in '{}'", reason, pos, token, path),
+    };
+    let chars: Vec<char> = path.chars().collect();
+    if chars.first() != Some(&'$') {
+        let token: String = chars.iter().take(1).collect();
+        return Err(invalid(0, &token, "Path must start with '$'"));
+    }
+
+    let mut i = 1;
+    while i < chars.len() {
+        match chars[i] {
+            '.' => {
+                // `..` is recursive descent, which may be followed directly by brackets
+                let recursive = chars.get(i + 1) == Some(&'.');
+                let start = if recursive { i + 2 } else { i + 1 };
+                if recursive && chars.get(start) == Some(&'[') {
+                    i = start;
+                    continue;
+                }
+                let end = chars[start..]
+                    .iter()
+                    .position(|&c| c == '.' || c == '[')
+                    .map_or(chars.len(), |p| start + p);
+                let field: String = chars[start..end].iter().collect();
+                if field.is_empty() {
+                    let token: String = chars[i..start].iter().collect();
+                    return Err(invalid(i, &token, "Expected a field name"));
+                }
+                if let Some(bad) = field
+                    .chars()
+                    .position(|c| c.is_whitespace() || "]()'\"?,:@*".contains(c))
+                    .filter(|_| field != "*")
+                {
+                    let reason = "Invalid character in field name";
+                    return Err(invalid(start + bad, &field, reason));
+                }
+                i = end;
+            }
+            '[' => {
+                let end = closing_bracket(&chars, i).ok_or_else(|| {
+                    let token: String = chars[i..].iter().collect();
+                    invalid(i, &token, "Unclosed '['")
+                })?;
+                let inner: String = chars[i + 1..end].iter().collect();
+                if let Err(reason) = validate_selector(&inner) {
+                    return Err(invalid(i, &format!("[{}]", inner), reason));
+                }
+                i = end + 1;
+            }
+            c => return Err(invalid(i, &c.to_string(), "Expected '.' or '['")),
+        }
+    }
+    Ok(())
+}
+
+/// Position of the `]` closing the `[` at `open`, skipping quoted text and
+/// nested brackets.
+fn closing_bracket(chars: &[char], open: usize) -> Option<usize> {
+    let mut depth = 0usize;
+    let mut quote: Option<char> = None;
+    for (i, &c) in chars.iter().enumerate().skip(open + 1) {
+        match (quote, c) {
+            (Some(q), c) if c == q => quote = None,
+            (Some(_), _) => {}
+            (None, '\'' | '"') => quote = Some(c),
+            (None, '[') => depth += 1,
+            (None, ']') if depth == 0 => return Some(i),
+            (None, ']') => depth -= 1,
+            _ => {}
+        }
+    }
+    None
+}
+
+/// Check the contents of one bracket segment.
+fn validate_selector(inner: &str) -> std::result::Result<(), &'static str> {
+    let inner = inner.trim();
+    if inner.is_empty() {
+        return Err("Empty brackets");
+    }
+    if let Some(expr) = inner.strip_prefix('?') {
+        let expr = expr.trim();
+        if expr.is_empty() || expr == "()" {
+            return Err("Empty filter expression");
+        }
+        if !balanced(expr) {
+            return Err("Unbalanced parentheses or quotes in filter");
+        }
+        return Ok(());
+    }
+
+    for part in split_union(inner) {
+        let part = part.trim();
+        let quoted = ['\'', '"']
+            .iter()
+            .any(|&q| part.len() >= 2 && part.starts_with(q) && part.ends_with(q));
+        if part.is_empty() {
+            return Err("Empty selector in union");
+        } else if quoted || part == "*" || part.parse::<i64>().is_ok() {
+            continue;
+        } else if part.starts_with(['\'', '"']) {
+            return Err("Unclosed quote");
+        } else if part.contains(':') {
+            let bounds: Vec<&str> = part.split(':').collect();
+            let numeric = bounds
+                .iter()
+                .all(|b| b.trim().is_empty() || b.trim().parse::<i64>().is_ok());
+            if bounds.len() > 3 || !numeric {
+                return Err("Invalid slice; expected [start:end] or [start:end:step]");
+            }
+        } else {
+            return Err("Invalid selector; expected an index, slice, quoted name, '*', or filter");
+        }
+    }
+    Ok(())
+}
+
+/// Split a union selector on commas outside quotes.
+fn split_union(inner: &str) -> Vec<&str> {
+    let mut parts = Vec::new();
+    let mut quote: Option<char> = None;
+    let mut start = 0;
+    for (i, c) in inner.char_indices() {
+        match (quote, c) {
+            (Some(q), c) if c == q => quote = None,
+            (Some(_), _) => {}
+            (None, '\'' | '"') => quote = Some(c),
+            (None, ',') => {
+                parts.push(&inner[start..i]);
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    parts.push(&inner[start..]);
+    parts
+}
+
+/// Whether parentheses balance and every quote is closed.
+fn balanced(expr: &str) -> bool {
+    let mut depth = 0i32;
+    let mut quote: Option<char> = None;
+    for c in expr.chars() {
+        match (quote, c) {
+            (Some(q), c) if c == q => quote = None,
+            (Some(_), _) => {}
+            (None, '\'' | '"') => quote = Some(c),
+            (None, '(') => depth += 1,
+            (None, ')') => {
+                depth -= 1;
+                if depth < 0 {
+                    return false;
+                }
+            }
+            _ => {}
+        }
+    }
+    depth == 0 && quote.is_none()
+}
+
+/// One step of a JSONPath expression.
+enum PathStep {
+    Field(String),
+    Index(usize),
+    Wildcard,
+}
+
+/// Parse a JSONPath into steps for multi-match evaluation.
+fn parse_path(path: &str) -> Result<Vec<PathStep>> {
+    let invalid = |reason: &str| McpError::InvalidArg {
+        name: "path".to_string(),
+        reason: format!("{} in '{}'", reason, path),
+    };
+    let rest = path
+        .strip_prefix('$')
+        .ok_or_else(|| invalid("Path must start with '$'"))?;
+    let chars: Vec<char> = rest.chars().collect();
+
+    let mut steps = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        match chars[i] {
+            '.' => {
+                let end = chars[i + 1..]
+                    .iter()
+                    .position(|&c| c == '.' || c == '[')
+                    .map_or(chars.len(), |p| i + 1 + p);
+                let name: String = chars[i + 1..end].iter().collect();
+                if name.is_empty() {
+                    return Err(invalid("Empty field name"));
+                }
+                steps.push(if name == "*" {
+                    PathStep::Wildcard
+                } else {
+                    PathStep::Field(name)
+                });
+                i = end;
+            }
+            '[' => {
+                let end = chars[i..]
+                    .iter()
+                    .position(|&c| c == ']')
+                    .map(|p| i + p)
+                    .ok_or_else(|| invalid("Unclosed '['"))?;
+                let inner: String = chars[i + 1..end].iter().collect();
+                let inner = inner.trim();
+                let quoted = ['\'', '"']
+                    .iter()
+                    .find_map(|&q| inner.strip_prefix(q).and_then(|s| s.strip_suffix(q)));
+                steps.push(match quoted {
+                    Some(name) => PathStep::Field(name.to_string()),
+                    None if inner == "*" => PathStep::Wildcard,
+                    None => PathStep::Index(inner.parse().map_err(|_| invalid("Invalid index"))?),
+                });
+                i = end + 1;
+            }
+            _ => return Err(invalid("Expected '.' or '['")),
+        }
+    }
+    Ok(steps)
+}
+
+/// Collect every value in `doc` matched by the path steps.
+fn select_all(doc: JsonValue, steps: &[PathStep]) -> Vec<JsonValue> {
+    let mut current = vec![doc];
+    for step in steps {
+        let mut next = Vec::new();
+        for value in current {
+            match (step, value) {
+                (PathStep::Field(name), JsonValue::Object(mut obj)) => {
+                    next.extend(obj.remove(name));
+                }
+                (PathStep::Index(i), JsonValue::Array(arr)) => next.extend(arr.into_iter().nth(*i)),
+                (PathStep::Wildcard, JsonValue::Array(arr)) => next.extend(arr),
+                (PathStep::Wildcard, JsonValue::Object(obj)) => next.extend(obj.into_values()),
+                _ => {}
+            }
+        }
+        current = next;
+    }
+    current
+}
+
+/// Add `by` to a numeric value, keeping integers exact when possible.
+fn increment(current: Value, by: &serde_json::Number, path: &str) -> Result<Value> {
+    match (current, by.as_i64()) {
+        (Value::Int(n), Some(by)) => n.checked_add(by).map(Value::Int).ok_or_else(|| {
+            McpError::Strata {
+                code: "OVERFLOW".to_string(),
+                message: format!("integer overflow incrementing {}", path),
+            }
+        }),
+        (Value::Int(n), None) => Ok(Value::Float(n as f64 + by.as_f64().unwrap_or(0.0))),
+        (Value::Float(f), _) => {
+            let sum = f + by.as_f64().unwrap_or(0.0);
+            if !sum.is_finite() {
+                return Err(McpError::Strata {
+                    code: "OVERFLOW".to_string(),
+                    message: format!("float overflow incrementing {}", path),
+                });
+            }
+            Ok(Value::Float(sum))
+        }
+        _ => Err(McpError::Strata {
+            code: "WRONG_TYPE".to_string(),
+            message: format!("value at {} is not a number", path),
+        }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validate_path_accepts_slices_and_filters() {
+        for path in [
+            "$",
+            "$.items[1:3]",
+            "$.items[::2]",
+            "$.items[-1]",
+            "$.items[0,2]",
+            "$['a b'].c",
+            "$..name",
+            "$..[0]",
+            "$.items[*].id",
+            "$.items[?(@.x > 1)]",
+            "$.items[?(@.name == 'a]b')]",
+        ] {
+            assert!(validate_path("path", path).is_ok(), "{}", path);
+        }
+    }
+
+    #[test]
+    fn test_validate_path_names_bad_token() {
+        let reason = |path: &str| match validate_path("path", path) {
+            Err(McpError::InvalidArg { reason, .. }) => reason,
+            other => panic!("expected InvalidArg for {}, got {:?}", path, other.err()),
+        };
+        assert!(reason("items").contains("start with '$'"));
+        assert!(reason("$.items[1:x]").contains("'[1:x]'"));
+        assert!(reason("$.items[1").contains("Unclosed '['"));
+        assert!(reason("$.items[?(@.x > 1]").contains("Unbalanced"));
+        assert!(reason("$.a..").contains("Expected a field name"));
+        assert!(reason("$.a b").contains("'a b'"));
+        assert!(reason("$.items[]").contains("Empty brackets"));
+    }
+}
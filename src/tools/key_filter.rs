@@ -0,0 +1,128 @@
+//! Key filters for list tools.
+//!
+//! Storage matches list prefixes exactly. A `case_insensitive` prefix or a
+//! `glob` pattern cannot be pushed down, so it is applied here after listing:
+//! a filtered call reads every key under the native prefix before `limit`
+//! is applied, which is slower than a plain prefix list on large keyspaces.
+
+use serde_json::{Map, Value as JsonValue};
+
+use crate::convert::{get_optional_bool, get_optional_string};
+
+/// Prefix and pattern filters read from list tool arguments.
+pub struct KeyFilter {
+    prefix: Option<String>,
+    glob: Option<String>,
+    case_insensitive: bool,
+}
+
+impl KeyFilter {
+    /// Read `prefix`, `glob`, and `case_insensitive` from list arguments.
+    pub fn from_args(args: &Map<String, JsonValue>) -> Self {
+        Self {
+            prefix: get_optional_string(args, "prefix"),
+            glob: get_optional_string(args, "glob"),
+            case_insensitive: get_optional_bool(args, "case_insensitive").unwrap_or(false),
+        }
+    }
+
+    /// Returns `true` if keys must be filtered after listing.
+    pub fn is_active(&self) -> bool {
+        self.glob.is_some() || (self.case_insensitive && self.prefix.is_some())
+    }
+
+    /// Prefix to pass to the list command.
+    ///
+    /// A case-insensitive prefix is matched after listing instead.
+    pub fn native_prefix(&self) -> Option<String> {
+        if self.case_insensitive {
+            None
+        } else {
+            self.prefix.clone()
+        }
+    }
+
+    /// Keep the keys that match the prefix and glob.
+    pub fn apply(&self, keys: Vec<String>) -> Vec<String> {
+        keys.into_iter().filter(|k| self.matches(k)).collect()
+    }
+
+    fn matches(&self, key: &str) -> bool {
+        let key = self.fold(key);
+        if let Some(prefix) = &self.prefix {
+            if !key.starts_with(&self.fold(prefix)) {
+                return false;
+            }
+        }
+        match &self.glob {
+            Some(glob) => glob_match(&self.fold(glob), &key),
+            None => true,
+        }
+    }
+
+    fn fold(&self, s: &str) -> String {
+        if self.case_insensitive {
+            s.to_lowercase()
+        } else {
+            s.to_string()
+        }
+    }
+}
+
+/// Match text against a glob where `*` matches any run of characters and
+/// `?` matches exactly one.
+fn glob_match(pattern: &str, text: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let text: Vec<char> = text.chars().collect();
+    let (mut p, mut t) = (0, 0);
+    // Position after the last `*` and the text position it has consumed up to
+    let mut star: Option<(usize, usize)> = None;
+
+    while t < text.len() {
+        if p < pattern.len() && (pattern[p] == '?' || pattern[p] == text[t]) {
+            p += 1;
+            t += 1;
+        } else if p < pattern.len() && pattern[p] == '*' {
+            star = Some((p + 1, t));
+            p += 1;
+        } else if let Some((after, consumed)) = star {
+            // Let the last `*` swallow one more character and retry
+            p = after;
+            t = consumed + 1;
+            star = Some((after, consumed + 1));
+        } else {
+            return false;
+        }
+    }
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_glob_match() {
+        assert!(glob_match("user:*", "user:alice"));
+        assert!(glob_match("user:*:name", "user:alice:name"));
+        assert!(glob_match("?at", "cat"));
+        assert!(glob_match("*", ""));
+        assert!(glob_match("a*b*c", "aXbYbZc"));
+        assert!(!glob_match("user:*", "users"));
+        assert!(!glob_match("?at", "at"));
+        assert!(!glob_match("a*c", "abcd"));
+    }
+
+    #[test]
+    fn test_case_insensitive_prefix() {
+        let mut args = Map::new();
+        args.insert("prefix".to_string(), JsonValue::from("user"));
+        args.insert("case_insensitive".to_string(), JsonValue::Bool(true));
+        let filter = KeyFilter::from_args(&args);
+        assert!(filter.is_active());
+        assert_eq!(filter.native_prefix(), None);
+
+        let keys = vec!["User:1".to_string(), "USER:2".to_string(), "item".to_string()];
+        assert_eq!(filter.apply(keys), vec!["User:1", "USER:2"]);
+    }
+}
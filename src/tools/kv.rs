@@ -1,18 +1,25 @@
 //! Key-value store tools.
 //!
-//! Tools: strata_kv_put, strata_kv_get, strata_kv_delete, strata_kv_list, strata_kv_history,
-//!        strata_kv_put_many, strata_kv_get_many, strata_kv_delete_many
+//! Tools: strata_kv_put, strata_kv_get, strata_kv_delete, strata_kv_list, strata_kv_scan,
+//!        strata_kv_history, strata_kv_put_many, strata_kv_get_many, strata_kv_delete_many,
+//!        strata_kv_rename, strata_kv_copy, strata_kv_wait, strata_kv_get_with_default,
+//!        strata_kv_compare_and_delete, strata_kv_merge, strata_kv_sweep_expired
 
 use serde_json::{Map, Value as JsonValue};
-use stratadb::Command;
+use std::collections::HashMap;
+use stratadb::{BranchId, Command, Output, Value};
 
 use crate::convert::{
-    get_optional_string, get_optional_u64, get_string_arg, get_value_arg, json_to_value,
-    output_to_json,
+    get_convert_options, get_optional_bool, get_optional_string, get_optional_u64, get_string_arg,
+    get_u64_arg, get_value_arg, history_window, json_to_value, output_to_json_with, value_to_json,
+    value_to_json_with,
 };
 use crate::error::{McpError, Result};
 use crate::schema;
 use crate::session::McpSession;
+use crate::tools::expiry;
+use crate::tools::key_filter::KeyFilter;
+use crate::tools::wait::{self, WaitOptions};
 use crate::tools::ToolDef;
 
 /// Get all KV tool definitions.
@@ -21,19 +28,35 @@ pub fn tools() -> Vec<ToolDef> {
         ToolDef::new(
             "strata_kv_put",
             "Store a key-value pair in the current branch/space. Values can be any JSON type. \
-             Returns the new version number. Use strata_kv_put_many for multiple keys.",
+             Returns the new version number. Use strata_kv_put_many for multiple keys. \
+             Pass ttl_secs to expire the key: once it elapses, KV reads treat it as missing. \
+             Expired keys are deleted by strata_kv_sweep_expired.",
             schema!(object {
-                required: { "key": string, "value": any }
+                required: { "key": string, "value": any },
+                optional: { "ttl_secs": integer }
             }),
         ),
         ToolDef::new(
             "strata_kv_get",
             "Get the value for a key with version info. Returns null if key doesn't exist. \
              Use strata_kv_get_many to fetch multiple keys in one call. \
-             Pass as_of (microsecond timestamp) for time-travel reads.",
+             Pass as_of (microsecond timestamp) for time-travel reads. \
+             Pass raw=true to return the bare value without version info. \
+             Pass distinguish_missing=true to get {found: false} for a missing key and \
+             found: true alongside the value otherwise, so a stored null is not mistaken \
+             for a missing key.",
             schema!(object {
                 required: { "key": string },
-                optional: { "as_of": integer }
+                optional: { "as_of": integer, "raw": boolean, "distinguish_missing": boolean }
+            }),
+        ),
+        ToolDef::new(
+            "strata_kv_get_with_default",
+            "Get the value for a key, or the given default if the key doesn't exist. \
+             Returns {value, version, was_present}; was_present is false and version null \
+             when the default was returned.",
+            schema!(object {
+                required: { "key": string, "default": any }
             }),
         ),
         ToolDef::new(
@@ -44,10 +67,54 @@ pub fn tools() -> Vec<ToolDef> {
                 required: { "key": string }
             }),
         ),
+        ToolDef::new(
+            "strata_kv_compare_and_delete",
+            "Delete a key only if its current version equals expected_version, checked and \
+             deleted in one transaction. Returns true if the key was deleted, false if the \
+             version did not match or the key does not exist. Useful for releasing locks.",
+            schema!(object {
+                required: { "key": string, "expected_version": integer }
+            }),
+        ),
+        ToolDef::new(
+            "strata_kv_merge",
+            "Merge the fields of an object into a key's object value, read and written in one \
+             transaction. By default the merge is shallow: each given field replaces the \
+             stored one. Pass deep=true to merge nested objects field by field. A missing key \
+             is created from the given object. Fails with WRONG_TYPE if the stored value is \
+             not an object. Returns the key, new version, and merged value.",
+            schema!(object {
+                required: { "key": string, "value": any },
+                optional: { "deep": boolean }
+            }),
+        ),
+        ToolDef::new(
+            "strata_kv_sweep_expired",
+            "Delete the KV keys in the current branch whose ttl_secs has elapsed, and drop \
+             expiry records left stale by later writes or deletes. \
+             Returns {keys_deleted, records_removed}.",
+            schema!(object {}),
+        ),
         ToolDef::new(
             "strata_kv_list",
             "List keys with optional prefix filter. Returns array of key names. \
              Use cursor and limit for pagination through large result sets. \
+             Pass as_of (microsecond timestamp) for time-travel reads. \
+             Set case_insensitive to match the prefix ignoring case, or pass a glob \
+             (* matches any characters, ? one character); these filter after listing, \
+             so they read every key under the prefix and are slower on large keyspaces.",
+            schema!(object {
+                optional: {
+                    "prefix": string, "cursor": string, "limit": integer, "as_of": integer,
+                    "case_insensitive": boolean, "glob": string
+                }
+            }),
+        ),
+        ToolDef::new(
+            "strata_kv_scan",
+            "List keys with their values in one call. Returns array of {key, value, version}. \
+             Takes the same prefix, cursor, and limit as strata_kv_list; pass the last key \
+             of a page as cursor to fetch the next page. \
              Pass as_of (microsecond timestamp) for time-travel reads.",
             schema!(object {
                 optional: { "prefix": string, "cursor": string, "limit": integer, "as_of": integer }
@@ -57,10 +124,12 @@ pub fn tools() -> Vec<ToolDef> {
             "strata_kv_history",
             "Get all historical versions of a key. Returns array of {value, version, timestamp}. \
              Useful for auditing changes or implementing undo. \
-             Pass as_of (microsecond timestamp) to get history up to that point.",
+             Pass as_of (microsecond timestamp) to get history up to that point. \
+             Pass limit to return only the most recent N versions, newest first, and \
+             since_version to return only versions newer than one already seen.",
             schema!(object {
                 required: { "key": string },
-                optional: { "as_of": integer }
+                optional: { "as_of": integer, "limit": integer, "since_version": integer }
             }),
         ),
         ToolDef::new(
@@ -74,9 +143,12 @@ pub fn tools() -> Vec<ToolDef> {
         ToolDef::new(
             "strata_kv_get_many",
             "Get multiple keys in a single operation. More efficient than multiple \
-             strata_kv_get calls. Returns array of values (null for missing keys).",
+             strata_kv_get calls. Returns array of values (null for missing keys). \
+             Pass raw=true to return bare values without version info. \
+             Pass distinguish_missing=true to get {found: false} for missing keys.",
             schema!(object {
-                required: { "keys": array_string }
+                required: { "keys": array_string },
+                optional: { "raw": boolean, "distinguish_missing": boolean }
             }),
         ),
         ToolDef::new(
@@ -87,6 +159,58 @@ pub fn tools() -> Vec<ToolDef> {
                 required: { "keys": array_string }
             }),
         ),
+        ToolDef::new(
+            "strata_kv_rename",
+            "Move a key's current value to a new key and delete the old key, in one \
+             transaction. The new key starts a fresh version history. Fails if `to` already \
+             exists unless overwrite=true. Returns from, to, and the new version.",
+            schema!(object {
+                required: { "from": string, "to": string },
+                optional: { "overwrite": boolean }
+            }),
+        ),
+        ToolDef::new(
+            "strata_kv_copy",
+            "Copy a key's current value to another key, replacing any value there. The target \
+             defaults to the current branch and space; pass to_space and/or to_branch to copy \
+             elsewhere. Set with_history to replay every version oldest first instead of just \
+             the current value. The writes run in a transaction on the target branch; inside an \
+             active transaction, to_branch must be its branch. Returns the target and \
+             versions_copied.",
+            schema!(object {
+                required: { "from": string, "to": string },
+                optional: { "to_space": string, "to_branch": string, "with_history": boolean }
+            }),
+        ),
+        ToolDef::new(
+            "strata_kv_wait",
+            "Block until a key changes, instead of polling strata_kv_get. By default returns \
+             once the key's version differs from when the call started. Pass expected to wait \
+             for a condition instead: version_gt (version greater than a number), value \
+             (equal to a value), and/or exists; every given field must hold. Gives up after \
+             timeout_ms (default 5000, max 60000), checking every poll_interval_ms \
+             (default 50, 10 to 1000). Returns satisfied, value, and version; satisfied is \
+             false if the timeout elapsed. Cannot be used inside a transaction.",
+            serde_json::json!({
+                "type": "object",
+                "properties": {
+                    "key": { "type": "string" },
+                    "expected": {
+                        "type": "object",
+                        "properties": {
+                            "version_gt": { "type": "integer" },
+                            "value": {},
+                            "exists": { "type": "boolean" }
+                        },
+                        "additionalProperties": false
+                    },
+                    "timeout_ms": { "type": "integer" },
+                    "poll_interval_ms": { "type": "integer" }
+                },
+                "required": ["key"],
+                "additionalProperties": false
+            }),
+        ),
     ]
 }
 
@@ -100,29 +224,75 @@ pub fn dispatch(
         "strata_kv_put" => {
             let key = get_string_arg(&args, "key")?;
             let value = get_value_arg(&args, "value")?;
+            let ttl_secs = get_optional_u64(&args, "ttl_secs");
+            if ttl_secs == Some(0) {
+                return Err(McpError::InvalidArg {
+                    name: "ttl_secs".to_string(),
+                    reason: "Must be at least 1".to_string(),
+                });
+            }
 
-            let cmd = Command::KvPut {
-                branch: session.branch_id(),
-                space: session.space_id(),
-                key,
-                value,
+            let put = |session: &mut McpSession| {
+                let cmd = Command::KvPut {
+                    branch: session.branch_id(),
+                    space: session.space_id(),
+                    key: key.clone(),
+                    value,
+                };
+                session.execute(cmd)
             };
-            let output = session.execute(cmd)?;
-            Ok(output_to_json(output))
+            let output = match ttl_secs {
+                None => put(session)?,
+                Some(ttl_secs) => session.atomically(|session| {
+                    let output = put(session)?;
+                    if let Output::Version(version) = &output {
+                        expiry::set_expiry(session, &key, *version, ttl_secs)?;
+                    }
+                    Ok(output)
+                })?,
+            };
+            Ok(output_to_json_with(output, session.convert_options()))
         }
 
         "strata_kv_get" => {
             let key = get_string_arg(&args, "key")?;
             let as_of = get_optional_u64(&args, "as_of");
+            let opts = get_convert_options(&args, session.convert_options());
 
             let cmd = Command::KvGet {
                 branch: session.branch_id(),
                 space: session.space_id(),
-                key,
+                key: key.clone(),
                 as_of,
             };
             let output = session.execute(cmd)?;
-            Ok(output_to_json(output))
+            let output = filter_expired(session, &key, as_of, output)?;
+            Ok(output_to_json_with(output, &opts))
+        }
+
+        "strata_kv_get_with_default" => {
+            let key = get_string_arg(&args, "key")?;
+            let default = args.get("default").cloned().unwrap_or(JsonValue::Null);
+
+            let cmd = Command::KvGet {
+                branch: session.branch_id(),
+                space: session.space_id(),
+                key: key.clone(),
+                as_of: None,
+            };
+            let output = session.execute(cmd)?;
+            Ok(match filter_expired(session, &key, None, output)? {
+                Output::MaybeVersioned(Some(vv)) => serde_json::json!({
+                    "value": value_to_json_with(vv.value, session.convert_options()),
+                    "version": vv.version,
+                    "was_present": true,
+                }),
+                _ => serde_json::json!({
+                    "value": default,
+                    "version": null,
+                    "was_present": false,
+                }),
+            })
         }
 
         "strata_kv_delete" => {
@@ -134,10 +304,99 @@ pub fn dispatch(
                 key,
             };
             let output = session.execute(cmd)?;
-            Ok(output_to_json(output))
+            Ok(output_to_json_with(output, session.convert_options()))
+        }
+
+        "strata_kv_compare_and_delete" => {
+            let key = get_string_arg(&args, "key")?;
+            let expected_version = get_u64_arg(&args, "expected_version")?;
+
+            session.check_write_access("KvDelete")?;
+            session.atomically(|session| {
+                let (branch, space) = (session.branch_id(), session.space_id());
+                let (_, version) = kv_entry(session, branch.clone(), space.clone(), &key)?;
+                if version != Some(expected_version) {
+                    return Ok(JsonValue::Bool(false));
+                }
+                session.execute(Command::KvDelete { branch, space, key })?;
+                Ok(JsonValue::Bool(true))
+            })
+        }
+
+        "strata_kv_merge" => {
+            let key = get_string_arg(&args, "key")?;
+            let patch = get_value_arg(&args, "value")?;
+            if !matches!(patch, Value::Object(_)) {
+                return Err(McpError::InvalidArg {
+                    name: "value".to_string(),
+                    reason: "Expected object".to_string(),
+                });
+            }
+            let deep = get_optional_bool(&args, "deep").unwrap_or(false);
+
+            let (version, merged) = session.atomically(|session| {
+                let (branch, space) = (session.branch_id(), session.space_id());
+                let mut merged = match kv_value(session, branch.clone(), space.clone(), &key)? {
+                    Some(existing @ Value::Object(_)) => existing,
+                    Some(_) => {
+                        return Err(McpError::Strata {
+                            code: "WRONG_TYPE".to_string(),
+                            message: format!("value of key {} is not an object", key),
+                        })
+                    }
+                    None => Value::Object(HashMap::new()),
+                };
+                merge_object(&mut merged, patch, deep);
+                let output = session.execute(Command::KvPut {
+                    branch,
+                    space,
+                    key: key.clone(),
+                    value: merged.clone(),
+                })?;
+                let version = match output {
+                    Output::Version(v) => Some(v),
+                    _ => None,
+                };
+                Ok((version, merged))
+            })?;
+
+            Ok(serde_json::json!({
+                "key": key,
+                "version": version,
+                "value": value_to_json_with(merged, session.convert_options()),
+            }))
         }
 
         "strata_kv_list" => {
+            let filter = KeyFilter::from_args(&args);
+            let cursor = get_optional_string(&args, "cursor");
+            let limit = get_optional_u64(&args, "limit");
+            let as_of = get_optional_u64(&args, "as_of");
+
+            let cmd = Command::KvList {
+                branch: session.branch_id(),
+                space: session.space_id(),
+                prefix: filter.native_prefix(),
+                cursor,
+                // The limit applies to filtered keys, so list them all first
+                limit: if filter.is_active() { None } else { limit },
+                as_of,
+            };
+            let output = match session.execute(cmd)? {
+                Output::Keys(keys) => {
+                    let keys = if filter.is_active() { filter.apply(keys) } else { keys };
+                    let mut keys = expiry::live_keys(session, keys, as_of)?;
+                    if let Some(limit) = limit {
+                        keys.truncate(limit as usize);
+                    }
+                    Output::Keys(keys)
+                }
+                other => other,
+            };
+            Ok(output_to_json_with(output, session.convert_options()))
+        }
+
+        "strata_kv_scan" => {
             let prefix = get_optional_string(&args, "prefix");
             let cursor = get_optional_string(&args, "cursor");
             let limit = get_optional_u64(&args, "limit");
@@ -151,22 +410,54 @@ pub fn dispatch(
                 limit,
                 as_of,
             };
-            let output = session.execute(cmd)?;
-            Ok(output_to_json(output))
+            let keys = match session.execute(cmd)? {
+                Output::Keys(keys) => keys,
+                _ => {
+                    return Err(McpError::Internal(
+                        "Unexpected output for KvList".to_string(),
+                    ))
+                }
+            };
+
+            let mut items = Vec::new();
+            for key in keys {
+                let cmd = Command::KvGet {
+                    branch: session.branch_id(),
+                    space: session.space_id(),
+                    key: key.clone(),
+                    as_of,
+                };
+                // Skip keys deleted between the list and the read, and expired keys
+                let output = session.execute(cmd)?;
+                if let Output::MaybeVersioned(Some(vv)) =
+                    filter_expired(session, &key, as_of, output)?
+                {
+                    items.push(serde_json::json!({
+                        "key": key,
+                        "value": value_to_json_with(vv.value, session.convert_options()),
+                        "version": vv.version,
+                    }));
+                }
+            }
+            Ok(JsonValue::Array(items))
         }
 
         "strata_kv_history" => {
             let key = get_string_arg(&args, "key")?;
             let as_of = get_optional_u64(&args, "as_of");
+            let limit = get_optional_u64(&args, "limit");
+            let since_version = get_optional_u64(&args, "since_version");
 
             let cmd = Command::KvGetv {
                 branch: session.branch_id(),
                 space: session.space_id(),
-                key,
+                key: key.clone(),
                 as_of,
             };
             let output = session.execute(cmd)?;
-            Ok(output_to_json(output))
+            let output = expiry::filter_history(session, &key, as_of, output)?;
+            let output = history_window(output, since_version, limit);
+            Ok(output_to_json_with(output, session.convert_options()))
         }
 
         "strata_kv_put_many" => {
@@ -199,7 +490,7 @@ pub fn dispatch(
                     value,
                 };
                 let output = session.execute(cmd)?;
-                versions.push(output_to_json(output));
+                versions.push(output_to_json_with(output, session.convert_options()));
             }
             Ok(JsonValue::Array(versions))
         }
@@ -209,6 +500,7 @@ pub fn dispatch(
                 .get("keys")
                 .and_then(|v| v.as_array())
                 .ok_or_else(|| McpError::MissingArg("keys".to_string()))?;
+            let opts = get_convert_options(&args, session.convert_options());
 
             let mut results = Vec::new();
             for key_value in keys {
@@ -223,11 +515,12 @@ pub fn dispatch(
                 let cmd = Command::KvGet {
                     branch: session.branch_id(),
                     space: session.space_id(),
-                    key,
+                    key: key.clone(),
                     as_of: None,
                 };
                 let output = session.execute(cmd)?;
-                results.push(output_to_json(output));
+                let output = filter_expired(session, &key, None, output)?;
+                results.push(output_to_json_with(output, &opts));
             }
             Ok(JsonValue::Array(results))
         }
@@ -254,11 +547,277 @@ pub fn dispatch(
                     key,
                 };
                 let output = session.execute(cmd)?;
-                results.push(output_to_json(output));
+                results.push(output_to_json_with(output, session.convert_options()));
             }
             Ok(JsonValue::Array(results))
         }
 
+        "strata_kv_rename" => {
+            let from = get_string_arg(&args, "from")?;
+            let to = get_string_arg(&args, "to")?;
+            let overwrite = get_optional_bool(&args, "overwrite").unwrap_or(false);
+            session.check_write_access("KvRename")?;
+            if from == to {
+                return Err(McpError::InvalidArg {
+                    name: "to".to_string(),
+                    reason: "Must differ from 'from'".to_string(),
+                });
+            }
+
+            let version = session.atomically(|session| {
+                let (branch, space) = (session.branch_id(), session.space_id());
+                let value = kv_value(session, branch, space, &from)?;
+                let value = value.ok_or_else(|| McpError::InvalidArg {
+                    name: "from".to_string(),
+                    reason: format!("Key '{}' does not exist", from),
+                })?;
+                let (branch, space) = (session.branch_id(), session.space_id());
+                if !overwrite && kv_value(session, branch, space, &to)?.is_some() {
+                    return Err(McpError::InvalidArg {
+                        name: "to".to_string(),
+                        reason: format!(
+                            "Key '{}' already exists; pass overwrite=true to replace it",
+                            to
+                        ),
+                    });
+                }
+
+                let output = session.execute(Command::KvPut {
+                    branch: session.branch_id(),
+                    space: session.space_id(),
+                    key: to.clone(),
+                    value,
+                })?;
+                session.execute(Command::KvDelete {
+                    branch: session.branch_id(),
+                    space: session.space_id(),
+                    key: from.clone(),
+                })?;
+                Ok(match output {
+                    Output::Version(v) => Some(v),
+                    _ => None,
+                })
+            })?;
+
+            Ok(serde_json::json!({
+                "from": from,
+                "to": to,
+                "version": version,
+            }))
+        }
+
+        "strata_kv_copy" => {
+            let from = get_string_arg(&args, "from")?;
+            let to = get_string_arg(&args, "to")?;
+            let to_space = get_optional_string(&args, "to_space")
+                .unwrap_or_else(|| session.space().to_string());
+            let to_branch = get_optional_string(&args, "to_branch")
+                .unwrap_or_else(|| session.branch().to_string());
+            let with_history = get_optional_bool(&args, "with_history").unwrap_or(false);
+            session.check_write_access("KvCopy")?;
+            if from == to && to_space == session.space() && to_branch == session.branch() {
+                return Err(McpError::InvalidArg {
+                    name: "to".to_string(),
+                    reason: "Cannot copy a key onto itself".to_string(),
+                });
+            }
+
+            if let Some(txn_branch) = session.txn_branch() {
+                if txn_branch != to_branch {
+                    return Err(McpError::InvalidArg {
+                        name: "to_branch".to_string(),
+                        reason: format!(
+                            "A transaction is active on branch '{}'; copy to another branch \
+                             outside it",
+                            txn_branch
+                        ),
+                    });
+                }
+            }
+
+            let values = if with_history {
+                kv_history(session, &from)?
+            } else {
+                let (branch, space) = (session.branch_id(), session.space_id());
+                kv_value(session, branch, space, &from)?.into_iter().collect()
+            };
+            if values.is_empty() {
+                return Err(McpError::InvalidArg {
+                    name: "from".to_string(),
+                    reason: format!("Key '{}' does not exist", from),
+                });
+            }
+
+            // The writes run in a transaction on the target branch
+            let copied = values.len();
+            session.atomically_on(&to_branch, |session| {
+                for value in values {
+                    session.execute(Command::KvPut {
+                        branch: session.branch_id(),
+                        space: Some(to_space.clone()),
+                        key: to.clone(),
+                        value,
+                    })?;
+                }
+                Ok(())
+            })?;
+
+            Ok(serde_json::json!({
+                "from": from,
+                "to": to,
+                "space": to_space,
+                "branch": to_branch,
+                "versions_copied": copied,
+            }))
+        }
+
+        "strata_kv_wait" => {
+            let key = get_string_arg(&args, "key")?;
+            let opts = WaitOptions::from_args(&args)?;
+            let expected = match args.get("expected") {
+                Some(JsonValue::Object(obj)) => Some(obj.clone()),
+                _ => None,
+            };
+
+            let (branch, space) = (session.branch_id(), session.space_id());
+            let initial = kv_entry(session, branch.clone(), space.clone(), &key)?.1;
+            let met = wait::poll(session, &opts, |session| {
+                let (value, version) = kv_entry(session, branch.clone(), space.clone(), &key)?;
+                let done = match &expected {
+                    Some(expected) => kv_expected(expected, &value, version),
+                    None => version != initial,
+                };
+                Ok(done.then_some((value, version)))
+            })?;
+
+            let satisfied = met.is_some();
+            let (value, version) = match met {
+                Some(entry) => entry,
+                None => kv_entry(session, branch, space, &key)?,
+            };
+            Ok(serde_json::json!({
+                "satisfied": satisfied,
+                "value": value,
+                "version": version,
+            }))
+        }
+
+        "strata_kv_sweep_expired" => expiry::sweep(session),
+
         _ => Err(McpError::UnknownTool(name.to_string())),
     }
 }
+
+/// Hide a `KvGet` output of a key in the current space if it has expired.
+fn filter_expired(
+    session: &mut McpSession,
+    key: &str,
+    as_of: Option<u64>,
+    output: Output,
+) -> Result<Output> {
+    let (branch, space) = (session.branch_id(), session.space().to_string());
+    expiry::filter_get(session, branch, &space, key, as_of, output)
+}
+
+/// Merge the fields of `patch` into `target`.
+///
+/// With `deep`, a field holding an object in both is merged recursively;
+/// otherwise the patch's field replaces the stored one.
+fn merge_object(target: &mut Value, patch: Value, deep: bool) {
+    match (target, patch) {
+        (Value::Object(fields), Value::Object(patch)) => {
+            for (name, value) in patch {
+                let nested = deep && matches!(value, Value::Object(_));
+                match fields.get_mut(&name) {
+                    Some(current @ Value::Object(_)) if nested => {
+                        merge_object(current, value, deep)
+                    }
+                    _ => {
+                        fields.insert(name, value);
+                    }
+                }
+            }
+        }
+        (target, patch) => *target = patch,
+    }
+}
+
+/// Read the current value of a key.
+fn kv_value(
+    session: &mut McpSession,
+    branch: Option<BranchId>,
+    space: Option<String>,
+    key: &str,
+) -> Result<Option<Value>> {
+    match read_live(session, branch, space, key)? {
+        Output::MaybeVersioned(vv) => Ok(vv.map(|vv| vv.value)),
+        _ => Ok(None),
+    }
+}
+
+/// Read the current value of a key as JSON with its version.
+fn kv_entry(
+    session: &mut McpSession,
+    branch: Option<BranchId>,
+    space: Option<String>,
+    key: &str,
+) -> Result<(JsonValue, Option<u64>)> {
+    match read_live(session, branch, space, key)? {
+        Output::MaybeVersioned(Some(vv)) => {
+            let version = Some(vv.version);
+            Ok((value_to_json_with(vv.value, session.convert_options()), version))
+        }
+        _ => Ok((JsonValue::Null, None)),
+    }
+}
+
+/// Read the current version of a key, treating an expired one as missing.
+fn read_live(
+    session: &mut McpSession,
+    branch: Option<BranchId>,
+    space: Option<String>,
+    key: &str,
+) -> Result<Output> {
+    let space_name = space.clone().unwrap_or_else(|| session.space().to_string());
+    let cmd = Command::KvGet {
+        branch: branch.clone(),
+        space,
+        key: key.to_string(),
+        as_of: None,
+    };
+    let output = session.execute(cmd)?;
+    expiry::filter_get(session, branch, &space_name, key, None, output)
+}
+
+/// Check a key's current value and version against a `strata_kv_wait`
+/// `expected` predicate.
+fn kv_expected(expected: &Map<String, JsonValue>, value: &JsonValue, version: Option<u64>) -> bool {
+    let version_ok = expected
+        .get("version_gt")
+        .and_then(|v| v.as_u64())
+        .is_none_or(|min| version.is_some_and(|v| v > min));
+    let value_ok = expected.get("value").is_none_or(|want| version.is_some() && want == value);
+    let exists_ok = expected
+        .get("exists")
+        .and_then(|e| e.as_bool())
+        .is_none_or(|exists| exists == version.is_some());
+    version_ok && value_ok && exists_ok
+}
+
+/// Read every version of a key in the current branch and space, oldest first.
+fn kv_history(session: &mut McpSession, key: &str) -> Result<Vec<Value>> {
+    let cmd = Command::KvGetv {
+        branch: session.branch_id(),
+        space: session.space_id(),
+        key: key.to_string(),
+        as_of: None,
+    };
+    let output = session.execute(cmd)?;
+    let output = expiry::filter_history(session, key, None, output)?;
+    let mut versions = match output {
+        Output::VersionHistory(Some(values)) => values,
+        _ => return Ok(Vec::new()),
+    };
+    versions.sort_by_key(|vv| vv.timestamp);
+    Ok(versions.into_iter().map(|vv| vv.value).collect())
+}
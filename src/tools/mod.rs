@@ -2,25 +2,35 @@
 //!
 //! Provides the infrastructure for registering and dispatching MCP tools.
 
+pub mod batch;
 pub mod branch;
 pub mod bundle;
+pub mod cache;
+pub mod completion;
 pub mod config;
 pub mod database;
 pub mod event;
+pub mod expiry;
 pub mod json;
+pub mod key_filter;
 pub mod kv;
 pub mod retention;
 pub mod search;
+pub mod server_info;
 pub mod space;
 pub mod state;
 pub mod txn;
+pub mod validate;
 pub mod vector;
+pub mod wait;
 
 use serde::{Deserialize, Serialize};
 use serde_json::{Map, Value as JsonValue};
 
+use crate::convert::normalize_name_args;
 use crate::error::{McpError, Result};
 use crate::session::McpSession;
+use crate::tools::cache::ResultCache;
 
 /// A tool definition for the MCP tools/list response.
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -32,6 +42,12 @@ pub struct ToolDef {
     /// JSON Schema for the input parameters
     #[serde(rename = "inputSchema")]
     pub input_schema: JsonValue,
+    /// Whether the tool is deprecated and may be removed in a future release
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub deprecated: bool,
+    /// Tool to use instead of this one, if deprecated
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub replacement: Option<String>,
 }
 
 impl ToolDef {
@@ -41,13 +57,26 @@ impl ToolDef {
             name: name.to_string(),
             description: description.to_string(),
             input_schema,
+            deprecated: false,
+            replacement: None,
         }
     }
+
+    /// Mark the tool deprecated, naming the tool that supersedes it.
+    pub fn deprecated_by(mut self, replacement: &str) -> Self {
+        self.deprecated = true;
+        self.replacement = Some(replacement.to_string());
+        self
+    }
 }
 
+/// Tools superseded by another tool, with their replacements.
+const DEPRECATED: &[(&str, &str)] = &[("strata_event_len", "strata_event_count")];
+
 /// Registry of all available tools.
 pub struct ToolRegistry {
     tools: Vec<ToolDef>,
+    prefix: String,
 }
 
 impl ToolRegistry {
@@ -69,8 +98,49 @@ impl ToolRegistry {
         tools.extend(bundle::tools());
         tools.extend(retention::tools());
         tools.extend(config::tools());
+        tools.extend(batch::tools());
+        tools.extend(server_info::tools());
 
-        Self { tools }
+        let tools = tools
+            .into_iter()
+            .map(|t| match DEPRECATED.iter().find(|(name, _)| *name == t.name) {
+                Some((_, replacement)) => t.deprecated_by(replacement),
+                None => t,
+            })
+            .collect();
+        Self {
+            tools,
+            prefix: String::new(),
+        }
+    }
+
+    /// Prepend `prefix` to every tool name, e.g. `acme_strata_kv_put`.
+    ///
+    /// Lets several servers sit behind one aggregator without name
+    /// collisions. Calls must use the prefixed names; the prefix is stripped
+    /// before routing. Tool descriptions still refer to unprefixed names.
+    pub fn with_prefix(mut self, prefix: &str) -> Self {
+        for tool in &mut self.tools {
+            tool.name = format!("{}{}", prefix, tool.name);
+            if let Some(replacement) = &mut tool.replacement {
+                *replacement = format!("{}{}", prefix, replacement);
+            }
+        }
+        self.prefix = prefix.to_string();
+        self
+    }
+
+    /// Name of a tool with the registry's prefix removed.
+    pub(crate) fn unprefixed<'a>(&self, name: &'a str) -> &'a str {
+        name.strip_prefix(self.prefix.as_str()).unwrap_or(name)
+    }
+
+    /// Look up a tool by its listed name.
+    fn find(&self, name: &str) -> Result<&ToolDef> {
+        self.tools
+            .iter()
+            .find(|t| t.name == name)
+            .ok_or_else(|| McpError::UnknownTool(name.to_string()))
     }
 
     /// Get all tool definitions.
@@ -78,44 +148,123 @@ impl ToolRegistry {
         &self.tools
     }
 
+    /// Get the tool definitions in one category, such as `"kv"` or `"vector"`.
+    pub fn tools_in_category(&self, name: &str) -> Vec<&ToolDef> {
+        self.tools
+            .iter()
+            .filter(|t| category(self.unprefixed(&t.name)) == Some(name))
+            .collect()
+    }
+
+    /// Complete a partial value for a tool argument.
+    pub fn complete(
+        &self,
+        session: &mut McpSession,
+        name: &str,
+        argument: &str,
+        partial: &str,
+    ) -> Result<Vec<String>> {
+        let tool = self.find(name)?;
+        completion::complete(session, self.unprefixed(name), tool, argument, partial)
+    }
+
     /// Dispatch a tool call to the appropriate handler.
+    ///
+    /// Arguments are validated against the tool's input schema first. If
+    /// the session has a result cache, cacheable reads are answered from it
+    /// and any other tool clears it.
     pub fn dispatch(
         &self,
         session: &mut McpSession,
         name: &str,
-        args: Map<String, JsonValue>,
+        mut args: Map<String, JsonValue>,
     ) -> Result<JsonValue> {
-        // Route based on prefix
-        if name.starts_with("strata_db_") {
-            database::dispatch(session, name, args)
-        } else if name.starts_with("strata_kv_") {
-            kv::dispatch(session, name, args)
-        } else if name.starts_with("strata_state_") {
-            state::dispatch(session, name, args)
-        } else if name.starts_with("strata_event_") {
-            event::dispatch(session, name, args)
-        } else if name.starts_with("strata_json_") {
-            json::dispatch(session, name, args)
-        } else if name.starts_with("strata_space_") {
-            space::dispatch(session, name, args)
-        } else if name.starts_with("strata_branch_") {
-            branch::dispatch(session, name, args)
-        } else if name.starts_with("strata_vector_") {
-            vector::dispatch(session, name, args)
-        } else if name.starts_with("strata_txn_") {
-            txn::dispatch(session, name, args)
-        } else if name.starts_with("strata_search") {
-            search::dispatch(session, name, args)
-        } else if name.starts_with("strata_configure_") {
-            config::dispatch(session, name, args)
-        } else if name.starts_with("strata_bundle_") {
-            bundle::dispatch(session, name, args)
-        } else if name.starts_with("strata_retention_") {
-            retention::dispatch(session, name, args)
-        } else {
-            Err(McpError::UnknownTool(name.to_string()))
+        let tool = self.find(name)?;
+        validate::validate_args(&tool.input_schema, &args)?;
+        let name = self.unprefixed(name);
+        normalize_name_args(name, &mut args, session.fold_name_case())?;
+
+        let in_transaction = session.in_transaction();
+        let (branch, space) = (session.branch().to_string(), session.space().to_string());
+        let cache_key = match session.result_cache_mut() {
+            Some(cache) => match ResultCache::key(name, &branch, &space, &args) {
+                Some(key) if !in_transaction => {
+                    if let Some(hit) = cache.get(&key) {
+                        return Ok(hit);
+                    }
+                    Some(key)
+                }
+                Some(_) => None,
+                None => {
+                    cache.clear();
+                    None
+                }
+            },
+            None => None,
+        };
+
+        let result = match category(name) {
+            Some("db") => database::dispatch(session, name, args),
+            Some("kv") => kv::dispatch(session, name, args),
+            Some("state") => state::dispatch(session, name, args),
+            Some("event") => event::dispatch(session, name, args),
+            Some("json") => json::dispatch(session, name, args),
+            Some("space") => space::dispatch(session, name, args),
+            Some("branch") => branch::dispatch(session, name, args),
+            Some("vector") => vector::dispatch(session, name, args),
+            Some("txn") => txn::dispatch(session, name, args),
+            Some("search") => search::dispatch(session, name, args),
+            Some("configure") => config::dispatch(session, name, args),
+            Some("bundle") => bundle::dispatch(session, name, args),
+            Some("retention") => retention::dispatch(session, name, args),
+            Some("batch") => batch::dispatch(self, session, name, args),
+            Some("server") => server_info::dispatch(self, session, name, args),
+            _ => Err(McpError::UnknownTool(name.to_string())),
+        };
+
+        if let (Ok(value), Some(key)) = (&result, cache_key) {
+            if let Some(cache) = session.result_cache_mut() {
+                cache.insert(key, value.clone());
+            }
         }
+        result
+    }
+}
+
+/// The category a tool belongs to, which decides how it is dispatched.
+///
+/// Most tools are named `strata_<category>_...`; the rest are listed here.
+pub fn category(name: &str) -> Option<&'static str> {
+    const PREFIXED: &[&str] = &[
+        "db",
+        "kv",
+        "state",
+        "event",
+        "json",
+        "space",
+        "branch",
+        "vector",
+        "txn",
+        "configure",
+        "bundle",
+        "retention",
+        "server",
+    ];
+    match name {
+        "strata_diff_values" => return Some("branch"),
+        "strata_list_all" => return Some("db"),
+        "strata_export_jsonl" | "strata_import_jsonl" => return Some("bundle"),
+        "strata_batch" | "strata_with_transaction" | "strata_event_replay" => return Some("batch"),
+        _ => {}
+    }
+    if name.starts_with("strata_search") {
+        return Some("search");
     }
+    let rest = name.strip_prefix("strata_")?;
+    PREFIXED
+        .iter()
+        .find(|c| rest.strip_prefix(**c).is_some_and(|r| r.starts_with('_')))
+        .copied()
 }
 
 impl Default for ToolRegistry {
@@ -142,7 +291,8 @@ macro_rules! schema {
         serde_json::json!({
             "type": "object",
             "properties": props,
-            "required": required
+            "required": required,
+            "additionalProperties": false
         })
     }};
 
@@ -159,7 +309,8 @@ macro_rules! schema {
         serde_json::json!({
             "type": "object",
             "properties": props,
-            "required": required
+            "required": required,
+            "additionalProperties": false
         })
     }};
 
@@ -173,7 +324,8 @@ macro_rules! schema {
         serde_json::json!({
             "type": "object",
             "properties": props,
-            "required": []
+            "required": [],
+            "additionalProperties": false
         })
     }};
 
@@ -182,7 +334,8 @@ macro_rules! schema {
         serde_json::json!({
             "type": "object",
             "properties": {},
-            "required": []
+            "required": [],
+            "additionalProperties": false
         })
     }};
 
@@ -1,39 +1,471 @@
 //! Retention tools.
 //!
-//! Tools: strata_retention_apply
+//! Tools: strata_retention_apply, strata_retention_preview
+
+use std::collections::BTreeMap;
+use std::time::{SystemTime, UNIX_EPOCH};
 
 use serde_json::{Map, Value as JsonValue};
-use stratadb::Command;
+use stratadb::{Command, Output};
 
-use crate::convert::output_to_json;
+use crate::convert::{get_optional_bool, output_to_json_with};
 use crate::error::{McpError, Result};
 use crate::schema;
 use crate::session::McpSession;
 use crate::tools::ToolDef;
 
+/// Primitives that accept per-primitive retention overrides.
+pub const RETENTION_PRIMITIVES: &[&str] = &["kv", "state", "event", "json", "vector"];
+
+/// Version and age bounds for one retention scope.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RetentionBounds {
+    /// Maximum number of versions to keep per key
+    pub max_versions: Option<u64>,
+    /// Maximum age of a version in seconds
+    pub max_age_secs: Option<u64>,
+}
+
+impl RetentionBounds {
+    /// Returns `true` if no bound is set.
+    pub fn is_empty(&self) -> bool {
+        self.max_versions.is_none() && self.max_age_secs.is_none()
+    }
+
+    /// Parse bounds from an argument object, naming errors under `scope`.
+    fn from_args(args: &Map<String, JsonValue>, scope: &str) -> Result<Self> {
+        Ok(Self {
+            max_versions: positive_bound(args, "max_versions", scope)?,
+            max_age_secs: positive_bound(args, "max_age_secs", scope)?,
+        })
+    }
+
+    fn to_json(self) -> JsonValue {
+        serde_json::json!({
+            "max_versions": self.max_versions,
+            "max_age_secs": self.max_age_secs,
+        })
+    }
+}
+
+/// A retention policy: default bounds plus per-primitive overrides.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RetentionPolicy {
+    /// Bounds applied to every primitive
+    pub defaults: RetentionBounds,
+    /// Bounds for specific primitives, keyed by primitive name
+    pub overrides: BTreeMap<String, RetentionBounds>,
+}
+
+impl RetentionPolicy {
+    /// Parse a policy from tool arguments.
+    ///
+    /// Reads `max_versions`, `max_age_secs`, and an optional `overrides` object
+    /// keyed by primitive. At least one bound must be set somewhere.
+    pub fn from_args(args: &Map<String, JsonValue>) -> Result<Self> {
+        let defaults = RetentionBounds::from_args(args, "")?;
+
+        let mut overrides = BTreeMap::new();
+        match args.get("overrides") {
+            None | Some(JsonValue::Null) => {}
+            Some(JsonValue::Object(map)) => {
+                for (primitive, value) in map {
+                    let name = format!("overrides.{}", primitive);
+                    if !RETENTION_PRIMITIVES.contains(&primitive.as_str()) {
+                        return Err(McpError::InvalidArg {
+                            name,
+                            reason: format!(
+                                "Unknown primitive; expected one of {}",
+                                RETENTION_PRIMITIVES.join(", ")
+                            ),
+                        });
+                    }
+                    let obj = value.as_object().ok_or_else(|| McpError::InvalidArg {
+                        name: name.clone(),
+                        reason: "Expected object with max_versions and/or max_age_secs"
+                            .to_string(),
+                    })?;
+                    let bounds = RetentionBounds::from_args(obj, &name)?;
+                    if bounds.is_empty() {
+                        return Err(McpError::InvalidArg {
+                            name,
+                            reason: "Override must set max_versions or max_age_secs".to_string(),
+                        });
+                    }
+                    overrides.insert(primitive.clone(), bounds);
+                }
+            }
+            Some(_) => {
+                return Err(McpError::InvalidArg {
+                    name: "overrides".to_string(),
+                    reason: "Expected object keyed by primitive".to_string(),
+                })
+            }
+        }
+
+        if defaults.is_empty() && overrides.is_empty() {
+            return Err(McpError::InvalidArg {
+                name: "max_versions".to_string(),
+                reason: "At least one of max_versions, max_age_secs, or overrides must be set"
+                    .to_string(),
+            });
+        }
+
+        Ok(Self {
+            defaults,
+            overrides,
+        })
+    }
+
+    /// Resolve the bounds for a primitive, falling back to the defaults.
+    pub fn bounds_for(&self, primitive: &str) -> RetentionBounds {
+        match self.overrides.get(primitive) {
+            Some(o) => RetentionBounds {
+                max_versions: o.max_versions.or(self.defaults.max_versions),
+                max_age_secs: o.max_age_secs.or(self.defaults.max_age_secs),
+            },
+            None => self.defaults,
+        }
+    }
+
+    /// Render the policy, including the resolved bounds for every primitive.
+    pub fn to_json(&self) -> JsonValue {
+        let overrides: Map<String, JsonValue> = self
+            .overrides
+            .iter()
+            .map(|(k, v)| (k.clone(), v.to_json()))
+            .collect();
+        let effective: Map<String, JsonValue> = RETENTION_PRIMITIVES
+            .iter()
+            .map(|p| (p.to_string(), self.bounds_for(p).to_json()))
+            .collect();
+        serde_json::json!({
+            "max_versions": self.defaults.max_versions,
+            "max_age_secs": self.defaults.max_age_secs,
+            "overrides": overrides,
+            "effective": effective,
+        })
+    }
+}
+
+/// Read an optional bound that must be a positive integer.
+fn positive_bound(args: &Map<String, JsonValue>, key: &str, scope: &str) -> Result<Option<u64>> {
+    match args.get(key) {
+        None | Some(JsonValue::Null) => Ok(None),
+        Some(v) => match v.as_u64() {
+            Some(n) if n > 0 => Ok(Some(n)),
+            _ => Err(McpError::InvalidArg {
+                name: if scope.is_empty() {
+                    key.to_string()
+                } else {
+                    format!("{}.{}", scope, key)
+                },
+                reason: "Expected a positive integer".to_string(),
+            }),
+        },
+    }
+}
+
+/// What a retention policy removes from one primitive.
+#[derive(Debug, Default, Clone, Copy)]
+struct PrimitiveCounts {
+    /// Keys, cells, documents, or events examined
+    scanned: u64,
+    /// Versions held by the scanned keys, cells, and documents
+    versions: u64,
+    /// Old versions that exceed the policy
+    versions_trimmed: u64,
+    /// Whole entries that expire under the policy
+    entries_removed: u64,
+}
+
+/// Per-primitive retention counts for a branch.
+#[derive(Debug, Default)]
+struct RetentionCounts {
+    per_primitive: BTreeMap<&'static str, PrimitiveCounts>,
+}
+
+impl RetentionCounts {
+    /// What disappeared between this scan and a later one.
+    fn removed_since(&self, after: &RetentionCounts) -> RetentionCounts {
+        let per_primitive = self
+            .per_primitive
+            .iter()
+            .map(|(primitive, before)| {
+                let after = after.per_primitive.get(primitive).copied().unwrap_or_default();
+                let counts = PrimitiveCounts {
+                    scanned: before.scanned,
+                    versions: after.versions,
+                    versions_trimmed: before.versions.saturating_sub(after.versions),
+                    entries_removed: before.scanned.saturating_sub(after.scanned),
+                };
+                (*primitive, counts)
+            })
+            .collect();
+        RetentionCounts { per_primitive }
+    }
+
+    fn to_json(&self) -> JsonValue {
+        let mut per_primitive = Map::new();
+        let (mut versions_trimmed, mut entries_removed) = (0, 0);
+        for (primitive, counts) in &self.per_primitive {
+            versions_trimmed += counts.versions_trimmed;
+            entries_removed += counts.entries_removed;
+            per_primitive.insert(
+                primitive.to_string(),
+                serde_json::json!({
+                    "scanned": counts.scanned,
+                    "versions_trimmed": counts.versions_trimmed,
+                    "entries_removed": counts.entries_removed,
+                }),
+            );
+        }
+        serde_json::json!({
+            "versions_trimmed": versions_trimmed,
+            "entries_removed": entries_removed,
+            "per_primitive": per_primitive,
+        })
+    }
+}
+
+/// Current time in microseconds, the unit of version timestamps.
+pub(crate) fn now_micros() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_micros() as u64)
+        .unwrap_or(0)
+}
+
+/// Timestamp before which versions are too old under `bounds`.
+fn age_cutoff(bounds: RetentionBounds, now: u64) -> Option<u64> {
+    bounds
+        .max_age_secs
+        .map(|secs| now.saturating_sub(secs.saturating_mul(1_000_000)))
+}
+
+/// Count the versions of one key that `bounds` would trim.
+///
+/// The newest version is always kept; older ones go if they are beyond
+/// `max_versions` or older than `max_age_secs`.
+fn trimmed_versions(mut timestamps: Vec<u64>, bounds: RetentionBounds, now: u64) -> u64 {
+    timestamps.sort_unstable_by(|a, b| b.cmp(a));
+    let cutoff = age_cutoff(bounds, now);
+    timestamps
+        .iter()
+        .enumerate()
+        .skip(1)
+        .filter(|(i, ts)| {
+            bounds.max_versions.is_some_and(|max| *i as u64 >= max)
+                || cutoff.is_some_and(|c| **ts < c)
+        })
+        .count() as u64
+}
+
+/// Version timestamps from a `*Getv` output.
+pub(crate) fn history_timestamps(output: Output) -> Vec<u64> {
+    match output {
+        Output::VersionHistory(Some(values)) => values
+            .into_iter()
+            .map(|vv| vv.timestamp)
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+/// Scan the current branch's histories and count what `policy` would remove.
+///
+/// Covers KV, state, and JSON histories and the event log across every space.
+/// Vectors keep no history and are not scanned.
+fn scan(session: &mut McpSession, policy: &RetentionPolicy) -> Result<RetentionCounts> {
+    let now = now_micros();
+    let branch = session.branch_id();
+    let spaces = match session.execute(Command::SpaceList {
+        branch: branch.clone(),
+    })? {
+        Output::SpaceList(spaces) => spaces,
+        _ => vec![session.space().to_string()],
+    };
+
+    let mut counts = RetentionCounts::default();
+    for space in spaces {
+        let space = Some(space);
+
+        let bounds = policy.bounds_for("kv");
+        let keys = match session.execute(Command::KvList {
+            branch: branch.clone(),
+            space: space.clone(),
+            prefix: None,
+            cursor: None,
+            limit: None,
+            as_of: None,
+        })? {
+            Output::Keys(keys) => keys,
+            _ => Vec::new(),
+        };
+        for key in keys {
+            let output = session.execute(Command::KvGetv {
+                branch: branch.clone(),
+                space: space.clone(),
+                key,
+                as_of: None,
+            })?;
+            let timestamps = history_timestamps(output);
+            let c = counts.per_primitive.entry("kv").or_default();
+            c.scanned += 1;
+            c.versions += timestamps.len() as u64;
+            c.versions_trimmed += trimmed_versions(timestamps, bounds, now);
+        }
+
+        let bounds = policy.bounds_for("state");
+        let cells = match session.execute(Command::StateList {
+            branch: branch.clone(),
+            space: space.clone(),
+            prefix: None,
+            as_of: None,
+        })? {
+            Output::Keys(keys) => keys,
+            _ => Vec::new(),
+        };
+        for cell in cells {
+            let output = session.execute(Command::StateGetv {
+                branch: branch.clone(),
+                space: space.clone(),
+                cell,
+                as_of: None,
+            })?;
+            let timestamps = history_timestamps(output);
+            let c = counts.per_primitive.entry("state").or_default();
+            c.scanned += 1;
+            c.versions += timestamps.len() as u64;
+            c.versions_trimmed += trimmed_versions(timestamps, bounds, now);
+        }
+
+        let bounds = policy.bounds_for("json");
+        let mut cursor = None;
+        loop {
+            let (keys, next) = match session.execute(Command::JsonList {
+                branch: branch.clone(),
+                space: space.clone(),
+                prefix: None,
+                cursor: cursor.take(),
+                limit: 1000,
+                as_of: None,
+            })? {
+                Output::JsonListResult { keys, cursor } => (keys, cursor),
+                _ => (Vec::new(), None),
+            };
+            for key in keys {
+                let output = session.execute(Command::JsonGetv {
+                    branch: branch.clone(),
+                    space: space.clone(),
+                    key,
+                    as_of: None,
+                })?;
+                let timestamps = history_timestamps(output);
+                let c = counts.per_primitive.entry("json").or_default();
+                c.scanned += 1;
+                c.versions += timestamps.len() as u64;
+                c.versions_trimmed += trimmed_versions(timestamps, bounds, now);
+            }
+            match next {
+                Some(next) => cursor = Some(next),
+                None => break,
+            }
+        }
+
+        let cutoff = age_cutoff(policy.bounds_for("event"), now);
+        let len = match session.execute(Command::EventLen {
+            branch: branch.clone(),
+            space: space.clone(),
+        })? {
+            Output::Uint(n) => n,
+            _ => 0,
+        };
+        for sequence in 0..len {
+            let output = session.execute(Command::EventGet {
+                branch: branch.clone(),
+                space: space.clone(),
+                sequence,
+                as_of: None,
+            })?;
+            let c = counts.per_primitive.entry("event").or_default();
+            c.scanned += 1;
+            if let Output::MaybeVersioned(Some(vv)) = output {
+                if cutoff.is_some_and(|cutoff| vv.timestamp < cutoff) {
+                    c.entries_removed += 1;
+                }
+            }
+        }
+    }
+
+    for primitive in ["kv", "state", "json", "event"] {
+        counts.per_primitive.entry(primitive).or_default();
+    }
+    Ok(counts)
+}
+
 /// Get all retention tool definitions.
 pub fn tools() -> Vec<ToolDef> {
-    vec![ToolDef::new(
-        "strata_retention_apply",
-        "Apply the retention policy to the current branch, trimming old versions \
-         and expired data according to configured rules. Returns null on success.",
-        schema!(object {}),
-    )]
+    vec![
+        ToolDef::new(
+            "strata_retention_apply",
+            "Apply the retention policy to the current branch, trimming old versions \
+             and expired data according to configured rules. Returns null on success. \
+             Pass report=true for a summary with versions_trimmed, entries_removed, and \
+             per-primitive counts; this scans every history in the branch before and after \
+             applying, so it is slow on large branches.",
+            schema!(object {
+                optional: { "report": boolean }
+            }),
+        ),
+        ToolDef::new(
+            "strata_retention_preview",
+            "Count what a retention policy would remove from the current branch \
+             without deleting anything. max_versions keeps the newest N versions per key; \
+             max_age_secs drops versions older than the given age. overrides maps a primitive \
+             (kv, state, event, json, vector) to its own bounds. At least one bound must be \
+             set. Returns versions_trimmed, entries_removed, per-primitive counts, and the \
+             effective policy.",
+            schema!(object {
+                optional: { "max_versions": integer, "max_age_secs": integer, "overrides": any }
+            }),
+        ),
+    ]
 }
 
 /// Dispatch a retention tool call.
 pub fn dispatch(
     session: &mut McpSession,
     name: &str,
-    _args: Map<String, JsonValue>,
+    args: Map<String, JsonValue>,
 ) -> Result<JsonValue> {
     match name {
         "strata_retention_apply" => {
+            session.check_write_access("RetentionApply")?;
             let cmd = Command::RetentionApply {
                 branch: session.branch_id(),
             };
-            let output = session.execute(cmd)?;
-            Ok(output_to_json(output))
+            if !get_optional_bool(&args, "report").unwrap_or(false) {
+                let output = session.execute(cmd)?;
+                return Ok(output_to_json_with(output, session.convert_options()));
+            }
+
+            // stratadb reports nothing back, so count by scanning around the apply
+            let policy = RetentionPolicy::default();
+            let before = scan(session, &policy)?;
+            session.execute(cmd)?;
+            let after = scan(session, &policy)?;
+
+            Ok(before.removed_since(&after).to_json())
+        }
+
+        "strata_retention_preview" => {
+            let policy = RetentionPolicy::from_args(&args)?;
+
+            let counts = scan(session, &policy)?;
+            let mut result = counts.to_json();
+            result["policy"] = policy.to_json();
+            Ok(result)
         }
 
         _ => Err(McpError::UnknownTool(name.to_string())),
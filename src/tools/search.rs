@@ -1,45 +1,84 @@
 //! Cross-primitive search tools.
 //!
-//! Tools: strata_search
+//! Tools: strata_search, strata_search_suggest
 
 use serde_json::{Map, Value as JsonValue};
-use stratadb::{Command, SearchQuery, TimeRangeInput};
+use stratadb::{Command, Output, SearchQuery, TimeRangeInput};
 
 use crate::convert::{
-    get_optional_bool, get_optional_string, get_optional_u64, get_string_arg, output_to_json,
+    get_optional_bool, get_optional_string, get_optional_u64, get_string_arg, output_to_json_with,
 };
 use crate::error::{McpError, Result};
 use crate::session::McpSession;
 use crate::tools::ToolDef;
 
+/// Suggestions returned by strata_search_suggest when k is not given.
+const SUGGEST_DEFAULT_K: u64 = 10;
+
+/// Upper bound on strata_search_suggest's k.
+const SUGGEST_MAX_K: u64 = 100;
+
+/// Primitives strata_search_suggest lists names from.
+const SUGGEST_PRIMITIVES: &[&str] = &["kv", "json", "state", "vector"];
+
 /// Get all search tool definitions.
 pub fn tools() -> Vec<ToolDef> {
-    vec![ToolDef::new(
-        "strata_search",
-        "Search across multiple primitives (kv, json, state, event) for matching content. \
-         Returns ranked results with scores and snippets. Use this to find data when you \
-         don't know which primitive contains it.",
-        serde_json::json!({
-            "type": "object",
-            "properties": {
-                "query": { "type": "string" },
-                "k": { "type": "integer" },
-                "primitives": { "type": "array", "items": { "type": "string" } },
-                "time_range": {
-                    "type": "object",
-                    "properties": {
-                        "start": { "type": "string" },
-                        "end": { "type": "string" }
+    vec![
+        ToolDef::new(
+            "strata_search",
+            "Search across multiple primitives (kv, json, state, event) for matching content. \
+             Returns ranked results with scores and snippets. Use this to find data when you \
+             don't know which primitive contains it. \
+             snippet_context keeps that many characters on each side of the first query term \
+             in each snippet, and snippet_length caps snippets at that many characters. \
+             Set collections to keep only vector results from those collections; results from \
+             other primitives are unaffected, and filtering happens after the top k are chosen.",
+            serde_json::json!({
+                "type": "object",
+                "properties": {
+                    "query": { "type": "string" },
+                    "k": { "type": "integer" },
+                    "primitives": { "type": "array", "items": { "type": "string" } },
+                    "collections": { "type": "array", "items": { "type": "string" } },
+                    "time_range": {
+                        "type": "object",
+                        "properties": {
+                            "start": { "type": "string" },
+                            "end": { "type": "string" }
+                        },
+                        "required": ["start", "end"]
                     },
-                    "required": ["start", "end"]
+                    "mode": { "type": "string", "enum": ["keyword", "hybrid"] },
+                    "expand": { "type": "boolean" },
+                    "rerank": { "type": "boolean" },
+                    "snippet_length": { "type": "integer" },
+                    "snippet_context": { "type": "integer" }
+                },
+                "required": ["query"],
+                "additionalProperties": false
+            }),
+        ),
+        ToolDef::new(
+            "strata_search_suggest",
+            "Suggest stored names starting with a prefix, for autocomplete. Returns up to k \
+             (default 10, max 100) entries of {key, primitive} in key order, drawn from kv, \
+             json, and state keys and vector collection names. Pass primitives to suggest \
+             from only some of them.",
+            serde_json::json!({
+                "type": "object",
+                "properties": {
+                    "prefix": {"type": "string"},
+                    "k": {"type": "integer"},
+                    "primitives": {
+                        "type": "array",
+                        "items": {"type": "string", "enum": SUGGEST_PRIMITIVES}
+                    }
                 },
-                "mode": { "type": "string", "enum": ["keyword", "hybrid"] },
-                "expand": { "type": "boolean" },
-                "rerank": { "type": "boolean" }
-            },
-            "required": ["query"]
-        }),
-    )]
+                "required": ["prefix"],
+                "additionalProperties": false
+            }),
+        ),
+    ]
 }
 
 /// Dispatch a search tool call.
@@ -53,10 +92,14 @@ pub fn dispatch(
             let query = get_string_arg(&args, "query")?;
             let k = get_optional_u64(&args, "k");
             let primitives = get_optional_string_array(&args, "primitives");
+            let collections = get_optional_string_array(&args, "collections");
             let time_range = get_optional_time_range(&args);
             let mode = get_optional_string(&args, "mode");
             let expand = get_optional_bool(&args, "expand");
             let rerank = get_optional_bool(&args, "rerank");
+            let snippet_length = get_optional_u64(&args, "snippet_length").map(|n| n as usize);
+            let snippet_context = get_optional_u64(&args, "snippet_context").map(|n| n as usize);
+            let terms: Vec<String> = query.split_whitespace().map(str::to_lowercase).collect();
 
             let sq = SearchQuery {
                 query,
@@ -74,13 +117,104 @@ pub fn dispatch(
                 search: sq,
             };
             let output = session.execute(cmd)?;
-            Ok(output_to_json(output))
+            let mut result = output_to_json_with(output, session.convert_options());
+            if let (Some(collections), Some(hits)) = (&collections, result.as_array_mut()) {
+                hits.retain(|hit| in_collections(hit, collections));
+            }
+            if snippet_length.is_some() || snippet_context.is_some() {
+                for hit in result.as_array_mut().into_iter().flatten() {
+                    if let Some(JsonValue::String(snippet)) = hit.get_mut("snippet") {
+                        *snippet = trim_snippet(snippet, &terms, snippet_context, snippet_length);
+                    }
+                }
+            }
+            Ok(result)
         }
 
+        "strata_search_suggest" => suggest(session, &args),
+
         _ => Err(McpError::UnknownTool(name.to_string())),
     }
 }
 
+/// Names under a prefix across primitives, merged in key order.
+///
+/// Storage lists each primitive in key order, so taking the first `k` of
+/// each and merging finds the first `k` overall without a full scan. State
+/// lists have no limit and vector collections are filtered here.
+fn suggest(session: &mut McpSession, args: &Map<String, JsonValue>) -> Result<JsonValue> {
+    let prefix = get_string_arg(args, "prefix")?;
+    let k = get_optional_u64(args, "k").unwrap_or(SUGGEST_DEFAULT_K);
+    if !(1..=SUGGEST_MAX_K).contains(&k) {
+        return Err(McpError::InvalidArg {
+            name: "k".to_string(),
+            reason: format!("Must be between 1 and {}", SUGGEST_MAX_K),
+        });
+    }
+    let primitives = get_optional_string_array(args, "primitives");
+    let wanted = |p: &str| primitives.as_ref().is_none_or(|ps| ps.iter().any(|w| w == p));
+
+    let mut candidates: Vec<(String, &str)> = Vec::new();
+    if wanted("kv") {
+        if let Output::Keys(keys) = session.execute(Command::KvList {
+            branch: session.branch_id(),
+            space: session.space_id(),
+            prefix: Some(prefix.clone()),
+            cursor: None,
+            limit: Some(k),
+            as_of: None,
+        })? {
+            candidates.extend(keys.into_iter().map(|key| (key, "kv")));
+        }
+    }
+    if wanted("json") {
+        if let Output::JsonListResult { keys, .. } = session.execute(Command::JsonList {
+            branch: session.branch_id(),
+            space: session.space_id(),
+            prefix: Some(prefix.clone()),
+            cursor: None,
+            limit: k,
+            as_of: None,
+        })? {
+            candidates.extend(keys.into_iter().map(|key| (key, "json")));
+        }
+    }
+    if wanted("state") {
+        if let Output::Keys(cells) = session.execute(Command::StateList {
+            branch: session.branch_id(),
+            space: session.space_id(),
+            prefix: Some(prefix.clone()),
+            as_of: None,
+        })? {
+            candidates.extend(cells.into_iter().map(|cell| (cell, "state")));
+        }
+    }
+    if wanted("vector") {
+        if let Output::VectorCollectionList(collections) =
+            session.execute(Command::VectorListCollections {
+                branch: session.branch_id(),
+                space: session.space_id(),
+            })?
+        {
+            candidates.extend(
+                collections
+                    .into_iter()
+                    .filter(|c| c.name.starts_with(&prefix))
+                    .map(|c| (c.name, "vector")),
+            );
+        }
+    }
+
+    candidates.sort();
+    candidates.truncate(k as usize);
+    Ok(JsonValue::Array(
+        candidates
+            .into_iter()
+            .map(|(key, primitive)| serde_json::json!({"key": key, "primitive": primitive}))
+            .collect(),
+    ))
+}
+
 /// Helper to get an optional array of strings.
 fn get_optional_string_array(args: &Map<String, JsonValue>, name: &str) -> Option<Vec<String>> {
     args.get(name).and_then(|v| v.as_array()).map(|arr| {
@@ -97,3 +231,56 @@ fn get_optional_time_range(args: &Map<String, JsonValue>) -> Option<TimeRangeInp
     let end = obj.get("end")?.as_str()?.to_string();
     Some(TimeRangeInput { start, end })
 }
+
+/// Returns `true` unless the hit is a vector result from a collection not
+/// in `collections`.
+///
+/// Vector entities are named `collection/key`.
+fn in_collections(hit: &JsonValue, collections: &[String]) -> bool {
+    let is_vector = hit["primitive"]
+        .as_str()
+        .is_some_and(|p| p.eq_ignore_ascii_case("vector"));
+    if !is_vector {
+        return true;
+    }
+    let entity = hit["entity"].as_str().unwrap_or_default();
+    let collection = entity.split_once('/').map_or(entity, |(c, _)| c);
+    collections.iter().any(|c| c == collection)
+}
+
+/// Shorten a snippet to the context around the first query term, then to
+/// at most `length` characters.
+fn trim_snippet(
+    snippet: &str,
+    terms: &[String],
+    context: Option<usize>,
+    length: Option<usize>,
+) -> String {
+    let chars: Vec<char> = snippet.chars().collect();
+    let (mut start, mut end) = (0, chars.len());
+
+    if let Some(context) = context {
+        let lower: Vec<char> = snippet.to_lowercase().chars().collect();
+        // Lowercasing can change the character count; only use it for matching if it does not
+        let found = (lower.len() == chars.len())
+            .then(|| {
+                terms
+                    .iter()
+                    .filter_map(|term| {
+                        let term: Vec<char> = term.chars().collect();
+                        let pos = lower.windows(term.len()).position(|w| w == term.as_slice())?;
+                        Some((pos, term.len()))
+                    })
+                    .min()
+            })
+            .flatten();
+        if let Some((pos, len)) = found {
+            start = pos.saturating_sub(context);
+            end = (pos + len + context).min(chars.len());
+        }
+    }
+    if let Some(length) = length {
+        end = end.min(start + length);
+    }
+    chars[start..end].iter().collect()
+}
@@ -0,0 +1,47 @@
+//! Server information tools.
+//!
+//! Tools: strata_server_info
+
+use serde_json::{Map, Value as JsonValue};
+
+use crate::error::{McpError, Result};
+use crate::schema;
+use crate::server::{PROTOCOL_VERSION, SERVER_NAME, SERVER_VERSION, SUPPORTED_PROTOCOL_VERSIONS};
+use crate::session::McpSession;
+use crate::tools::{ToolDef, ToolRegistry};
+
+/// Get all server information tool definitions.
+pub fn tools() -> Vec<ToolDef> {
+    vec![ToolDef::new(
+        "strata_server_info",
+        "Describe this server: name, version, preferred and supported MCP protocol \
+         versions, enabled features (embed, auto_embed, read_only), and the number of \
+         tools. The same details are available from initialize, for clients that only \
+         discover through tools.",
+        schema!(object {}),
+    )]
+}
+
+/// Dispatch a server information tool call.
+pub fn dispatch(
+    registry: &ToolRegistry,
+    session: &mut McpSession,
+    name: &str,
+    _args: Map<String, JsonValue>,
+) -> Result<JsonValue> {
+    match name {
+        "strata_server_info" => Ok(serde_json::json!({
+            "name": SERVER_NAME,
+            "version": SERVER_VERSION,
+            "protocol_version": PROTOCOL_VERSION,
+            "supported_protocol_versions": SUPPORTED_PROTOCOL_VERSIONS,
+            "features": {
+                "embed": cfg!(feature = "embed"),
+                "auto_embed": session.runtime_config().auto_embed,
+                "read_only": session.is_read_only(),
+            },
+            "tool_count": registry.tools().len(),
+        })),
+        _ => Err(McpError::UnknownTool(name.to_string())),
+    }
+}
@@ -1,16 +1,20 @@
 //! Space management tools.
 //!
-//! Tools: strata_space_list, strata_space_create, strata_space_delete, strata_space_switch
+//! Tools: strata_space_list, strata_space_create, strata_space_delete, strata_space_switch,
+//!        strata_space_copy, strata_space_clear
 
 use serde_json::{Map, Value as JsonValue};
-use stratadb::Command;
+use stratadb::{Command, Output, Value};
 
-use crate::convert::{get_optional_bool, get_string_arg, output_to_json};
+use crate::convert::{get_optional_bool, get_string_arg, output_to_json_with};
 use crate::error::{McpError, Result};
 use crate::schema;
 use crate::session::McpSession;
 use crate::tools::ToolDef;
 
+/// Primitives whose entries strata_space_clear can delete.
+const CLEARABLE_PRIMITIVES: &[&str] = &["kv", "state", "json", "vector"];
+
 /// Get all space tool definitions.
 pub fn tools() -> Vec<ToolDef> {
     vec![
@@ -23,9 +27,12 @@ pub fn tools() -> Vec<ToolDef> {
         ToolDef::new(
             "strata_space_create",
             "Create a new space explicitly. Spaces are auto-created on first write, \
-             but this allows pre-creation for organizational purposes.",
+             but this allows pre-creation for organizational purposes. Pass \
+             if_not_exists=true to succeed when the space already exists; returns \
+             {space, created}.",
             schema!(object {
-                required: { "space": string }
+                required: { "space": string },
+                optional: { "if_not_exists": boolean }
             }),
         ),
         ToolDef::new(
@@ -52,6 +59,30 @@ pub fn tools() -> Vec<ToolDef> {
                 required: { "space": string }
             }),
         ),
+        ToolDef::new(
+            "strata_space_copy",
+            "Copy the KV, state, and JSON data of one space into another space in the \
+             current branch, creating it if needed. Fails if the target exists unless \
+             overwrite=true, which replaces its KV, state, and JSON entries in the same \
+             transaction as the copy and then drops its vector collections; its events \
+             are append-only and kept. Events and vectors are not copied; their counts are \
+             reported under skipped. Returns per-primitive counts.",
+            schema!(object {
+                required: { "from": string, "to": string },
+                optional: { "overwrite": boolean }
+            }),
+        ),
+        ToolDef::new(
+            "strata_space_clear",
+            "Delete all entries in a space but keep the space. primitives limits what is \
+             cleared (kv, state, json, vector; default all). Events are append-only and \
+             cannot be cleared. Clearing the 'default' space requires confirm=true. \
+             Returns counts removed per primitive.",
+            schema!(object {
+                required: { "space": string },
+                optional: { "primitives": array_string, "confirm": boolean }
+            }),
+        ),
     ]
 }
 
@@ -67,18 +98,25 @@ pub fn dispatch(
                 branch: session.branch_id(),
             };
             let output = session.execute(cmd)?;
-            Ok(output_to_json(output))
+            Ok(output_to_json_with(output, session.convert_options()))
         }
 
         "strata_space_create" => {
             let space = get_string_arg(&args, "space")?;
+            let if_not_exists = get_optional_bool(&args, "if_not_exists").unwrap_or(false);
+            if if_not_exists && space_exists(session, &space)? {
+                return Ok(serde_json::json!({ "space": space, "created": false }));
+            }
 
             let cmd = Command::SpaceCreate {
                 branch: session.branch_id(),
-                space,
+                space: space.clone(),
             };
             let output = session.execute(cmd)?;
-            Ok(output_to_json(output))
+            if if_not_exists {
+                return Ok(serde_json::json!({ "space": space, "created": true }));
+            }
+            Ok(output_to_json_with(output, session.convert_options()))
         }
 
         "strata_space_exists" => {
@@ -89,7 +127,7 @@ pub fn dispatch(
                 space,
             };
             let output = session.execute(cmd)?;
-            Ok(output_to_json(output))
+            Ok(output_to_json_with(output, session.convert_options()))
         }
 
         "strata_space_delete" => {
@@ -102,7 +140,7 @@ pub fn dispatch(
                 force,
             };
             let output = session.execute(cmd)?;
-            Ok(output_to_json(output))
+            Ok(output_to_json_with(output, session.convert_options()))
         }
 
         "strata_space_switch" => {
@@ -114,6 +152,366 @@ pub fn dispatch(
             }))
         }
 
+        "strata_space_copy" => {
+            let from = get_string_arg(&args, "from")?;
+            let to = get_string_arg(&args, "to")?;
+            let overwrite = get_optional_bool(&args, "overwrite").unwrap_or(false);
+            copy_space(session, &from, &to, overwrite)
+        }
+
+        "strata_space_clear" => {
+            let space = get_string_arg(&args, "space")?;
+            let confirm = get_optional_bool(&args, "confirm").unwrap_or(false);
+            let primitives: Vec<String> = match args.get("primitives").and_then(|v| v.as_array()) {
+                Some(arr) => arr
+                    .iter()
+                    .filter_map(|v| v.as_str().map(String::from))
+                    .collect(),
+                None => CLEARABLE_PRIMITIVES.iter().map(|p| p.to_string()).collect(),
+            };
+            if let Some(bad) = primitives
+                .iter()
+                .find(|p| !CLEARABLE_PRIMITIVES.contains(&p.as_str()))
+            {
+                return Err(McpError::InvalidArg {
+                    name: "primitives".to_string(),
+                    reason: format!(
+                        "Cannot clear '{}'; expected one of {}",
+                        bad,
+                        CLEARABLE_PRIMITIVES.join(", ")
+                    ),
+                });
+            }
+            if space == "default" && !confirm {
+                return Err(McpError::InvalidArg {
+                    name: "confirm".to_string(),
+                    reason: "Clearing the default space requires confirm=true".to_string(),
+                });
+            }
+            clear_space(session, &space, &primitives)
+        }
+
         _ => Err(McpError::UnknownTool(name.to_string())),
     }
 }
+
+/// Copy a space's KV, state, and JSON entries into another space.
+fn copy_space(
+    session: &mut McpSession,
+    from: &str,
+    to: &str,
+    overwrite: bool,
+) -> Result<JsonValue> {
+    session.check_write_access("SpaceCopy")?;
+    if from == to {
+        return Err(McpError::InvalidArg {
+            name: "to".to_string(),
+            reason: "Source and target spaces must differ".to_string(),
+        });
+    }
+    if !space_exists(session, from)? {
+        return Err(McpError::InvalidArg {
+            name: "from".to_string(),
+            reason: format!("Space '{}' does not exist", from),
+        });
+    }
+    let replacing = space_exists(session, to)?;
+    if replacing && !overwrite {
+        return Err(McpError::InvalidArg {
+            name: "to".to_string(),
+            reason: format!("Space '{}' already exists; pass overwrite=true to replace it", to),
+        });
+    }
+    if !replacing {
+        session.execute(Command::SpaceCreate {
+            branch: session.branch_id(),
+            space: to.to_string(),
+        })?;
+    }
+
+    let (src, dst) = (Some(from.to_string()), Some(to.to_string()));
+    let (kv, state, json) = session.atomically(|session| {
+        let branch = session.branch_id();
+
+        // The old entries go in the same transaction, so a failed copy leaves
+        // the target as it was
+        if replacing {
+            delete_entries(session, &dst, |_| true)?;
+        }
+
+        let mut kv = 0u64;
+        for key in kv_keys(session, &src)? {
+            let output = session.execute(Command::KvGet {
+                branch: branch.clone(),
+                space: src.clone(),
+                key: key.clone(),
+                as_of: None,
+            })?;
+            if let Some(value) = value_of(output) {
+                session.execute(Command::KvPut {
+                    branch: branch.clone(),
+                    space: dst.clone(),
+                    key,
+                    value,
+                })?;
+                kv += 1;
+            }
+        }
+
+        let mut state = 0u64;
+        for cell in state_cells(session, &src)? {
+            let output = session.execute(Command::StateGet {
+                branch: branch.clone(),
+                space: src.clone(),
+                cell: cell.clone(),
+                as_of: None,
+            })?;
+            if let Some(value) = value_of(output) {
+                session.execute(Command::StateSet {
+                    branch: branch.clone(),
+                    space: dst.clone(),
+                    cell,
+                    value,
+                })?;
+                state += 1;
+            }
+        }
+
+        let mut json = 0u64;
+        for key in json_keys(session, &src)? {
+            let output = session.execute(Command::JsonGet {
+                branch: branch.clone(),
+                space: src.clone(),
+                key: key.clone(),
+                path: "$".to_string(),
+                as_of: None,
+            })?;
+            if let Some(value) = value_of(output) {
+                session.execute(Command::JsonSet {
+                    branch: branch.clone(),
+                    space: dst.clone(),
+                    key,
+                    path: "$".to_string(),
+                    value,
+                })?;
+                json += 1;
+            }
+        }
+
+        Ok((kv, state, json))
+    })?;
+
+    if replacing {
+        for collection in collection_names(session, &dst)? {
+            session.execute(Command::VectorDeleteCollection {
+                branch: session.branch_id(),
+                space: dst.clone(),
+                collection,
+            })?;
+        }
+    }
+
+    let events = event_count(session, &src)?;
+    let collections = collection_names(session, &src)?.len();
+
+    Ok(serde_json::json!({
+        "from": from,
+        "to": to,
+        "kv": kv,
+        "state": state,
+        "json": json,
+        "skipped": {
+            "event": events,
+            "vector": collections,
+        },
+    }))
+}
+
+/// Delete the entries of the selected primitives from a space.
+fn clear_space(
+    session: &mut McpSession,
+    space: &str,
+    primitives: &[String],
+) -> Result<JsonValue> {
+    session.check_write_access("SpaceClear")?;
+    if !space_exists(session, space)? {
+        return Err(McpError::InvalidArg {
+            name: "space".to_string(),
+            reason: format!("Space '{}' does not exist", space),
+        });
+    }
+
+    let target = Some(space.to_string());
+    let selected = |p: &str| primitives.iter().any(|s| s == p);
+    let mut result = Map::new();
+
+    let (kv, state, json) =
+        session.atomically(|session| delete_entries(session, &target, &selected))?;
+    if selected("kv") {
+        result.insert("kv".to_string(), kv.into());
+    }
+    if selected("state") {
+        result.insert("state".to_string(), state.into());
+    }
+    if selected("json") {
+        result.insert("json".to_string(), json.into());
+    }
+
+    // Collections are dropped after the entry deletes have committed
+    if selected("vector") {
+        let collections = collection_names(session, &target)?;
+        for collection in &collections {
+            session.execute(Command::VectorDeleteCollection {
+                branch: session.branch_id(),
+                space: target.clone(),
+                collection: collection.clone(),
+            })?;
+        }
+        result.insert("vector".to_string(), collections.len().into());
+    }
+
+    Ok(serde_json::json!({
+        "space": space,
+        "removed": result,
+    }))
+}
+
+/// Delete the KV, state, and JSON entries of the selected primitives from a
+/// space, returning how many of each were removed.
+fn delete_entries(
+    session: &mut McpSession,
+    space: &Option<String>,
+    selected: impl Fn(&str) -> bool,
+) -> Result<(u64, u64, u64)> {
+    let branch = session.branch_id();
+    let (mut kv, mut state, mut json) = (0u64, 0u64, 0u64);
+
+    if selected("kv") {
+        for key in kv_keys(session, space)? {
+            session.execute(Command::KvDelete {
+                branch: branch.clone(),
+                space: space.clone(),
+                key,
+            })?;
+            kv += 1;
+        }
+    }
+    if selected("state") {
+        for cell in state_cells(session, space)? {
+            session.execute(Command::StateDelete {
+                branch: branch.clone(),
+                space: space.clone(),
+                cell,
+            })?;
+            state += 1;
+        }
+    }
+    if selected("json") {
+        for key in json_keys(session, space)? {
+            session.execute(Command::JsonDelete {
+                branch: branch.clone(),
+                space: space.clone(),
+                key,
+                path: "$".to_string(),
+            })?;
+            json += 1;
+        }
+    }
+
+    Ok((kv, state, json))
+}
+
+fn space_exists(session: &mut McpSession, space: &str) -> Result<bool> {
+    let cmd = Command::SpaceExists {
+        branch: session.branch_id(),
+        space: space.to_string(),
+    };
+    Ok(matches!(session.execute(cmd)?, Output::Bool(true)))
+}
+
+fn kv_keys(session: &mut McpSession, space: &Option<String>) -> Result<Vec<String>> {
+    let cmd = Command::KvList {
+        branch: session.branch_id(),
+        space: space.clone(),
+        prefix: None,
+        cursor: None,
+        limit: None,
+        as_of: None,
+    };
+    Ok(keys_of(session.execute(cmd)?))
+}
+
+fn state_cells(session: &mut McpSession, space: &Option<String>) -> Result<Vec<String>> {
+    let cmd = Command::StateList {
+        branch: session.branch_id(),
+        space: space.clone(),
+        prefix: None,
+        as_of: None,
+    };
+    Ok(keys_of(session.execute(cmd)?))
+}
+
+fn json_keys(session: &mut McpSession, space: &Option<String>) -> Result<Vec<String>> {
+    let mut all = Vec::new();
+    let mut cursor = None;
+    loop {
+        let cmd = Command::JsonList {
+            branch: session.branch_id(),
+            space: space.clone(),
+            prefix: None,
+            cursor: cursor.take(),
+            limit: 1000,
+            as_of: None,
+        };
+        match session.execute(cmd)? {
+            Output::JsonListResult { keys, cursor: next } => {
+                all.extend(keys);
+                match next {
+                    Some(next) => cursor = Some(next),
+                    None => break,
+                }
+            }
+            _ => break,
+        }
+    }
+    Ok(all)
+}
+
+fn collection_names(session: &mut McpSession, space: &Option<String>) -> Result<Vec<String>> {
+    let cmd = Command::VectorListCollections {
+        branch: session.branch_id(),
+        space: space.clone(),
+    };
+    match session.execute(cmd)? {
+        Output::VectorCollectionList(collections) => {
+            Ok(collections.into_iter().map(|c| c.name).collect())
+        }
+        _ => Ok(Vec::new()),
+    }
+}
+
+fn event_count(session: &mut McpSession, space: &Option<String>) -> Result<u64> {
+    let cmd = Command::EventLen {
+        branch: session.branch_id(),
+        space: space.clone(),
+    };
+    match session.execute(cmd)? {
+        Output::Uint(n) => Ok(n),
+        _ => Ok(0),
+    }
+}
+
+fn keys_of(output: Output) -> Vec<String> {
+    match output {
+        Output::Keys(keys) => keys,
+        _ => Vec::new(),
+    }
+}
+
+fn value_of(output: Output) -> Option<Value> {
+    match output {
+        Output::MaybeVersioned(Some(vv)) => Some(vv.value),
+        Output::Maybe(value) => value,
+        _ => None,
+    }
+}
@@ -1,17 +1,21 @@
 //! State cell tools.
 //!
 //! Tools: strata_state_set, strata_state_get, strata_state_delete, strata_state_init,
-//!        strata_state_cas, strata_state_list, strata_state_history
+//!        strata_state_cas, strata_state_list, strata_state_history, strata_state_batch_set,
+//!        strata_state_wait, strata_state_delete_many
 
 use serde_json::{Map, Value as JsonValue};
-use stratadb::Command;
+use stratadb::{BranchId, Command, Output};
 
 use crate::convert::{
-    get_optional_string, get_optional_u64, get_string_arg, get_value_arg, output_to_json,
+    get_convert_options, get_optional_u64, get_string_arg, get_value_arg, history_window,
+    json_to_value, output_to_json_with, value_to_json_with,
 };
 use crate::error::{McpError, Result};
 use crate::schema;
 use crate::session::McpSession;
+use crate::tools::key_filter::KeyFilter;
+use crate::tools::wait::{self, WaitOptions};
 use crate::tools::ToolDef;
 
 /// Get all state tool definitions.
@@ -27,10 +31,13 @@ pub fn tools() -> Vec<ToolDef> {
         ToolDef::new(
             "strata_state_get",
             "Get the current value of a state cell. Returns null if cell doesn't exist. \
-             Pass as_of (microsecond timestamp) for time-travel reads.",
+             Pass as_of (microsecond timestamp) for time-travel reads. \
+             Pass raw=true to return the bare value without version info. \
+             Pass distinguish_missing=true to get {found: false} for a missing cell and \
+             found: true alongside the value otherwise.",
             schema!(object {
                 required: { "cell": string },
-                optional: { "as_of": integer }
+                optional: { "as_of": integer, "raw": boolean, "distinguish_missing": boolean }
             }),
         ),
         ToolDef::new(
@@ -40,6 +47,14 @@ pub fn tools() -> Vec<ToolDef> {
                 required: { "cell": string }
             }),
         ),
+        ToolDef::new(
+            "strata_state_delete_many",
+            "Delete multiple state cells in one transaction. Returns array of booleans \
+             (true if the cell existed).",
+            schema!(object {
+                required: { "cells": array_string }
+            }),
+        ),
         ToolDef::new(
             "strata_state_init",
             "Initialize a state cell only if it doesn't exist. Returns the version number.",
@@ -58,18 +73,49 @@ pub fn tools() -> Vec<ToolDef> {
         ToolDef::new(
             "strata_state_list",
             "List state cell names with optional prefix filter. \
-             Pass as_of (microsecond timestamp) for time-travel reads.",
+             Pass as_of (microsecond timestamp) for time-travel reads. \
+             Set case_insensitive to match the prefix ignoring case, or pass a glob \
+             (* matches any characters, ? one character); these filter after listing.",
             schema!(object {
-                optional: { "prefix": string, "as_of": integer }
+                optional: {
+                    "prefix": string, "as_of": integer, "case_insensitive": boolean, "glob": string
+                }
             }),
         ),
         ToolDef::new(
             "strata_state_history",
             "Get the full version history for a state cell. \
-             Pass as_of (microsecond timestamp) to get history up to that point.",
+             Pass as_of (microsecond timestamp) to get history up to that point. \
+             Pass limit to return only the most recent N versions, newest first, and \
+             since_version to return only versions newer than one already seen.",
             schema!(object {
                 required: { "cell": string },
-                optional: { "as_of": integer }
+                optional: { "as_of": integer, "limit": integer, "since_version": integer }
+            }),
+        ),
+        ToolDef::new(
+            "strata_state_batch_set",
+            "Set multiple state cells atomically. Each item is {cell, value}. \
+             All items are validated before anything is written, and the writes run in \
+             a transaction. Returns array of version numbers.",
+            schema!(object {
+                required: { "items": array_object }
+            }),
+        ),
+        ToolDef::new(
+            "strata_state_wait",
+            "Block until a state cell reaches a target, for cells used as locks or signals. \
+             Pass value to wait until the cell equals it, and/or min_version to wait until its \
+             version is at least that number. Gives up after timeout_ms (default 5000, max \
+             60000), checking every poll_interval_ms (default 50, 10 to 1000). Returns satisfied, \
+             value, and version, like strata_kv_wait; satisfied is false if the timeout elapsed, \
+             with the cell's current value and version. Cannot be used inside a transaction.",
+            schema!(object {
+                required: { "cell": string },
+                optional: {
+                    "value": any, "min_version": integer, "timeout_ms": integer,
+                    "poll_interval_ms": integer
+                }
             }),
         ),
     ]
@@ -93,12 +139,13 @@ pub fn dispatch(
                 value,
             };
             let output = session.execute(cmd)?;
-            Ok(output_to_json(output))
+            Ok(output_to_json_with(output, session.convert_options()))
         }
 
         "strata_state_get" => {
             let cell = get_string_arg(&args, "cell")?;
             let as_of = get_optional_u64(&args, "as_of");
+            let opts = get_convert_options(&args, session.convert_options());
 
             let cmd = Command::StateGet {
                 branch: session.branch_id(),
@@ -107,7 +154,7 @@ pub fn dispatch(
                 as_of,
             };
             let output = session.execute(cmd)?;
-            Ok(output_to_json(output))
+            Ok(output_to_json_with(output, &opts))
         }
 
         "strata_state_delete" => {
@@ -119,7 +166,38 @@ pub fn dispatch(
                 cell,
             };
             let output = session.execute(cmd)?;
-            Ok(output_to_json(output))
+            Ok(output_to_json_with(output, session.convert_options()))
+        }
+
+        "strata_state_delete_many" => {
+            let cells = args
+                .get("cells")
+                .and_then(|v| v.as_array())
+                .ok_or_else(|| McpError::MissingArg("cells".to_string()))?;
+            let cells = cells
+                .iter()
+                .map(|v| {
+                    v.as_str().map(String::from).ok_or_else(|| McpError::InvalidArg {
+                        name: "cells".to_string(),
+                        reason: "Cells must be strings".to_string(),
+                    })
+                })
+                .collect::<Result<Vec<_>>>()?;
+
+            session.check_write_access("StateDeleteMany")?;
+            session.atomically(|session| {
+                let mut results = Vec::new();
+                for cell in cells {
+                    let cmd = Command::StateDelete {
+                        branch: session.branch_id(),
+                        space: session.space_id(),
+                        cell,
+                    };
+                    let output = session.execute(cmd)?;
+                    results.push(output_to_json_with(output, session.convert_options()));
+                }
+                Ok(JsonValue::Array(results))
+            })
         }
 
         "strata_state_init" => {
@@ -133,7 +211,7 @@ pub fn dispatch(
                 value,
             };
             let output = session.execute(cmd)?;
-            Ok(output_to_json(output))
+            Ok(output_to_json_with(output, session.convert_options()))
         }
 
         "strata_state_cas" => {
@@ -149,26 +227,31 @@ pub fn dispatch(
                 value,
             };
             let output = session.execute(cmd)?;
-            Ok(output_to_json(output))
+            Ok(output_to_json_with(output, session.convert_options()))
         }
 
         "strata_state_list" => {
-            let prefix = get_optional_string(&args, "prefix");
+            let filter = KeyFilter::from_args(&args);
             let as_of = get_optional_u64(&args, "as_of");
 
             let cmd = Command::StateList {
                 branch: session.branch_id(),
                 space: session.space_id(),
-                prefix,
+                prefix: filter.native_prefix(),
                 as_of,
             };
-            let output = session.execute(cmd)?;
-            Ok(output_to_json(output))
+            let output = match session.execute(cmd)? {
+                Output::Keys(keys) => Output::Keys(filter.apply(keys)),
+                other => other,
+            };
+            Ok(output_to_json_with(output, session.convert_options()))
         }
 
         "strata_state_history" => {
             let cell = get_string_arg(&args, "cell")?;
             let as_of = get_optional_u64(&args, "as_of");
+            let limit = get_optional_u64(&args, "limit");
+            let since_version = get_optional_u64(&args, "since_version");
 
             let cmd = Command::StateGetv {
                 branch: session.branch_id(),
@@ -176,10 +259,104 @@ pub fn dispatch(
                 cell,
                 as_of,
             };
-            let output = session.execute(cmd)?;
-            Ok(output_to_json(output))
+            let output = history_window(session.execute(cmd)?, since_version, limit);
+            Ok(output_to_json_with(output, session.convert_options()))
+        }
+
+        "strata_state_batch_set" => {
+            let items = args
+                .get("items")
+                .and_then(|v| v.as_array())
+                .ok_or_else(|| McpError::MissingArg("items".to_string()))?;
+
+            let mut cells = Vec::with_capacity(items.len());
+            for (i, item) in items.iter().enumerate() {
+                let cell = item
+                    .get("cell")
+                    .and_then(|v| v.as_str())
+                    .ok_or_else(|| McpError::InvalidArg {
+                        name: format!("items[{}].cell", i),
+                        reason: "Each item must have a 'cell' string field".to_string(),
+                    })?
+                    .to_string();
+                let value_json = item.get("value").cloned().ok_or_else(|| McpError::InvalidArg {
+                    name: format!("items[{}].value", i),
+                    reason: "Each item must have a 'value' field".to_string(),
+                })?;
+                cells.push((cell, json_to_value(value_json)?));
+            }
+
+            session.check_write_access("StateBatchSet")?;
+            session.atomically(|session| {
+                let mut versions = Vec::new();
+                for (cell, value) in cells {
+                    let cmd = Command::StateSet {
+                        branch: session.branch_id(),
+                        space: session.space_id(),
+                        cell,
+                        value,
+                    };
+                    let output = session.execute(cmd)?;
+                    versions.push(output_to_json_with(output, session.convert_options()));
+                }
+                Ok(JsonValue::Array(versions))
+            })
+        }
+
+        "strata_state_wait" => {
+            let cell = get_string_arg(&args, "cell")?;
+            let target = args.get("value").filter(|v| !v.is_null()).cloned();
+            let min_version = get_optional_u64(&args, "min_version");
+            if target.is_none() && min_version.is_none() {
+                return Err(McpError::InvalidArg {
+                    name: "value".to_string(),
+                    reason: "Pass a target value and/or min_version to wait for".to_string(),
+                });
+            }
+            let opts = WaitOptions::from_args(&args)?;
+
+            let (branch, space) = (session.branch_id(), session.space_id());
+            let reached = wait::poll(session, &opts, |session| {
+                let (value, version) = state_entry(session, branch.clone(), space.clone(), &cell)?;
+                let value_ok = target.as_ref().is_none_or(|t| version.is_some() && *t == value);
+                let version_ok = min_version.is_none_or(|min| version.is_some_and(|v| v >= min));
+                Ok((value_ok && version_ok).then_some((value, version)))
+            })?;
+
+            let satisfied = reached.is_some();
+            let (value, version) = match reached {
+                Some(entry) => entry,
+                None => state_entry(session, branch, space, &cell)?,
+            };
+            Ok(serde_json::json!({
+                "satisfied": satisfied,
+                "value": value,
+                "version": version,
+            }))
         }
 
         _ => Err(McpError::UnknownTool(name.to_string())),
     }
 }
+
+/// Read the current value of a state cell as JSON with its version.
+fn state_entry(
+    session: &mut McpSession,
+    branch: Option<BranchId>,
+    space: Option<String>,
+    cell: &str,
+) -> Result<(JsonValue, Option<u64>)> {
+    let cmd = Command::StateGet {
+        branch,
+        space,
+        cell: cell.to_string(),
+        as_of: None,
+    };
+    match session.execute(cmd)? {
+        Output::MaybeVersioned(Some(vv)) => {
+            let version = Some(vv.version);
+            Ok((value_to_json_with(vv.value, session.convert_options()), version))
+        }
+        _ => Ok((JsonValue::Null, None)),
+    }
+}
@@ -2,10 +2,12 @@
 //!
 //! Tools: strata_txn_begin, strata_txn_commit, strata_txn_rollback, strata_txn_info, strata_txn_active
 
+use std::time::Duration;
+
 use serde_json::{Map, Value as JsonValue};
-use stratadb::{Command, TxnOptions};
+use stratadb::{Command, Output, TxnOptions};
 
-use crate::convert::{get_optional_bool, output_to_json};
+use crate::convert::{get_optional_bool, get_optional_u64, output_to_json_with};
 use crate::error::{McpError, Result};
 use crate::schema;
 use crate::session::McpSession;
@@ -16,9 +18,11 @@ pub fn tools() -> Vec<ToolDef> {
     vec![
         ToolDef::new(
             "strata_txn_begin",
-            "Begin a new transaction on the current branch. Operations within the transaction are atomic.",
+            "Begin a new transaction on the current branch. Operations within the transaction \
+             are atomic. Pass timeout_ms to roll the transaction back automatically if it is still open at the \
+             first operation after that many milliseconds; that operation fails with TXN_TIMEOUT.",
             schema!(object {
-                optional: { "read_only": boolean }
+                optional: { "read_only": boolean, "timeout_ms": integer }
             }),
         ),
         ToolDef::new(
@@ -33,7 +37,9 @@ pub fn tools() -> Vec<ToolDef> {
         ),
         ToolDef::new(
             "strata_txn_info",
-            "Get information about the current transaction. Returns null if no transaction is active.",
+            "Get information about the current transaction: id, status, started_at, read_only, \
+             and operations (write commands buffered so far). \
+             Returns null if no transaction is active.",
             schema!(object {}),
         ),
         ToolDef::new(
@@ -53,35 +59,69 @@ pub fn dispatch(
     match name {
         "strata_txn_begin" => {
             let read_only = get_optional_bool(&args, "read_only").unwrap_or(false);
+            let timeout_ms = get_optional_u64(&args, "timeout_ms");
+            if session.in_transaction() {
+                return Err(already_active(session));
+            }
 
             let cmd = Command::TxnBegin {
                 branch: session.branch_id(),
                 options: Some(TxnOptions { read_only }),
             };
-            let output = session.execute(cmd)?;
-            Ok(output_to_json(output))
+            let output = match session.execute(cmd) {
+                Err(McpError::Strata { code, .. }) if code == "TXN_ALREADY_ACTIVE" => {
+                    return Err(already_active(session))
+                }
+                other => other?,
+            };
+            if let Some(ms) = timeout_ms {
+                session.set_txn_timeout(Duration::from_millis(ms));
+            }
+            Ok(output_to_json_with(output, session.convert_options()))
         }
 
         "strata_txn_commit" => {
             let output = session.execute(Command::TxnCommit)?;
-            Ok(output_to_json(output))
+            Ok(output_to_json_with(output, session.convert_options()))
         }
 
         "strata_txn_rollback" => {
             let output = session.execute(Command::TxnRollback)?;
-            Ok(output_to_json(output))
+            Ok(output_to_json_with(output, session.convert_options()))
         }
 
         "strata_txn_info" => {
             let output = session.execute(Command::TxnInfo)?;
-            Ok(output_to_json(output))
+            let mut result = output_to_json_with(output, session.convert_options());
+            // stratadb does not report pending work, so it comes from the session
+            if let JsonValue::Object(obj) = &mut result {
+                obj.insert("operations".to_string(), session.txn_operations().into());
+                obj.insert("read_only".to_string(), session.txn_read_only().into());
+            }
+            Ok(result)
         }
 
         "strata_txn_active" => {
             let output = session.execute(Command::TxnIsActive)?;
-            Ok(output_to_json(output))
+            Ok(output_to_json_with(output, session.convert_options()))
         }
 
         _ => Err(McpError::UnknownTool(name.to_string())),
     }
 }
+
+/// Error for beginning a transaction while one is open, naming the open one.
+fn already_active(session: &mut McpSession) -> McpError {
+    let id = match session.execute(Command::TxnInfo) {
+        Ok(Output::TxnInfo(Some(ti))) => serde_json::json!(ti.id).to_string(),
+        _ => "unknown".to_string(),
+    };
+    McpError::Strata {
+        code: "TXN_ALREADY_ACTIVE".to_string(),
+        message: format!(
+            "transaction {} is already active; call strata_txn_commit to keep its changes or \
+             strata_txn_rollback to discard them before beginning a new one",
+            id
+        ),
+    }
+}
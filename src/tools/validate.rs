@@ -0,0 +1,114 @@
+//! Tool argument validation.
+//!
+//! Checks call arguments against a tool's `input_schema` before dispatch, so
+//! every tool reports missing, mistyped, and unknown arguments the same way.
+//! Supports the subset of JSON Schema the tool definitions use: `type`,
+//! `properties`, `required`, `items`, `enum`, and `additionalProperties: false`.
+
+use serde_json::{Map, Value as JsonValue};
+
+use crate::error::{McpError, Result};
+
+/// Validate tool arguments against an object schema.
+pub fn validate_args(schema: &JsonValue, args: &Map<String, JsonValue>) -> Result<()> {
+    validate_object(schema, args, "")
+}
+
+fn validate_object(schema: &JsonValue, obj: &Map<String, JsonValue>, path: &str) -> Result<()> {
+    let props = schema.get("properties").and_then(|p| p.as_object());
+
+    if let Some(required) = schema.get("required").and_then(|r| r.as_array()) {
+        for name in required.iter().filter_map(|r| r.as_str()) {
+            if !obj.contains_key(name) {
+                return Err(McpError::MissingArg(join(path, name)));
+            }
+        }
+    }
+
+    let strict = schema.get("additionalProperties") == Some(&JsonValue::Bool(false));
+    for (name, value) in obj {
+        match props.and_then(|p| p.get(name)) {
+            Some(prop) => validate_value(prop, value, &join(path, name))?,
+            None if strict => {
+                return Err(McpError::InvalidArg {
+                    name: join(path, name),
+                    reason: "Unknown property".to_string(),
+                })
+            }
+            None => {}
+        }
+    }
+
+    Ok(())
+}
+
+fn validate_value(schema: &JsonValue, value: &JsonValue, path: &str) -> Result<()> {
+    // Null stands in for an omitted optional argument
+    if value.is_null() {
+        return Ok(());
+    }
+
+    if let Some(ty) = schema.get("type").and_then(|t| t.as_str()) {
+        let ok = match ty {
+            "string" => value.is_string(),
+            "integer" => value.is_i64() || value.is_u64(),
+            "number" => value.is_number(),
+            "boolean" => value.is_boolean(),
+            "array" => value.is_array(),
+            "object" => value.is_object(),
+            _ => true,
+        };
+        if !ok {
+            return Err(McpError::InvalidArg {
+                name: path.to_string(),
+                reason: format!("Expected {}, got {}", ty, type_name(value)),
+            });
+        }
+    }
+
+    if let Some(allowed) = schema.get("enum").and_then(|e| e.as_array()) {
+        if !allowed.contains(value) {
+            let options: Vec<String> = allowed.iter().map(|a| a.to_string()).collect();
+            return Err(McpError::InvalidArg {
+                name: path.to_string(),
+                reason: format!("Expected one of {}", options.join(", ")),
+            });
+        }
+    }
+
+    match value {
+        JsonValue::Array(items) => {
+            if let Some(item_schema) = schema.get("items") {
+                for (i, item) in items.iter().enumerate() {
+                    validate_value(item_schema, item, &format!("{}[{}]", path, i))?;
+                }
+            }
+        }
+        JsonValue::Object(obj) if schema.get("properties").is_some() => {
+            validate_object(schema, obj, path)?;
+        }
+        _ => {}
+    }
+
+    Ok(())
+}
+
+fn join(path: &str, name: &str) -> String {
+    if path.is_empty() {
+        name.to_string()
+    } else {
+        format!("{}.{}", path, name)
+    }
+}
+
+fn type_name(value: &JsonValue) -> &'static str {
+    match value {
+        JsonValue::Null => "null",
+        JsonValue::Bool(_) => "boolean",
+        JsonValue::Number(n) if n.is_f64() => "number",
+        JsonValue::Number(_) => "integer",
+        JsonValue::String(_) => "string",
+        JsonValue::Array(_) => "array",
+        JsonValue::Object(_) => "object",
+    }
+}
@@ -2,14 +2,19 @@
 //!
 //! Tools: strata_vector_upsert, strata_vector_get, strata_vector_delete, strata_vector_search,
 //!        strata_vector_create_collection, strata_vector_delete_collection,
-//!        strata_vector_list_collections, strata_vector_stats, strata_vector_batch_upsert
+//!        strata_vector_list_collections, strata_vector_stats, strata_vector_batch_upsert,
+//!        strata_vector_rename_collection, strata_vector_reindex, strata_vector_filter,
+//!        strata_vector_search_many, strata_vector_get_many, strata_vector_memory,
+//!        strata_vector_clear, strata_vector_distance
 
 use serde_json::{Map, Value as JsonValue};
-use stratadb::{BatchVectorEntry, Command, DistanceMetric, FilterOp, MetadataFilter};
+use stratadb::{
+    BatchVectorEntry, Command, DistanceMetric, FilterOp, MetadataFilter, Output, Value,
+};
 
 use crate::convert::{
-    get_optional_string, get_optional_u64, get_string_arg, get_u64_arg, get_value_arg,
-    get_vector_arg, json_to_value, output_to_json,
+    get_optional_bool, get_optional_string, get_optional_u64, get_string_arg, get_u64_arg,
+    get_value_arg, get_vector_arg, json_to_value, output_to_json_with, value_to_json_with,
 };
 use crate::error::{McpError, Result};
 use crate::schema;
@@ -21,10 +26,20 @@ pub fn tools() -> Vec<ToolDef> {
     vec![
         ToolDef::new(
             "strata_vector_upsert",
-            "Insert or update a vector with optional metadata. Returns the version number.",
-            schema!(object {
-                required: { "collection": string, "key": string, "vector": array_number },
-                optional: { "metadata": any }
+            "Insert or update a vector with optional metadata. Returns the version number. \
+             Set mode to \"insert\" to fail with a KEY_EXISTS conflict instead of overwriting \
+             an existing key; the default \"upsert\" overwrites.",
+            serde_json::json!({
+                "type": "object",
+                "properties": {
+                    "collection": {"type": "string"},
+                    "key": {"type": "string"},
+                    "vector": {"type": "array", "items": {"type": "number"}},
+                    "metadata": {},
+                    "mode": {"type": "string", "enum": ["upsert", "insert"]}
+                },
+                "required": ["collection", "key", "vector"],
+                "additionalProperties": false
             }),
         ),
         ToolDef::new(
@@ -36,6 +51,17 @@ pub fn tools() -> Vec<ToolDef> {
                 optional: { "as_of": integer }
             }),
         ),
+        ToolDef::new(
+            "strata_vector_get_many",
+            "Get several vectors from one collection in a single call. Returns an array of \
+             vector data (embedding, metadata, and version info) in the order of keys, with \
+             null for missing keys. Set as_map to return an object keyed by vector key instead. \
+             Pass as_of (microsecond timestamp) for time-travel reads.",
+            schema!(object {
+                required: { "collection": string, "keys": array_string },
+                optional: { "as_map": boolean, "as_of": integer }
+            }),
+        ),
         ToolDef::new(
             "strata_vector_delete",
             "Delete a vector. Returns true if the vector existed.",
@@ -48,6 +74,8 @@ pub fn tools() -> Vec<ToolDef> {
             "Search for similar vectors. Returns top-k matches with scores. \
              Filters narrow results by metadata: each filter has field (metadata key), \
              op (eq|ne|gt|gte|lt|lte|in|contains), and value. \
+             metric overrides the collection's distance metric for this search. \
+             Set include_embeddings to add each match's embedding to the results. \
              Pass as_of (microsecond timestamp) for time-travel reads.",
             serde_json::json!({
                 "type": "object",
@@ -55,34 +83,61 @@ pub fn tools() -> Vec<ToolDef> {
                     "collection": {"type": "string"},
                     "query": {"type": "array", "items": {"type": "number"}},
                     "k": {"type": "integer"},
-                    "filter": {
+                    "filter": filter_schema(),
+                    "metric": {"type": "string", "enum": ["cosine", "euclidean", "dot_product"]},
+                    "include_embeddings": {"type": "boolean", "description": "Include each match's embedding"},
+                    "as_of": {"type": "integer", "description": "Microsecond timestamp for time-travel reads"}
+                },
+                "required": ["collection", "query", "k"]
+            }),
+        ),
+        ToolDef::new(
+            "strata_vector_search_many",
+            "Run several similarity searches against one collection in a single call. \
+             Takes an array of query vectors sharing the same k, filter, and metric. \
+             Returns an array of result arrays, one per query in input order.",
+            serde_json::json!({
+                "type": "object",
+                "properties": {
+                    "collection": {"type": "string"},
+                    "queries": {
                         "type": "array",
-                        "items": {
-                            "type": "object",
-                            "properties": {
-                                "field": {"type": "string", "description": "Metadata field name"},
-                                "op": {
-                                    "type": "string",
-                                    "enum": ["eq", "ne", "gt", "gte", "lt", "lte", "in", "contains"],
-                                    "description": "Comparison operator"
-                                },
-                                "value": {"description": "Value to compare against"}
-                            },
-                            "required": ["field", "op", "value"]
-                        }
+                        "items": {"type": "array", "items": {"type": "number"}}
                     },
+                    "k": {"type": "integer"},
+                    "filter": filter_schema(),
                     "metric": {"type": "string", "enum": ["cosine", "euclidean", "dot_product"]},
                     "as_of": {"type": "integer", "description": "Microsecond timestamp for time-travel reads"}
                 },
-                "required": ["collection", "query", "k"]
+                "required": ["collection", "queries", "k"],
+                "additionalProperties": false
+            }),
+        ),
+        ToolDef::new(
+            "strata_vector_filter",
+            "List vectors whose metadata matches all filters, without a query vector or \
+             similarity scoring. Filters use the same field/op/value form as strata_vector_search. \
+             Returns key and metadata for each match, sorted by key, up to limit.",
+            serde_json::json!({
+                "type": "object",
+                "properties": {
+                    "collection": {"type": "string"},
+                    "filter": filter_schema(),
+                    "limit": {"type": "integer"}
+                },
+                "required": ["collection", "filter"],
+                "additionalProperties": false
             }),
         ),
         ToolDef::new(
             "strata_vector_create_collection",
-            "Create a new vector collection with specified dimension and distance metric.",
+            "Create a new vector collection with specified dimension and distance metric. \
+             Pass if_not_exists=true to succeed when the collection already exists with the \
+             same dimension and metric; returns {collection, created}, and an existing \
+             collection with a different dimension or metric is a COLLECTION_EXISTS error.",
             schema!(object {
                 required: { "collection": string, "dimension": integer },
-                optional: { "metric": string }
+                optional: { "metric": string, "if_not_exists": boolean }
             }),
         ),
         ToolDef::new(
@@ -92,6 +147,14 @@ pub fn tools() -> Vec<ToolDef> {
                 required: { "collection": string }
             }),
         ),
+        ToolDef::new(
+            "strata_vector_clear",
+            "Delete every vector in a collection but keep the collection, with its dimension \
+             and metric. Returns the number of vectors deleted.",
+            schema!(object {
+                required: { "collection": string }
+            }),
+        ),
         ToolDef::new(
             "strata_vector_list_collections",
             "List all vector collections in the current branch/space.",
@@ -104,6 +167,13 @@ pub fn tools() -> Vec<ToolDef> {
                 required: { "collection": string }
             }),
         ),
+        ToolDef::new(
+            "strata_vector_memory",
+            "Report vector memory use across every collection in the current branch and \
+             space. Returns total_memory_bytes, total_vectors, and collection_count, with a \
+             per-collection breakdown of name, count, index_type, and memory_bytes.",
+            schema!(object {}),
+        ),
         ToolDef::new(
             "strata_vector_batch_upsert",
             "Insert or update multiple vectors in a single operation. Returns version numbers.",
@@ -111,9 +181,69 @@ pub fn tools() -> Vec<ToolDef> {
                 required: { "collection": string, "entries": array_object }
             }),
         ),
+        ToolDef::new(
+            "strata_vector_rename_collection",
+            "Rename a vector collection, keeping its dimension, metric, vectors, and metadata. \
+             Fails if a collection named 'to' already exists.",
+            schema!(object {
+                required: { "from": string, "to": string }
+            }),
+        ),
+        ToolDef::new(
+            "strata_vector_reindex",
+            "Rebuild a collection under a different distance metric (cosine, euclidean, or \
+             dot_product), keeping its vectors and metadata. \
+             The rebuild goes into a staging collection '<collection>__reindex' that replaces \
+             the original once complete; if the swap fails, the vectors are left there. \
+             Later searches rank by the new metric.",
+            schema!(object {
+                required: { "collection": string, "metric": string }
+            }),
+        ),
+        ToolDef::new(
+            "strata_vector_distance",
+            "Compute the score between two vectors without a search. Pass two keys from the \
+             collection, or two raw vectors. metric defaults to the collection's metric: \
+             cosine returns cosine similarity, euclidean the euclidean distance, and \
+             dot_product the dot product.",
+            serde_json::json!({
+                "type": "object",
+                "properties": {
+                    "collection": {"type": "string"},
+                    "keys": {"type": "array", "items": {"type": "string"}},
+                    "vectors": {
+                        "type": "array",
+                        "items": {"type": "array", "items": {"type": "number"}}
+                    },
+                    "metric": {"type": "string", "enum": ["cosine", "euclidean", "dot_product"]}
+                },
+                "required": ["collection"],
+                "additionalProperties": false
+            }),
+        ),
     ]
 }
 
+/// Schema for the metadata filter array shared by search, filter, and event lists.
+pub(crate) fn filter_schema() -> JsonValue {
+    serde_json::json!({
+        "type": "array",
+        "items": {
+            "type": "object",
+            "properties": {
+                "field": {"type": "string", "description": "Metadata field name"},
+                "op": {
+                    "type": "string",
+                    "enum": ["eq", "ne", "gt", "gte", "lt", "lte", "in", "contains"],
+                    "description": "Comparison operator"
+                },
+                "value": {"description": "Value to compare against"}
+            },
+            "required": ["field", "op", "value"]
+        }
+    })
+}
+
 /// Parse a distance metric from a string.
 fn parse_metric(s: Option<&str>) -> Result<DistanceMetric> {
     match s {
@@ -149,7 +279,7 @@ fn parse_filter_op(s: &str) -> Result<FilterOp> {
 }
 
 /// Parse filters from JSON array.
-fn parse_filters(args: &Map<String, JsonValue>) -> Result<Option<Vec<MetadataFilter>>> {
+pub(crate) fn parse_filters(args: &Map<String, JsonValue>) -> Result<Option<Vec<MetadataFilter>>> {
     let arr = match args.get("filter") {
         Some(JsonValue::Array(a)) => a,
         Some(JsonValue::Null) | None => return Ok(None),
@@ -202,6 +332,34 @@ fn parse_filters(args: &Map<String, JsonValue>) -> Result<Option<Vec<MetadataFil
     Ok(Some(filters))
 }
 
+/// Parse an array of vectors, such as the queries of strata_vector_search_many.
+fn parse_vectors(args: &Map<String, JsonValue>, name: &str) -> Result<Vec<Vec<f32>>> {
+    let arr = args
+        .get(name)
+        .and_then(|v| v.as_array())
+        .ok_or_else(|| McpError::MissingArg(name.to_string()))?;
+
+    arr.iter()
+        .enumerate()
+        .map(|(i, query)| {
+            let values = query.as_array().ok_or_else(|| McpError::InvalidArg {
+                name: format!("{}[{}]", name, i),
+                reason: "Expected array of numbers".to_string(),
+            })?;
+            values
+                .iter()
+                .enumerate()
+                .map(|(j, v)| {
+                    v.as_f64().map(|f| f as f32).ok_or_else(|| McpError::InvalidArg {
+                        name: format!("{}[{}][{}]", name, i, j),
+                        reason: "Expected number".to_string(),
+                    })
+                })
+                .collect()
+        })
+        .collect()
+}
+
 /// Parse batch entries from JSON array.
 fn parse_batch_entries(args: &Map<String, JsonValue>) -> Result<Vec<BatchVectorEntry>> {
     let arr = args
@@ -270,21 +428,58 @@ pub fn dispatch(
             let collection = get_string_arg(&args, "collection")?;
             let key = get_string_arg(&args, "key")?;
             let vector = get_vector_arg(&args, "vector")?;
+            if let Some(dimension) = collection_dimension(session, &collection)? {
+                check_dimension(&collection, dimension, "vector", vector.len())?;
+            }
             let metadata = match args.get("metadata") {
                 Some(JsonValue::Null) | None => None,
                 Some(_) => Some(get_value_arg(&args, "metadata")?),
             };
 
-            let cmd = Command::VectorUpsert {
-                branch: session.branch_id(),
-                space: session.space_id(),
-                collection,
-                key,
-                vector,
-                metadata,
-            };
-            let output = session.execute(cmd)?;
-            Ok(output_to_json(output))
+            let insert_only = get_optional_string(&args, "mode").as_deref() == Some("insert");
+
+            if !insert_only {
+                let cmd = Command::VectorUpsert {
+                    branch: session.branch_id(),
+                    space: session.space_id(),
+                    collection,
+                    key,
+                    vector,
+                    metadata,
+                };
+                let output = session.execute(cmd)?;
+                return Ok(output_to_json_with(output, session.convert_options()));
+            }
+
+            session.check_write_access("VectorUpsert")?;
+            // Check and write in one transaction so a concurrent insert cannot slip between
+            let output = session.atomically(|session| {
+                let cmd = Command::VectorGet {
+                    branch: session.branch_id(),
+                    space: session.space_id(),
+                    collection: collection.clone(),
+                    key: key.clone(),
+                    as_of: None,
+                };
+                if let Output::VectorData(Some(_)) = session.execute(cmd)? {
+                    return Err(McpError::Strata {
+                        code: "KEY_EXISTS".to_string(),
+                        message: format!(
+                            "Vector '{}' already exists in collection '{}'",
+                            key, collection
+                        ),
+                    });
+                }
+                session.execute(Command::VectorUpsert {
+                    branch: session.branch_id(),
+                    space: session.space_id(),
+                    collection,
+                    key,
+                    vector,
+                    metadata,
+                })
+            })?;
+            Ok(output_to_json_with(output, session.convert_options()))
         }
 
         "strata_vector_get" => {
@@ -300,7 +495,44 @@ pub fn dispatch(
                 as_of,
             };
             let output = session.execute(cmd)?;
-            Ok(output_to_json(output))
+            Ok(output_to_json_with(output, session.convert_options()))
+        }
+
+        "strata_vector_get_many" => {
+            let collection = get_string_arg(&args, "collection")?;
+            let keys = args
+                .get("keys")
+                .and_then(|v| v.as_array())
+                .ok_or_else(|| McpError::MissingArg("keys".to_string()))?;
+            let as_map = get_optional_bool(&args, "as_map").unwrap_or(false);
+            let as_of = get_optional_u64(&args, "as_of");
+
+            let mut results = Vec::new();
+            for key_value in keys {
+                let key = key_value
+                    .as_str()
+                    .ok_or_else(|| McpError::InvalidArg {
+                        name: "keys".to_string(),
+                        reason: "Keys must be strings".to_string(),
+                    })?
+                    .to_string();
+
+                let cmd = Command::VectorGet {
+                    branch: session.branch_id(),
+                    space: session.space_id(),
+                    collection: collection.clone(),
+                    key: key.clone(),
+                    as_of,
+                };
+                let output = session.execute(cmd)?;
+                results.push((key, output_to_json_with(output, session.convert_options())));
+            }
+
+            if as_map {
+                Ok(JsonValue::Object(results.into_iter().collect()))
+            } else {
+                Ok(JsonValue::Array(results.into_iter().map(|(_, v)| v).collect()))
+            }
         }
 
         "strata_vector_delete" => {
@@ -314,35 +546,129 @@ pub fn dispatch(
                 key,
             };
             let output = session.execute(cmd)?;
-            Ok(output_to_json(output))
+            Ok(output_to_json_with(output, session.convert_options()))
         }
 
         "strata_vector_search" => {
             let collection = get_string_arg(&args, "collection")?;
             let query = get_vector_arg(&args, "query")?;
+            if let Some(dimension) = collection_dimension(session, &collection)? {
+                check_dimension(&collection, dimension, "query", query.len())?;
+            }
             let k = get_u64_arg(&args, "k")?;
             let filter = parse_filters(&args)?;
-            let metric = parse_metric(get_optional_string(&args, "metric").as_deref())?;
+            // Without an explicit metric the collection's own metric applies
+            let metric = match get_optional_string(&args, "metric") {
+                Some(m) => Some(parse_metric(Some(&m))?),
+                None => None,
+            };
             let as_of = get_optional_u64(&args, "as_of");
+            let include_embeddings =
+                get_optional_bool(&args, "include_embeddings").unwrap_or(false);
 
             let cmd = Command::VectorSearch {
                 branch: session.branch_id(),
                 space: session.space_id(),
-                collection,
+                collection: collection.clone(),
                 query,
                 k,
                 filter,
-                metric: Some(metric),
+                metric,
                 as_of,
             };
             let output = session.execute(cmd)?;
-            Ok(output_to_json(output))
+            let mut result = output_to_json_with(output, session.convert_options());
+
+            if include_embeddings {
+                if let JsonValue::Array(matches) = &mut result {
+                    for m in matches.iter_mut() {
+                        let key = match m.get("key").and_then(|k| k.as_str()) {
+                            Some(key) => key.to_string(),
+                            None => continue,
+                        };
+                        let embedding = embedding_of(session, &collection, key, as_of)?;
+                        m["embedding"] = embedding.map_or(JsonValue::Null, |e| e.into());
+                    }
+                }
+            }
+            Ok(result)
+        }
+
+        "strata_vector_search_many" => {
+            let collection = get_string_arg(&args, "collection")?;
+            let queries = parse_vectors(&args, "queries")?;
+            if let Some(dimension) = collection_dimension(session, &collection)? {
+                for (i, query) in queries.iter().enumerate() {
+                    let name = format!("queries[{}]", i);
+                    check_dimension(&collection, dimension, &name, query.len())?;
+                }
+            }
+            let k = get_u64_arg(&args, "k")?;
+            let filter = parse_filters(&args)?;
+            let metric = match get_optional_string(&args, "metric") {
+                Some(m) => Some(parse_metric(Some(&m))?),
+                None => None,
+            };
+            let as_of = get_optional_u64(&args, "as_of");
+
+            let mut results = Vec::with_capacity(queries.len());
+            for query in queries {
+                let cmd = Command::VectorSearch {
+                    branch: session.branch_id(),
+                    space: session.space_id(),
+                    collection: collection.clone(),
+                    query,
+                    k,
+                    filter: filter.clone(),
+                    metric: metric.clone(),
+                    as_of,
+                };
+                let output = session.execute(cmd)?;
+                results.push(output_to_json_with(output, session.convert_options()));
+            }
+            Ok(JsonValue::Array(results))
+        }
+
+        "strata_vector_filter" => {
+            let collection = get_string_arg(&args, "collection")?;
+            let filter = parse_filters(&args)?.ok_or_else(|| McpError::InvalidArg {
+                name: "filter".to_string(),
+                reason: "At least one filter is required".to_string(),
+            })?;
+            let limit = get_optional_u64(&args, "limit");
+
+            let summary = collection_summary(session, &collection)?.ok_or_else(|| {
+                McpError::InvalidArg {
+                    name: "collection".to_string(),
+                    reason: format!("Collection '{}' does not exist", collection),
+                }
+            })?;
+            let mut matches = scan_matches(session, &collection, &summary, Some(filter))?;
+            matches.sort_by(|a, b| a.0.cmp(&b.0));
+            if let Some(limit) = limit {
+                matches.truncate(limit as usize);
+            }
+
+            let opts = session.convert_options();
+            let arr: Vec<JsonValue> = matches
+                .into_iter()
+                .map(|(key, metadata)| {
+                    serde_json::json!({
+                        "key": key,
+                        "metadata": metadata.map(|v| value_to_json_with(v, opts)),
+                    })
+                })
+                .collect();
+            Ok(JsonValue::Array(arr))
         }
 
         "strata_vector_create_collection" => {
             let collection = get_string_arg(&args, "collection")?;
             let dimension = get_u64_arg(&args, "dimension")?;
             let metric = parse_metric(get_optional_string(&args, "metric").as_deref())?;
+            if get_optional_bool(&args, "if_not_exists").unwrap_or(false) {
+                return create_if_not_exists(session, &collection, dimension, metric);
+            }
 
             let cmd = Command::VectorCreateCollection {
                 branch: session.branch_id(),
@@ -352,7 +678,7 @@ pub fn dispatch(
                 metric,
             };
             let output = session.execute(cmd)?;
-            Ok(output_to_json(output))
+            Ok(output_to_json_with(output, session.convert_options()))
         }
 
         "strata_vector_delete_collection" => {
@@ -364,7 +690,12 @@ pub fn dispatch(
                 collection,
             };
             let output = session.execute(cmd)?;
-            Ok(output_to_json(output))
+            Ok(output_to_json_with(output, session.convert_options()))
+        }
+
+        "strata_vector_clear" => {
+            let collection = get_string_arg(&args, "collection")?;
+            clear_collection(session, &collection)
         }
 
         "strata_vector_list_collections" => {
@@ -373,7 +704,37 @@ pub fn dispatch(
                 space: session.space_id(),
             };
             let output = session.execute(cmd)?;
-            Ok(output_to_json(output))
+            Ok(output_to_json_with(output, session.convert_options()))
+        }
+
+        "strata_vector_memory" => {
+            let cmd = Command::VectorListCollections {
+                branch: session.branch_id(),
+                space: session.space_id(),
+            };
+            let listed = match session.execute(cmd)? {
+                Output::VectorCollectionList(collections) => collections,
+                _ => Vec::new(),
+            };
+
+            let (mut total_memory, mut total_vectors) = (0u64, 0u64);
+            let mut collections = Vec::new();
+            for c in listed {
+                total_memory += c.memory_bytes;
+                total_vectors += c.count;
+                collections.push(serde_json::json!({
+                    "name": c.name,
+                    "count": c.count,
+                    "index_type": c.index_type,
+                    "memory_bytes": c.memory_bytes,
+                }));
+            }
+            Ok(serde_json::json!({
+                "total_memory_bytes": total_memory,
+                "total_vectors": total_vectors,
+                "collection_count": collections.len(),
+                "collections": collections,
+            }))
         }
 
         "strata_vector_stats" => {
@@ -385,12 +746,18 @@ pub fn dispatch(
                 collection,
             };
             let output = session.execute(cmd)?;
-            Ok(output_to_json(output))
+            Ok(output_to_json_with(output, session.convert_options()))
         }
 
         "strata_vector_batch_upsert" => {
             let collection = get_string_arg(&args, "collection")?;
             let entries = parse_batch_entries(&args)?;
+            if let Some(dimension) = collection_dimension(session, &collection)? {
+                for (i, entry) in entries.iter().enumerate() {
+                    let name = format!("entries[{}].vector", i);
+                    check_dimension(&collection, dimension, &name, entry.vector.len())?;
+                }
+            }
 
             let cmd = Command::VectorBatchUpsert {
                 branch: session.branch_id(),
@@ -399,9 +766,505 @@ pub fn dispatch(
                 entries,
             };
             let output = session.execute(cmd)?;
-            Ok(output_to_json(output))
+            Ok(output_to_json_with(output, session.convert_options()))
+        }
+
+        "strata_vector_rename_collection" => {
+            let from = get_string_arg(&args, "from")?;
+            let to = get_string_arg(&args, "to")?;
+            rename_collection(session, &from, &to)
+        }
+
+        "strata_vector_reindex" => {
+            let collection = get_string_arg(&args, "collection")?;
+            let metric_name = get_string_arg(&args, "metric")?;
+            let metric = parse_metric(Some(&metric_name))?;
+            reindex_collection(session, &collection, &metric_name, metric)
+        }
+
+        "strata_vector_distance" => {
+            let collection = get_string_arg(&args, "collection")?;
+            let metric = match get_optional_string(&args, "metric") {
+                Some(metric) => parse_metric(Some(&metric))?,
+                None => match collection_summary(session, &collection)? {
+                    Some(summary) => summary.metric,
+                    None => {
+                        return Err(McpError::Strata {
+                            code: "COLLECTION_NOT_FOUND".to_string(),
+                            message: format!("Collection '{}' does not exist", collection),
+                        })
+                    }
+                },
+            };
+            let (a, b) = distance_operands(session, &collection, &args)?;
+            let score = vector_score(&metric, &a, &b)?;
+            Ok(serde_json::json!({
+                "metric": metric_name(&metric),
+                "score": score,
+            }))
         }
 
         _ => Err(McpError::UnknownTool(name.to_string())),
     }
 }
+
+/// The two vectors strata_vector_distance compares, from `keys` or `vectors`.
+fn distance_operands(
+    session: &mut McpSession,
+    collection: &str,
+    args: &Map<String, JsonValue>,
+) -> Result<(Vec<f32>, Vec<f32>)> {
+    let (name, mut vectors) = if let Some(keys) = args.get("keys").and_then(|v| v.as_array()) {
+        let mut vectors = Vec::with_capacity(keys.len());
+        for key in keys {
+            let key = key.as_str().ok_or_else(|| McpError::InvalidArg {
+                name: "keys".to_string(),
+                reason: "Expected array of strings".to_string(),
+            })?;
+            match embedding_of(session, collection, key.to_string(), None)? {
+                Some(embedding) => vectors.push(embedding),
+                None => {
+                    return Err(McpError::Strata {
+                        code: "KEY_NOT_FOUND".to_string(),
+                        message: format!("Vector '{}' not found in '{}'", key, collection),
+                    })
+                }
+            }
+        }
+        ("keys", vectors)
+    } else if args.contains_key("vectors") {
+        ("vectors", parse_vectors(args, "vectors")?)
+    } else {
+        return Err(McpError::MissingArg("keys".to_string()));
+    };
+
+    if vectors.len() != 2 {
+        return Err(McpError::InvalidArg {
+            name: name.to_string(),
+            reason: format!("Expected exactly 2 entries, got {}", vectors.len()),
+        });
+    }
+    let b = vectors.pop().unwrap_or_default();
+    let a = vectors.pop().unwrap_or_default();
+    if a.len() != b.len() {
+        return Err(McpError::InvalidArg {
+            name: name.to_string(),
+            reason: format!("Dimensions differ: {} and {}", a.len(), b.len()),
+        });
+    }
+    Ok((a, b))
+}
+
+/// Score two equal-length vectors under a metric.
+///
+/// Cosine is a similarity in [-1, 1], euclidean a distance, and dot product
+/// the raw product, computed in f64 to avoid accumulating f32 error.
+fn vector_score(metric: &DistanceMetric, a: &[f32], b: &[f32]) -> Result<f64> {
+    let dot: f64 = a.iter().zip(b).map(|(x, y)| *x as f64 * *y as f64).sum();
+    match metric {
+        DistanceMetric::Cosine => {
+            let norm = |v: &[f32]| v.iter().map(|x| (*x as f64).powi(2)).sum::<f64>().sqrt();
+            let (norm_a, norm_b) = (norm(a), norm(b));
+            if norm_a == 0.0 || norm_b == 0.0 {
+                return Err(McpError::InvalidArg {
+                    name: "metric".to_string(),
+                    reason: "Cosine similarity is undefined for a zero vector".to_string(),
+                });
+            }
+            Ok(dot / (norm_a * norm_b))
+        }
+        DistanceMetric::Euclidean => Ok(a
+            .iter()
+            .zip(b)
+            .map(|(x, y)| (*x as f64 - *y as f64).powi(2))
+            .sum::<f64>()
+            .sqrt()),
+        _ => Ok(dot),
+    }
+}
+
+/// Fetch the embedding stored under a key.
+fn embedding_of(
+    session: &mut McpSession,
+    collection: &str,
+    key: String,
+    as_of: Option<u64>,
+) -> Result<Option<Vec<f32>>> {
+    let cmd = Command::VectorGet {
+        branch: session.branch_id(),
+        space: session.space_id(),
+        collection: collection.to_string(),
+        key,
+        as_of,
+    };
+    match session.execute(cmd)? {
+        Output::VectorData(Some(vd)) => Ok(Some(vd.data.embedding)),
+        _ => Ok(None),
+    }
+}
+
+/// Dimension, metric, and size of an existing collection.
+struct CollectionSummary {
+    dimension: u64,
+    metric: DistanceMetric,
+    count: u64,
+}
+
+fn collection_summary(
+    session: &mut McpSession,
+    collection: &str,
+) -> Result<Option<CollectionSummary>> {
+    let cmd = Command::VectorListCollections {
+        branch: session.branch_id(),
+        space: session.space_id(),
+    };
+    match session.execute(cmd)? {
+        Output::VectorCollectionList(collections) => Ok(collections
+            .into_iter()
+            .find(|c| c.name == collection)
+            .map(|c| CollectionSummary {
+                dimension: c.dimension,
+                metric: c.metric,
+                count: c.count,
+            })),
+        _ => Ok(None),
+    }
+}
+
+/// Declared dimension of a collection, or `None` if it does not exist.
+fn collection_dimension(session: &mut McpSession, collection: &str) -> Result<Option<u64>> {
+    Ok(collection_summary(session, collection)?.map(|s| s.dimension))
+}
+
+/// Reject a vector whose length differs from the collection's dimension.
+fn check_dimension(collection: &str, dimension: u64, name: &str, len: usize) -> Result<()> {
+    if len as u64 != dimension {
+        return Err(McpError::InvalidArg {
+            name: name.to_string(),
+            reason: format!(
+                "Collection '{}' expects {} dimensions, got {}",
+                collection, dimension, len
+            ),
+        });
+    }
+    Ok(())
+}
+
+/// Key and metadata of every vector in a collection that passes `filter`.
+///
+/// There is no key listing for vectors, so this searches for all `count`
+/// entries and lets the engine apply the metadata filters.
+fn scan_matches(
+    session: &mut McpSession,
+    collection: &str,
+    summary: &CollectionSummary,
+    filter: Option<Vec<MetadataFilter>>,
+) -> Result<Vec<(String, Option<Value>)>> {
+    if summary.count == 0 {
+        return Ok(Vec::new());
+    }
+
+    let mut query = vec![0.0f32; summary.dimension as usize];
+    if let Some(first) = query.first_mut() {
+        *first = 1.0;
+    }
+    let cmd = Command::VectorSearch {
+        branch: session.branch_id(),
+        space: session.space_id(),
+        collection: collection.to_string(),
+        query,
+        k: summary.count,
+        filter,
+        metric: None,
+        as_of: None,
+    };
+    match session.execute(cmd)? {
+        Output::VectorMatches(matches) => {
+            Ok(matches.into_iter().map(|m| (m.key, m.metadata)).collect())
+        }
+        _ => Ok(Vec::new()),
+    }
+}
+
+/// Read every vector in a collection with its embedding and metadata.
+///
+/// Fails unless all `count` vectors were read, so callers that go on to drop
+/// the collection never work from a partial copy.
+fn read_entries(
+    session: &mut McpSession,
+    collection: &str,
+    summary: &CollectionSummary,
+) -> Result<Vec<BatchVectorEntry>> {
+    let keys: Vec<String> = scan_matches(session, collection, summary, None)?
+        .into_iter()
+        .map(|(key, _)| key)
+        .collect();
+    check_read_count(collection, summary, keys.len())?;
+
+    let mut entries = Vec::with_capacity(keys.len());
+    for key in keys {
+        let cmd = Command::VectorGet {
+            branch: session.branch_id(),
+            space: session.space_id(),
+            collection: collection.to_string(),
+            key: key.clone(),
+            as_of: None,
+        };
+        if let Output::VectorData(Some(vd)) = session.execute(cmd)? {
+            entries.push(BatchVectorEntry {
+                key,
+                vector: vd.data.embedding,
+                metadata: vd.data.metadata,
+            });
+        }
+    }
+    check_read_count(collection, summary, entries.len())?;
+    Ok(entries)
+}
+
+/// Fail if fewer vectors were read than the collection reports.
+fn check_read_count(collection: &str, summary: &CollectionSummary, read: usize) -> Result<()> {
+    if read as u64 != summary.count {
+        return Err(McpError::Internal(format!(
+            "Read {} of {} vectors in collection '{}'",
+            read, summary.count, collection
+        )));
+    }
+    Ok(())
+}
+
+/// Create a collection and upsert `entries` into it in one transaction.
+///
+/// The new collection is dropped again if the upsert fails.
+fn create_filled(
+    session: &mut McpSession,
+    collection: &str,
+    dimension: u64,
+    metric: DistanceMetric,
+    entries: Vec<BatchVectorEntry>,
+) -> Result<()> {
+    session.execute(Command::VectorCreateCollection {
+        branch: session.branch_id(),
+        space: session.space_id(),
+        collection: collection.to_string(),
+        dimension,
+        metric,
+    })?;
+    if entries.is_empty() {
+        return Ok(());
+    }
+
+    let filled = session.atomically(|session| {
+        session.execute(Command::VectorBatchUpsert {
+            branch: session.branch_id(),
+            space: session.space_id(),
+            collection: collection.to_string(),
+            entries,
+        })
+    });
+    if let Err(err) = filled {
+        let cmd = Command::VectorDeleteCollection {
+            branch: session.branch_id(),
+            space: session.space_id(),
+            collection: collection.to_string(),
+        };
+        if let Err(drop_err) = session.execute(cmd) {
+            tracing::warn!("Failed to drop collection '{}': {}", collection, drop_err);
+        }
+        return Err(err);
+    }
+    Ok(())
+}
+
+/// Create a collection unless one with the same dimension and metric exists.
+///
+/// Creation is attempted first so a concurrent creator is caught by the
+/// `COLLECTION_EXISTS` error rather than by a racy pre-check.
+fn create_if_not_exists(
+    session: &mut McpSession,
+    collection: &str,
+    dimension: u64,
+    metric: DistanceMetric,
+) -> Result<JsonValue> {
+    let wanted = metric_name(&metric);
+    let cmd = Command::VectorCreateCollection {
+        branch: session.branch_id(),
+        space: session.space_id(),
+        collection: collection.to_string(),
+        dimension,
+        metric,
+    };
+    let exists_err = match session.execute(cmd) {
+        Ok(_) => return Ok(serde_json::json!({ "collection": collection, "created": true })),
+        Err(e) if matches!(&e, McpError::Strata { code, .. } if code == "COLLECTION_EXISTS") => e,
+        Err(e) => return Err(e),
+    };
+
+    match collection_summary(session, collection)? {
+        Some(s) if s.dimension == dimension && metric_name(&s.metric) == wanted => {
+            Ok(serde_json::json!({ "collection": collection, "created": false }))
+        }
+        Some(s) => Err(McpError::Strata {
+            code: "COLLECTION_EXISTS".to_string(),
+            message: format!(
+                "collection '{}' already exists with dimension {} and metric {}, \
+                 not dimension {} and metric {}",
+                collection,
+                s.dimension,
+                metric_name(&s.metric),
+                dimension,
+                wanted
+            ),
+        }),
+        // Deleted again since the failed create
+        None => Err(exists_err),
+    }
+}
+
+/// Lowercase name of a metric, as reported by collection listings.
+fn metric_name(metric: &DistanceMetric) -> String {
+    format!("{:?}", metric).to_lowercase()
+}
+
+fn rename_collection(session: &mut McpSession, from: &str, to: &str) -> Result<JsonValue> {
+    session.check_write_access("VectorRenameCollection")?;
+    if from == to {
+        return Err(McpError::InvalidArg {
+            name: "to".to_string(),
+            reason: "Must differ from 'from'".to_string(),
+        });
+    }
+
+    let summary = collection_summary(session, from)?.ok_or_else(|| McpError::InvalidArg {
+        name: "from".to_string(),
+        reason: format!("Collection '{}' does not exist", from),
+    })?;
+    if collection_summary(session, to)?.is_some() {
+        return Err(McpError::InvalidArg {
+            name: "to".to_string(),
+            reason: format!("Collection '{}' already exists", to),
+        });
+    }
+
+    let vectors = move_collection(session, from, to, summary)?;
+    Ok(serde_json::json!({
+        "from": from,
+        "to": to,
+        "vectors": vectors,
+    }))
+}
+
+/// Copy every vector of `from` into a new collection `to`, then drop `from`.
+///
+/// The source is dropped only once the copy has committed. Returns the
+/// number of vectors moved.
+fn move_collection(
+    session: &mut McpSession,
+    from: &str,
+    to: &str,
+    summary: CollectionSummary,
+) -> Result<usize> {
+    let entries = read_entries(session, from, &summary)?;
+    let vectors = entries.len();
+    create_filled(session, to, summary.dimension, summary.metric, entries)?;
+    session.execute(Command::VectorDeleteCollection {
+        branch: session.branch_id(),
+        space: session.space_id(),
+        collection: from.to_string(),
+    })?;
+    Ok(vectors)
+}
+
+fn clear_collection(session: &mut McpSession, collection: &str) -> Result<JsonValue> {
+    session.check_write_access("VectorClear")?;
+    let summary =
+        collection_summary(session, collection)?.ok_or_else(|| McpError::InvalidArg {
+            name: "collection".to_string(),
+            reason: format!("Collection '{}' does not exist", collection),
+        })?;
+
+    let keys: Vec<String> = scan_matches(session, collection, &summary, None)?
+        .into_iter()
+        .map(|(key, _)| key)
+        .collect();
+    check_read_count(collection, &summary, keys.len())?;
+    let deleted = session.atomically(|session| {
+        let mut deleted = 0;
+        for key in keys {
+            let cmd = Command::VectorDelete {
+                branch: session.branch_id(),
+                space: session.space_id(),
+                collection: collection.to_string(),
+                key,
+            };
+            if matches!(session.execute(cmd)?, Output::Bool(true)) {
+                deleted += 1;
+            }
+        }
+        Ok(deleted)
+    })?;
+
+    Ok(serde_json::json!({
+        "collection": collection,
+        "deleted": deleted,
+    }))
+}
+
+/// Suffix of the collection a reindex builds into before swapping it in.
+const REINDEX_SUFFIX: &str = "__reindex";
+
+fn reindex_collection(
+    session: &mut McpSession,
+    collection: &str,
+    metric_name: &str,
+    metric: DistanceMetric,
+) -> Result<JsonValue> {
+    session.check_write_access("VectorReindex")?;
+    let summary =
+        collection_summary(session, collection)?.ok_or_else(|| McpError::InvalidArg {
+            name: "collection".to_string(),
+            reason: format!("Collection '{}' does not exist", collection),
+        })?;
+
+    let staging = format!("{}{}", collection, REINDEX_SUFFIX);
+    if collection_summary(session, &staging)?.is_some() {
+        return Err(McpError::InvalidArg {
+            name: "collection".to_string(),
+            reason: format!(
+                "Staging collection '{}' already exists; remove it before reindexing",
+                staging
+            ),
+        });
+    }
+
+    // Build the reindexed copy under the staging name while the source is intact
+    let entries = read_entries(session, collection, &summary)?;
+    let vectors = entries.len();
+    create_filled(session, &staging, summary.dimension, metric, entries)?;
+
+    // Swap it in: the source goes only once the staging copy is complete, and
+    // the staging copy only once it has been moved back under the source name
+    session.execute(Command::VectorDeleteCollection {
+        branch: session.branch_id(),
+        space: session.space_id(),
+        collection: collection.to_string(),
+    })?;
+    let staged = collection_summary(session, &staging)?.ok_or_else(|| {
+        McpError::Internal(format!("Staging collection '{}' vanished during reindex", staging))
+    })?;
+    if let Err(err) = move_collection(session, &staging, collection, staged) {
+        tracing::warn!(
+            "Reindexed vectors of '{}' are left in '{}': {}",
+            collection,
+            staging,
+            err
+        );
+        return Err(err);
+    }
+
+    Ok(serde_json::json!({
+        "collection": collection,
+        "metric": metric_name,
+        "vectors": vectors,
+    }))
+}
@@ -0,0 +1,92 @@
+//! Bounded polling for wait tools.
+//!
+//! Wait tools re-read a key or cell until a condition holds instead of
+//! holding a subscription open: each call polls at `poll_interval_ms` until
+//! its condition is met or `timeout_ms` elapses, then returns.
+
+use std::thread;
+use std::time::{Duration, Instant};
+
+use serde_json::{Map, Value as JsonValue};
+
+use crate::convert::get_optional_u64;
+use crate::error::{McpError, Result};
+use crate::session::McpSession;
+
+/// Timeout used when `timeout_ms` is not given.
+pub const DEFAULT_TIMEOUT_MS: u64 = 5_000;
+
+/// Longest a single wait call may block.
+pub const MAX_TIMEOUT_MS: u64 = 60_000;
+
+/// Poll interval used when `poll_interval_ms` is not given.
+pub const DEFAULT_POLL_INTERVAL_MS: u64 = 50;
+
+/// Lower bound for `poll_interval_ms`.
+pub const MIN_POLL_INTERVAL_MS: u64 = 10;
+/// Upper bound for `poll_interval_ms`.
+pub const MAX_POLL_INTERVAL_MS: u64 = 1_000;
+
+/// How long to wait and how often to check.
+pub struct WaitOptions {
+    timeout: Duration,
+    interval: Duration,
+}
+
+impl WaitOptions {
+    /// Read `timeout_ms` and `poll_interval_ms` from tool arguments.
+    pub fn from_args(args: &Map<String, JsonValue>) -> Result<Self> {
+        let timeout = get_optional_u64(args, "timeout_ms").unwrap_or(DEFAULT_TIMEOUT_MS);
+        if timeout > MAX_TIMEOUT_MS {
+            return Err(McpError::InvalidArg {
+                name: "timeout_ms".to_string(),
+                reason: format!("Must be at most {}", MAX_TIMEOUT_MS),
+            });
+        }
+        let interval =
+            get_optional_u64(args, "poll_interval_ms").unwrap_or(DEFAULT_POLL_INTERVAL_MS);
+        if !(MIN_POLL_INTERVAL_MS..=MAX_POLL_INTERVAL_MS).contains(&interval) {
+            return Err(McpError::InvalidArg {
+                name: "poll_interval_ms".to_string(),
+                reason: format!(
+                    "Must be between {} and {}",
+                    MIN_POLL_INTERVAL_MS, MAX_POLL_INTERVAL_MS
+                ),
+            });
+        }
+        Ok(Self {
+            timeout: Duration::from_millis(timeout),
+            interval: Duration::from_millis(interval),
+        })
+    }
+}
+
+/// Call `check` until it returns `Some` or the timeout elapses.
+///
+/// `check` always runs at least once. Returns `None` on timeout. Waiting
+/// inside a transaction is rejected, since its snapshot never sees other
+/// writers.
+pub fn poll<T>(
+    session: &mut McpSession,
+    opts: &WaitOptions,
+    mut check: impl FnMut(&mut McpSession) -> Result<Option<T>>,
+) -> Result<Option<T>> {
+    if session.in_transaction() {
+        return Err(McpError::InvalidArg {
+            name: "timeout_ms".to_string(),
+            reason: "Cannot wait inside a transaction; its snapshot never changes".to_string(),
+        });
+    }
+
+    let deadline = Instant::now() + opts.timeout;
+    loop {
+        if let Some(found) = check(session)? {
+            return Ok(Some(found));
+        }
+        let now = Instant::now();
+        if now >= deadline {
+            return Ok(None);
+        }
+        thread::sleep(opts.interval.min(deadline - now));
+    }
+}
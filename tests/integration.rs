@@ -1,7 +1,11 @@
 //! Integration tests for the MCP server.
 
 use serde_json::{json, Map, Value as JsonValue};
-use strata_mcp::{McpSession, ToolRegistry};
+use std::io::Read;
+use strata_mcp::{
+    BytesEncoding, ConvertOptions, McpServer, McpSession, RuntimeConfig, ShutdownHandle,
+    ToolRegistry,
+};
 use stratadb::Strata;
 
 /// Create a test session with an in-memory database.
@@ -114,6 +118,182 @@ fn test_db_compact() {
     assert_eq!(result, json!(null));
 }
 
+#[test]
+fn test_db_time_range_scoped() {
+    let mut session = test_session();
+    let registry = ToolRegistry::new();
+
+    call_tool(&mut session, &registry, "strata_kv_put", json!({"key": "a", "value": 1}));
+    call_tool(&mut session, &registry, "strata_kv_put", json!({"key": "b", "value": 2}));
+    call_tool(&mut session, &registry, "strata_space_create", json!({"space": "empty"}));
+
+    let result = call_tool(&mut session, &registry, "strata_db_time_range", json!({"space": "empty"}));
+    assert_eq!(result["oldest_ts"], json!(null));
+    assert_eq!(result["latest_ts"], json!(null));
+
+    let result = call_tool(&mut session, &registry, "strata_db_time_range", json!({"space": "default"}));
+    let oldest = result["oldest_ts"].as_u64().expect("Expected oldest_ts");
+    let latest = result["latest_ts"].as_u64().expect("Expected latest_ts");
+    assert!(oldest <= latest);
+
+    let result = call_tool(
+        &mut session,
+        &registry,
+        "strata_db_time_range",
+        json!({"space": "default", "primitive": "state"}),
+    );
+    assert_eq!(result["oldest_ts"], json!(null));
+
+    let result = call_tool(&mut session, &registry, "strata_db_time_range", json!({"primitive": "kv"}));
+    assert_eq!(result["oldest_ts"], json!(oldest));
+    assert_eq!(result["latest_ts"], json!(latest));
+}
+
+#[test]
+fn test_list_all() {
+    let mut session = test_session();
+    let registry = ToolRegistry::new();
+
+    call_tool(&mut session, &registry, "strata_kv_put", json!({"key": "user:1", "value": 1}));
+    call_tool(&mut session, &registry, "strata_kv_put", json!({"key": "user:2", "value": 2}));
+    call_tool(&mut session, &registry, "strata_kv_put", json!({"key": "item:1", "value": 3}));
+    call_tool(&mut session, &registry, "strata_json_set", json!({"key": "user:doc", "path": "$", "value": {"a": 1}}));
+    call_tool(&mut session, &registry, "strata_state_set", json!({"cell": "user:state", "value": 1}));
+    call_tool(&mut session, &registry, "strata_vector_create_collection", json!({"collection": "user_vecs", "dimension": 2}));
+    call_tool(&mut session, &registry, "strata_vector_create_collection", json!({"collection": "docs", "dimension": 2}));
+    call_tool(&mut session, &registry, "strata_event_append", json!({"event_type": "login", "payload": {}}));
+
+    let result = call_tool(&mut session, &registry, "strata_list_all", json!({}));
+    assert_eq!(result["kv"].as_array().expect("Expected kv").len(), 3);
+    assert_eq!(result["json"], json!(["user:doc"]));
+    assert_eq!(result["state"], json!(["user:state"]));
+    assert_eq!(result["vector_collections"].as_array().expect("Expected collections").len(), 2);
+    assert_eq!(result["events"], json!(1));
+    assert_eq!(result["truncated"], json!([]));
+
+    let result = call_tool(&mut session, &registry, "strata_list_all", json!({"prefix": "user"}));
+    assert_eq!(result["kv"].as_array().expect("Expected kv").len(), 2);
+    assert_eq!(result["vector_collections"], json!(["user_vecs"]));
+
+    let result = call_tool(&mut session, &registry, "strata_list_all", json!({"primitives": ["kv", "state"], "limit": 2}));
+    assert_eq!(result["kv"].as_array().expect("Expected kv").len(), 2);
+    assert_eq!(result["state"], json!(["user:state"]));
+    assert!(result.get("json").is_none());
+    assert!(result.get("events").is_none());
+    assert_eq!(result["truncated"], json!(["kv"]));
+
+    call_tool_err(&mut session, &registry, "strata_list_all", json!({"primitives": ["blob"]}));
+}
+
+#[test]
+fn test_server_info() {
+    let mut session = test_session();
+    let registry = ToolRegistry::new();
+
+    let result = call_tool(&mut session, &registry, "strata_server_info", json!({}));
+    assert_eq!(result["name"], json!("strata-mcp"));
+    assert_eq!(result["version"], json!(env!("CARGO_PKG_VERSION")));
+    assert_eq!(result["tool_count"], json!(registry.tools().len()));
+    assert_eq!(result["features"]["embed"], json!(cfg!(feature = "embed")));
+    assert_eq!(result["features"]["read_only"], json!(false));
+    let supported = result["supported_protocol_versions"].as_array().unwrap();
+    assert!(supported.contains(&result["protocol_version"]));
+}
+
+#[test]
+fn test_db_backup() {
+    let mut session = test_session();
+    let registry = ToolRegistry::new();
+
+    call_tool(&mut session, &registry, "strata_kv_put", json!({"key": "k1", "value": "v1"}));
+    call_tool(&mut session, &registry, "strata_branch_create", json!({"branch_id": "other"}));
+
+    let dir = tempfile::tempdir().expect("Failed to create temp dir");
+    let path = dir.path().join("backup");
+    let result = call_tool(
+        &mut session,
+        &registry,
+        "strata_db_backup",
+        json!({"path": path.to_str().unwrap()}),
+    );
+    assert!(result["entry_count"].as_u64().unwrap() > 0);
+    assert_eq!(result["branch_count"], json!(2));
+
+    let manifest: JsonValue =
+        serde_json::from_slice(&std::fs::read(path.join("manifest.json")).unwrap()).unwrap();
+    assert_eq!(manifest["branches"].as_array().map(|b| b.len()), Some(2));
+
+    // Refuses to overwrite an existing backup
+    call_tool_err(
+        &mut session,
+        &registry,
+        "strata_db_backup",
+        json!({"path": path.to_str().unwrap()}),
+    );
+}
+
+#[test]
+fn test_db_backup_restore_roundtrip() {
+    let mut session = test_session();
+    let registry = ToolRegistry::new();
+
+    call_tool(&mut session, &registry, "strata_branch_create", json!({"branch_id": "saved"}));
+    call_tool(&mut session, &registry, "strata_branch_switch", json!({"branch": "saved"}));
+    call_tool(&mut session, &registry, "strata_kv_put", json!({"key": "keep", "value": "me"}));
+
+    let dir = tempfile::tempdir().expect("Failed to create temp dir");
+    let path = dir.path().join("backup");
+    let path_str = path.to_str().unwrap();
+    call_tool(&mut session, &registry, "strata_db_backup", json!({"path": path_str}));
+
+    // Delete the data
+    call_tool(&mut session, &registry, "strata_branch_switch", json!({"branch": "default"}));
+    call_tool(&mut session, &registry, "strata_branch_delete", json!({"branch": "saved"}));
+
+    let result = call_tool(&mut session, &registry, "strata_db_restore", json!({"path": path_str}));
+    assert_eq!(result["branches_restored"], json!(1));
+    assert_eq!(result["skipped"], json!(["default"]));
+
+    call_tool(&mut session, &registry, "strata_branch_switch", json!({"branch": "saved"}));
+    let result = call_tool(&mut session, &registry, "strata_kv_get", json!({"key": "keep"}));
+    assert_eq!(extract_value(&result), &json!("me"));
+}
+
+#[test]
+fn test_db_restore_checks_manifest_before_writing() {
+    let mut session = test_session();
+    let registry = ToolRegistry::new();
+
+    call_tool(&mut session, &registry, "strata_branch_create", json!({"branch_id": "saved"}));
+    call_tool(&mut session, &registry, "strata_branch_switch", json!({"branch": "saved"}));
+    call_tool(&mut session, &registry, "strata_kv_put", json!({"key": "keep", "value": "me"}));
+
+    let dir = tempfile::tempdir().expect("Failed to create temp dir");
+    let path = dir.path().join("backup");
+    let path_str = path.to_str().unwrap();
+    call_tool(&mut session, &registry, "strata_db_backup", json!({"path": path_str}));
+    let manifest_path = path.join("manifest.json");
+    let manifest: JsonValue = serde_json::from_slice(&std::fs::read(&manifest_path).unwrap()).unwrap();
+
+    // Files outside the backup directory are refused
+    for file in ["../outside.bundle", "/tmp/outside.bundle"] {
+        let mut escaped = manifest.clone();
+        escaped["branches"][0]["file"] = json!(file);
+        std::fs::write(&manifest_path, serde_json::to_vec(&escaped).unwrap()).unwrap();
+        let err = call_tool_err(&mut session, &registry, "strata_db_restore", json!({"path": path_str, "overwrite": true}));
+        assert!(err.to_string().contains("plain file name"), "Unexpected error: {err}");
+    }
+
+    // A corrupt bundle fails validation before the existing branch is deleted
+    std::fs::write(&manifest_path, serde_json::to_vec(&manifest).unwrap()).unwrap();
+    for entry in manifest["branches"].as_array().unwrap() {
+        std::fs::write(path.join(entry["file"].as_str().unwrap()), b"not a bundle").unwrap();
+    }
+    call_tool_err(&mut session, &registry, "strata_db_restore", json!({"path": path_str, "overwrite": true}));
+    let result = call_tool(&mut session, &registry, "strata_kv_get", json!({"key": "keep"}));
+    assert_eq!(extract_value(&result), &json!("me"));
+}
+
 // =============================================================================
 // KV Tools
 // =============================================================================
@@ -187,6 +367,55 @@ fn test_kv_list() {
     assert_eq!(keys.len(), 2);
 }
 
+fn sorted_strings(result: &JsonValue) -> Vec<String> {
+    let mut keys: Vec<String> = result
+        .as_array()
+        .expect("Expected array")
+        .iter()
+        .filter_map(|k| k.as_str().map(String::from))
+        .collect();
+    keys.sort();
+    keys
+}
+
+#[test]
+fn test_kv_list_case_insensitive_and_glob() {
+    let mut session = test_session();
+    let registry = ToolRegistry::new();
+
+    for key in ["User:1", "USER:2", "user:3", "item:1"] {
+        call_tool(&mut session, &registry, "strata_kv_put", json!({"key": key, "value": 1}));
+    }
+
+    let result = call_tool(
+        &mut session,
+        &registry,
+        "strata_kv_list",
+        json!({"prefix": "user:", "case_insensitive": true}),
+    );
+    assert_eq!(sorted_strings(&result), vec!["USER:2", "User:1", "user:3"]);
+
+    // The limit applies after filtering
+    let result = call_tool(
+        &mut session,
+        &registry,
+        "strata_kv_list",
+        json!({"prefix": "USER", "case_insensitive": true, "limit": 2}),
+    );
+    assert_eq!(result.as_array().unwrap().len(), 2);
+
+    let result = call_tool(&mut session, &registry, "strata_kv_list", json!({"glob": "*:1"}));
+    assert_eq!(sorted_strings(&result), vec!["User:1", "item:1"]);
+
+    let result = call_tool(
+        &mut session,
+        &registry,
+        "strata_kv_list",
+        json!({"glob": "user:?", "case_insensitive": true}),
+    );
+    assert_eq!(result.as_array().unwrap().len(), 3);
+}
+
 #[test]
 fn test_kv_history() {
     let mut session = test_session();
@@ -201,6 +430,125 @@ fn test_kv_history() {
     assert!(history.len() >= 2, "Expected at least 2 versions");
 }
 
+/// Version numbers of a history result, in the order returned.
+fn history_versions(result: &JsonValue) -> Vec<u64> {
+    result
+        .as_array()
+        .expect("Expected array of versions")
+        .iter()
+        .map(|v| v["version"].as_u64().expect("Expected version"))
+        .collect()
+}
+
+#[test]
+fn test_kv_history_limit() {
+    let mut session = test_session();
+    let registry = ToolRegistry::new();
+
+    for value in 1..=4 {
+        call_tool(&mut session, &registry, "strata_kv_put", json!({"key": "k", "value": value}));
+    }
+
+    let result = call_tool(&mut session, &registry, "strata_kv_history", json!({"key": "k", "limit": 2}));
+    let history = result.as_array().expect("Expected array of versions");
+    assert_eq!(history.len(), 2);
+    assert_eq!(history[0]["value"], json!(4));
+    assert_eq!(history[1]["value"], json!(3));
+    let versions = history_versions(&result);
+    assert!(versions[0] > versions[1]);
+
+    // A limit beyond the history returns every version
+    let result = call_tool(&mut session, &registry, "strata_kv_history", json!({"key": "k", "limit": 10}));
+    assert_eq!(result.as_array().unwrap().len(), 4);
+}
+
+#[test]
+fn test_kv_history_since_version() {
+    let mut session = test_session();
+    let registry = ToolRegistry::new();
+
+    for value in 1..=4 {
+        call_tool(&mut session, &registry, "strata_kv_put", json!({"key": "k", "value": value}));
+    }
+    let history = call_tool(&mut session, &registry, "strata_kv_history", json!({"key": "k"}));
+    let mut sorted = history_versions(&history);
+    sorted.sort_unstable();
+    let (second, newest) = (sorted[1], sorted[3]);
+
+    let result = call_tool(
+        &mut session,
+        &registry,
+        "strata_kv_history",
+        json!({"key": "k", "since_version": second}),
+    );
+    let versions = history_versions(&result);
+    assert_eq!(versions, vec![sorted[3], sorted[2]]);
+    assert_eq!(result[0]["value"], json!(4));
+
+    // Combined with limit, the newest versions are kept
+    let result = call_tool(
+        &mut session,
+        &registry,
+        "strata_kv_history",
+        json!({"key": "k", "since_version": second, "limit": 1}),
+    );
+    assert_eq!(history_versions(&result), vec![newest]);
+
+    // Nothing is newer than the latest version
+    let result = call_tool(
+        &mut session,
+        &registry,
+        "strata_kv_history",
+        json!({"key": "k", "since_version": newest}),
+    );
+    assert!(result.as_array().unwrap().is_empty());
+}
+
+#[test]
+fn test_kv_scan() {
+    let mut session = test_session();
+    let registry = ToolRegistry::new();
+
+    for i in 1..=5 {
+        let key = format!("scan:{}", i);
+        call_tool(&mut session, &registry, "strata_kv_put", json!({"key": key, "value": i * 10}));
+    }
+    call_tool(&mut session, &registry, "strata_kv_put", json!({"key": "other", "value": 0}));
+
+    let result = call_tool(&mut session, &registry, "strata_kv_scan", json!({"prefix": "scan:"}));
+    let items = result.as_array().expect("Expected array of items");
+    assert_eq!(items.len(), 5);
+    let first = items.iter().find(|i| i["key"] == "scan:1").expect("Missing scan:1");
+    assert_eq!(first["value"], json!(10));
+    assert!(first["version"].is_number());
+
+    // Page through two at a time
+    let mut seen = std::collections::BTreeMap::new();
+    let mut cursor: Option<String> = None;
+    for _ in 0..10 {
+        let mut args = json!({"prefix": "scan:", "limit": 2});
+        if let Some(c) = &cursor {
+            args["cursor"] = json!(c);
+        }
+        let page = call_tool(&mut session, &registry, "strata_kv_scan", args);
+        let page = page.as_array().unwrap().clone();
+        assert!(page.len() <= 2);
+        let mut new = 0;
+        for item in &page {
+            let key = item["key"].as_str().unwrap().to_string();
+            if seen.insert(key, item["value"].clone()).is_none() {
+                new += 1;
+            }
+        }
+        if new == 0 {
+            break;
+        }
+        cursor = page.last().map(|i| i["key"].as_str().unwrap().to_string());
+    }
+    assert_eq!(seen.len(), 5);
+    assert_eq!(seen["scan:5"], json!(50));
+}
+
 #[test]
 fn test_kv_put_many_get_many() {
     let mut session = test_session();
@@ -250,832 +598,4102 @@ fn test_kv_delete_many() {
     assert_eq!(results[1], json!(true));
 }
 
-// =============================================================================
-// State Tools
-// =============================================================================
-
 #[test]
-fn test_state_set_get() {
+fn test_state_delete_many() {
     let mut session = test_session();
     let registry = ToolRegistry::new();
 
-    let result = call_tool(
-        &mut session,
-        &registry,
-        "strata_state_set",
-        json!({"cell": "counter", "value": 100}),
-    );
-    assert!(result.get("version").is_some());
+    call_tool(&mut session, &registry, "strata_state_set", json!({"cell": "sdm:1", "value": 1}));
+    call_tool(&mut session, &registry, "strata_state_set", json!({"cell": "sdm:3", "value": 3}));
 
     let result = call_tool(
         &mut session,
         &registry,
-        "strata_state_get",
-        json!({"cell": "counter"}),
+        "strata_state_delete_many",
+        json!({"cells": ["sdm:1", "sdm:2", "sdm:3"]}),
     );
-    assert_eq!(extract_value(&result), &json!(100));
+    assert_eq!(result, json!([true, false, true]));
+    let result = call_tool(&mut session, &registry, "strata_state_get", json!({"cell": "sdm:1"}));
+    assert_eq!(result, json!(null));
 }
 
 #[test]
-fn test_state_init() {
+fn test_json_delete_many() {
     let mut session = test_session();
     let registry = ToolRegistry::new();
 
-    call_tool(
-        &mut session,
-        &registry,
-        "strata_state_init",
-        json!({"cell": "status", "value": "pending"}),
-    );
+    call_tool(&mut session, &registry, "strata_json_set", json!({"key": "jdm:1", "path": "$", "value": {"a": 1}}));
 
     let result = call_tool(
         &mut session,
         &registry,
-        "strata_state_get",
-        json!({"cell": "status"}),
+        "strata_json_delete_many",
+        json!({"keys": ["jdm:1", "jdm:2"]}),
     );
-    assert_eq!(extract_value(&result), &json!("pending"));
-}
-
-#[test]
-fn test_state_delete() {
-    let mut session = test_session();
-    let registry = ToolRegistry::new();
-
-    call_tool(&mut session, &registry, "strata_state_set", json!({"cell": "temp", "value": 1}));
-    let result = call_tool(&mut session, &registry, "strata_state_delete", json!({"cell": "temp"}));
-    assert_eq!(result, json!(true));
-
-    let result = call_tool(&mut session, &registry, "strata_state_get", json!({"cell": "temp"}));
+    assert_eq!(result, json!([1, 0]));
+    let result = call_tool(&mut session, &registry, "strata_json_get", json!({"key": "jdm:1", "path": "$"}));
     assert_eq!(result, json!(null));
 }
 
 #[test]
-fn test_state_list() {
+fn test_kv_rename() {
     let mut session = test_session();
     let registry = ToolRegistry::new();
 
-    call_tool(&mut session, &registry, "strata_state_set", json!({"cell": "cfg:a", "value": 1}));
-    call_tool(&mut session, &registry, "strata_state_set", json!({"cell": "cfg:b", "value": 2}));
+    call_tool(&mut session, &registry, "strata_kv_put", json!({"key": "old", "value": "payload"}));
 
-    let result = call_tool(&mut session, &registry, "strata_state_list", json!({"prefix": "cfg:"}));
-    let cells = result.as_array().expect("Expected array");
-    assert_eq!(cells.len(), 2);
+    let result = call_tool(&mut session, &registry, "strata_kv_rename", json!({"from": "old", "to": "new"}));
+    assert_eq!(result["from"], json!("old"));
+    assert_eq!(result["to"], json!("new"));
+    assert!(result["version"].is_u64());
+
+    let moved = call_tool(&mut session, &registry, "strata_kv_get", json!({"key": "new"}));
+    assert_eq!(extract_value(&moved), &json!("payload"));
+    let gone = call_tool(&mut session, &registry, "strata_kv_get", json!({"key": "old"}));
+    assert!(gone.is_null());
 }
 
 #[test]
-fn test_state_cas() {
+fn test_kv_rename_existing_target() {
     let mut session = test_session();
     let registry = ToolRegistry::new();
 
-    let v = call_tool(&mut session, &registry, "strata_state_set", json!({"cell": "lock", "value": "free"}));
-    let version = v.get("version").and_then(|v| v.as_u64()).unwrap();
+    call_tool(&mut session, &registry, "strata_kv_put", json!({"key": "a", "value": 1}));
+    call_tool(&mut session, &registry, "strata_kv_put", json!({"key": "b", "value": 2}));
 
-    // CAS with matching expected_counter should succeed
-    let result = call_tool(
+    let err = call_tool_err(&mut session, &registry, "strata_kv_rename", json!({"from": "a", "to": "b"}));
+    assert!(err.to_string().contains("already exists"));
+    let kept = call_tool(&mut session, &registry, "strata_kv_get", json!({"key": "a"}));
+    assert_eq!(extract_value(&kept), &json!(1));
+
+    call_tool(
         &mut session,
         &registry,
-        "strata_state_cas",
-        json!({"cell": "lock", "value": "taken", "expected_counter": version}),
+        "strata_kv_rename",
+        json!({"from": "a", "to": "b", "overwrite": true}),
     );
-    // Result is a version number (success) or null (CAS failure)
-    assert!(result.is_number(), "Expected version number, got: {:?}", result);
+    let replaced = call_tool(&mut session, &registry, "strata_kv_get", json!({"key": "b"}));
+    assert_eq!(extract_value(&replaced), &json!(1));
+    let gone = call_tool(&mut session, &registry, "strata_kv_get", json!({"key": "a"}));
+    assert!(gone.is_null());
+
+    let err = call_tool_err(&mut session, &registry, "strata_kv_rename", json!({"from": "a", "to": "c"}));
+    assert!(err.to_string().contains("does not exist"));
 }
 
 #[test]
-fn test_state_history() {
+fn test_kv_copy_same_space() {
     let mut session = test_session();
     let registry = ToolRegistry::new();
 
-    call_tool(&mut session, &registry, "strata_state_set", json!({"cell": "ver", "value": 1}));
-    call_tool(&mut session, &registry, "strata_state_set", json!({"cell": "ver", "value": 2}));
+    call_tool(&mut session, &registry, "strata_kv_put", json!({"key": "fixture", "value": {"n": 1}}));
 
-    let result = call_tool(&mut session, &registry, "strata_state_history", json!({"cell": "ver"}));
-    let history = result.as_array().expect("Expected array");
-    assert!(history.len() >= 2);
-}
+    let result = call_tool(&mut session, &registry, "strata_kv_copy", json!({"from": "fixture", "to": "copy"}));
+    assert_eq!(result["versions_copied"], json!(1));
+    assert_eq!(result["space"], json!("default"));
 
-// =============================================================================
-// Event Tools
-// =============================================================================
+    for key in ["fixture", "copy"] {
+        let value = call_tool(&mut session, &registry, "strata_kv_get", json!({"key": key}));
+        assert_eq!(extract_value(&value), &json!({"n": 1}));
+    }
+
+    let err = call_tool_err(&mut session, &registry, "strata_kv_copy", json!({"from": "fixture", "to": "fixture"}));
+    assert!(err.to_string().contains("onto itself"));
+}
 
 #[test]
-fn test_event_append_get() {
+fn test_kv_copy_cross_space_with_history() {
     let mut session = test_session();
     let registry = ToolRegistry::new();
 
+    call_tool(&mut session, &registry, "strata_space_create", json!({"space": "fixtures"}));
+    for value in [1, 2, 3] {
+        call_tool(&mut session, &registry, "strata_kv_put", json!({"key": "counter", "value": value}));
+    }
+
     let result = call_tool(
         &mut session,
         &registry,
-        "strata_event_append",
-        json!({"event_type": "user_action", "payload": {"action": "click", "target": "button"}}),
+        "strata_kv_copy",
+        json!({"from": "counter", "to": "counter", "to_space": "fixtures", "with_history": true}),
     );
-    assert!(result.get("version").is_some());
+    assert_eq!(result["versions_copied"], json!(3));
+    assert_eq!(result["space"], json!("fixtures"));
 
-    let result = call_tool(&mut session, &registry, "strata_event_len", json!({}));
-    assert_eq!(result, json!(1));
+    call_tool(&mut session, &registry, "strata_space_switch", json!({"space": "fixtures"}));
+    let value = call_tool(&mut session, &registry, "strata_kv_get", json!({"key": "counter"}));
+    assert_eq!(extract_value(&value), &json!(3));
 }
 
 #[test]
-fn test_event_get_by_sequence() {
+fn test_kv_copy_cross_branch() {
     let mut session = test_session();
     let registry = ToolRegistry::new();
 
-    call_tool(
+    call_tool(&mut session, &registry, "strata_branch_create", json!({"branch_id": "target"}));
+    for value in [1, 2] {
+        call_tool(&mut session, &registry, "strata_kv_put", json!({"key": "config", "value": value}));
+    }
+
+    let result = call_tool(
         &mut session,
         &registry,
-        "strata_event_append",
-        json!({"event_type": "log", "payload": {"msg": "first"}}),
+        "strata_kv_copy",
+        json!({"from": "config", "to": "config", "to_branch": "target", "with_history": true}),
     );
+    assert_eq!(result["versions_copied"], json!(2));
+    assert_eq!(result["branch"], json!("target"));
+    assert_eq!(session.branch(), "default");
 
-    let result = call_tool(&mut session, &registry, "strata_event_get", json!({"sequence": 0}));
-    assert!(!result.is_null());
+    call_tool(&mut session, &registry, "strata_branch_switch", json!({"branch": "target"}));
+    let value = call_tool(&mut session, &registry, "strata_kv_get", json!({"key": "config"}));
+    assert_eq!(extract_value(&value), &json!(2));
+    let history = call_tool(&mut session, &registry, "strata_kv_history", json!({"key": "config"}));
+    assert_eq!(history.as_array().map(|h| h.len()), Some(2));
+
+    // A transaction on one branch cannot cover writes to another
+    call_tool(&mut session, &registry, "strata_txn_begin", json!({}));
+    let err = call_tool_err(&mut session, &registry, "strata_kv_copy", json!({"from": "config", "to": "config", "to_branch": "default"}));
+    assert!(matches!(err, strata_mcp::McpError::InvalidArg { ref name, .. } if name == "to_branch"));
+    call_tool(&mut session, &registry, "strata_txn_rollback", json!({}));
 }
 
 #[test]
-fn test_event_list_by_type() {
-    let mut session = test_session();
+fn test_kv_wait_sees_concurrent_write() {
+    let db = Strata::cache().expect("Failed to create cache database");
+    let mut writer = db.session();
+    let mut session = McpSession::new(db);
     let registry = ToolRegistry::new();
 
-    call_tool(&mut session, &registry, "strata_event_append", json!({"event_type": "a", "payload": {"n": 1}}));
-    call_tool(&mut session, &registry, "strata_event_append", json!({"event_type": "b", "payload": {"n": 2}}));
-    call_tool(&mut session, &registry, "strata_event_append", json!({"event_type": "a", "payload": {"n": 3}}));
-
-    let result = call_tool(&mut session, &registry, "strata_event_list", json!({"event_type": "a"}));
-    let events = result.as_array().expect("Expected array");
-    assert_eq!(events.len(), 2);
+    call_tool(&mut session, &registry, "strata_kv_put", json!({"key": "signal", "value": 1}));
+    let version = call_tool(&mut session, &registry, "strata_kv_get", json!({"key": "signal"}))["version"].clone();
+
+    let result = std::thread::scope(|s| {
+        s.spawn(move || {
+            std::thread::sleep(std::time::Duration::from_millis(100));
+            writer
+                .execute(stratadb::Command::KvPut {
+                    branch: None,
+                    space: None,
+                    key: "signal".to_string(),
+                    value: stratadb::Value::Int(2),
+                })
+                .expect("Failed to put");
+        });
+        call_tool(&mut session, &registry, "strata_kv_wait", json!({"key": "signal", "expected": {"version_gt": version}, "timeout_ms": 10000}))
+    });
+    assert_eq!(result["satisfied"], json!(true));
+    assert_eq!(result["value"], json!(2));
 }
 
 #[test]
-fn test_event_list_paginated() {
+fn test_kv_wait_timeout() {
     let mut session = test_session();
     let registry = ToolRegistry::new();
 
-    for i in 0..5 {
-        call_tool(&mut session, &registry, "strata_event_append", json!({"event_type": "pg", "payload": {"i": i}}));
-    }
+    call_tool(&mut session, &registry, "strata_kv_put", json!({"key": "idle", "value": "unchanged"}));
 
-    let result = call_tool(
-        &mut session,
-        &registry,
-        "strata_event_list",
-        json!({"event_type": "pg", "limit": 2}),
-    );
-    let events = result.as_array().expect("Expected array");
-    assert_eq!(events.len(), 2);
-}
+    let start = std::time::Instant::now();
+    let result = call_tool(&mut session, &registry, "strata_kv_wait", json!({"key": "idle", "timeout_ms": 100, "poll_interval_ms": 10}));
+    assert!(start.elapsed() >= std::time::Duration::from_millis(100));
+    assert_eq!(result["satisfied"], json!(false));
+    assert_eq!(result["value"], json!("unchanged"));
 
-// =============================================================================
-// JSON Tools
-// =============================================================================
+    // A condition that already holds returns immediately
+    let result = call_tool(&mut session, &registry, "strata_kv_wait", json!({"key": "idle", "expected": {"exists": true}}));
+    assert_eq!(result["satisfied"], json!(true));
+
+    let err = call_tool_err(&mut session, &registry, "strata_kv_wait", json!({"key": "idle", "timeout_ms": 600000}));
+    assert!(matches!(err, strata_mcp::McpError::InvalidArg { ref name, .. } if name == "timeout_ms"));
+}
 
 #[test]
-fn test_json_set_get() {
+fn test_kv_get_with_default() {
     let mut session = test_session();
     let registry = ToolRegistry::new();
 
-    call_tool(
-        &mut session,
-        &registry,
-        "strata_json_set",
-        json!({"key": "config", "path": "$", "value": {"theme": "dark", "lang": "en"}}),
-    );
+    call_tool(&mut session, &registry, "strata_kv_put", json!({"key": "present", "value": {"n": 1}}));
+
+    let result = call_tool(&mut session, &registry, "strata_kv_get_with_default", json!({"key": "present", "default": "fallback"}));
+    assert_eq!(result["value"], json!({"n": 1}));
+    assert_eq!(result["was_present"], json!(true));
+    assert!(result["version"].is_u64());
+
+    let result = call_tool(&mut session, &registry, "strata_kv_get_with_default", json!({"key": "absent", "default": "fallback"}));
+    assert_eq!(result["value"], json!("fallback"));
+    assert_eq!(result["was_present"], json!(false));
+    assert!(result["version"].is_null());
+
+    // A null default is returned as-is
+    let result = call_tool(&mut session, &registry, "strata_kv_get_with_default", json!({"key": "absent", "default": null}));
+    assert!(result["value"].is_null());
+    assert_eq!(result["was_present"], json!(false));
+}
+
+#[test]
+fn test_distinguish_missing() {
+    let mut session = test_session();
+    let registry = ToolRegistry::new();
+
+    call_tool(&mut session, &registry, "strata_kv_put", json!({"key": "nothing", "value": null}));
+
+    // Without the flag both read as null
+    let stored = call_tool(&mut session, &registry, "strata_kv_get", json!({"key": "nothing", "raw": true}));
+    let absent = call_tool(&mut session, &registry, "strata_kv_get", json!({"key": "absent", "raw": true}));
+    assert_eq!(stored, absent);
+
+    let result = call_tool(&mut session, &registry, "strata_kv_get", json!({"key": "nothing", "distinguish_missing": true}));
+    assert_eq!(result["found"], json!(true));
+    assert!(result["value"].is_null());
+    assert!(result["version"].is_u64());
+
+    let result = call_tool(&mut session, &registry, "strata_kv_get", json!({"key": "nothing", "raw": true, "distinguish_missing": true}));
+    assert_eq!(result, json!({"found": true, "value": null}));
+
+    let result = call_tool(&mut session, &registry, "strata_kv_get", json!({"key": "absent", "distinguish_missing": true}));
+    assert_eq!(result, json!({"found": false}));
+
+    let result = call_tool(&mut session, &registry, "strata_kv_get_many", json!({"keys": ["nothing", "absent"], "raw": true, "distinguish_missing": true}));
+    assert_eq!(result, json!([{"found": true, "value": null}, {"found": false}]));
+
+    call_tool(&mut session, &registry, "strata_state_set", json!({"cell": "empty", "value": null}));
+    let result = call_tool(&mut session, &registry, "strata_state_get", json!({"cell": "empty", "raw": true, "distinguish_missing": true}));
+    assert_eq!(result, json!({"found": true, "value": null}));
+    let result = call_tool(&mut session, &registry, "strata_state_get", json!({"cell": "unset", "distinguish_missing": true}));
+    assert_eq!(result, json!({"found": false}));
+}
+
+#[test]
+fn test_kv_put_ttl_expires() {
+    let mut session = test_session();
+    let registry = ToolRegistry::new();
+
+    call_tool(&mut session, &registry, "strata_kv_put", json!({"key": "session", "value": "token", "ttl_secs": 1}));
+    call_tool(&mut session, &registry, "strata_kv_put", json!({"key": "other", "value": "token", "ttl_secs": 1}));
+    call_tool(&mut session, &registry, "strata_kv_put", json!({"key": "kept", "value": "forever"}));
+
+    let result = call_tool(&mut session, &registry, "strata_kv_get", json!({"key": "session"}));
+    assert_eq!(result["value"], json!("token"));
+
+    // Rewriting without a TTL clears the expiry
+    call_tool(&mut session, &registry, "strata_kv_put", json!({"key": "other", "value": "renewed"}));
+
+    std::thread::sleep(std::time::Duration::from_millis(1100));
+
+    let result = call_tool(&mut session, &registry, "strata_kv_get", json!({"key": "session"}));
+    assert!(result.is_null());
+    let result = call_tool(&mut session, &registry, "strata_kv_get", json!({"key": "other"}));
+    assert_eq!(result["value"], json!("renewed"));
+    let result = call_tool(&mut session, &registry, "strata_kv_get_many", json!({"keys": ["session", "kept"]}));
+    assert!(result[0].is_null());
+    assert_eq!(result[1]["value"], json!("forever"));
+
+    // Reads hide the expired key without deleting it
+    let keys = call_tool(&mut session, &registry, "strata_kv_list", json!({}));
+    let keys = keys.as_array().expect("Expected array");
+    assert!(!keys.contains(&json!("session")));
+    assert!(keys.contains(&json!("kept")));
+    let history = call_tool(&mut session, &registry, "strata_kv_history", json!({"key": "session"}));
+    assert!(history.as_array().map_or(true, |h| h.is_empty()), "Expected no live versions: {history}");
+    let result = call_tool(&mut session, &registry, "strata_kv_scan", json!({}));
+    assert!(!result.to_string().contains("\"session\""));
+
+    // The expiry records live in a reserved space hidden from listings
+    let spaces = call_tool(&mut session, &registry, "strata_space_list", json!({}));
+    assert!(!spaces.to_string().contains("_mcp_"), "Reserved space listed: {spaces}");
+    let err = call_tool_err(&mut session, &registry, "strata_space_switch", json!({"space": "_mcp_kv_expiry"}));
+    assert!(err.to_string().contains("reserved"));
+
+    let result = call_tool(&mut session, &registry, "strata_kv_sweep_expired", json!({}));
+    assert_eq!(result["keys_deleted"], json!(1));
+    assert_eq!(result["records_removed"], json!(2));
+    let result = call_tool(&mut session, &registry, "strata_kv_sweep_expired", json!({}));
+    assert_eq!(result, json!({"keys_deleted": 0, "records_removed": 0}));
+    let result = call_tool(&mut session, &registry, "strata_kv_get", json!({"key": "other"}));
+    assert_eq!(result["value"], json!("renewed"));
+
+    let err = call_tool_err(&mut session, &registry, "strata_kv_put", json!({"key": "k", "value": 1, "ttl_secs": 0}));
+    assert!(err.to_string().contains("ttl_secs"));
+}
+
+#[test]
+fn test_kv_merge() {
+    let mut session = test_session();
+    let registry = ToolRegistry::new();
+
+    let doc = json!({"name": "a", "settings": {"theme": "dark", "lang": "en"}});
+    call_tool(&mut session, &registry, "strata_kv_put", json!({"key": "shallow", "value": doc.clone()}));
+    call_tool(&mut session, &registry, "strata_kv_put", json!({"key": "deep", "value": doc}));
+
+    let patch = json!({"settings": {"theme": "light"}, "age": 3});
+    let result = call_tool(&mut session, &registry, "strata_kv_merge", json!({"key": "shallow", "value": patch.clone()}));
+    assert_eq!(result["value"], json!({"name": "a", "settings": {"theme": "light"}, "age": 3}));
+    assert!(result["version"].is_u64());
+    let stored = call_tool(&mut session, &registry, "strata_kv_get", json!({"key": "shallow", "raw": true}));
+    assert_eq!(stored, result["value"]);
+
+    let result = call_tool(&mut session, &registry, "strata_kv_merge", json!({"key": "deep", "value": patch, "deep": true}));
+    assert_eq!(result["value"], json!({"name": "a", "settings": {"theme": "light", "lang": "en"}, "age": 3}));
+
+    // A missing key is created from the patch
+    let result = call_tool(&mut session, &registry, "strata_kv_merge", json!({"key": "new", "value": {"x": 1}}));
+    assert_eq!(result["value"], json!({"x": 1}));
+
+    call_tool(&mut session, &registry, "strata_kv_put", json!({"key": "scalar", "value": 5}));
+    let err = call_tool_err(&mut session, &registry, "strata_kv_merge", json!({"key": "scalar", "value": {"x": 1}}));
+    match err {
+        strata_mcp::McpError::Strata { code, .. } => assert_eq!(code, "WRONG_TYPE"),
+        other => panic!("Expected WRONG_TYPE, got {:?}", other),
+    }
+    let stored = call_tool(&mut session, &registry, "strata_kv_get", json!({"key": "scalar", "raw": true}));
+    assert_eq!(stored, json!(5));
+
+    call_tool_err(&mut session, &registry, "strata_kv_merge", json!({"key": "scalar", "value": [1]}));
+}
+
+#[test]
+fn test_kv_compare_and_delete() {
+    let mut session = test_session();
+    let registry = ToolRegistry::new();
+
+    call_tool(&mut session, &registry, "strata_kv_put", json!({"key": "lock", "value": "owner-a"}));
+    let held = call_tool(&mut session, &registry, "strata_kv_get", json!({"key": "lock"}));
+    let version = held["version"].as_u64().expect("Expected version");
+
+    // Mismatched version leaves the key in place
+    let result = call_tool(&mut session, &registry, "strata_kv_compare_and_delete", json!({"key": "lock", "expected_version": version + 1}));
+    assert_eq!(result, json!(false));
+    let result = call_tool(&mut session, &registry, "strata_kv_get", json!({"key": "lock"}));
+    assert_eq!(extract_value(&result), &json!("owner-a"));
+
+    let result = call_tool(&mut session, &registry, "strata_kv_compare_and_delete", json!({"key": "lock", "expected_version": version}));
+    assert_eq!(result, json!(true));
+    let result = call_tool(&mut session, &registry, "strata_kv_get", json!({"key": "lock"}));
+    assert_eq!(result, json!(null));
+
+    let result = call_tool(&mut session, &registry, "strata_kv_compare_and_delete", json!({"key": "absent", "expected_version": version}));
+    assert_eq!(result, json!(false));
+}
+
+#[test]
+fn test_kv_get_raw() {
+    let mut session = test_session();
+    let registry = ToolRegistry::new();
+
+    call_tool(&mut session, &registry, "strata_kv_put", json!({"key": "raw-key", "value": {"a": 1}}));
+
+    // Normal mode keeps the version wrapper
+    let result = call_tool(&mut session, &registry, "strata_kv_get", json!({"key": "raw-key"}));
+    assert!(result.get("version").is_some());
+    assert_eq!(result.get("value"), Some(&json!({"a": 1})));
 
+    // Raw mode returns the bare value
     let result = call_tool(
         &mut session,
         &registry,
-        "strata_json_get",
-        json!({"key": "config", "path": "$"}),
+        "strata_kv_get",
+        json!({"key": "raw-key", "raw": true}),
     );
-    let value = extract_value(&result);
-    assert_eq!(value.get("theme").and_then(|v| v.as_str()), Some("dark"));
+    assert_eq!(result, json!({"a": 1}));
 
     let result = call_tool(
         &mut session,
         &registry,
-        "strata_json_get",
-        json!({"key": "config", "path": "$.theme"}),
+        "strata_kv_get_many",
+        json!({"keys": ["raw-key", "missing"], "raw": true}),
     );
-    assert_eq!(extract_value(&result), &json!("dark"));
+    assert_eq!(result, json!([{"a": 1}, null]));
 }
 
 #[test]
-fn test_json_delete() {
+fn test_kv_get_raw_session_default() {
     let mut session = test_session();
     let registry = ToolRegistry::new();
+    session.set_convert_options(ConvertOptions {
+        raw: true,
+        ..Default::default()
+    });
 
-    call_tool(&mut session, &registry, "strata_json_set", json!({"key": "temp", "path": "$", "value": 42}));
-    let result = call_tool(&mut session, &registry, "strata_json_delete", json!({"key": "temp", "path": "$"}));
-    assert!(result.is_number());
+    call_tool(&mut session, &registry, "strata_kv_put", json!({"key": "k", "value": "v"}));
+
+    let result = call_tool(&mut session, &registry, "strata_kv_get", json!({"key": "k"}));
+    assert_eq!(result, json!("v"));
+
+    // A per-call raw=false overrides the session default
+    let result = call_tool(&mut session, &registry, "strata_kv_get", json!({"key": "k", "raw": false}));
+    assert_eq!(extract_value(&result), &json!("v"));
+    assert!(result.get("version").is_some());
 }
 
-#[test]
-fn test_json_list() {
+/// Store a byte value directly and read it back with the given encoding.
+fn get_bytes_with(encoding: BytesEncoding, bytes: Vec<u8>) -> JsonValue {
     let mut session = test_session();
     let registry = ToolRegistry::new();
-
-    call_tool(&mut session, &registry, "strata_json_set", json!({"key": "doc:a", "path": "$", "value": 1}));
-    call_tool(&mut session, &registry, "strata_json_set", json!({"key": "doc:b", "path": "$", "value": 2}));
-
-    let result = call_tool(&mut session, &registry, "strata_json_list", json!({"prefix": "doc:"}));
-    let keys = result.get("keys").and_then(|v| v.as_array()).expect("Expected keys array");
-    assert_eq!(keys.len(), 2);
+    session
+        .execute(stratadb::Command::KvPut {
+            branch: None,
+            space: None,
+            key: "bytes".to_string(),
+            value: stratadb::Value::Bytes(bytes),
+        })
+        .expect("Failed to put bytes");
+    session.set_convert_options(ConvertOptions {
+        bytes: encoding,
+        raw: true,
+        ..Default::default()
+    });
+    call_tool(&mut session, &registry, "strata_kv_get", json!({"key": "bytes"}))
 }
 
 #[test]
-fn test_json_history() {
-    let mut session = test_session();
-    let registry = ToolRegistry::new();
+fn test_kv_bytes_encoding_base64() {
+    let bytes = vec![0xde, 0xad, 0xbe, 0xef];
+    let expected: JsonValue = stratadb::Value::Bytes(bytes.clone()).into();
+    assert_eq!(get_bytes_with(BytesEncoding::Base64, bytes), expected);
+}
 
-    call_tool(&mut session, &registry, "strata_json_set", json!({"key": "versioned", "path": "$", "value": "v1"}));
-    call_tool(&mut session, &registry, "strata_json_set", json!({"key": "versioned", "path": "$", "value": "v2"}));
+#[test]
+fn test_kv_bytes_encoding_hex() {
+    let result = get_bytes_with(BytesEncoding::Hex, vec![0xde, 0xad, 0xbe, 0xef]);
+    assert_eq!(result, json!("deadbeef"));
+}
 
-    let result = call_tool(&mut session, &registry, "strata_json_history", json!({"key": "versioned"}));
-    let history = result.as_array().expect("Expected array");
-    assert!(history.len() >= 2);
+#[test]
+fn test_kv_bytes_encoding_array() {
+    let result = get_bytes_with(BytesEncoding::Array, vec![0xde, 0xad, 0xbe, 0xef]);
+    assert_eq!(result, json!([222, 173, 190, 239]));
 }
 
 // =============================================================================
-// Branch Tools
+// State Tools
 // =============================================================================
 
 #[test]
-fn test_branch_create_list() {
+fn test_state_set_get() {
     let mut session = test_session();
     let registry = ToolRegistry::new();
 
     let result = call_tool(
         &mut session,
         &registry,
-        "strata_branch_create",
-        json!({"branch_id": "test-branch"}),
+        "strata_state_set",
+        json!({"cell": "counter", "value": 100}),
     );
-    assert_eq!(result.get("id").and_then(|v| v.as_str()), Some("test-branch"));
-
-    let result = call_tool(&mut session, &registry, "strata_branch_list", json!({}));
-    let branches = result.as_array().expect("Expected array");
-    assert!(branches.len() >= 2);
+    assert!(result.get("version").is_some());
 
     let result = call_tool(
         &mut session,
         &registry,
-        "strata_branch_exists",
-        json!({"branch": "test-branch"}),
+        "strata_state_get",
+        json!({"cell": "counter"}),
     );
-    assert_eq!(result, json!(true));
+    assert_eq!(extract_value(&result), &json!(100));
 }
 
 #[test]
-fn test_branch_create_with_metadata() {
+fn test_state_init() {
     let mut session = test_session();
     let registry = ToolRegistry::new();
 
+    call_tool(
+        &mut session,
+        &registry,
+        "strata_state_init",
+        json!({"cell": "status", "value": "pending"}),
+    );
+
     let result = call_tool(
         &mut session,
         &registry,
-        "strata_branch_create",
-        json!({"branch_id": "meta-branch", "metadata": {"purpose": "experiment"}}),
+        "strata_state_get",
+        json!({"cell": "status"}),
     );
-    assert_eq!(result.get("id").and_then(|v| v.as_str()), Some("meta-branch"));
+    assert_eq!(extract_value(&result), &json!("pending"));
 }
 
 #[test]
-fn test_branch_switch() {
+fn test_state_delete() {
     let mut session = test_session();
     let registry = ToolRegistry::new();
 
-    call_tool(&mut session, &registry, "strata_branch_create", json!({"branch_id": "feature"}));
-    call_tool(&mut session, &registry, "strata_kv_put", json!({"key": "x", "value": 1}));
+    call_tool(&mut session, &registry, "strata_state_set", json!({"cell": "temp", "value": 1}));
+    let result = call_tool(&mut session, &registry, "strata_state_delete", json!({"cell": "temp"}));
+    assert_eq!(result, json!(true));
 
-    call_tool(&mut session, &registry, "strata_branch_switch", json!({"branch": "feature"}));
-    let result = call_tool(&mut session, &registry, "strata_kv_get", json!({"key": "x"}));
+    let result = call_tool(&mut session, &registry, "strata_state_get", json!({"cell": "temp"}));
     assert_eq!(result, json!(null));
-
-    call_tool(&mut session, &registry, "strata_kv_put", json!({"key": "x", "value": 2}));
-    call_tool(&mut session, &registry, "strata_branch_switch", json!({"branch": "default"}));
-
-    let result = call_tool(&mut session, &registry, "strata_kv_get", json!({"key": "x"}));
-    assert_eq!(extract_value(&result), &json!(1));
 }
 
 #[test]
-fn test_branch_fork() {
+fn test_state_list() {
     let mut session = test_session();
     let registry = ToolRegistry::new();
 
-    call_tool(&mut session, &registry, "strata_kv_put", json!({"key": "shared", "value": "original"}));
-
-    let result = call_tool(&mut session, &registry, "strata_branch_fork", json!({"destination": "forked"}));
-    assert!(result.get("keys_copied").is_some());
+    call_tool(&mut session, &registry, "strata_state_set", json!({"cell": "cfg:a", "value": 1}));
+    call_tool(&mut session, &registry, "strata_state_set", json!({"cell": "cfg:b", "value": 2}));
 
-    call_tool(&mut session, &registry, "strata_branch_switch", json!({"branch": "forked"}));
-    let result = call_tool(&mut session, &registry, "strata_kv_get", json!({"key": "shared"}));
-    assert_eq!(extract_value(&result), &json!("original"));
+    let result = call_tool(&mut session, &registry, "strata_state_list", json!({"prefix": "cfg:"}));
+    let cells = result.as_array().expect("Expected array");
+    assert_eq!(cells.len(), 2);
 }
 
 #[test]
-fn test_branch_get() {
+fn test_state_list_case_insensitive() {
     let mut session = test_session();
     let registry = ToolRegistry::new();
 
-    let result = call_tool(&mut session, &registry, "strata_branch_get", json!({"branch": "default"}));
-    assert_eq!(result.get("id").and_then(|v| v.as_str()), Some("default"));
-    assert!(result.get("status").is_some());
-    assert!(result.get("version").is_some());
-}
-
-#[test]
-fn test_branch_delete() {
-    let mut session = test_session();
-    let registry = ToolRegistry::new();
+    for cell in ["Cfg:a", "CFG:b", "other"] {
+        call_tool(&mut session, &registry, "strata_state_set", json!({"cell": cell, "value": 1}));
+    }
 
-    call_tool(&mut session, &registry, "strata_branch_create", json!({"branch_id": "to-delete"}));
-    call_tool(&mut session, &registry, "strata_branch_delete", json!({"branch": "to-delete"}));
+    let result = call_tool(
+        &mut session,
+        &registry,
+        "strata_state_list",
+        json!({"prefix": "cfg:", "case_insensitive": true}),
+    );
+    assert_eq!(sorted_strings(&result), vec!["CFG:b", "Cfg:a"]);
 
-    let result = call_tool(&mut session, &registry, "strata_branch_exists", json!({"branch": "to-delete"}));
-    assert_eq!(result, json!(false));
+    // Without the flag the prefix is exact
+    let result = call_tool(&mut session, &registry, "strata_state_list", json!({"prefix": "cfg:"}));
+    assert!(result.as_array().unwrap().is_empty());
 }
 
 #[test]
-fn test_branch_diff() {
+fn test_state_cas() {
     let mut session = test_session();
     let registry = ToolRegistry::new();
 
-    call_tool(&mut session, &registry, "strata_kv_put", json!({"key": "a", "value": 1}));
-    call_tool(&mut session, &registry, "strata_branch_create", json!({"branch_id": "diff-target"}));
+    let v = call_tool(&mut session, &registry, "strata_state_set", json!({"cell": "lock", "value": "free"}));
+    let version = v.get("version").and_then(|v| v.as_u64()).unwrap();
 
+    // CAS with matching expected_counter should succeed
     let result = call_tool(
         &mut session,
         &registry,
-        "strata_branch_diff",
-        json!({"branch_a": "default", "branch_b": "diff-target"}),
+        "strata_state_cas",
+        json!({"cell": "lock", "value": "taken", "expected_counter": version}),
     );
-    assert!(result.get("summary").is_some());
+    // Result is a version number (success) or null (CAS failure)
+    assert!(result.is_number(), "Expected version number, got: {:?}", result);
 }
 
 #[test]
-fn test_branch_merge() {
+fn test_state_batch_set() {
     let mut session = test_session();
     let registry = ToolRegistry::new();
 
-    call_tool(&mut session, &registry, "strata_branch_create", json!({"branch_id": "merge-src"}));
-    call_tool(&mut session, &registry, "strata_branch_switch", json!({"branch": "merge-src"}));
-    call_tool(&mut session, &registry, "strata_kv_put", json!({"key": "merged", "value": "from-src"}));
-    call_tool(&mut session, &registry, "strata_branch_switch", json!({"branch": "default"}));
-
     let result = call_tool(
         &mut session,
         &registry,
-        "strata_branch_merge",
-        json!({"source": "merge-src"}),
+        "strata_state_batch_set",
+        json!({"items": [
+            {"cell": "s1", "value": 1},
+            {"cell": "s2", "value": "two"},
+            {"cell": "s3", "value": {"n": 3}}
+        ]}),
     );
-    assert!(result.get("keys_applied").is_some());
-}
+    assert_eq!(result.as_array().expect("Expected array of versions").len(), 3);
 
-// =============================================================================
-// Space Tools
-// =============================================================================
+    let result = call_tool(&mut session, &registry, "strata_state_get", json!({"cell": "s2"}));
+    assert_eq!(extract_value(&result), &json!("two"));
+    let result = call_tool(&mut session, &registry, "strata_state_get", json!({"cell": "s3"}));
+    assert_eq!(extract_value(&result), &json!({"n": 3}));
+}
 
 #[test]
-fn test_space_operations() {
+fn test_state_batch_set_rejects_malformed_item() {
     let mut session = test_session();
     let registry = ToolRegistry::new();
 
-    call_tool(&mut session, &registry, "strata_space_create", json!({"space": "my-space"}));
-
-    let result = call_tool(&mut session, &registry, "strata_space_list", json!({}));
-    let spaces = result.as_array().expect("Expected array");
-    assert!(spaces.iter().any(|s| s.as_str() == Some("my-space")));
-
-    call_tool(&mut session, &registry, "strata_space_switch", json!({"space": "my-space"}));
-    call_tool(&mut session, &registry, "strata_kv_put", json!({"key": "space-key", "value": "space-value"}));
+    let err = call_tool_err(
+        &mut session,
+        &registry,
+        "strata_state_batch_set",
+        json!({"items": [{"cell": "first", "value": 1}, {"value": 2}]}),
+    );
+    assert!(format!("{}", err).contains("items[1].cell"));
 
-    call_tool(&mut session, &registry, "strata_space_switch", json!({"space": "default"}));
-    let result = call_tool(&mut session, &registry, "strata_kv_get", json!({"key": "space-key"}));
+    // Nothing is written when validation fails
+    let result = call_tool(&mut session, &registry, "strata_state_get", json!({"cell": "first"}));
     assert_eq!(result, json!(null));
 }
 
 #[test]
-fn test_space_exists() {
+fn test_state_history() {
     let mut session = test_session();
     let registry = ToolRegistry::new();
 
-    let result = call_tool(&mut session, &registry, "strata_space_exists", json!({"space": "default"}));
-    assert_eq!(result, json!(true));
+    call_tool(&mut session, &registry, "strata_state_set", json!({"cell": "ver", "value": 1}));
+    call_tool(&mut session, &registry, "strata_state_set", json!({"cell": "ver", "value": 2}));
 
-    let result = call_tool(&mut session, &registry, "strata_space_exists", json!({"space": "nonexistent"}));
-    assert_eq!(result, json!(false));
+    let result = call_tool(&mut session, &registry, "strata_state_history", json!({"cell": "ver"}));
+    let history = result.as_array().expect("Expected array");
+    assert!(history.len() >= 2);
 }
 
 #[test]
-fn test_space_delete() {
+fn test_state_history_limit() {
     let mut session = test_session();
     let registry = ToolRegistry::new();
 
-    call_tool(&mut session, &registry, "strata_space_create", json!({"space": "to-remove"}));
-    call_tool(&mut session, &registry, "strata_space_delete", json!({"space": "to-remove", "force": true}));
+    for value in 1..=3 {
+        call_tool(&mut session, &registry, "strata_state_set", json!({"cell": "ver", "value": value}));
+    }
 
-    let result = call_tool(&mut session, &registry, "strata_space_exists", json!({"space": "to-remove"}));
-    assert_eq!(result, json!(false));
+    let result = call_tool(&mut session, &registry, "strata_state_history", json!({"cell": "ver", "limit": 1}));
+    let history = result.as_array().expect("Expected array");
+    assert_eq!(history.len(), 1);
+    assert_eq!(history[0]["value"], json!(3));
 }
 
-// =============================================================================
-// Vector Tools
-// =============================================================================
+#[test]
+fn test_state_wait_satisfied() {
+    let db = Strata::cache().expect("Failed to create cache database");
+    let mut writer = db.session();
+    let mut session = McpSession::new(db);
+    let registry = ToolRegistry::new();
+
+    call_tool(&mut session, &registry, "strata_state_set", json!({"cell": "lock", "value": "held"}));
+
+    let result = std::thread::scope(|s| {
+        s.spawn(move || {
+            std::thread::sleep(std::time::Duration::from_millis(100));
+            writer
+                .execute(stratadb::Command::StateSet {
+                    branch: None,
+                    space: None,
+                    cell: "lock".to_string(),
+                    value: stratadb::Value::String("free".to_string()),
+                })
+                .expect("Failed to set");
+        });
+        call_tool(&mut session, &registry, "strata_state_wait", json!({"cell": "lock", "value": "free", "timeout_ms": 10000, "poll_interval_ms": 20}))
+    });
+    assert_eq!(result["satisfied"], json!(true));
+    assert_eq!(result["value"], json!("free"));
+    assert!(result["version"].is_u64());
+}
 
 #[test]
-fn test_vector_operations() {
+fn test_state_wait_timeout() {
     let mut session = test_session();
     let registry = ToolRegistry::new();
 
-    call_tool(
-        &mut session,
-        &registry,
-        "strata_vector_create_collection",
-        json!({"collection": "embeddings", "dimension": 4, "metric": "cosine"}),
-    );
+    call_tool(&mut session, &registry, "strata_state_set", json!({"cell": "lock", "value": "held"}));
 
-    call_tool(
-        &mut session,
-        &registry,
-        "strata_vector_upsert",
-        json!({"collection": "embeddings", "key": "v1", "vector": [1.0, 0.0, 0.0, 0.0]}),
-    );
-    call_tool(
+    let result = call_tool(&mut session, &registry, "strata_state_wait", json!({"cell": "lock", "value": "free", "timeout_ms": 100}));
+    assert_eq!(result["satisfied"], json!(false));
+    assert_eq!(result["value"], json!("held"));
+    assert!(result["version"].is_u64());
+
+    let err = call_tool_err(&mut session, &registry, "strata_state_wait", json!({"cell": "lock", "poll_interval_ms": 5000}));
+    assert!(matches!(err, strata_mcp::McpError::InvalidArg { ref name, .. } if name == "value"));
+    let err = call_tool_err(&mut session, &registry, "strata_state_wait", json!({"cell": "lock", "min_version": 1, "poll_interval_ms": 5000}));
+    assert!(matches!(err, strata_mcp::McpError::InvalidArg { ref name, .. } if name == "poll_interval_ms"));
+}
+
+// =============================================================================
+// Event Tools
+// =============================================================================
+
+#[test]
+fn test_event_append_get() {
+    let mut session = test_session();
+    let registry = ToolRegistry::new();
+
+    let result = call_tool(
         &mut session,
         &registry,
-        "strata_vector_upsert",
-        json!({"collection": "embeddings", "key": "v2", "vector": [0.0, 1.0, 0.0, 0.0]}),
+        "strata_event_append",
+        json!({"event_type": "user_action", "payload": {"action": "click", "target": "button"}}),
     );
+    assert!(result.get("version").is_some());
 
-    let result = call_tool(
+    let result = call_tool(&mut session, &registry, "strata_event_len", json!({}));
+    assert_eq!(result, json!(1));
+}
+
+#[test]
+fn test_event_get_by_sequence() {
+    let mut session = test_session();
+    let registry = ToolRegistry::new();
+
+    call_tool(
         &mut session,
         &registry,
-        "strata_vector_search",
-        json!({"collection": "embeddings", "query": [1.0, 0.0, 0.0, 0.0], "k": 2}),
+        "strata_event_append",
+        json!({"event_type": "log", "payload": {"msg": "first"}}),
     );
-    let matches = result.as_array().expect("Expected array");
-    assert_eq!(matches.len(), 2);
-    assert_eq!(matches[0].get("key").and_then(|v| v.as_str()), Some("v1"));
 
-    let result = call_tool(&mut session, &registry, "strata_vector_list_collections", json!({}));
-    let collections = result.as_array().expect("Expected array");
-    assert!(collections
-        .iter()
-        .any(|c| c.get("name").and_then(|v| v.as_str()) == Some("embeddings")));
+    let result = call_tool(&mut session, &registry, "strata_event_get", json!({"sequence": 0}));
+    assert!(!result.is_null());
 }
 
 #[test]
-fn test_vector_get() {
+fn test_event_replay_commands() {
     let mut session = test_session();
     let registry = ToolRegistry::new();
 
-    call_tool(&mut session, &registry, "strata_vector_create_collection", json!({"collection": "vget", "dimension": 3}));
-    call_tool(&mut session, &registry, "strata_vector_upsert", json!({"collection": "vget", "key": "k1", "vector": [1.0, 2.0, 3.0], "metadata": {"label": "test"}}));
+    let log = [
+        json!({"tool": "strata_kv_put", "arguments": {"key": "a", "value": 1}}),
+        json!({"note": "not a command"}),
+        json!({"tool": "strata_kv_put", "arguments": {"key": "b", "value": 2}}),
+        json!({"tool": "strata_state_set", "arguments": {"cell": "status", "value": "done"}}),
+    ];
+    for payload in log {
+        call_tool(&mut session, &registry, "strata_event_append", json!({"event_type": "cmd", "payload": payload}));
+    }
 
-    let result = call_tool(&mut session, &registry, "strata_vector_get", json!({"collection": "vget", "key": "k1"}));
-    assert!(result.get("embedding").is_some());
-    assert!(result.get("version").is_some());
+    let err = call_tool_err(&mut session, &registry, "strata_event_replay", json!({"confirm": false}));
+    assert!(matches!(err, strata_mcp::McpError::InvalidArg { ref name, .. } if name == "confirm"));
+    let result = call_tool(&mut session, &registry, "strata_kv_get", json!({"key": "a"}));
+    assert_eq!(result, json!(null));
+
+    let result = call_tool(&mut session, &registry, "strata_event_replay", json!({"confirm": true, "to_sequence": 2}));
+    assert_eq!(result["replayed"], json!(2));
+    assert_eq!(result["skipped"], json!(1));
+    assert_eq!(result["results"][1]["sequence"], json!(2));
+    let result = call_tool(&mut session, &registry, "strata_kv_get", json!({"key": "b"}));
+    assert_eq!(extract_value(&result), &json!(2));
+    let result = call_tool(&mut session, &registry, "strata_state_get", json!({"cell": "status"}));
+    assert_eq!(result, json!(null));
+
+    let result = call_tool(&mut session, &registry, "strata_event_replay", json!({"confirm": true, "from_sequence": 3, "event_type": "cmd"}));
+    assert_eq!(result["replayed"], json!(1));
+    let result = call_tool(&mut session, &registry, "strata_state_get", json!({"cell": "status"}));
+    assert_eq!(extract_value(&result), &json!("done"));
 }
 
 #[test]
-fn test_vector_delete() {
+fn test_event_replay_rolls_back_on_failure() {
     let mut session = test_session();
     let registry = ToolRegistry::new();
 
-    call_tool(&mut session, &registry, "strata_vector_create_collection", json!({"collection": "vdel", "dimension": 2}));
-    call_tool(&mut session, &registry, "strata_vector_upsert", json!({"collection": "vdel", "key": "k1", "vector": [1.0, 2.0]}));
-
-    let result = call_tool(&mut session, &registry, "strata_vector_delete", json!({"collection": "vdel", "key": "k1"}));
-    assert_eq!(result, json!(true));
+    let log = [
+        json!({"tool": "strata_kv_put", "arguments": {"key": "a", "value": 1}}),
+        json!({"tool": "strata_kv_put", "arguments": {}}),
+    ];
+    for payload in log {
+        call_tool(&mut session, &registry, "strata_event_append", json!({"event_type": "cmd", "payload": payload}));
+    }
 
-    let result = call_tool(&mut session, &registry, "strata_vector_get", json!({"collection": "vdel", "key": "k1"}));
+    call_tool_err(&mut session, &registry, "strata_event_replay", json!({"confirm": true}));
+    let result = call_tool(&mut session, &registry, "strata_kv_get", json!({"key": "a"}));
     assert_eq!(result, json!(null));
 }
 
 #[test]
-fn test_vector_delete_collection() {
+fn test_event_list_by_type() {
     let mut session = test_session();
     let registry = ToolRegistry::new();
 
-    call_tool(&mut session, &registry, "strata_vector_create_collection", json!({"collection": "temp_coll", "dimension": 2}));
-    let result = call_tool(&mut session, &registry, "strata_vector_delete_collection", json!({"collection": "temp_coll"}));
-    assert_eq!(result, json!(true));
+    call_tool(&mut session, &registry, "strata_event_append", json!({"event_type": "a", "payload": {"n": 1}}));
+    call_tool(&mut session, &registry, "strata_event_append", json!({"event_type": "b", "payload": {"n": 2}}));
+    call_tool(&mut session, &registry, "strata_event_append", json!({"event_type": "a", "payload": {"n": 3}}));
+
+    let result = call_tool(&mut session, &registry, "strata_event_list", json!({"event_type": "a"}));
+    let events = result.as_array().expect("Expected array");
+    assert_eq!(events.len(), 2);
 }
 
 #[test]
-fn test_vector_stats() {
+fn test_event_count() {
     let mut session = test_session();
     let registry = ToolRegistry::new();
 
-    call_tool(&mut session, &registry, "strata_vector_create_collection", json!({"collection": "stats_coll", "dimension": 4}));
-    call_tool(&mut session, &registry, "strata_vector_upsert", json!({"collection": "stats_coll", "key": "s1", "vector": [1.0, 0.0, 0.0, 0.0]}));
+    for event_type in ["a", "b", "a", "a"] {
+        call_tool(&mut session, &registry, "strata_event_append", json!({"event_type": event_type, "payload": {}}));
+    }
 
-    let result = call_tool(&mut session, &registry, "strata_vector_stats", json!({"collection": "stats_coll"}));
-    // VectorCollectionStats returns VectorCollectionList (array with single entry)
-    let stats = if result.is_array() {
-        result.as_array().unwrap().first().expect("Expected at least one stats entry").clone()
-    } else {
-        result
-    };
-    assert_eq!(stats.get("dimension").and_then(|v| v.as_u64()), Some(4));
-    assert_eq!(stats.get("count").and_then(|v| v.as_u64()), Some(1));
+    assert_eq!(call_tool(&mut session, &registry, "strata_event_count", json!({"event_type": "a"})), json!(3));
+    assert_eq!(call_tool(&mut session, &registry, "strata_event_count", json!({"event_type": "b"})), json!(1));
+    assert_eq!(call_tool(&mut session, &registry, "strata_event_count", json!({"event_type": "c"})), json!(0));
+
+    let total = call_tool(&mut session, &registry, "strata_event_count", json!({}));
+    assert_eq!(total, json!(4));
+    assert_eq!(total, call_tool(&mut session, &registry, "strata_event_len", json!({})));
 }
 
 #[test]
-fn test_vector_batch_upsert() {
+fn test_event_list_paginated() {
     let mut session = test_session();
     let registry = ToolRegistry::new();
 
-    call_tool(&mut session, &registry, "strata_vector_create_collection", json!({"collection": "batch", "dimension": 2}));
+    for i in 0..5 {
+        call_tool(&mut session, &registry, "strata_event_append", json!({"event_type": "pg", "payload": {"i": i}}));
+    }
 
     let result = call_tool(
         &mut session,
         &registry,
-        "strata_vector_batch_upsert",
-        json!({"collection": "batch", "entries": [
-            {"key": "b1", "vector": [1.0, 0.0]},
-            {"key": "b2", "vector": [0.0, 1.0], "metadata": {"tag": "second"}}
-        ]}),
+        "strata_event_list",
+        json!({"event_type": "pg", "limit": 2}),
     );
-    let versions = result.as_array().expect("Expected array");
-    assert_eq!(versions.len(), 2);
+    let events = result.as_array().expect("Expected array");
+    assert_eq!(events.len(), 2);
 }
 
 #[test]
-fn test_vector_search_filtered() {
+fn test_event_list_payload_filter() {
     let mut session = test_session();
     let registry = ToolRegistry::new();
 
-    call_tool(&mut session, &registry, "strata_vector_create_collection", json!({"collection": "filtered", "dimension": 2}));
-    call_tool(&mut session, &registry, "strata_vector_upsert", json!({"collection": "filtered", "key": "f1", "vector": [1.0, 0.0], "metadata": {"color": "red"}}));
-    call_tool(&mut session, &registry, "strata_vector_upsert", json!({"collection": "filtered", "key": "f2", "vector": [0.9, 0.1], "metadata": {"color": "blue"}}));
+    let payloads = [
+        json!({"action": "click", "user": {"id": 1}}),
+        json!({"action": "view", "user": {"id": 2}}),
+        json!({"action": "click", "user": {"id": 3}}),
+        json!({"action": "click", "user": {"id": 4}}),
+    ];
+    for payload in payloads {
+        call_tool(&mut session, &registry, "strata_event_append", json!({"event_type": "ui", "payload": payload}));
+    }
 
     let result = call_tool(
         &mut session,
         &registry,
-        "strata_vector_search",
+        "strata_event_list",
+        json!({"event_type": "ui", "filter": [{"field": "action", "op": "eq", "value": "click"}]}),
+    );
+    let events = result.as_array().expect("Expected array");
+    assert_eq!(events.len(), 3);
+    assert!(events.iter().all(|e| e["value"]["action"] == json!("click")));
+
+    // Nested fields, several conditions, and a limit applied after filtering
+    let result = call_tool(
+        &mut session,
+        &registry,
+        "strata_event_list",
         json!({
-            "collection": "filtered",
-            "query": [1.0, 0.0],
-            "k": 10,
-            "filter": [{"field": "color", "op": "eq", "value": "red"}]
+            "event_type": "ui",
+            "filter": [
+                {"field": "action", "op": "eq", "value": "click"},
+                {"field": "user.id", "op": "gt", "value": 1}
+            ],
+            "limit": 1
         }),
     );
-    let matches = result.as_array().expect("Expected array");
-    assert_eq!(matches.len(), 1);
-    assert_eq!(matches[0].get("key").and_then(|v| v.as_str()), Some("f1"));
+    let events = result.as_array().expect("Expected array");
+    assert_eq!(events.len(), 1);
+    assert_eq!(events[0]["value"]["user"]["id"], json!(3));
 }
 
-// =============================================================================
-// Transaction Tools
-// =============================================================================
+/// Format a microsecond timestamp as an ISO-8601 UTC string.
+fn iso_timestamp(micros: u64) -> String {
+    let secs = (micros / 1_000_000) as i64;
+    let (days, rem) = (secs.div_euclid(86_400), secs.rem_euclid(86_400));
+    // Civil date from days since the epoch (Howard Hinnant's algorithm)
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z - era * 146_097;
+    let yoe = (doe - doe / 1_460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}.{:06}Z",
+        year,
+        month,
+        day,
+        rem / 3_600,
+        rem % 3_600 / 60,
+        rem % 60,
+        micros % 1_000_000
+    )
+}
 
 #[test]
-fn test_transaction_commit() {
+fn test_event_get_by_time() {
     let mut session = test_session();
     let registry = ToolRegistry::new();
 
-    let result = call_tool(&mut session, &registry, "strata_txn_begin", json!({}));
-    assert_eq!(result.get("status").and_then(|v| v.as_str()), Some("begun"));
+    for (event_type, n) in [("tick", 1), ("tock", 2), ("tick", 3)] {
+        call_tool(&mut session, &registry, "strata_event_append", json!({"event_type": event_type, "payload": {"n": n}}));
+        std::thread::sleep(std::time::Duration::from_millis(2));
+    }
+    let ticks = call_tool(&mut session, &registry, "strata_event_list", json!({"event_type": "tick"}));
+    let tocks = call_tool(&mut session, &registry, "strata_event_list", json!({"event_type": "tock"}));
+    let ts = |events: &JsonValue, i: usize| events[i]["timestamp"].as_u64().unwrap();
+    let (first, second, third) = (ts(&ticks, 0), ts(&tocks, 0), ts(&ticks, 1));
 
-    let result = call_tool(&mut session, &registry, "strata_txn_active", json!({}));
-    assert_eq!(result, json!(true));
+    // Window covering the last two events
+    let result = call_tool(
+        &mut session,
+        &registry,
+        "strata_event_get_by_time",
+        json!({"start": iso_timestamp(second), "end": iso_timestamp(third)}),
+    );
+    let events = result.as_array().expect("Expected array");
+    assert_eq!(events.len(), 2);
+    assert_eq!(events[0]["value"]["n"], json!(2));
+    assert_eq!(events[1]["value"]["n"], json!(3));
 
-    call_tool(&mut session, &registry, "strata_kv_put", json!({"key": "txn-key", "value": "txn-value"}));
+    // Typed and limited
+    let result = call_tool(
+        &mut session,
+        &registry,
+        "strata_event_get_by_time",
+        json!({"start": iso_timestamp(first), "end": iso_timestamp(third), "event_type": "tick", "limit": 1}),
+    );
+    let events = result.as_array().expect("Expected array");
+    assert_eq!(events.len(), 1);
+    assert_eq!(events[0]["value"]["n"], json!(1));
 
-    let result = call_tool(&mut session, &registry, "strata_txn_commit", json!({}));
-    assert_eq!(result.get("status").and_then(|v| v.as_str()), Some("committed"));
+    // A window before any events
+    let result = call_tool(
+        &mut session,
+        &registry,
+        "strata_event_get_by_time",
+        json!({"start": "2000-01-01T00:00:00Z", "end": "2000-12-31"}),
+    );
+    assert!(result.as_array().unwrap().is_empty());
 
-    let result = call_tool(&mut session, &registry, "strata_kv_get", json!({"key": "txn-key"}));
-    assert_eq!(extract_value(&result), &json!("txn-value"));
+    let err = call_tool_err(
+        &mut session,
+        &registry,
+        "strata_event_get_by_time",
+        json!({"start": "not a time", "end": "2000-12-31"}),
+    );
+    assert!(err.to_string().contains("ISO-8601"));
 }
 
+// =============================================================================
+// JSON Tools
+// =============================================================================
+
 #[test]
-fn test_transaction_rollback() {
+fn test_json_get_multi_wildcard() {
     let mut session = test_session();
     let registry = ToolRegistry::new();
 
-    call_tool(&mut session, &registry, "strata_kv_put", json!({"key": "rollback-key", "value": "initial"}));
+    call_tool(
+        &mut session,
+        &registry,
+        "strata_json_set",
+        json!({"key": "order", "path": "$", "value": {"items": [
+            {"name": "apple", "qty": 2},
+            {"name": "pear", "qty": 1},
+            {"qty": 5}
+        ]}}),
+    );
 
-    call_tool(&mut session, &registry, "strata_txn_begin", json!({}));
-    call_tool(&mut session, &registry, "strata_kv_put", json!({"key": "rollback-key", "value": "modified"}));
+    let get = |session: &mut McpSession, path: &str| {
+        call_tool(session, &registry, "strata_json_get", json!({"key": "order", "path": path, "multi": true}))
+    };
+    assert_eq!(get(&mut session, "$.items[*].name"), json!(["apple", "pear"]));
+    assert_eq!(get(&mut session, "$.items[*].qty"), json!([2, 1, 5]));
+    assert_eq!(get(&mut session, "$.items[1]['name']"), json!(["pear"]));
+    assert_eq!(get(&mut session, "$.missing[*]"), json!([]));
 
-    let result = call_tool(&mut session, &registry, "strata_txn_rollback", json!({}));
-    assert_eq!(result.get("status").and_then(|v| v.as_str()), Some("aborted"));
+    let result = call_tool(&mut session, &registry, "strata_json_get", json!({"key": "absent", "path": "$.a", "multi": true}));
+    assert_eq!(result, json!([]));
 
-    let result = call_tool(&mut session, &registry, "strata_kv_get", json!({"key": "rollback-key"}));
-    assert_eq!(extract_value(&result), &json!("initial"));
+    let err = call_tool_err(&mut session, &registry, "strata_json_get", json!({"key": "order", "path": "items", "multi": true}));
+    assert!(format!("{}", err).contains("path"));
 }
 
 #[test]
-fn test_transaction_info() {
+fn test_json_exists() {
     let mut session = test_session();
     let registry = ToolRegistry::new();
 
-    // No active transaction
-    let result = call_tool(&mut session, &registry, "strata_txn_info", json!({}));
-    assert_eq!(result, json!(null));
-
-    // Begin transaction
-    call_tool(&mut session, &registry, "strata_txn_begin", json!({}));
-    let result = call_tool(&mut session, &registry, "strata_txn_info", json!({}));
-    assert!(result.get("id").is_some());
-    assert!(result.get("started_at").is_some());
+    call_tool(
+        &mut session,
+        &registry,
+        "strata_json_set",
+        json!({"key": "doc", "path": "$", "value": {"name": "x", "gone": null}}),
+    );
 
-    call_tool(&mut session, &registry, "strata_txn_rollback", json!({}));
+    let exists = |session: &mut McpSession, key: &str, path: &str| {
+        call_tool(session, &registry, "strata_json_exists", json!({"key": key, "path": path}))
+    };
+    assert_eq!(exists(&mut session, "doc", "$.name"), json!(true));
+    assert_eq!(exists(&mut session, "doc", "$.missing"), json!(false));
+    assert_eq!(exists(&mut session, "doc", "$.gone"), json!(true));
+    assert_eq!(exists(&mut session, "nodoc", "$"), json!(false));
 }
 
 #[test]
-fn test_transaction_read_only() {
+fn test_json_path_syntax_validation() {
     let mut session = test_session();
     let registry = ToolRegistry::new();
 
-    call_tool(&mut session, &registry, "strata_txn_begin", json!({"read_only": true}));
-    let result = call_tool(&mut session, &registry, "strata_txn_active", json!({}));
-    assert_eq!(result, json!(true));
+    let err = call_tool_err(
+        &mut session,
+        &registry,
+        "strata_json_set",
+        json!({"key": "doc", "path": "$.items[1:x]", "value": 1}),
+    );
+    match err {
+        strata_mcp::McpError::InvalidArg { name, reason } => {
+            assert_eq!(name, "path");
+            assert!(reason.contains("[1:x]"), "{}", reason);
+        }
+        other => panic!("Expected InvalidArg, got {:?}", other),
+    }
+    // Rejected before reaching the database, so nothing was written
+    let result = call_tool(&mut session, &registry, "strata_json_get", json!({"key": "doc", "path": "$"}));
+    assert_eq!(result, json!(null));
 
-    call_tool(&mut session, &registry, "strata_txn_rollback", json!({}));
+    call_tool(
+        &mut session,
+        &registry,
+        "strata_json_set",
+        json!({"key": "doc", "path": "$", "value": {"items": [1, 2, 3, 4]}}),
+    );
+    // A slice passes validation; whether it evaluates is up to the engine
+    let args = json!({"key": "doc", "path": "$.items[1:3]"});
+    let result = registry.dispatch(&mut session, "strata_json_get", args.as_object().unwrap().clone());
+    assert!(!matches!(result, Err(strata_mcp::McpError::InvalidArg { .. })));
 }
 
-// =============================================================================
-// Bundle Tools
-// =============================================================================
-
 #[test]
-fn test_bundle_export_import() {
+fn test_json_path_count() {
     let mut session = test_session();
     let registry = ToolRegistry::new();
 
-    // Create a separate branch for export to avoid "default already exists" on import
-    call_tool(&mut session, &registry, "strata_branch_create", json!({"branch_id": "export-branch"}));
-    call_tool(&mut session, &registry, "strata_branch_switch", json!({"branch": "export-branch"}));
-    call_tool(&mut session, &registry, "strata_kv_put", json!({"key": "export-key", "value": "export-value"}));
+    let doc = json!({"tags": ["a", "b", "c"], "meta": {"x": 1, "y": 2}, "name": "doc"});
+    call_tool(&mut session, &registry, "strata_json_set", json!({"key": "d", "path": "$", "value": doc}));
 
-    let dir = tempfile::tempdir().expect("Failed to create temp dir");
-    let path = dir.path().join("test.bundle");
-    let path_str = path.to_str().unwrap();
+    let count = |session: &mut McpSession, path: &str| {
+        call_tool(session, &registry, "strata_json_path_count", json!({"key": "d", "path": path}))
+    };
+    assert_eq!(count(&mut session, "$.tags"), json!(3));
+    assert_eq!(count(&mut session, "$.meta"), json!(2));
+    assert_eq!(count(&mut session, "$"), json!(3));
+    assert_eq!(count(&mut session, "$.missing"), json!(null));
+
+    let err = call_tool_err(
+        &mut session,
+        &registry,
+        "strata_json_path_count",
+        json!({"key": "d", "path": "$.name"}),
+    );
+    assert!(err.to_string().contains("not an array or object"));
+}
+
+#[test]
+fn test_json_set_many() {
+    let mut session = test_session();
+    let registry = ToolRegistry::new();
 
-    // Export
     let result = call_tool(
         &mut session,
         &registry,
-        "strata_bundle_export",
-        json!({"branch": "export-branch", "path": path_str}),
+        "strata_json_set_many",
+        json!({"items": [
+            {"key": "user:1", "path": "$", "value": {"name": "Ada"}},
+            {"key": "user:2", "path": "$", "value": {"name": "Grace"}},
+            {"key": "user:1", "path": "$.age", "value": 36}
+        ]}),
     );
-    assert!(result.get("entry_count").is_some());
+    assert_eq!(result.as_array().expect("Expected array of versions").len(), 3);
+
+    let result = call_tool(&mut session, &registry, "strata_json_get", json!({"key": "user:1", "path": "$"}));
+    assert_eq!(extract_value(&result), &json!({"name": "Ada", "age": 36}));
+    let result = call_tool(&mut session, &registry, "strata_json_get", json!({"key": "user:2", "path": "$.name"}));
+    assert_eq!(extract_value(&result), &json!("Grace"));
+}
+
+#[test]
+fn test_json_set_many_rejects_malformed_item() {
+    let mut session = test_session();
+    let registry = ToolRegistry::new();
+
+    let err = call_tool_err(
+        &mut session,
+        &registry,
+        "strata_json_set_many",
+        json!({"items": [{"key": "first", "path": "$", "value": 1}, {"key": "second", "value": 2}]}),
+    );
+    assert!(format!("{}", err).contains("items[1].path"));
+
+    let result = call_tool(&mut session, &registry, "strata_json_get", json!({"key": "first", "path": "$"}));
+    assert_eq!(result, json!(null));
+}
+
+#[test]
+fn test_json_set_many_rolls_back_on_failure() {
+    let mut session = test_session();
+    let registry = ToolRegistry::new();
+
+    call_tool(&mut session, &registry, "strata_json_set", json!({"key": "scalar", "path": "$", "value": 5}));
+
+    // Setting a field inside a number fails after the first write has run
+    call_tool_err(
+        &mut session,
+        &registry,
+        "strata_json_set_many",
+        json!({"items": [
+            {"key": "fresh", "path": "$", "value": {"ok": true}},
+            {"key": "scalar", "path": "$.field", "value": 1}
+        ]}),
+    );
+    assert!(!session.in_transaction());
+
+    let result = call_tool(&mut session, &registry, "strata_json_get", json!({"key": "fresh", "path": "$"}));
+    assert_eq!(result, json!(null));
+}
+
+#[test]
+fn test_json_get_many() {
+    let mut session = test_session();
+    let registry = ToolRegistry::new();
+
+    call_tool(
+        &mut session,
+        &registry,
+        "strata_json_set",
+        json!({"key": "profile", "path": "$", "value": {"name": "Ada", "age": 36, "tags": ["a"]}}),
+    );
+
+    let result = call_tool(
+        &mut session,
+        &registry,
+        "strata_json_get_many",
+        json!({"key": "profile", "paths": ["$.name", "$.age", "$.tags", "$.missing"]}),
+    );
+    assert_eq!(result["$.name"], json!("Ada"));
+    assert_eq!(result["$.age"], json!(36));
+    assert_eq!(result["$.tags"], json!(["a"]));
+    assert_eq!(result["$.missing"], json!(null));
+    assert_eq!(result.as_object().unwrap().len(), 4);
+}
+
+#[test]
+fn test_json_numincr() {
+    let mut session = test_session();
+    let registry = ToolRegistry::new();
+
+    call_tool(
+        &mut session,
+        &registry,
+        "strata_json_set",
+        json!({"key": "stats", "path": "$", "value": {"count": 5, "ratio": 1.5, "name": "x"}}),
+    );
+
+    let result = call_tool(
+        &mut session,
+        &registry,
+        "strata_json_numincr",
+        json!({"key": "stats", "path": "$.count", "by": 3}),
+    );
+    assert_eq!(result, json!(8));
+
+    let result = call_tool(
+        &mut session,
+        &registry,
+        "strata_json_numincr",
+        json!({"key": "stats", "path": "$.count", "by": -10}),
+    );
+    assert_eq!(result, json!(-2));
+
+    let result = call_tool(
+        &mut session,
+        &registry,
+        "strata_json_numincr",
+        json!({"key": "stats", "path": "$.ratio", "by": 0.5}),
+    );
+    assert_eq!(result, json!(2.0));
+
+    let result = call_tool(
+        &mut session,
+        &registry,
+        "strata_json_get",
+        json!({"key": "stats", "path": "$.count", "raw": true}),
+    );
+    assert_eq!(result, json!(-2));
+}
+
+#[test]
+fn test_json_numincr_wrong_type() {
+    let mut session = test_session();
+    let registry = ToolRegistry::new();
+
+    call_tool(
+        &mut session,
+        &registry,
+        "strata_json_set",
+        json!({"key": "doc", "path": "$", "value": {"name": "x"}}),
+    );
+
+    let err = call_tool_err(
+        &mut session,
+        &registry,
+        "strata_json_numincr",
+        json!({"key": "doc", "path": "$.name", "by": 1}),
+    );
+    match err {
+        strata_mcp::McpError::Strata { code, .. } => assert_eq!(code, "WRONG_TYPE"),
+        other => panic!("Expected WRONG_TYPE, got {}", other),
+    }
+
+    let result = call_tool(
+        &mut session,
+        &registry,
+        "strata_json_get",
+        json!({"key": "doc", "path": "$.name", "raw": true}),
+    );
+    assert_eq!(result, json!("x"));
+}
+
+#[test]
+fn test_json_set_get() {
+    let mut session = test_session();
+    let registry = ToolRegistry::new();
+
+    call_tool(
+        &mut session,
+        &registry,
+        "strata_json_set",
+        json!({"key": "config", "path": "$", "value": {"theme": "dark", "lang": "en"}}),
+    );
+
+    let result = call_tool(
+        &mut session,
+        &registry,
+        "strata_json_get",
+        json!({"key": "config", "path": "$"}),
+    );
+    let value = extract_value(&result);
+    assert_eq!(value.get("theme").and_then(|v| v.as_str()), Some("dark"));
+
+    let result = call_tool(
+        &mut session,
+        &registry,
+        "strata_json_get",
+        json!({"key": "config", "path": "$.theme"}),
+    );
+    assert_eq!(extract_value(&result), &json!("dark"));
+}
+
+#[test]
+fn test_json_delete() {
+    let mut session = test_session();
+    let registry = ToolRegistry::new();
+
+    call_tool(&mut session, &registry, "strata_json_set", json!({"key": "temp", "path": "$", "value": 42}));
+    let result = call_tool(&mut session, &registry, "strata_json_delete", json!({"key": "temp", "path": "$"}));
+    assert!(result.is_number());
+}
+
+#[test]
+fn test_json_list() {
+    let mut session = test_session();
+    let registry = ToolRegistry::new();
+
+    call_tool(&mut session, &registry, "strata_json_set", json!({"key": "doc:a", "path": "$", "value": 1}));
+    call_tool(&mut session, &registry, "strata_json_set", json!({"key": "doc:b", "path": "$", "value": 2}));
+
+    let result = call_tool(&mut session, &registry, "strata_json_list", json!({"prefix": "doc:"}));
+    let keys = result.get("keys").and_then(|v| v.as_array()).expect("Expected keys array");
+    assert_eq!(keys.len(), 2);
+}
+
+#[test]
+fn test_json_list_case_insensitive() {
+    let mut session = test_session();
+    let registry = ToolRegistry::new();
+
+    for key in ["Doc:a", "DOC:b", "doc:c", "note"] {
+        call_tool(&mut session, &registry, "strata_json_set", json!({"key": key, "path": "$", "value": 1}));
+    }
+
+    let result = call_tool(
+        &mut session,
+        &registry,
+        "strata_json_list",
+        json!({"prefix": "doc:", "case_insensitive": true}),
+    );
+    assert_eq!(sorted_strings(&result["keys"]), vec!["DOC:b", "Doc:a", "doc:c"]);
+    assert!(result.get("cursor").is_none());
+
+    // A filtered page returns a cursor while matches remain
+    let first = call_tool(
+        &mut session,
+        &registry,
+        "strata_json_list",
+        json!({"prefix": "doc:", "case_insensitive": true, "limit": 2}),
+    );
+    assert_eq!(first["keys"].as_array().unwrap().len(), 2);
+    let cursor = first["cursor"].as_str().expect("Expected cursor").to_string();
+    let second = call_tool(
+        &mut session,
+        &registry,
+        "strata_json_list",
+        json!({"prefix": "doc:", "case_insensitive": true, "limit": 2, "cursor": cursor}),
+    );
+    assert_eq!(second["keys"].as_array().unwrap().len(), 1);
+    assert!(second.get("cursor").is_none());
+}
+
+#[test]
+fn test_json_history() {
+    let mut session = test_session();
+    let registry = ToolRegistry::new();
+
+    call_tool(&mut session, &registry, "strata_json_set", json!({"key": "versioned", "path": "$", "value": "v1"}));
+    call_tool(&mut session, &registry, "strata_json_set", json!({"key": "versioned", "path": "$", "value": "v2"}));
+
+    let result = call_tool(&mut session, &registry, "strata_json_history", json!({"key": "versioned"}));
+    let history = result.as_array().expect("Expected array");
+    assert!(history.len() >= 2);
+}
+
+#[test]
+fn test_json_history_limit() {
+    let mut session = test_session();
+    let registry = ToolRegistry::new();
+
+    for value in ["v1", "v2", "v3"] {
+        call_tool(&mut session, &registry, "strata_json_set", json!({"key": "doc", "path": "$", "value": value}));
+    }
+
+    let result = call_tool(&mut session, &registry, "strata_json_history", json!({"key": "doc", "limit": 2}));
+    let history = result.as_array().expect("Expected array");
+    assert_eq!(history.len(), 2);
+    assert_eq!(history[0]["value"], json!("v3"));
+    assert_eq!(history[1]["value"], json!("v2"));
+}
+
+// =============================================================================
+// Branch Tools
+// =============================================================================
+
+#[test]
+fn test_branch_create_list() {
+    let mut session = test_session();
+    let registry = ToolRegistry::new();
+
+    let result = call_tool(
+        &mut session,
+        &registry,
+        "strata_branch_create",
+        json!({"branch_id": "test-branch"}),
+    );
+    assert_eq!(result.get("id").and_then(|v| v.as_str()), Some("test-branch"));
+
+    let result = call_tool(&mut session, &registry, "strata_branch_list", json!({}));
+    let branches = result.as_array().expect("Expected array");
+    assert!(branches.len() >= 2);
+
+    let result = call_tool(
+        &mut session,
+        &registry,
+        "strata_branch_exists",
+        json!({"branch": "test-branch"}),
+    );
+    assert_eq!(result, json!(true));
+}
+
+#[test]
+fn test_branch_create_with_metadata() {
+    let mut session = test_session();
+    let registry = ToolRegistry::new();
+
+    let result = call_tool(
+        &mut session,
+        &registry,
+        "strata_branch_create",
+        json!({"branch_id": "meta-branch", "metadata": {"purpose": "experiment"}}),
+    );
+    assert_eq!(result.get("id").and_then(|v| v.as_str()), Some("meta-branch"));
+}
+
+#[test]
+fn test_branch_create_if_not_exists() {
+    let mut session = test_session();
+    let registry = ToolRegistry::new();
+
+    let args = json!({"branch_id": "setup", "if_not_exists": true});
+    let result = call_tool(&mut session, &registry, "strata_branch_create", args.clone());
+    assert_eq!(result["id"], json!("setup"));
+    assert_eq!(result["created"], json!(true));
+
+    let result = call_tool(&mut session, &registry, "strata_branch_create", args);
+    assert_eq!(result["id"], json!("setup"));
+    assert_eq!(result["created"], json!(false));
+
+    // Without the flag a repeated create still fails
+    let err = call_tool_err(&mut session, &registry, "strata_branch_create", json!({"branch_id": "setup"}));
+    match err {
+        strata_mcp::McpError::Strata { code, .. } => assert_eq!(code, "BRANCH_EXISTS"),
+        other => panic!("Expected BRANCH_EXISTS, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_branch_switch() {
+    let mut session = test_session();
+    let registry = ToolRegistry::new();
+
+    call_tool(&mut session, &registry, "strata_branch_create", json!({"branch_id": "feature"}));
+    call_tool(&mut session, &registry, "strata_kv_put", json!({"key": "x", "value": 1}));
+
+    call_tool(&mut session, &registry, "strata_branch_switch", json!({"branch": "feature"}));
+    let result = call_tool(&mut session, &registry, "strata_kv_get", json!({"key": "x"}));
+    assert_eq!(result, json!(null));
+
+    call_tool(&mut session, &registry, "strata_kv_put", json!({"key": "x", "value": 2}));
+    call_tool(&mut session, &registry, "strata_branch_switch", json!({"branch": "default"}));
+
+    let result = call_tool(&mut session, &registry, "strata_kv_get", json!({"key": "x"}));
+    assert_eq!(extract_value(&result), &json!(1));
+}
+
+#[test]
+fn test_branch_names_are_trimmed() {
+    let mut session = test_session();
+    let registry = ToolRegistry::new();
+
+    call_tool(&mut session, &registry, "strata_branch_create", json!({"branch_id": " feature "}));
+    let result = call_tool(
+        &mut session,
+        &registry,
+        "strata_branch_exists",
+        json!({"branch": "feature"}),
+    );
+    assert_eq!(result, json!(true));
+
+    call_tool(&mut session, &registry, "strata_branch_switch", json!({"branch": "\tfeature  "}));
+    assert_eq!(session.branch(), "feature");
+
+    let err =
+        call_tool_err(&mut session, &registry, "strata_branch_switch", json!({"branch": "  "}));
+    assert!(matches!(err, strata_mcp::McpError::InvalidArg { ref name, .. } if name == "branch"));
+}
+
+#[test]
+fn test_fold_name_case() {
+    let mut session = test_session();
+    session.set_fold_name_case(true);
+    let registry = ToolRegistry::new();
+
+    call_tool(&mut session, &registry, "strata_branch_create", json!({"branch_id": "Feature"}));
+    call_tool(&mut session, &registry, "strata_branch_switch", json!({"branch": " FEATURE"}));
+    assert_eq!(session.branch(), "feature");
+
+    call_tool(&mut session, &registry, "strata_space_switch", json!({"space": "Docs "}));
+    assert_eq!(session.space(), "docs");
+}
+
+#[test]
+fn test_branch_fork() {
+    let mut session = test_session();
+    let registry = ToolRegistry::new();
+
+    call_tool(&mut session, &registry, "strata_kv_put", json!({"key": "shared", "value": "original"}));
+
+    let result = call_tool(&mut session, &registry, "strata_branch_fork", json!({"destination": "forked"}));
+    assert!(result.get("keys_copied").is_some());
+
+    call_tool(&mut session, &registry, "strata_branch_switch", json!({"branch": "forked"}));
+    let result = call_tool(&mut session, &registry, "strata_kv_get", json!({"key": "shared"}));
+    assert_eq!(extract_value(&result), &json!("original"));
+}
+
+#[test]
+fn test_branch_get() {
+    let mut session = test_session();
+    let registry = ToolRegistry::new();
+
+    let result = call_tool(&mut session, &registry, "strata_branch_get", json!({"branch": "default"}));
+    assert_eq!(result.get("id").and_then(|v| v.as_str()), Some("default"));
+    assert!(result.get("status").is_some());
+    assert!(result.get("version").is_some());
+}
+
+#[test]
+fn test_branch_delete() {
+    let mut session = test_session();
+    let registry = ToolRegistry::new();
+
+    call_tool(&mut session, &registry, "strata_branch_create", json!({"branch_id": "to-delete"}));
+    call_tool(&mut session, &registry, "strata_branch_delete", json!({"branch": "to-delete"}));
+
+    let result = call_tool(&mut session, &registry, "strata_branch_exists", json!({"branch": "to-delete"}));
+    assert_eq!(result, json!(false));
+}
+
+#[test]
+fn test_branch_delete_with_active_transaction() {
+    let mut session = test_session();
+    let registry = ToolRegistry::new();
+
+    call_tool(&mut session, &registry, "strata_branch_create", json!({"branch_id": "busy"}));
+    call_tool(&mut session, &registry, "strata_branch_switch", json!({"branch": "busy"}));
+    call_tool(&mut session, &registry, "strata_txn_begin", json!({}));
+    call_tool(&mut session, &registry, "strata_kv_put", json!({"key": "k", "value": 1}));
+
+    let err = call_tool_err(&mut session, &registry, "strata_branch_delete", json!({"branch": "busy"}));
+    assert!(matches!(err, strata_mcp::McpError::InvalidArg { ref reason, .. } if reason.contains("force=true")));
+    assert!(session.in_transaction());
+    let result = call_tool(&mut session, &registry, "strata_branch_exists", json!({"branch": "busy"}));
+    assert_eq!(result, json!(true));
+
+    call_tool(&mut session, &registry, "strata_branch_delete", json!({"branch": "busy", "force": true}));
+    assert!(!session.in_transaction());
+    assert_eq!(session.branch(), "default");
+    let result = call_tool(&mut session, &registry, "strata_branch_exists", json!({"branch": "busy"}));
+    assert_eq!(result, json!(false));
+}
+
+#[test]
+fn test_branch_diff() {
+    let mut session = test_session();
+    let registry = ToolRegistry::new();
+
+    call_tool(&mut session, &registry, "strata_kv_put", json!({"key": "a", "value": 1}));
+    call_tool(&mut session, &registry, "strata_branch_create", json!({"branch_id": "diff-target"}));
+
+    let result = call_tool(
+        &mut session,
+        &registry,
+        "strata_branch_diff",
+        json!({"branch_a": "default", "branch_b": "diff-target"}),
+    );
+    assert!(result.get("summary").is_some());
+}
+
+#[test]
+fn test_branch_diff_compact_values() {
+    let mut session = test_session();
+    let registry = ToolRegistry::new();
+
+    call_tool(&mut session, &registry, "strata_kv_put", json!({"key": "big", "value": "x".repeat(1000)}));
+    call_tool(&mut session, &registry, "strata_branch_fork", json!({"destination": "compact"}));
+    call_tool(&mut session, &registry, "strata_branch_switch", json!({"branch": "compact"}));
+    call_tool(&mut session, &registry, "strata_kv_put", json!({"key": "big", "value": "y".repeat(1000)}));
+    call_tool(&mut session, &registry, "strata_kv_put", json!({"key": "new", "value": 1}));
+    call_tool(&mut session, &registry, "strata_branch_switch", json!({"branch": "default"}));
+
+    let entries = |result: &JsonValue| -> Vec<JsonValue> {
+        result["spaces"]
+            .as_array()
+            .expect("Expected spaces")
+            .iter()
+            .flat_map(|s| ["added", "removed", "modified"].map(|k| s[k].as_array().cloned().unwrap_or_default()))
+            .flatten()
+            .collect()
+    };
+
+    let full = call_tool(&mut session, &registry, "strata_branch_diff", json!({"branch_a": "default", "branch_b": "compact"}));
+    assert!(!entries(&full).is_empty());
+
+    let compact = call_tool(&mut session, &registry, "strata_branch_diff", json!({"branch_a": "default", "branch_b": "compact", "values": false}));
+    let compact_entries = entries(&compact);
+    assert_eq!(compact_entries.len(), entries(&full).len());
+    for entry in &compact_entries {
+        assert!(entry.get("key").is_some() && entry.get("primitive").is_some());
+        assert!(entry.get("value_a").is_none() && entry.get("value_b").is_none(), "inline value in {}", entry);
+    }
+
+    let hashed = call_tool(&mut session, &registry, "strata_branch_diff", json!({"branch_a": "default", "branch_b": "compact", "hash": true}));
+    for entry in entries(&hashed) {
+        assert!(entry.get("value_a").is_none() && entry.get("value_b").is_none(), "inline value in {}", entry);
+        if entry["key"] == json!("big") {
+            let (a, b) = (entry["hash_a"].as_str().unwrap(), entry["hash_b"].as_str().unwrap());
+            assert_eq!(a.len(), 16);
+            assert_ne!(a, b);
+        }
+    }
+
+    let err = call_tool_err(&mut session, &registry, "strata_branch_diff", json!({"branch_a": "default", "branch_b": "compact", "values": "full"}));
+    assert!(matches!(err, strata_mcp::McpError::InvalidArg { ref name, .. } if name == "values"));
+    let err = call_tool_err(&mut session, &registry, "strata_branch_diff", json!({"branch_a": "default", "branch_b": "compact", "value": false}));
+    assert!(matches!(err, strata_mcp::McpError::InvalidArg { ref name, .. } if name == "value"));
+    let err = call_tool_err(&mut session, &registry, "strata_branch_diff", json!({"branch_a": "default", "branch_b": "compact", "values": true, "hash": true}));
+    assert!(matches!(err, strata_mcp::McpError::InvalidArg { ref name, .. } if name == "hash"));
+}
+
+#[test]
+fn test_diff_values() {
+    let mut session = test_session();
+    let registry = ToolRegistry::new();
+
+    call_tool(&mut session, &registry, "strata_kv_put", json!({"key": "same", "value": 1}));
+    call_tool(&mut session, &registry, "strata_kv_put", json!({"key": "changed", "value": "old"}));
+    call_tool(&mut session, &registry, "strata_branch_fork", json!({"destination": "feature"}));
+    call_tool(&mut session, &registry, "strata_branch_switch", json!({"branch": "feature"}));
+    call_tool(&mut session, &registry, "strata_kv_put", json!({"key": "changed", "value": "new"}));
+    call_tool(&mut session, &registry, "strata_kv_put", json!({"key": "added", "value": true}));
+    call_tool(&mut session, &registry, "strata_branch_switch", json!({"branch": "default"}));
+
+    let diff = |session: &mut McpSession, key: &str| {
+        call_tool(
+            session,
+            &registry,
+            "strata_diff_values",
+            json!({"key": key, "primitive": "kv", "branch_a": "default", "branch_b": "feature"}),
+        )
+    };
+
+    let result = diff(&mut session, "same");
+    assert_eq!(result, json!({"value_a": 1, "value_b": 1, "equal": true}));
+
+    let result = diff(&mut session, "changed");
+    assert_eq!(result, json!({"value_a": "old", "value_b": "new", "equal": false}));
+
+    // Present only in branch_b
+    let result = diff(&mut session, "added");
+    assert_eq!(result, json!({"value_a": null, "value_b": true, "equal": false}));
+
+    // Reading did not switch branches
+    assert_eq!(session.branch(), "default");
+}
+
+#[test]
+fn test_branch_merge() {
+    let mut session = test_session();
+    let registry = ToolRegistry::new();
+
+    call_tool(&mut session, &registry, "strata_branch_create", json!({"branch_id": "merge-src"}));
+    call_tool(&mut session, &registry, "strata_branch_switch", json!({"branch": "merge-src"}));
+    call_tool(&mut session, &registry, "strata_kv_put", json!({"key": "merged", "value": "from-src"}));
+    call_tool(&mut session, &registry, "strata_branch_switch", json!({"branch": "default"}));
+
+    let result = call_tool(
+        &mut session,
+        &registry,
+        "strata_branch_merge",
+        json!({"source": "merge-src"}),
+    );
+    assert!(result.get("keys_applied").is_some());
+}
+
+fn branch_ids(result: &JsonValue) -> Vec<&str> {
+    result
+        .as_array()
+        .expect("Expected array")
+        .iter()
+        .filter_map(|b| b["id"].as_str())
+        .collect()
+}
+
+#[test]
+fn test_branch_list_state_filter_and_cursor() {
+    let mut session = test_session();
+    let registry = ToolRegistry::new();
+
+    for id in ["a", "b", "c"] {
+        call_tool(&mut session, &registry, "strata_branch_create", json!({"branch_id": id}));
+    }
+
+    // Filter by the status new branches report, in any case
+    let info = call_tool(&mut session, &registry, "strata_branch_get", json!({"branch": "a"}));
+    let status = info["status"].as_str().expect("Expected status").to_uppercase();
+    let result = call_tool(&mut session, &registry, "strata_branch_list", json!({"state": status}));
+    assert_eq!(branch_ids(&result), vec!["a", "b", "c", "default"]);
+    let result = call_tool(&mut session, &registry, "strata_branch_list", json!({"state": "no-such-state"}));
+    assert_eq!(branch_ids(&result), Vec::<&str>::new());
+    let result = call_tool(&mut session, &registry, "strata_branch_list", json!({"state": status, "offset": 3}));
+    assert_eq!(result.as_array().map(|a| a.len()), Some(1));
+
+    let result = call_tool(&mut session, &registry, "strata_branch_list", json!({"limit": 2}));
+    assert_eq!(branch_ids(&result), vec!["a", "b"]);
+    let result = call_tool(&mut session, &registry, "strata_branch_list", json!({"limit": 2, "cursor": "b"}));
+    assert_eq!(branch_ids(&result), vec!["c", "default"]);
+    let result = call_tool(&mut session, &registry, "strata_branch_list", json!({"limit": 2, "cursor": "default"}));
+    assert_eq!(branch_ids(&result), Vec::<&str>::new());
+
+    call_tool_err(&mut session, &registry, "strata_branch_list", json!({"offset": 1, "cursor": "a"}));
+}
+
+/// Fork `default` into `feature`, then change `k1` and `k2` on both branches.
+fn conflicting_branches(session: &mut McpSession, registry: &ToolRegistry) {
+    call_tool(session, registry, "strata_kv_put", json!({"key": "k1", "value": "base"}));
+    call_tool(session, registry, "strata_kv_put", json!({"key": "k2", "value": "base"}));
+    call_tool(session, registry, "strata_branch_fork", json!({"destination": "feature"}));
+
+    call_tool(session, registry, "strata_branch_switch", json!({"branch": "feature"}));
+    call_tool(session, registry, "strata_kv_put", json!({"key": "k1", "value": "src1"}));
+    call_tool(session, registry, "strata_kv_put", json!({"key": "k2", "value": "src2"}));
+
+    call_tool(session, registry, "strata_branch_switch", json!({"branch": "default"}));
+    call_tool(session, registry, "strata_kv_put", json!({"key": "k1", "value": "tgt1"}));
+    call_tool(session, registry, "strata_kv_put", json!({"key": "k2", "value": "tgt2"}));
+}
+
+#[test]
+fn test_branch_merge_resolutions() {
+    let mut session = test_session();
+    let registry = ToolRegistry::new();
+    conflicting_branches(&mut session, &registry);
+
+    let result = call_tool(
+        &mut session,
+        &registry,
+        "strata_branch_merge",
+        json!({
+            "source": "feature",
+            "strategy": "strict",
+            "resolutions": {"k1": "source", "k2": "target"}
+        }),
+    );
+    assert!(result.get("keys_applied").is_some());
+
+    let result = call_tool(&mut session, &registry, "strata_kv_get", json!({"key": "k1"}));
+    assert_eq!(extract_value(&result), &json!("src1"));
+    let result = call_tool(&mut session, &registry, "strata_kv_get", json!({"key": "k2"}));
+    assert_eq!(extract_value(&result), &json!("tgt2"));
+}
+
+#[test]
+fn test_branch_merge_strict_unresolved_conflict() {
+    let mut session = test_session();
+    let registry = ToolRegistry::new();
+    conflicting_branches(&mut session, &registry);
+
+    let err = call_tool_err(
+        &mut session,
+        &registry,
+        "strata_branch_merge",
+        json!({"source": "feature", "strategy": "strict", "resolutions": {"k1": "source"}}),
+    );
+    assert!(format!("{}", err).contains("k2"));
+    assert_eq!(err.rpc_code(), strata_mcp::rpc_codes::CONFLICT);
+
+    // Nothing was merged
+    let result = call_tool(&mut session, &registry, "strata_kv_get", json!({"key": "k1"}));
+    assert_eq!(extract_value(&result), &json!("tgt1"));
+
+    let result = call_tool(
+        &mut session,
+        &registry,
+        "strata_branch_merge",
+        json!({"source": "feature", "resolutions": {"k2": {"value": "manual"}}}),
+    );
+    assert!(result.get("conflicts").is_some());
+    let result = call_tool(&mut session, &registry, "strata_kv_get", json!({"key": "k2"}));
+    assert_eq!(extract_value(&result), &json!("manual"));
+}
+
+#[test]
+fn test_branch_merge_rejects_ambiguous_resolution() {
+    let mut session = test_session();
+    let registry = ToolRegistry::new();
+
+    // "dup" conflicts both as a KV key and as a state cell
+    call_tool(&mut session, &registry, "strata_kv_put", json!({"key": "dup", "value": "base"}));
+    call_tool(&mut session, &registry, "strata_state_set", json!({"cell": "dup", "value": "base"}));
+    call_tool(&mut session, &registry, "strata_branch_fork", json!({"destination": "feature"}));
+    call_tool(&mut session, &registry, "strata_branch_switch", json!({"branch": "feature"}));
+    call_tool(&mut session, &registry, "strata_kv_put", json!({"key": "dup", "value": "src"}));
+    call_tool(&mut session, &registry, "strata_state_set", json!({"cell": "dup", "value": "src"}));
+    call_tool(&mut session, &registry, "strata_branch_switch", json!({"branch": "default"}));
+    call_tool(&mut session, &registry, "strata_kv_put", json!({"key": "dup", "value": "tgt"}));
+    call_tool(&mut session, &registry, "strata_state_set", json!({"cell": "dup", "value": "tgt"}));
+
+    let err = call_tool_err(
+        &mut session,
+        &registry,
+        "strata_branch_merge",
+        json!({"source": "feature", "resolutions": {"dup": "target"}}),
+    );
+    assert!(format!("{}", err).contains("more than one"));
+
+    // Nothing was merged
+    let result = call_tool(&mut session, &registry, "strata_kv_get", json!({"key": "dup"}));
+    assert_eq!(extract_value(&result), &json!("tgt"));
+}
+
+// =============================================================================
+// Space Tools
+// =============================================================================
+
+#[test]
+fn test_space_operations() {
+    let mut session = test_session();
+    let registry = ToolRegistry::new();
+
+    call_tool(&mut session, &registry, "strata_space_create", json!({"space": "my-space"}));
+
+    let result = call_tool(&mut session, &registry, "strata_space_list", json!({}));
+    let spaces = result.as_array().expect("Expected array");
+    assert!(spaces.iter().any(|s| s.as_str() == Some("my-space")));
+
+    call_tool(&mut session, &registry, "strata_space_switch", json!({"space": "my-space"}));
+    call_tool(&mut session, &registry, "strata_kv_put", json!({"key": "space-key", "value": "space-value"}));
+
+    call_tool(&mut session, &registry, "strata_space_switch", json!({"space": "default"}));
+    let result = call_tool(&mut session, &registry, "strata_kv_get", json!({"key": "space-key"}));
+    assert_eq!(result, json!(null));
+}
+
+#[test]
+fn test_result_cache() {
+    let mut session = test_session();
+    session.enable_result_cache(std::time::Duration::from_secs(60), 16);
+    let registry = ToolRegistry::new();
+
+    let before = call_tool(&mut session, &registry, "strata_space_list", json!({}));
+
+    // A space created behind the registry's back is hidden by the cached list
+    session
+        .execute(stratadb::Command::SpaceCreate { branch: None, space: "hidden".to_string() })
+        .expect("SpaceCreate failed");
+    let cached = call_tool(&mut session, &registry, "strata_space_list", json!({}));
+    assert_eq!(cached, before);
+
+    // Any write tool clears the cache
+    call_tool(&mut session, &registry, "strata_kv_put", json!({"key": "k", "value": 1}));
+    let fresh = call_tool(&mut session, &registry, "strata_space_list", json!({}));
+    let spaces = fresh.as_array().expect("Expected array");
+    assert!(spaces.iter().any(|s| s.as_str() == Some("hidden")), "{}", fresh);
+}
+
+#[test]
+fn test_space_create_if_not_exists() {
+    let mut session = test_session();
+    let registry = ToolRegistry::new();
+
+    let result = call_tool(&mut session, &registry, "strata_space_create", json!({"space": "setup", "if_not_exists": true}));
+    assert_eq!(result, json!({"space": "setup", "created": true}));
+    let result = call_tool(&mut session, &registry, "strata_space_exists", json!({"space": "setup"}));
+    assert_eq!(result, json!(true));
+
+    let result = call_tool(&mut session, &registry, "strata_space_create", json!({"space": "setup", "if_not_exists": true}));
+    assert_eq!(result, json!({"space": "setup", "created": false}));
+
+    let result = call_tool(&mut session, &registry, "strata_space_create", json!({"space": "default", "if_not_exists": true}));
+    assert_eq!(result["created"], json!(false));
+}
+
+#[test]
+fn test_space_exists() {
+    let mut session = test_session();
+    let registry = ToolRegistry::new();
+
+    let result = call_tool(&mut session, &registry, "strata_space_exists", json!({"space": "default"}));
+    assert_eq!(result, json!(true));
+
+    let result = call_tool(&mut session, &registry, "strata_space_exists", json!({"space": "nonexistent"}));
+    assert_eq!(result, json!(false));
+}
+
+#[test]
+fn test_space_delete() {
+    let mut session = test_session();
+    let registry = ToolRegistry::new();
+
+    call_tool(&mut session, &registry, "strata_space_create", json!({"space": "to-remove"}));
+    call_tool(&mut session, &registry, "strata_space_delete", json!({"space": "to-remove", "force": true}));
+
+    let result = call_tool(&mut session, &registry, "strata_space_exists", json!({"space": "to-remove"}));
+    assert_eq!(result, json!(false));
+}
+
+#[test]
+fn test_space_copy() {
+    let mut session = test_session();
+    let registry = ToolRegistry::new();
+
+    call_tool(&mut session, &registry, "strata_space_create", json!({"space": "config"}));
+    call_tool(&mut session, &registry, "strata_space_switch", json!({"space": "config"}));
+    call_tool(&mut session, &registry, "strata_kv_put", json!({"key": "mode", "value": "prod"}));
+    call_tool(
+        &mut session,
+        &registry,
+        "strata_json_set",
+        json!({"key": "settings", "path": "$", "value": {"retries": 3}}),
+    );
+
+    let result = call_tool(
+        &mut session,
+        &registry,
+        "strata_space_copy",
+        json!({"from": "config", "to": "sandbox"}),
+    );
+    assert_eq!(result["kv"], json!(1));
+    assert_eq!(result["json"], json!(1));
+
+    // Changes in the copy leave the source untouched
+    call_tool(&mut session, &registry, "strata_space_switch", json!({"space": "sandbox"}));
+    let result = call_tool(&mut session, &registry, "strata_kv_get", json!({"key": "mode"}));
+    assert_eq!(extract_value(&result), &json!("prod"));
+    let result = call_tool(
+        &mut session,
+        &registry,
+        "strata_json_get",
+        json!({"key": "settings", "path": "$.retries", "raw": true}),
+    );
+    assert_eq!(result, json!(3));
+    call_tool(&mut session, &registry, "strata_kv_put", json!({"key": "mode", "value": "test"}));
+
+    call_tool(&mut session, &registry, "strata_space_switch", json!({"space": "config"}));
+    let result = call_tool(&mut session, &registry, "strata_kv_get", json!({"key": "mode"}));
+    assert_eq!(extract_value(&result), &json!("prod"));
+}
+
+#[test]
+fn test_space_clear() {
+    let mut session = test_session();
+    let registry = ToolRegistry::new();
+
+    call_tool(&mut session, &registry, "strata_space_create", json!({"space": "scratch"}));
+    call_tool(&mut session, &registry, "strata_space_switch", json!({"space": "scratch"}));
+    call_tool(&mut session, &registry, "strata_kv_put", json!({"key": "k1", "value": 1}));
+    call_tool(&mut session, &registry, "strata_kv_put", json!({"key": "k2", "value": 2}));
+    call_tool(&mut session, &registry, "strata_state_set", json!({"cell": "c", "value": 1}));
+    call_tool(
+        &mut session,
+        &registry,
+        "strata_json_set",
+        json!({"key": "doc", "path": "$", "value": {"a": 1}}),
+    );
+
+    // Only KV first
+    let result = call_tool(
+        &mut session,
+        &registry,
+        "strata_space_clear",
+        json!({"space": "scratch", "primitives": ["kv"]}),
+    );
+    assert_eq!(result["removed"], json!({"kv": 2}));
+    let result = call_tool(&mut session, &registry, "strata_state_get", json!({"cell": "c"}));
+    assert_eq!(extract_value(&result), &json!(1));
+
+    let result = call_tool(&mut session, &registry, "strata_space_clear", json!({"space": "scratch"}));
+    assert_eq!(result["removed"]["state"], json!(1));
+    assert_eq!(result["removed"]["json"], json!(1));
+
+    let result = call_tool(&mut session, &registry, "strata_space_exists", json!({"space": "scratch"}));
+    assert_eq!(result, json!(true));
+    let result = call_tool(&mut session, &registry, "strata_kv_list", json!({}));
+    assert_eq!(result, json!([]));
+    let result = call_tool(&mut session, &registry, "strata_state_list", json!({}));
+    assert_eq!(result, json!([]));
+}
+
+#[test]
+fn test_space_clear_default_requires_confirm() {
+    let mut session = test_session();
+    let registry = ToolRegistry::new();
+
+    call_tool(&mut session, &registry, "strata_kv_put", json!({"key": "k", "value": 1}));
+
+    let err = call_tool_err(&mut session, &registry, "strata_space_clear", json!({"space": "default"}));
+    assert!(format!("{}", err).contains("confirm"));
+    let result = call_tool(&mut session, &registry, "strata_kv_get", json!({"key": "k"}));
+    assert_eq!(extract_value(&result), &json!(1));
+
+    let err = call_tool_err(
+        &mut session,
+        &registry,
+        "strata_space_clear",
+        json!({"space": "default", "primitives": ["event"], "confirm": true}),
+    );
+    assert!(format!("{}", err).contains("primitives"));
+
+    call_tool(
+        &mut session,
+        &registry,
+        "strata_space_clear",
+        json!({"space": "default", "confirm": true}),
+    );
+    let result = call_tool(&mut session, &registry, "strata_kv_get", json!({"key": "k"}));
+    assert_eq!(result, json!(null));
+}
+
+#[test]
+fn test_space_copy_requires_overwrite() {
+    let mut session = test_session();
+    let registry = ToolRegistry::new();
+
+    call_tool(&mut session, &registry, "strata_space_create", json!({"space": "a"}));
+    call_tool(&mut session, &registry, "strata_space_create", json!({"space": "b"}));
+
+    let err = call_tool_err(&mut session, &registry, "strata_space_copy", json!({"from": "a", "to": "b"}));
+    assert!(format!("{}", err).contains("overwrite"));
+
+    let result = call_tool(
+        &mut session,
+        &registry,
+        "strata_space_copy",
+        json!({"from": "a", "to": "b", "overwrite": true}),
+    );
+    assert_eq!(result["to"], json!("b"));
+}
+
+#[test]
+fn test_space_copy_failed_overwrite_keeps_target() {
+    let mut session = test_session();
+    let registry = ToolRegistry::new();
+
+    call_tool(&mut session, &registry, "strata_space_create", json!({"space": "a"}));
+    call_tool(&mut session, &registry, "strata_space_create", json!({"space": "b"}));
+    call_tool(&mut session, &registry, "strata_space_switch", json!({"space": "a"}));
+    call_tool(&mut session, &registry, "strata_kv_put", json!({"key": "new", "value": 1}));
+    call_tool(&mut session, &registry, "strata_space_switch", json!({"space": "b"}));
+    call_tool(&mut session, &registry, "strata_kv_put", json!({"key": "old", "value": 2}));
+
+    // The copy runs in the batch transaction, which the failing call rolls back
+    call_tool_err(
+        &mut session,
+        &registry,
+        "strata_batch",
+        json!({"atomic": true, "operations": [
+            {"tool": "strata_space_copy", "arguments": {"from": "a", "to": "b", "overwrite": true}},
+            {"tool": "strata_kv_put", "arguments": {}}
+        ]}),
+    );
+
+    let result = call_tool(&mut session, &registry, "strata_kv_get", json!({"key": "old"}));
+    assert_eq!(extract_value(&result), &json!(2));
+    let result = call_tool(&mut session, &registry, "strata_kv_get", json!({"key": "new"}));
+    assert_eq!(result, json!(null));
+}
+
+// =============================================================================
+// Vector Tools
+// =============================================================================
+
+#[test]
+fn test_vector_create_collection_if_not_exists() {
+    let mut session = test_session();
+    let registry = ToolRegistry::new();
+
+    let args = json!({"collection": "idem", "dimension": 3, "metric": "euclidean", "if_not_exists": true});
+    let result = call_tool(&mut session, &registry, "strata_vector_create_collection", args.clone());
+    assert_eq!(result, json!({"collection": "idem", "created": true}));
+
+    let result = call_tool(&mut session, &registry, "strata_vector_create_collection", args);
+    assert_eq!(result, json!({"collection": "idem", "created": false}));
+
+    // Without the flag an existing collection is still an error
+    let err = call_tool_err(&mut session, &registry, "strata_vector_create_collection", json!({"collection": "idem", "dimension": 3, "metric": "euclidean"}));
+    assert!(matches!(err, strata_mcp::McpError::Strata { ref code, .. } if code == "COLLECTION_EXISTS"));
+
+    let err = call_tool_err(&mut session, &registry, "strata_vector_create_collection", json!({"collection": "idem", "dimension": 4, "metric": "euclidean", "if_not_exists": true}));
+    assert!(matches!(err, strata_mcp::McpError::Strata { ref code, .. } if code == "COLLECTION_EXISTS"));
+    assert!(err.to_string().contains("dimension 3"));
+
+    let err = call_tool_err(&mut session, &registry, "strata_vector_create_collection", json!({"collection": "idem", "dimension": 3, "metric": "cosine", "if_not_exists": true}));
+    assert!(err.to_string().contains("metric euclidean"));
+}
+
+#[test]
+fn test_vector_operations() {
+    let mut session = test_session();
+    let registry = ToolRegistry::new();
+
+    call_tool(
+        &mut session,
+        &registry,
+        "strata_vector_create_collection",
+        json!({"collection": "embeddings", "dimension": 4, "metric": "cosine"}),
+    );
+
+    call_tool(
+        &mut session,
+        &registry,
+        "strata_vector_upsert",
+        json!({"collection": "embeddings", "key": "v1", "vector": [1.0, 0.0, 0.0, 0.0]}),
+    );
+    call_tool(
+        &mut session,
+        &registry,
+        "strata_vector_upsert",
+        json!({"collection": "embeddings", "key": "v2", "vector": [0.0, 1.0, 0.0, 0.0]}),
+    );
+
+    let result = call_tool(
+        &mut session,
+        &registry,
+        "strata_vector_search",
+        json!({"collection": "embeddings", "query": [1.0, 0.0, 0.0, 0.0], "k": 2}),
+    );
+    let matches = result.as_array().expect("Expected array");
+    assert_eq!(matches.len(), 2);
+    assert_eq!(matches[0].get("key").and_then(|v| v.as_str()), Some("v1"));
+
+    let result = call_tool(&mut session, &registry, "strata_vector_list_collections", json!({}));
+    let collections = result.as_array().expect("Expected array");
+    assert!(collections
+        .iter()
+        .any(|c| c.get("name").and_then(|v| v.as_str()) == Some("embeddings")));
+}
+
+#[test]
+fn test_vector_get() {
+    let mut session = test_session();
+    let registry = ToolRegistry::new();
+
+    call_tool(&mut session, &registry, "strata_vector_create_collection", json!({"collection": "vget", "dimension": 3}));
+    call_tool(&mut session, &registry, "strata_vector_upsert", json!({"collection": "vget", "key": "k1", "vector": [1.0, 2.0, 3.0], "metadata": {"label": "test"}}));
+
+    let result = call_tool(&mut session, &registry, "strata_vector_get", json!({"collection": "vget", "key": "k1"}));
+    assert!(result.get("embedding").is_some());
+    assert!(result.get("version").is_some());
+}
+
+#[test]
+fn test_vector_upsert_modes() {
+    let mut session = test_session();
+    let registry = ToolRegistry::new();
+
+    call_tool(&mut session, &registry, "strata_vector_create_collection", json!({"collection": "vmode", "dimension": 2}));
+    call_tool(&mut session, &registry, "strata_vector_upsert", json!({"collection": "vmode", "key": "k1", "vector": [1.0, 0.0], "mode": "insert"}));
+
+    let err = call_tool_err(&mut session, &registry, "strata_vector_upsert", json!({"collection": "vmode", "key": "k1", "vector": [0.0, 1.0], "mode": "insert"}));
+    assert!(matches!(err, strata_mcp::McpError::Strata { ref code, .. } if code == "KEY_EXISTS"));
+    assert!(!err.is_retryable());
+    let result = call_tool(&mut session, &registry, "strata_vector_get", json!({"collection": "vmode", "key": "k1"}));
+    assert_eq!(result["embedding"], json!([1.0, 0.0]));
+
+    call_tool(&mut session, &registry, "strata_vector_upsert", json!({"collection": "vmode", "key": "k1", "vector": [0.0, 1.0], "mode": "upsert"}));
+    let result = call_tool(&mut session, &registry, "strata_vector_get", json!({"collection": "vmode", "key": "k1"}));
+    assert_eq!(result["embedding"], json!([0.0, 1.0]));
+}
+
+#[test]
+fn test_vector_get_many() {
+    let mut session = test_session();
+    let registry = ToolRegistry::new();
+
+    call_tool(&mut session, &registry, "strata_vector_create_collection", json!({"collection": "vmany", "dimension": 2}));
+    call_tool(&mut session, &registry, "strata_vector_upsert", json!({"collection": "vmany", "key": "a", "vector": [1.0, 0.0], "metadata": {"n": 1}}));
+    call_tool(&mut session, &registry, "strata_vector_upsert", json!({"collection": "vmany", "key": "c", "vector": [0.0, 1.0]}));
+
+    let result = call_tool(&mut session, &registry, "strata_vector_get_many", json!({"collection": "vmany", "keys": ["a", "b", "c"]}));
+    let items = result.as_array().expect("Expected array");
+    assert_eq!(items.len(), 3);
+    assert!(items[0].get("embedding").is_some());
+    assert!(items[1].is_null());
+    assert!(items[2].get("embedding").is_some());
+
+    let result = call_tool(&mut session, &registry, "strata_vector_get_many", json!({"collection": "vmany", "keys": ["a", "b", "c"], "as_map": true}));
+    let map = result.as_object().expect("Expected object");
+    assert_eq!(map.len(), 3);
+    assert!(map["a"].get("embedding").is_some());
+    assert!(map["b"].is_null());
+    assert!(map["c"].get("version").is_some());
+}
+
+#[test]
+fn test_vector_delete() {
+    let mut session = test_session();
+    let registry = ToolRegistry::new();
+
+    call_tool(&mut session, &registry, "strata_vector_create_collection", json!({"collection": "vdel", "dimension": 2}));
+    call_tool(&mut session, &registry, "strata_vector_upsert", json!({"collection": "vdel", "key": "k1", "vector": [1.0, 2.0]}));
+
+    let result = call_tool(&mut session, &registry, "strata_vector_delete", json!({"collection": "vdel", "key": "k1"}));
+    assert_eq!(result, json!(true));
+
+    let result = call_tool(&mut session, &registry, "strata_vector_get", json!({"collection": "vdel", "key": "k1"}));
+    assert_eq!(result, json!(null));
+}
+
+#[test]
+fn test_vector_delete_collection() {
+    let mut session = test_session();
+    let registry = ToolRegistry::new();
+
+    call_tool(&mut session, &registry, "strata_vector_create_collection", json!({"collection": "temp_coll", "dimension": 2}));
+    let result = call_tool(&mut session, &registry, "strata_vector_delete_collection", json!({"collection": "temp_coll"}));
+    assert_eq!(result, json!(true));
+}
+
+#[test]
+fn test_vector_clear_keeps_collection() {
+    let mut session = test_session();
+    let registry = ToolRegistry::new();
+
+    call_tool(&mut session, &registry, "strata_vector_create_collection", json!({"collection": "clearme", "dimension": 2, "metric": "euclidean"}));
+    for i in 0..3 {
+        call_tool(&mut session, &registry, "strata_vector_upsert", json!({"collection": "clearme", "key": format!("k{}", i), "vector": [1.0, i as f64]}));
+    }
+
+    let result = call_tool(&mut session, &registry, "strata_vector_clear", json!({"collection": "clearme"}));
+    assert_eq!(result["deleted"], json!(3));
+
+    let collections = call_tool(&mut session, &registry, "strata_vector_list_collections", json!({}));
+    let coll = collections.as_array().unwrap().iter().find(|c| c["name"] == "clearme").expect("collection kept");
+    assert_eq!(coll["count"], json!(0));
+    assert_eq!(coll["dimension"], json!(2));
+    assert_eq!(coll["metric"], json!("euclidean"));
+
+    let result = call_tool(&mut session, &registry, "strata_vector_get", json!({"collection": "clearme", "key": "k0"}));
+    assert_eq!(result, json!(null));
+}
+
+#[test]
+fn test_vector_memory_totals() {
+    let mut session = test_session();
+    let registry = ToolRegistry::new();
+
+    for (collection, n) in [("mem_a", 3), ("mem_b", 5)] {
+        call_tool(&mut session, &registry, "strata_vector_create_collection", json!({"collection": collection, "dimension": 2}));
+        for i in 0..n {
+            call_tool(&mut session, &registry, "strata_vector_upsert", json!({"collection": collection, "key": format!("k{}", i), "vector": [1.0, i as f64]}));
+        }
+    }
+
+    let listed = call_tool(&mut session, &registry, "strata_vector_list_collections", json!({}));
+    let listed = listed.as_array().expect("Expected array");
+    let listed_memory: u64 = listed.iter().map(|c| c["memory_bytes"].as_u64().unwrap_or(0)).sum();
+
+    let result = call_tool(&mut session, &registry, "strata_vector_memory", json!({}));
+    assert_eq!(result["total_memory_bytes"], json!(listed_memory));
+    assert_eq!(result["total_vectors"], json!(8));
+    assert_eq!(result["collection_count"], json!(listed.len()));
+    let breakdown: u64 = result["collections"].as_array().unwrap().iter().map(|c| c["memory_bytes"].as_u64().unwrap_or(0)).sum();
+    assert_eq!(breakdown, listed_memory);
+}
+
+#[test]
+fn test_vector_stats() {
+    let mut session = test_session();
+    let registry = ToolRegistry::new();
+
+    call_tool(&mut session, &registry, "strata_vector_create_collection", json!({"collection": "stats_coll", "dimension": 4}));
+    call_tool(&mut session, &registry, "strata_vector_upsert", json!({"collection": "stats_coll", "key": "s1", "vector": [1.0, 0.0, 0.0, 0.0]}));
+
+    let result = call_tool(&mut session, &registry, "strata_vector_stats", json!({"collection": "stats_coll"}));
+    // VectorCollectionStats returns VectorCollectionList (array with single entry)
+    let stats = if result.is_array() {
+        result.as_array().unwrap().first().expect("Expected at least one stats entry").clone()
+    } else {
+        result
+    };
+    assert_eq!(stats.get("dimension").and_then(|v| v.as_u64()), Some(4));
+    assert_eq!(stats.get("count").and_then(|v| v.as_u64()), Some(1));
+}
+
+#[test]
+fn test_vector_batch_upsert() {
+    let mut session = test_session();
+    let registry = ToolRegistry::new();
+
+    call_tool(&mut session, &registry, "strata_vector_create_collection", json!({"collection": "batch", "dimension": 2}));
+
+    let result = call_tool(
+        &mut session,
+        &registry,
+        "strata_vector_batch_upsert",
+        json!({"collection": "batch", "entries": [
+            {"key": "b1", "vector": [1.0, 0.0]},
+            {"key": "b2", "vector": [0.0, 1.0], "metadata": {"tag": "second"}}
+        ]}),
+    );
+    let versions = result.as_array().expect("Expected array");
+    assert_eq!(versions.len(), 2);
+}
+
+#[test]
+fn test_vector_search_filtered() {
+    let mut session = test_session();
+    let registry = ToolRegistry::new();
+
+    call_tool(&mut session, &registry, "strata_vector_create_collection", json!({"collection": "filtered", "dimension": 2}));
+    call_tool(&mut session, &registry, "strata_vector_upsert", json!({"collection": "filtered", "key": "f1", "vector": [1.0, 0.0], "metadata": {"color": "red"}}));
+    call_tool(&mut session, &registry, "strata_vector_upsert", json!({"collection": "filtered", "key": "f2", "vector": [0.9, 0.1], "metadata": {"color": "blue"}}));
+
+    let result = call_tool(
+        &mut session,
+        &registry,
+        "strata_vector_search",
+        json!({
+            "collection": "filtered",
+            "query": [1.0, 0.0],
+            "k": 10,
+            "filter": [{"field": "color", "op": "eq", "value": "red"}]
+        }),
+    );
+    let matches = result.as_array().expect("Expected array");
+    assert_eq!(matches.len(), 1);
+    assert_eq!(matches[0].get("key").and_then(|v| v.as_str()), Some("f1"));
+}
+
+#[test]
+fn test_vector_dimension_mismatch() {
+    let mut session = test_session();
+    let registry = ToolRegistry::new();
+
+    call_tool(&mut session, &registry, "strata_vector_create_collection", json!({"collection": "dims", "dimension": 3}));
+
+    let err = call_tool_err(&mut session, &registry, "strata_vector_upsert", json!({"collection": "dims", "key": "k", "vector": [1.0, 0.0]}));
+    let msg = format!("{}", err);
+    assert!(msg.contains("vector"));
+    assert!(msg.contains("expects 3 dimensions, got 2"));
+
+    let err = call_tool_err(
+        &mut session,
+        &registry,
+        "strata_vector_batch_upsert",
+        json!({"collection": "dims", "entries": [
+            {"key": "ok", "vector": [1.0, 0.0, 0.0]},
+            {"key": "bad", "vector": [1.0, 0.0, 0.0, 0.0]}
+        ]}),
+    );
+    let msg = format!("{}", err);
+    assert!(msg.contains("entries[1].vector"));
+    assert!(msg.contains("expects 3 dimensions, got 4"));
+
+    let err = call_tool_err(&mut session, &registry, "strata_vector_search", json!({"collection": "dims", "query": [1.0], "k": 1}));
+    let msg = format!("{}", err);
+    assert!(msg.contains("query"));
+    assert!(msg.contains("expects 3 dimensions, got 1"));
+
+    let result = call_tool(&mut session, &registry, "strata_vector_get", json!({"collection": "dims", "key": "ok"}));
+    assert_eq!(result, json!(null));
+}
+
+#[test]
+fn test_vector_search_many() {
+    let mut session = test_session();
+    let registry = ToolRegistry::new();
+
+    call_tool(&mut session, &registry, "strata_vector_create_collection", json!({"collection": "multi", "dimension": 2}));
+    call_tool(&mut session, &registry, "strata_vector_upsert", json!({"collection": "multi", "key": "x", "vector": [1.0, 0.0]}));
+    call_tool(&mut session, &registry, "strata_vector_upsert", json!({"collection": "multi", "key": "y", "vector": [0.0, 1.0]}));
+
+    let result = call_tool(
+        &mut session,
+        &registry,
+        "strata_vector_search_many",
+        json!({"collection": "multi", "queries": [[1.0, 0.1], [0.1, 1.0]], "k": 1}),
+    );
+    let results = result.as_array().expect("Expected array of result arrays");
+    assert_eq!(results.len(), 2);
+    assert_eq!(results[0][0]["key"], json!("x"));
+    assert_eq!(results[1][0]["key"], json!("y"));
+    assert_eq!(results[0].as_array().map(|r| r.len()), Some(1));
+
+    let err = call_tool_err(
+        &mut session,
+        &registry,
+        "strata_vector_search_many",
+        json!({"collection": "multi", "queries": [[1.0, 0.0], [1.0]], "k": 1}),
+    );
+    assert!(format!("{}", err).contains("queries[1]"));
+
+    let err = call_tool_err(
+        &mut session,
+        &registry,
+        "strata_vector_search_many",
+        json!({"collection": "multi", "queries": [[1.0, 0.0]], "k": 1, "query": [1.0, 0.0]}),
+    );
+    assert_eq!(format!("{}", err), "invalid argument 'query': Unknown property");
+}
+
+#[test]
+fn test_vector_distance() {
+    let mut session = test_session();
+    let registry = ToolRegistry::new();
+
+    call_tool(&mut session, &registry, "strata_vector_create_collection", json!({"collection": "dist", "dimension": 2, "metric": "euclidean"}));
+    call_tool(&mut session, &registry, "strata_vector_upsert", json!({"collection": "dist", "key": "a", "vector": [0.0, 0.0]}));
+    call_tool(&mut session, &registry, "strata_vector_upsert", json!({"collection": "dist", "key": "b", "vector": [3.0, 4.0]}));
+
+    // Keys default to the collection's metric
+    let result = call_tool(&mut session, &registry, "strata_vector_distance", json!({"collection": "dist", "keys": ["a", "b"]}));
+    assert_eq!(result["metric"], json!("euclidean"));
+    assert!((result["score"].as_f64().unwrap() - 5.0).abs() < 1e-6);
+
+    let result = call_tool(
+        &mut session,
+        &registry,
+        "strata_vector_distance",
+        json!({"collection": "dist", "vectors": [[1.0, 0.0], [1.0, 1.0]], "metric": "cosine"}),
+    );
+    assert_eq!(result["metric"], json!("cosine"));
+    assert!((result["score"].as_f64().unwrap() - std::f64::consts::FRAC_1_SQRT_2).abs() < 1e-6);
+
+    let result = call_tool(
+        &mut session,
+        &registry,
+        "strata_vector_distance",
+        json!({"collection": "dist", "vectors": [[1.0, 0.0], [-2.0, 0.0]], "metric": "cosine"}),
+    );
+    assert!((result["score"].as_f64().unwrap() + 1.0).abs() < 1e-6);
+
+    let err = call_tool_err(&mut session, &registry, "strata_vector_distance", json!({"collection": "dist", "keys": ["a", "missing"]}));
+    match err {
+        strata_mcp::McpError::Strata { code, .. } => assert_eq!(code, "KEY_NOT_FOUND"),
+        other => panic!("Expected KEY_NOT_FOUND, got {:?}", other),
+    }
+
+    let err = call_tool_err(&mut session, &registry, "strata_vector_distance", json!({"collection": "dist", "vectors": [[1.0, 0.0], [1.0]]}));
+    assert!(format!("{}", err).contains("Dimensions differ"));
+
+    let err = call_tool_err(&mut session, &registry, "strata_vector_distance", json!({"collection": "dist", "keys": ["a"]}));
+    assert!(format!("{}", err).contains("exactly 2"));
+}
+
+#[test]
+fn test_vector_filter_without_query() {
+    let mut session = test_session();
+    let registry = ToolRegistry::new();
+
+    call_tool(&mut session, &registry, "strata_vector_create_collection", json!({"collection": "colors", "dimension": 2}));
+    call_tool(&mut session, &registry, "strata_vector_upsert", json!({"collection": "colors", "key": "r1", "vector": [1.0, 0.0], "metadata": {"color": "red"}}));
+    call_tool(&mut session, &registry, "strata_vector_upsert", json!({"collection": "colors", "key": "b1", "vector": [0.9, 0.1], "metadata": {"color": "blue"}}));
+    call_tool(&mut session, &registry, "strata_vector_upsert", json!({"collection": "colors", "key": "r2", "vector": [0.0, 1.0], "metadata": {"color": "red"}}));
+
+    let filter = json!([{"field": "color", "op": "eq", "value": "red"}]);
+    let result = call_tool(&mut session, &registry, "strata_vector_filter", json!({"collection": "colors", "filter": filter}));
+    let matches = result.as_array().expect("Expected array");
+    let keys: Vec<&str> = matches.iter().filter_map(|m| m["key"].as_str()).collect();
+    assert_eq!(keys, vec!["r1", "r2"]);
+    assert!(matches.iter().all(|m| m["metadata"] == json!({"color": "red"})));
+    assert!(matches.iter().all(|m| m.get("score").is_none()));
+
+    let result = call_tool(&mut session, &registry, "strata_vector_filter", json!({"collection": "colors", "filter": filter, "limit": 1}));
+    assert_eq!(result, json!([{"key": "r1", "metadata": {"color": "red"}}]));
+
+    let err = call_tool_err(&mut session, &registry, "strata_vector_filter", json!({"collection": "colors", "filter": filter, "k": 1}));
+    assert_eq!(format!("{}", err), "invalid argument 'k': Unknown property");
+}
+
+#[test]
+fn test_vector_search_include_embeddings() {
+    let mut session = test_session();
+    let registry = ToolRegistry::new();
+
+    call_tool(&mut session, &registry, "strata_vector_create_collection", json!({"collection": "emb", "dimension": 2}));
+    call_tool(&mut session, &registry, "strata_vector_upsert", json!({"collection": "emb", "key": "e1", "vector": [1.0, 0.0]}));
+    call_tool(&mut session, &registry, "strata_vector_upsert", json!({"collection": "emb", "key": "e2", "vector": [0.0, 1.0]}));
+
+    let result = call_tool(&mut session, &registry, "strata_vector_search", json!({"collection": "emb", "query": [1.0, 0.0], "k": 2}));
+    let matches = result.as_array().expect("Expected array");
+    assert_eq!(matches.len(), 2);
+    assert!(matches.iter().all(|m| m.get("embedding").is_none()));
+
+    let result = call_tool(
+        &mut session,
+        &registry,
+        "strata_vector_search",
+        json!({"collection": "emb", "query": [1.0, 0.0], "k": 2, "include_embeddings": true}),
+    );
+    let matches = result.as_array().expect("Expected array");
+    assert_eq!(matches[0]["key"], json!("e1"));
+    assert_eq!(matches[0]["embedding"], json!([1.0, 0.0]));
+    assert_eq!(matches[1]["embedding"], json!([0.0, 1.0]));
+}
+
+#[test]
+fn test_vector_rename_collection() {
+    let mut session = test_session();
+    let registry = ToolRegistry::new();
+
+    call_tool(&mut session, &registry, "strata_vector_create_collection", json!({"collection": "old", "dimension": 2, "metric": "euclidean"}));
+    call_tool(&mut session, &registry, "strata_vector_upsert", json!({"collection": "old", "key": "a", "vector": [1.0, 0.0], "metadata": {"tag": "first"}}));
+    call_tool(&mut session, &registry, "strata_vector_upsert", json!({"collection": "old", "key": "b", "vector": [0.0, 1.0]}));
+
+    let result = call_tool(&mut session, &registry, "strata_vector_rename_collection", json!({"from": "old", "to": "new"}));
+    assert_eq!(result["vectors"], json!(2));
+
+    let collections = call_tool(&mut session, &registry, "strata_vector_list_collections", json!({}));
+    let collections = collections.as_array().expect("Expected array");
+    assert_eq!(collections.len(), 1);
+    assert_eq!(collections[0]["name"], json!("new"));
+    assert_eq!(collections[0]["metric"], json!("euclidean"));
+
+    let result = call_tool(&mut session, &registry, "strata_vector_get", json!({"collection": "new", "key": "a"}));
+    assert_eq!(result["embedding"], json!([1.0, 0.0]));
+    assert_eq!(result["metadata"], json!({"tag": "first"}));
+    let result = call_tool(&mut session, &registry, "strata_vector_get", json!({"collection": "new", "key": "b"}));
+    assert_eq!(result["embedding"], json!([0.0, 1.0]));
+}
+
+#[test]
+fn test_vector_rename_collection_rejects_existing_name() {
+    let mut session = test_session();
+    let registry = ToolRegistry::new();
+
+    call_tool(&mut session, &registry, "strata_vector_create_collection", json!({"collection": "one", "dimension": 2}));
+    call_tool(&mut session, &registry, "strata_vector_create_collection", json!({"collection": "two", "dimension": 2}));
+    call_tool(&mut session, &registry, "strata_vector_upsert", json!({"collection": "one", "key": "k", "vector": [1.0, 0.0]}));
+
+    let err = call_tool_err(&mut session, &registry, "strata_vector_rename_collection", json!({"from": "one", "to": "two"}));
+    assert!(format!("{}", err).contains("already exists"));
+
+    let result = call_tool(&mut session, &registry, "strata_vector_get", json!({"collection": "one", "key": "k"}));
+    assert_eq!(result["key"], json!("k"));
+}
+
+#[test]
+fn test_vector_reindex_changes_ordering() {
+    let mut session = test_session();
+    let registry = ToolRegistry::new();
+
+    call_tool(&mut session, &registry, "strata_vector_create_collection", json!({"collection": "metrics", "dimension": 2, "metric": "euclidean"}));
+    call_tool(&mut session, &registry, "strata_vector_upsert", json!({"collection": "metrics", "key": "far", "vector": [10.0, 0.0], "metadata": {"tag": "far"}}));
+    call_tool(&mut session, &registry, "strata_vector_upsert", json!({"collection": "metrics", "key": "near", "vector": [1.0, 0.1]}));
+
+    let search = json!({"collection": "metrics", "query": [1.0, 0.0], "k": 2});
+    let result = call_tool(&mut session, &registry, "strata_vector_search", search.clone());
+    assert_eq!(result[0]["key"], json!("near"));
+
+    let result = call_tool(&mut session, &registry, "strata_vector_reindex", json!({"collection": "metrics", "metric": "dot_product"}));
+    assert_eq!(result["vectors"], json!(2));
+
+    // The staging collection was swapped in and is gone
+    let collections = call_tool(&mut session, &registry, "strata_vector_list_collections", json!({}));
+    assert_eq!(collections.as_array().map(|c| c.len()), Some(1));
+    assert_eq!(collections[0]["name"], json!("metrics"));
+    assert_eq!(collections[0]["metric"], json!("dotproduct"));
+
+    let result = call_tool(&mut session, &registry, "strata_vector_search", search);
+    assert_eq!(result[0]["key"], json!("far"));
+
+    let result = call_tool(&mut session, &registry, "strata_vector_get", json!({"collection": "metrics", "key": "far"}));
+    assert_eq!(result["metadata"], json!({"tag": "far"}));
+
+    // A leftover staging collection blocks the reindex before anything is dropped
+    call_tool(&mut session, &registry, "strata_vector_create_collection", json!({"collection": "metrics__reindex", "dimension": 2}));
+    let err = call_tool_err(&mut session, &registry, "strata_vector_reindex", json!({"collection": "metrics", "metric": "cosine"}));
+    assert!(err.to_string().contains("metrics__reindex"));
+    let result = call_tool(&mut session, &registry, "strata_vector_get", json!({"collection": "metrics", "key": "near"}));
+    assert!(!result.is_null());
+}
+
+// =============================================================================
+// Transaction Tools
+// =============================================================================
+
+#[test]
+fn test_transaction_commit() {
+    let mut session = test_session();
+    let registry = ToolRegistry::new();
+
+    let result = call_tool(&mut session, &registry, "strata_txn_begin", json!({}));
+    assert_eq!(result.get("status").and_then(|v| v.as_str()), Some("begun"));
+
+    let result = call_tool(&mut session, &registry, "strata_txn_active", json!({}));
+    assert_eq!(result, json!(true));
+
+    call_tool(&mut session, &registry, "strata_kv_put", json!({"key": "txn-key", "value": "txn-value"}));
+
+    let result = call_tool(&mut session, &registry, "strata_txn_commit", json!({}));
+    assert_eq!(result.get("status").and_then(|v| v.as_str()), Some("committed"));
+
+    let result = call_tool(&mut session, &registry, "strata_kv_get", json!({"key": "txn-key"}));
+    assert_eq!(extract_value(&result), &json!("txn-value"));
+}
+
+#[test]
+fn test_transaction_rollback() {
+    let mut session = test_session();
+    let registry = ToolRegistry::new();
+
+    call_tool(&mut session, &registry, "strata_kv_put", json!({"key": "rollback-key", "value": "initial"}));
+
+    call_tool(&mut session, &registry, "strata_txn_begin", json!({}));
+    call_tool(&mut session, &registry, "strata_kv_put", json!({"key": "rollback-key", "value": "modified"}));
+
+    let result = call_tool(&mut session, &registry, "strata_txn_rollback", json!({}));
+    assert_eq!(result.get("status").and_then(|v| v.as_str()), Some("aborted"));
+
+    let result = call_tool(&mut session, &registry, "strata_kv_get", json!({"key": "rollback-key"}));
+    assert_eq!(extract_value(&result), &json!("initial"));
+}
+
+#[test]
+fn test_transaction_info() {
+    let mut session = test_session();
+    let registry = ToolRegistry::new();
+
+    // No active transaction
+    let result = call_tool(&mut session, &registry, "strata_txn_info", json!({}));
+    assert_eq!(result, json!(null));
+
+    // Begin transaction
+    call_tool(&mut session, &registry, "strata_txn_begin", json!({}));
+    let result = call_tool(&mut session, &registry, "strata_txn_info", json!({}));
+    assert!(result.get("id").is_some());
+    assert!(result.get("started_at").is_some());
+
+    call_tool(&mut session, &registry, "strata_txn_rollback", json!({}));
+}
+
+#[test]
+fn test_transaction_double_begin() {
+    let mut session = test_session();
+    let registry = ToolRegistry::new();
+
+    call_tool(&mut session, &registry, "strata_txn_begin", json!({}));
+    let info = call_tool(&mut session, &registry, "strata_txn_info", json!({}));
+
+    let err = call_tool_err(&mut session, &registry, "strata_txn_begin", json!({}));
+    let msg = format!("{}", err);
+    assert!(msg.contains("already active"));
+    assert!(msg.contains("strata_txn_commit"));
+    assert!(msg.contains("strata_txn_rollback"));
+    assert!(msg.contains(&info["id"].to_string()));
+
+    // The original transaction is still usable
+    assert!(session.in_transaction());
+    call_tool(&mut session, &registry, "strata_txn_rollback", json!({}));
+    call_tool(&mut session, &registry, "strata_txn_begin", json!({}));
+    call_tool(&mut session, &registry, "strata_txn_rollback", json!({}));
+}
+
+#[test]
+fn test_transaction_timeout_rolls_back() {
+    let mut session = test_session();
+    let registry = ToolRegistry::new();
+
+    call_tool(&mut session, &registry, "strata_txn_begin", json!({"timeout_ms": 10}));
+    call_tool(&mut session, &registry, "strata_kv_put", json!({"key": "early", "value": 1}));
+    std::thread::sleep(std::time::Duration::from_millis(30));
+
+    let err = call_tool_err(&mut session, &registry, "strata_kv_put", json!({"key": "late", "value": 2}));
+    match err {
+        strata_mcp::McpError::Strata { code, .. } => assert_eq!(code, "TXN_TIMEOUT"),
+        other => panic!("Expected TXN_TIMEOUT, got {:?}", other),
+    }
+    assert!(!session.in_transaction());
+
+    let result = call_tool(&mut session, &registry, "strata_kv_get", json!({"key": "early"}));
+    assert_eq!(result, json!(null));
+    let result = call_tool(&mut session, &registry, "strata_kv_get", json!({"key": "late"}));
+    assert_eq!(result, json!(null));
+}
+
+#[test]
+fn test_transaction_info_counts_operations() {
+    let mut session = test_session();
+    let registry = ToolRegistry::new();
+
+    call_tool(&mut session, &registry, "strata_txn_begin", json!({}));
+    let result = call_tool(&mut session, &registry, "strata_txn_info", json!({}));
+    assert_eq!(result["operations"], json!(0));
+    assert_eq!(result["read_only"], json!(false));
+
+    call_tool(&mut session, &registry, "strata_kv_put", json!({"key": "a", "value": 1}));
+    call_tool(&mut session, &registry, "strata_kv_put", json!({"key": "b", "value": 2}));
+    call_tool(&mut session, &registry, "strata_kv_get", json!({"key": "a"}));
+    call_tool(&mut session, &registry, "strata_state_set", json!({"cell": "c", "value": 3}));
+
+    let result = call_tool(&mut session, &registry, "strata_txn_info", json!({}));
+    assert_eq!(result["operations"], json!(3));
+    call_tool(&mut session, &registry, "strata_txn_commit", json!({}));
+
+    call_tool(&mut session, &registry, "strata_txn_begin", json!({"read_only": true}));
+    let result = call_tool(&mut session, &registry, "strata_txn_info", json!({}));
+    assert_eq!(result["operations"], json!(0));
+    assert_eq!(result["read_only"], json!(true));
+    call_tool(&mut session, &registry, "strata_txn_rollback", json!({}));
+}
+
+#[test]
+fn test_transaction_read_only() {
+    let mut session = test_session();
+    let registry = ToolRegistry::new();
+
+    call_tool(&mut session, &registry, "strata_txn_begin", json!({"read_only": true}));
+    let result = call_tool(&mut session, &registry, "strata_txn_active", json!({}));
+    assert_eq!(result, json!(true));
+
+    call_tool(&mut session, &registry, "strata_txn_rollback", json!({}));
+}
+
+// =============================================================================
+// Bundle Tools
+// =============================================================================
+
+#[test]
+fn test_bundle_export_import() {
+    let mut session = test_session();
+    let registry = ToolRegistry::new();
+
+    // Create a separate branch for export to avoid "default already exists" on import
+    call_tool(&mut session, &registry, "strata_branch_create", json!({"branch_id": "export-branch"}));
+    call_tool(&mut session, &registry, "strata_branch_switch", json!({"branch": "export-branch"}));
+    call_tool(&mut session, &registry, "strata_kv_put", json!({"key": "export-key", "value": "export-value"}));
+
+    let dir = tempfile::tempdir().expect("Failed to create temp dir");
+    let path = dir.path().join("test.bundle");
+    let path_str = path.to_str().unwrap();
+
+    // Export
+    let result = call_tool(
+        &mut session,
+        &registry,
+        "strata_bundle_export",
+        json!({"branch": "export-branch", "path": path_str}),
+    );
+    assert!(result.get("entry_count").is_some());
+
+    // Validate
+    let result = call_tool(
+        &mut session,
+        &registry,
+        "strata_bundle_validate",
+        json!({"path": path_str}),
+    );
+    assert_eq!(result.get("checksums_valid").and_then(|v| v.as_bool()), Some(true));
+
+    // Delete the branch so import can re-create it
+    call_tool(&mut session, &registry, "strata_branch_switch", json!({"branch": "default"}));
+    call_tool(&mut session, &registry, "strata_branch_delete", json!({"branch": "export-branch"}));
+
+    // Import
+    let result = call_tool(
+        &mut session,
+        &registry,
+        "strata_bundle_import",
+        json!({"path": path_str}),
+    );
+    assert!(result.get("keys_written").is_some());
+}
+
+#[test]
+fn test_bundle_export_filtered_round_trip() {
+    let mut session = test_session();
+    let registry = ToolRegistry::new();
+
+    call_tool(&mut session, &registry, "strata_branch_create", json!({"branch_id": "partial"}));
+    call_tool(&mut session, &registry, "strata_branch_switch", json!({"branch": "partial"}));
+    call_tool(&mut session, &registry, "strata_kv_put", json!({"key": "config:a", "value": 1}));
+    call_tool(&mut session, &registry, "strata_kv_put", json!({"key": "config:b", "value": 2}));
+    call_tool(&mut session, &registry, "strata_kv_put", json!({"key": "data:c", "value": 3}));
+    call_tool(&mut session, &registry, "strata_state_set", json!({"cell": "config:cell", "value": 4}));
+
+    let dir = tempfile::tempdir().expect("Failed to create temp dir");
+    let full_path = dir.path().join("full.bundle");
+    let path = dir.path().join("config.bundle");
+    let full = call_tool(&mut session, &registry, "strata_bundle_export", json!({"branch": "partial", "path": full_path.to_str().unwrap()}));
+    let filtered = call_tool(
+        &mut session,
+        &registry,
+        "strata_bundle_export",
+        json!({"branch": "partial", "path": path.to_str().unwrap(), "key_prefix": "config:", "primitives": ["kv"]}),
+    );
+    assert!(filtered["entry_count"].as_u64().unwrap() < full["entry_count"].as_u64().unwrap());
+
+    call_tool(&mut session, &registry, "strata_branch_switch", json!({"branch": "default"}));
+    call_tool(&mut session, &registry, "strata_branch_delete", json!({"branch": "partial"}));
+    call_tool(&mut session, &registry, "strata_bundle_import", json!({"path": path.to_str().unwrap()}));
+    call_tool(&mut session, &registry, "strata_branch_switch", json!({"branch": "partial"}));
+
+    let keys = call_tool(&mut session, &registry, "strata_kv_list", json!({}));
+    assert_eq!(keys, json!(["config:a", "config:b"]));
+    let cell = call_tool(&mut session, &registry, "strata_state_get", json!({"cell": "config:cell"}));
+    assert!(cell.is_null(), "state was not requested: {}", cell);
+}
+
+#[test]
+fn test_bundle_list() {
+    let mut session = test_session();
+    let registry = ToolRegistry::new();
+    let dir = tempfile::tempdir().expect("Failed to create temp dir");
+
+    for branch in ["list-a", "list-b"] {
+        call_tool(&mut session, &registry, "strata_branch_create", json!({"branch_id": branch}));
+        call_tool(&mut session, &registry, "strata_branch_switch", json!({"branch": branch}));
+        call_tool(&mut session, &registry, "strata_kv_put", json!({"key": "k", "value": branch}));
+        let path = dir.path().join(format!("{}.bundle", branch));
+        call_tool(&mut session, &registry, "strata_bundle_export", json!({"branch": branch, "path": path.to_str().unwrap()}));
+    }
+    std::fs::write(dir.path().join("notes.txt"), "not a bundle").expect("Failed to write file");
+
+    let result = call_tool(&mut session, &registry, "strata_bundle_list", json!({"directory": dir.path().to_str().unwrap()}));
+    let bundles = result.as_array().expect("Expected array");
+    assert_eq!(bundles.len(), 2, "{}", result);
+    for (bundle, branch) in bundles.iter().zip(["list-a", "list-b"]) {
+        assert!(bundle["path"].as_str().unwrap().ends_with(&format!("{}.bundle", branch)));
+        assert!(!bundle["branch_id"].is_null());
+        assert!(bundle["entry_count"].as_u64().is_some());
+        assert!(bundle["bundle_size"].as_u64().unwrap() > 0);
+    }
+}
+
+#[test]
+fn test_export_jsonl() {
+    let mut session = test_session();
+    let registry = ToolRegistry::new();
+
+    call_tool(&mut session, &registry, "strata_branch_create", json!({"branch_id": "jsonl"}));
+    call_tool(&mut session, &registry, "strata_branch_switch", json!({"branch": "jsonl"}));
+    call_tool(&mut session, &registry, "strata_kv_put", json!({"key": "k1", "value": "v1"}));
+    call_tool(&mut session, &registry, "strata_kv_put", json!({"key": "k2", "value": {"n": 2}}));
+    call_tool(&mut session, &registry, "strata_state_set", json!({"cell": "c", "value": 3}));
+    call_tool(&mut session, &registry, "strata_json_set", json!({"key": "doc", "path": "$", "value": {"a": [1, 2]}}));
+    call_tool(&mut session, &registry, "strata_event_append", json!({"event_type": "t", "payload": {"e": 1}}));
+    call_tool(&mut session, &registry, "strata_branch_switch", json!({"branch": "default"}));
+
+    let dir = tempfile::tempdir().expect("Failed to create temp dir");
+    let path = dir.path().join("export.jsonl");
+    let path_str = path.to_str().unwrap();
+    let result = call_tool(
+        &mut session,
+        &registry,
+        "strata_export_jsonl",
+        json!({"branch": "jsonl", "path": path_str}),
+    );
+    assert_eq!(result["lines"], json!(5));
+
+    let contents = std::fs::read_to_string(&path).unwrap();
+    let lines: Vec<JsonValue> = contents
+        .lines()
+        .map(|l| serde_json::from_str(l).expect("Expected a JSON line"))
+        .collect();
+    assert_eq!(lines.len(), 5);
+    let find = |primitive: &str, key: JsonValue| {
+        lines
+            .iter()
+            .find(|l| l["primitive"] == json!(primitive) && l["key"] == key)
+            .unwrap_or_else(|| panic!("Missing {} entry {}", primitive, key))
+    };
+    assert_eq!(find("kv", json!("k1"))["value"], json!("v1"));
+    assert_eq!(find("kv", json!("k2"))["value"], json!({"n": 2}));
+    assert_eq!(find("state", json!("c"))["value"], json!(3));
+    assert_eq!(find("json", json!("doc"))["value"], json!({"a": [1, 2]}));
+    assert_eq!(find("event", json!(0))["value"], json!({"e": 1}));
+    assert!(lines.iter().all(|l| l["space"] == json!("default")));
+
+    // An existing file is not overwritten
+    let err = call_tool_err(
+        &mut session,
+        &registry,
+        "strata_export_jsonl",
+        json!({"branch": "jsonl", "path": path_str}),
+    );
+    assert!(matches!(err, strata_mcp::McpError::Io(_)));
+}
+
+/// Write NDJSON lines to a file in a fresh temp dir.
+fn write_jsonl(lines: &[&str]) -> (tempfile::TempDir, String) {
+    let dir = tempfile::tempdir().expect("Failed to create temp dir");
+    let path = dir.path().join("import.jsonl");
+    std::fs::write(&path, lines.join("\n")).unwrap();
+    let path = path.to_str().unwrap().to_string();
+    (dir, path)
+}
+
+#[test]
+fn test_import_jsonl() {
+    let mut session = test_session();
+    let registry = ToolRegistry::new();
+
+    let (_dir, path) = write_jsonl(&[
+        r#"{"primitive": "kv", "space": "default", "key": "k", "value": {"n": 1}}"#,
+        r#"{"primitive": "state", "key": "c", "value": 2}"#,
+        r#"{"primitive": "json", "key": "doc", "value": {"tags": ["a"]}, "version": 7}"#,
+        "",
+        r#"{"primitive": "event", "key": 0, "value": {}}"#,
+        "not json",
+        r#"{"primitive": "kv", "value": 1}"#,
+    ]);
+
+    let result = call_tool(&mut session, &registry, "strata_import_jsonl", json!({"path": path}));
+    assert_eq!(result["dry_run"], json!(false));
+    assert_eq!(result["imported"], json!({"kv": 1, "state": 1, "json": 1}));
+    let skipped = result["skipped"].as_array().unwrap();
+    let lines: Vec<u64> = skipped.iter().map(|s| s["line"].as_u64().unwrap()).collect();
+    assert_eq!(lines, vec![5, 6, 7]);
+
+    let kv = call_tool(&mut session, &registry, "strata_kv_get", json!({"key": "k"}));
+    assert_eq!(extract_value(&kv), &json!({"n": 1}));
+    let state = call_tool(&mut session, &registry, "strata_state_get", json!({"cell": "c"}));
+    assert_eq!(extract_value(&state), &json!(2));
+    let doc = call_tool(&mut session, &registry, "strata_json_get", json!({"key": "doc", "path": "$.tags"}));
+    assert_eq!(extract_value(&doc), &json!(["a"]));
+}
+
+#[test]
+fn test_import_jsonl_dry_run() {
+    let mut session = test_session();
+    let registry = ToolRegistry::new();
+
+    let (_dir, path) = write_jsonl(&[r#"{"primitive": "kv", "key": "dry", "value": 1}"#]);
+
+    let result = call_tool(
+        &mut session,
+        &registry,
+        "strata_import_jsonl",
+        json!({"path": path, "dry_run": true}),
+    );
+    assert_eq!(result["dry_run"], json!(true));
+    assert_eq!(result["imported"]["kv"], json!(1));
+
+    let kv = call_tool(&mut session, &registry, "strata_kv_get", json!({"key": "dry"}));
+    assert!(kv.is_null());
+}
+
+// =============================================================================
+// Retention Tool
+// =============================================================================
+
+#[test]
+fn test_retention_apply() {
+    let mut session = test_session();
+    let registry = ToolRegistry::new();
+
+    let result = call_tool(&mut session, &registry, "strata_retention_apply", json!({}));
+    assert_eq!(result, json!(null));
+}
+
+#[test]
+fn test_retention_apply_reports_counts() {
+    let mut session = test_session();
+    let registry = ToolRegistry::new();
+
+    for v in 1..=3 {
+        call_tool(&mut session, &registry, "strata_kv_put", json!({"key": "k", "value": v}));
+        call_tool(&mut session, &registry, "strata_state_set", json!({"cell": "c", "value": v}));
+    }
+    call_tool(&mut session, &registry, "strata_event_append", json!({"event_type": "e", "payload": {}}));
+    let before = call_tool(&mut session, &registry, "strata_kv_history", json!({"key": "k"}));
+    let before = before.as_array().unwrap().len() as u64;
+
+    let result = call_tool(&mut session, &registry, "strata_retention_apply", json!({"report": true}));
+    let after = call_tool(&mut session, &registry, "strata_kv_history", json!({"key": "k"}));
+    let after = after.as_array().unwrap().len() as u64;
+
+    // Counts reflect what the engine's own policy actually removed
+    assert_eq!(result["per_primitive"]["kv"]["scanned"], json!(1));
+    assert_eq!(result["per_primitive"]["state"]["scanned"], json!(1));
+    assert_eq!(result["per_primitive"]["event"]["scanned"], json!(1));
+    assert_eq!(result["per_primitive"]["kv"]["versions_trimmed"], json!(before - after));
+    assert!(result["versions_trimmed"].as_u64().unwrap() >= before - after);
+    assert!(result["entries_removed"].is_u64());
+}
+
+#[test]
+fn test_retention_preview_counts_policy() {
+    let mut session = test_session();
+    let registry = ToolRegistry::new();
+
+    for v in 1..=4 {
+        call_tool(&mut session, &registry, "strata_kv_put", json!({"key": "a", "value": v}));
+    }
+    for v in 1..=3 {
+        call_tool(&mut session, &registry, "strata_kv_put", json!({"key": "b", "value": v}));
+    }
+
+    let policy = json!({"max_versions": 2});
+    let preview = call_tool(&mut session, &registry, "strata_retention_preview", policy.clone());
+    assert_eq!(preview["per_primitive"]["kv"]["scanned"], json!(2));
+    assert_eq!(preview["per_primitive"]["kv"]["versions_trimmed"], json!(3));
+    assert_eq!(preview["versions_trimmed"], json!(3));
+
+    // Preview must not delete anything
+    let history = call_tool(&mut session, &registry, "strata_kv_history", json!({"key": "a"}));
+    assert_eq!(history.as_array().unwrap().len(), 4);
+    let preview = call_tool(&mut session, &registry, "strata_retention_preview", policy);
+    assert_eq!(preview["versions_trimmed"], json!(3));
+}
+
+#[test]
+fn test_retention_preview_supplied_policy() {
+    let mut session = test_session();
+    let registry = ToolRegistry::new();
+
+    call_tool(&mut session, &registry, "strata_kv_put", json!({"key": "k", "value": 1}));
+    call_tool(&mut session, &registry, "strata_kv_put", json!({"key": "k", "value": 2}));
+
+    let preview = call_tool(
+        &mut session,
+        &registry,
+        "strata_retention_preview",
+        json!({"overrides": {"kv": {"max_versions": 1}}}),
+    );
+    assert_eq!(preview["per_primitive"]["kv"]["versions_trimmed"], json!(1));
+    assert_eq!(preview["policy"]["effective"]["kv"]["max_versions"], json!(1));
+}
+
+#[test]
+fn test_retention_preview_requires_bound() {
+    let mut session = test_session();
+    let registry = ToolRegistry::new();
+
+    let err = call_tool_err(&mut session, &registry, "strata_retention_preview", json!({}));
+    assert!(format!("{}", err).contains("max_versions"));
+
+    let err = call_tool_err(
+        &mut session,
+        &registry,
+        "strata_retention_preview",
+        json!({"overrides": {"graph": {"max_versions": 2}}}),
+    );
+    assert!(format!("{}", err).contains("overrides.graph"));
+
+    let err = call_tool_err(
+        &mut session,
+        &registry,
+        "strata_retention_preview",
+        json!({"max_versions": 0}),
+    );
+    assert!(format!("{}", err).contains("positive"));
+}
+
+// =============================================================================
+// Search Tool
+// =============================================================================
+
+#[test]
+fn test_search() {
+    let mut session = test_session();
+    let registry = ToolRegistry::new();
+
+    call_tool(&mut session, &registry, "strata_kv_put", json!({"key": "search-key", "value": "searchable text content"}));
+
+    let result = call_tool(
+        &mut session,
+        &registry,
+        "strata_search",
+        json!({"query": "searchable", "k": 5}),
+    );
+    // Result is an array of search hits
+    assert!(result.is_array());
+}
+
+#[test]
+fn test_search_empty_database() {
+    let mut session = test_session();
+    let registry = ToolRegistry::new();
+
+    let result = call_tool(
+        &mut session,
+        &registry,
+        "strata_search",
+        json!({"query": "nothing"}),
+    );
+    assert_eq!(result, json!([]));
+}
+
+#[test]
+fn test_search_suggest() {
+    let mut session = test_session();
+    let registry = ToolRegistry::new();
+
+    call_tool(&mut session, &registry, "strata_kv_put", json!({"key": "user:bob", "value": 1}));
+    call_tool(&mut session, &registry, "strata_kv_put", json!({"key": "user:alice", "value": 1}));
+    call_tool(&mut session, &registry, "strata_kv_put", json!({"key": "order:1", "value": 1}));
+    call_tool(&mut session, &registry, "strata_json_set", json!({"key": "user:carol", "path": "$", "value": {"a": 1}}));
+    call_tool(&mut session, &registry, "strata_state_set", json!({"cell": "user:active", "value": 1}));
+    call_tool(&mut session, &registry, "strata_vector_create_collection", json!({"collection": "user:embeddings", "dimension": 2}));
+
+    let result = call_tool(&mut session, &registry, "strata_search_suggest", json!({"prefix": "user:"}));
+    assert_eq!(
+        result,
+        json!([
+            {"key": "user:active", "primitive": "state"},
+            {"key": "user:alice", "primitive": "kv"},
+            {"key": "user:bob", "primitive": "kv"},
+            {"key": "user:carol", "primitive": "json"},
+            {"key": "user:embeddings", "primitive": "vector"},
+        ])
+    );
+
+    let result = call_tool(&mut session, &registry, "strata_search_suggest", json!({"prefix": "user:", "k": 2}));
+    assert_eq!(result.as_array().map(|a| a.len()), Some(2));
+    assert_eq!(result[1]["key"], json!("user:alice"));
+
+    let result = call_tool(&mut session, &registry, "strata_search_suggest", json!({"prefix": "user:", "primitives": ["json"]}));
+    assert_eq!(result, json!([{"key": "user:carol", "primitive": "json"}]));
+
+    let result = call_tool(&mut session, &registry, "strata_search_suggest", json!({"prefix": "nobody"}));
+    assert_eq!(result, json!([]));
+
+    let err = call_tool_err(&mut session, &registry, "strata_search_suggest", json!({"prefix": "user:", "k": 0}));
+    assert!(format!("{}", err).contains("'k'"));
+}
+
+#[test]
+fn test_search_with_primitives_filter() {
+    let mut session = test_session();
+    let registry = ToolRegistry::new();
+
+    call_tool(&mut session, &registry, "strata_kv_put", json!({"key": "k1", "value": "data"}));
+
+    let result = call_tool(
+        &mut session,
+        &registry,
+        "strata_search",
+        json!({"query": "data", "primitives": ["kv"]}),
+    );
+    assert!(result.is_array());
+}
+
+#[test]
+fn test_search_snippet_length() {
+    let mut session = test_session();
+    let registry = ToolRegistry::new();
+
+    let text = "a long document that mentions the word ranking somewhere in the middle of its text";
+    call_tool(&mut session, &registry, "strata_kv_put", json!({"key": "doc", "value": text}));
+
+    let snippets = |result: &serde_json::Value| -> Vec<String> {
+        result
+            .as_array()
+            .expect("Expected array")
+            .iter()
+            .filter_map(|h| h["snippet"].as_str().map(String::from))
+            .collect()
+    };
+
+    let full = snippets(&call_tool(&mut session, &registry, "strata_search", json!({"query": "ranking"})));
+    let short = snippets(&call_tool(&mut session, &registry, "strata_search", json!({"query": "ranking", "snippet_length": 10})));
+    assert_eq!(full.len(), short.len());
+    for (full, short) in full.iter().zip(&short) {
+        assert!(short.chars().count() <= 10, "snippet too long: {}", short);
+        assert!(full.starts_with(short.as_str()));
+    }
+
+    let around = snippets(&call_tool(&mut session, &registry, "strata_search", json!({"query": "ranking", "snippet_context": 4})));
+    for snippet in around.iter().filter(|s| s.contains("ranking")) {
+        assert!(snippet.chars().count() <= "ranking".len() + 8, "snippet too long: {}", snippet);
+    }
+}
+
+#[test]
+fn test_search_collections_filter() {
+    let mut session = test_session();
+    let registry = ToolRegistry::new();
+
+    for collection in ["kept", "excluded"] {
+        call_tool(&mut session, &registry, "strata_vector_create_collection", json!({"collection": collection, "dimension": 2}));
+        call_tool(&mut session, &registry, "strata_vector_upsert", json!({"collection": collection, "key": "shared", "vector": [1.0, 0.0], "metadata": {"note": "shared vector"}}));
+    }
+    call_tool(&mut session, &registry, "strata_kv_put", json!({"key": "note", "value": "shared vector"}));
+
+    let result = call_tool(&mut session, &registry, "strata_search", json!({"query": "shared vector", "collections": ["kept"]}));
+    let hits = result.as_array().expect("Expected array");
+    for hit in hits {
+        let entity = hit["entity"].as_str().unwrap_or_default();
+        assert!(!entity.starts_with("excluded/"), "excluded collection in results: {}", hit);
+    }
+    assert!(hits.iter().any(|h| h["primitive"].as_str() != Some("vector")), "non-vector results should be kept");
+}
+
+#[test]
+fn test_search_with_mode() {
+    let mut session = test_session();
+    let registry = ToolRegistry::new();
+
+    let result = call_tool(
+        &mut session,
+        &registry,
+        "strata_search",
+        json!({"query": "test", "mode": "keyword"}),
+    );
+    assert!(result.is_array());
+}
+
+#[test]
+fn test_search_with_expand_rerank_disabled() {
+    let mut session = test_session();
+    let registry = ToolRegistry::new();
+
+    let result = call_tool(
+        &mut session,
+        &registry,
+        "strata_search",
+        json!({"query": "test", "expand": false, "rerank": false}),
+    );
+    assert!(result.is_array());
+}
+
+#[test]
+fn test_search_with_time_range() {
+    let mut session = test_session();
+    let registry = ToolRegistry::new();
+
+    let result = call_tool(
+        &mut session,
+        &registry,
+        "strata_search",
+        json!({
+            "query": "test",
+            "time_range": {"start": "2020-01-01T00:00:00Z", "end": "2030-01-01T00:00:00Z"}
+        }),
+    );
+    assert!(result.is_array());
+}
+
+#[test]
+fn test_search_with_all_options() {
+    let mut session = test_session();
+    let registry = ToolRegistry::new();
+
+    let result = call_tool(
+        &mut session,
+        &registry,
+        "strata_search",
+        json!({
+            "query": "hello",
+            "k": 5,
+            "primitives": ["kv"],
+            "mode": "hybrid",
+            "expand": false,
+            "rerank": false
+        }),
+    );
+    assert!(result.is_array());
+}
+
+// =============================================================================
+// Batch Tool
+// =============================================================================
+
+#[test]
+fn test_batch_errors_redacted() {
+    let mut session = test_session();
+    let registry = ToolRegistry::new();
+    session.set_redact_errors(true);
+
+    call_tool(&mut session, &registry, "strata_vector_create_collection", json!({"collection": "secret_coll", "dimension": 2}));
+    let insert = json!({"tool": "strata_vector_upsert", "arguments": {"collection": "secret_coll", "key": "k", "vector": [1.0, 0.0], "mode": "insert"}});
+
+    let result = call_tool(&mut session, &registry, "strata_batch", json!({"operations": [insert, insert]}));
+    let message = result[1]["error"]["message"].as_str().expect("Expected error message");
+    assert!(!message.contains("secret_coll"), "{}", message);
+    assert!(message.contains("KEY_EXISTS"), "{}", message);
+
+    let result = call_tool(&mut session, &registry, "strata_with_transaction", json!({"operations": [insert]}));
+    assert_eq!(result["rolled_back"], json!(true));
+    let message = result["error"]["message"].as_str().expect("Expected error message");
+    assert!(!message.contains("secret_coll"), "{}", message);
+    assert!(message.contains("KEY_EXISTS"), "{}", message);
+}
+
+#[test]
+fn test_batch_ordered_execution() {
+    let mut session = test_session();
+    let registry = ToolRegistry::new();
+
+    let result = call_tool(
+        &mut session,
+        &registry,
+        "strata_batch",
+        json!({"operations": [
+            {"tool": "strata_kv_put", "arguments": {"key": "b", "value": 1}},
+            {"tool": "strata_kv_put", "arguments": {"key": "b", "value": 2}},
+            {"tool": "strata_kv_get", "arguments": {"key": "b", "raw": true}}
+        ]}),
+    );
+    let results = result.as_array().expect("Expected array");
+    assert_eq!(results.len(), 3);
+    assert_eq!(results[2].get("result"), Some(&json!(2)));
+}
+
+#[test]
+fn test_batch_per_op_errors() {
+    let mut session = test_session();
+    let registry = ToolRegistry::new();
+
+    let result = call_tool(
+        &mut session,
+        &registry,
+        "strata_batch",
+        json!({"operations": [
+            {"tool": "strata_kv_put", "arguments": {}},
+            {"tool": "strata_kv_put", "arguments": {"key": "after", "value": 1}}
+        ]}),
+    );
+    let results = result.as_array().expect("Expected array");
+    assert_eq!(results.len(), 2);
+    assert!(results[0].get("error").is_some());
+    assert!(results[1].get("result").is_some());
+
+    let result = call_tool(
+        &mut session,
+        &registry,
+        "strata_batch",
+        json!({"stop_on_error": true, "operations": [
+            {"tool": "strata_kv_put", "arguments": {}},
+            {"tool": "strata_kv_put", "arguments": {"key": "skipped", "value": 1}}
+        ]}),
+    );
+    assert_eq!(result.as_array().map(|a| a.len()), Some(1));
+    let result = call_tool(&mut session, &registry, "strata_kv_get", json!({"key": "skipped"}));
+    assert_eq!(result, json!(null));
+}
+
+#[test]
+fn test_batch_atomic_rollback() {
+    let mut session = test_session();
+    let registry = ToolRegistry::new();
+
+    call_tool_err(
+        &mut session,
+        &registry,
+        "strata_batch",
+        json!({"atomic": true, "operations": [
+            {"tool": "strata_kv_put", "arguments": {"key": "atomic", "value": 1}},
+            {"tool": "strata_kv_put", "arguments": {}}
+        ]}),
+    );
+
+    let result = call_tool(&mut session, &registry, "strata_kv_get", json!({"key": "atomic"}));
+    assert_eq!(result, json!(null));
+    let result = call_tool(&mut session, &registry, "strata_txn_active", json!({}));
+    assert_eq!(result, json!(false));
+}
+
+#[test]
+fn test_batch_atomic_commit() {
+    let mut session = test_session();
+    let registry = ToolRegistry::new();
+
+    call_tool(
+        &mut session,
+        &registry,
+        "strata_batch",
+        json!({"atomic": true, "operations": [
+            {"tool": "strata_kv_put", "arguments": {"key": "a1", "value": 1}},
+            {"tool": "strata_kv_put", "arguments": {"key": "a2", "value": 2}}
+        ]}),
+    );
+
+    let result = call_tool(&mut session, &registry, "strata_kv_get", json!({"key": "a2"}));
+    assert_eq!(extract_value(&result), &json!(2));
+}
+
+#[test]
+fn test_with_transaction_commits() {
+    let mut session = test_session();
+    let registry = ToolRegistry::new();
+
+    let result = call_tool(
+        &mut session,
+        &registry,
+        "strata_with_transaction",
+        json!({"operations": [
+            {"tool": "strata_kv_put", "arguments": {"key": "t1", "value": 1}},
+            {"tool": "strata_state_set", "arguments": {"cell": "t2", "value": 2}}
+        ]}),
+    );
+    assert_eq!(result["committed"], json!(true));
+    assert_eq!(result["results"].as_array().map(|r| r.len()), Some(2));
+    assert!(!session.in_transaction());
+
+    let result = call_tool(&mut session, &registry, "strata_kv_get", json!({"key": "t1"}));
+    assert_eq!(extract_value(&result), &json!(1));
+}
+
+#[test]
+fn test_with_transaction_rolls_back_on_error() {
+    let mut session = test_session();
+    let registry = ToolRegistry::new();
+
+    let result = call_tool(
+        &mut session,
+        &registry,
+        "strata_with_transaction",
+        json!({"operations": [
+            {"tool": "strata_kv_put", "arguments": {"key": "r1", "value": 1}},
+            {"tool": "strata_kv_get", "arguments": {}},
+            {"tool": "strata_kv_put", "arguments": {"key": "r2", "value": 2}}
+        ]}),
+    );
+    assert_eq!(result["rolled_back"], json!(true));
+    assert_eq!(result["error"]["index"], json!(1));
+    assert_eq!(result["error"]["tool"], json!("strata_kv_get"));
+    assert_eq!(result["results"].as_array().map(|r| r.len()), Some(1));
+    assert!(!session.in_transaction());
+
+    let result = call_tool(&mut session, &registry, "strata_kv_get", json!({"key": "r1"}));
+    assert_eq!(result, json!(null));
+
+    // A transaction tool inside the wrapper is rejected before anything runs
+    call_tool_err(
+        &mut session,
+        &registry,
+        "strata_with_transaction",
+        json!({"operations": [{"tool": "strata_txn_commit"}]}),
+    );
+}
+
+// =============================================================================
+// Server Loop
+// =============================================================================
+
+/// Reader that yields its data, then requests shutdown and blocks forever
+/// instead of reaching EOF.
+struct ShutdownAfterReader {
+    data: std::io::Cursor<Vec<u8>>,
+    handle: ShutdownHandle,
+}
+
+impl Read for ShutdownAfterReader {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let n = self.data.read(buf)?;
+        if n > 0 {
+            return Ok(n);
+        }
+        self.handle.shutdown();
+        loop {
+            std::thread::park();
+        }
+    }
+}
+
+/// Reader that yields its data, then blocks forever instead of reaching EOF.
+struct StallingReader {
+    data: std::io::Cursor<Vec<u8>>,
+}
+
+impl Read for StallingReader {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let n = self.data.read(buf)?;
+        if n > 0 {
+            return Ok(n);
+        }
+        loop {
+            std::thread::park();
+        }
+    }
+}
+
+/// Reader that yields a ping every millisecond and never reaches EOF.
+struct PingingReader {
+    pending: std::io::Cursor<Vec<u8>>,
+    id: u64,
+}
+
+impl Read for PingingReader {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        if self.pending.position() as usize == self.pending.get_ref().len() {
+            std::thread::sleep(std::time::Duration::from_millis(1));
+            self.id += 1;
+            let ping = json!({"jsonrpc": "2.0", "id": self.id, "method": "ping"});
+            self.pending = std::io::Cursor::new(format!("{}\n", ping).into_bytes());
+        }
+        self.pending.read(buf)
+    }
+}
+
+/// Serialize an initialize request as a single input line.
+fn initialize_line(id: u64) -> String {
+    let request = json!({
+        "jsonrpc": "2.0",
+        "id": id,
+        "method": "initialize",
+        "params": {"protocolVersion": "2024-11-05", "capabilities": {}}
+    });
+    format!("{}\n", request)
+}
+
+/// Serialize a tools/call request as a single input line.
+fn tool_call_line(id: u64, name: &str, args: JsonValue) -> String {
+    let request = json!({
+        "jsonrpc": "2.0",
+        "id": id,
+        "method": "tools/call",
+        "params": {"name": name, "arguments": args}
+    });
+    format!("{}\n", request)
+}
+
+#[test]
+fn test_shutdown_flushes_before_exit() {
+    let dir = tempfile::tempdir().expect("Failed to create temp dir");
+    {
+        let db = Strata::open_with(dir.path(), stratadb::OpenOptions::new())
+            .expect("Failed to open db");
+        let mut server = McpServer::new(McpSession::new(db)).with_signal_handling(false);
+        let reader = ShutdownAfterReader {
+            data: std::io::Cursor::new(
+                format!(
+                    "{}{}",
+                    initialize_line(0),
+                    tool_call_line(1, "strata_kv_put", json!({"key": "durable", "value": 1}))
+                )
+                .into_bytes(),
+            ),
+            handle: server.shutdown_handle(),
+        };
+
+        let mut output = Vec::new();
+        server.run_with(reader, &mut output).expect("Server failed");
+        let output = String::from_utf8(output).unwrap();
+        assert_eq!(output.lines().count(), 2, "Expected two responses: {}", output);
+    }
+
+    let db = Strata::open_with(dir.path(), stratadb::OpenOptions::new()).expect("Failed to reopen");
+    let mut session = McpSession::new(db);
+    let registry = ToolRegistry::new();
+    let result = call_tool(&mut session, &registry, "strata_kv_get", json!({"key": "durable"}));
+    assert_eq!(extract_value(&result), &json!(1));
+}
+
+#[test]
+fn test_shutdown_while_client_is_busy() {
+    let mut server = McpServer::new(test_session()).with_signal_handling(false);
+    let handle = server.shutdown_handle();
+    std::thread::spawn(move || {
+        std::thread::sleep(std::time::Duration::from_millis(200));
+        handle.shutdown();
+    });
+
+    let reader = PingingReader {
+        pending: std::io::Cursor::new(Vec::new()),
+        id: 0,
+    };
+    let mut output = Vec::new();
+    server.run_with(reader, &mut output).expect("Server failed");
+    assert!(!output.is_empty(), "Expected ping responses before shutdown");
+}
+
+#[test]
+fn test_eof_flushes_before_exit() {
+    let dir = tempfile::tempdir().expect("Failed to create temp dir");
+    {
+        let db = Strata::open_with(dir.path(), stratadb::OpenOptions::new())
+            .expect("Failed to open db");
+        let mut server = McpServer::new(McpSession::new(db)).with_signal_handling(false);
+        let input = format!(
+            "{}{}",
+            initialize_line(0),
+            tool_call_line(1, "strata_kv_put", json!({"key": "eof-key", "value": "kept"}))
+        );
+
+        let mut output = Vec::new();
+        server
+            .run_with(std::io::Cursor::new(input.into_bytes()), &mut output)
+            .expect("Server failed");
+    }
+
+    let db = Strata::open_with(dir.path(), stratadb::OpenOptions::new()).expect("Failed to reopen");
+    let mut session = McpSession::new(db);
+    let registry = ToolRegistry::new();
+    let result = call_tool(&mut session, &registry, "strata_kv_get", json!({"key": "eof-key"}));
+    assert_eq!(extract_value(&result), &json!("kept"));
+}
+
+#[test]
+fn test_idle_timeout_exits_and_flushes() {
+    let dir = tempfile::tempdir().expect("Failed to create temp dir");
+    {
+        let db = Strata::open_with(dir.path(), stratadb::OpenOptions::new())
+            .expect("Failed to open db");
+        let mut server = McpServer::new(McpSession::new(db))
+            .with_signal_handling(false)
+            .with_idle_timeout(std::time::Duration::from_millis(200));
+        let input = format!(
+            "{}{}",
+            initialize_line(0),
+            tool_call_line(1, "strata_kv_put", json!({"key": "idle-key", "value": "kept"}))
+        );
+        let reader = StallingReader {
+            data: std::io::Cursor::new(input.into_bytes()),
+        };
+
+        let start = std::time::Instant::now();
+        let mut output = Vec::new();
+        server.run_with(reader, &mut output).expect("Server failed");
+        assert!(start.elapsed() >= std::time::Duration::from_millis(200));
+        assert_eq!(String::from_utf8(output).unwrap().lines().count(), 2);
+    }
 
-    // Validate
-    let result = call_tool(
-        &mut session,
-        &registry,
-        "strata_bundle_validate",
-        json!({"path": path_str}),
-    );
-    assert_eq!(result.get("checksums_valid").and_then(|v| v.as_bool()), Some(true));
+    let db = Strata::open_with(dir.path(), stratadb::OpenOptions::new()).expect("Failed to reopen");
+    let mut session = McpSession::new(db);
+    let registry = ToolRegistry::new();
+    let result = call_tool(&mut session, &registry, "strata_kv_get", json!({"key": "idle-key"}));
+    assert_eq!(extract_value(&result), &json!("kept"));
+}
 
-    // Delete the branch so import can re-create it
-    call_tool(&mut session, &registry, "strata_branch_switch", json!({"branch": "default"}));
-    call_tool(&mut session, &registry, "strata_branch_delete", json!({"branch": "export-branch"}));
+#[test]
+fn test_idle_timeout_counts_pings() {
+    let mut server = McpServer::new(test_session())
+        .with_signal_handling(false)
+        .with_idle_timeout(std::time::Duration::from_millis(150));
+    let handle = server.shutdown_handle();
+    std::thread::spawn(move || {
+        std::thread::sleep(std::time::Duration::from_millis(600));
+        handle.shutdown();
+    });
+
+    // Pings alone keep the connection alive until the shutdown
+    let reader = PingingReader {
+        pending: std::io::Cursor::new(Vec::new()),
+        id: 0,
+    };
+    let start = std::time::Instant::now();
+    let mut output = Vec::new();
+    server.run_with(reader, &mut output).expect("Server failed");
+    assert!(start.elapsed() >= std::time::Duration::from_millis(600));
+}
 
-    // Import
-    let result = call_tool(
-        &mut session,
-        &registry,
-        "strata_bundle_import",
-        json!({"path": path_str}),
-    );
-    assert!(result.get("keys_written").is_some());
+#[test]
+fn test_oversized_request_rejected() {
+    let mut server = McpServer::new(test_session())
+        .with_signal_handling(false)
+        .with_max_message_bytes(256);
+
+    let oversized = tool_call_line(1, "strata_kv_put", json!({"key": "big", "value": "x".repeat(1024)}));
+    let ping = "{\"jsonrpc\":\"2.0\",\"id\":2,\"method\":\"ping\"}\n";
+    let input = format!("{}{}", oversized, ping);
+
+    let mut output = Vec::new();
+    server
+        .run_with(std::io::Cursor::new(input.into_bytes()), &mut output)
+        .expect("Server failed");
+
+    let responses: Vec<JsonValue> = String::from_utf8(output)
+        .unwrap()
+        .lines()
+        .map(|l| serde_json::from_str(l).unwrap())
+        .collect();
+    assert_eq!(responses.len(), 2);
+    assert_eq!(responses[0]["error"]["code"], json!(-32600));
+    assert_eq!(responses[1]["id"], json!(2));
+    assert!(responses[1].get("result").is_some());
 }
 
-// =============================================================================
-// Retention Tool
-// =============================================================================
+#[test]
+fn test_max_result_items_truncates_kv_list() {
+    let mut server = McpServer::new(test_session())
+        .with_signal_handling(false)
+        .with_max_result_items(3);
+
+    let mut input = initialize_line(1000);
+    for i in 0..10 {
+        input.push_str(&tool_call_line(i, "strata_kv_put", json!({"key": format!("k{}", i), "value": i})));
+    }
+    input.push_str(&tool_call_line(100, "strata_kv_list", json!({})));
+
+    let mut output = Vec::new();
+    server
+        .run_with(std::io::Cursor::new(input.into_bytes()), &mut output)
+        .expect("Server failed");
+
+    let response: JsonValue = String::from_utf8(output)
+        .unwrap()
+        .lines()
+        .map(|l| serde_json::from_str::<JsonValue>(l).unwrap())
+        .find(|r| r["id"] == json!(100))
+        .expect("Missing kv_list response");
+    let text = response["result"]["content"][0]["text"].as_str().unwrap();
+    let result: JsonValue = serde_json::from_str(text).unwrap();
+    assert_eq!(result["truncated"], json!(true));
+    assert_eq!(result["total"], json!(10));
+    assert_eq!(result["returned"], json!(3));
+    assert_eq!(result["items"].as_array().map(|a| a.len()), Some(3));
+}
 
 #[test]
-fn test_retention_apply() {
-    let mut session = test_session();
-    let registry = ToolRegistry::new();
+fn test_stream_token_streams_kv_list() {
+    let mut server = McpServer::new(test_session())
+        .with_signal_handling(false)
+        .with_max_result_items(3);
+
+    let items: Vec<JsonValue> = (0..250)
+        .map(|i| json!({"key": format!("k{:03}", i), "value": i}))
+        .collect();
+    let mut input = initialize_line(1000);
+    input.push_str(&tool_call_line(1, "strata_kv_put_many", json!({"items": items})));
+    let request = json!({
+        "jsonrpc": "2.0",
+        "id": 2,
+        "method": "tools/call",
+        "params": {"name": "strata_kv_list", "arguments": {}, "_meta": {"streamToken": "list-1"}}
+    });
+    input.push_str(&format!("{}\n", request));
+
+    let mut output = Vec::new();
+    server
+        .run_with(std::io::Cursor::new(input.into_bytes()), &mut output)
+        .expect("Server failed");
+
+    let lines: Vec<JsonValue> = String::from_utf8(output)
+        .unwrap()
+        .lines()
+        .map(|l| serde_json::from_str::<JsonValue>(l).unwrap())
+        .collect();
+    let notifications: Vec<&JsonValue> = lines
+        .iter()
+        .filter(|l| l["method"] == json!("notifications/progress"))
+        .collect();
+    assert_eq!(notifications.len(), 3);
+    let mut keys = Vec::new();
+    for (n, expected) in notifications.iter().zip([100, 200, 250]) {
+        assert!(n.get("id").is_none());
+        assert_eq!(n["params"]["progressToken"], json!("list-1"));
+        assert_eq!(n["params"]["progress"], json!(expected));
+        assert_eq!(n["params"]["total"], json!(250));
+        keys.extend(n["params"]["items"].as_array().unwrap().iter().cloned());
+    }
+    assert_eq!(keys.len(), 250);
+    assert!(keys.contains(&json!("k000")) && keys.contains(&json!("k249")));
 
-    let result = call_tool(&mut session, &registry, "strata_retention_apply", json!({}));
-    assert_eq!(result, json!(null));
+    // The response follows the last notification and carries no items
+    let position = lines.iter().position(|l| l["id"] == json!(2)).expect("Missing response");
+    let last_notification = lines
+        .iter()
+        .rposition(|l| l["method"] == json!("notifications/progress"))
+        .unwrap();
+    assert!(position > last_notification);
+    let text = lines[position]["result"]["content"][0]["text"].as_str().unwrap();
+    assert_eq!(serde_json::from_str::<JsonValue>(text).unwrap(), json!([]));
 }
 
-// =============================================================================
-// Search Tool
-// =============================================================================
+#[test]
+fn test_initialize_negotiates_protocol_version() {
+    for (requested, negotiated) in [
+        (json!("2025-03-26"), "2025-03-26"),
+        (json!("2099-01-01"), "2025-06-18"),
+        (json!("2023-01-01"), "2024-11-05"),
+        (JsonValue::Null, "2024-11-05"),
+    ] {
+        let mut server = McpServer::new(test_session()).with_signal_handling(false);
+        let request = json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": "initialize",
+            "params": {"protocolVersion": requested, "capabilities": {}}
+        });
+
+        let mut output = Vec::new();
+        server
+            .run_with(std::io::Cursor::new(format!("{}\n", request).into_bytes()), &mut output)
+            .expect("Server failed");
+
+        let response: JsonValue = serde_json::from_str(String::from_utf8(output).unwrap().trim())
+            .expect("Invalid response");
+        assert_eq!(response["result"]["protocolVersion"], json!(negotiated), "requested {}", requested);
+    }
+}
 
 #[test]
-fn test_search() {
-    let mut session = test_session();
-    let registry = ToolRegistry::new();
+fn test_report_timing_meta() {
+    let call = |timing: bool| -> JsonValue {
+        let mut server = McpServer::new(test_session())
+            .with_signal_handling(false)
+            .with_timing(timing);
+        let mut input = initialize_line(0);
+        input.push_str(&tool_call_line(1, "strata_kv_put", json!({"key": "k", "value": 1})));
+
+        let mut output = Vec::new();
+        server
+            .run_with(std::io::Cursor::new(input.into_bytes()), &mut output)
+            .expect("Server failed");
+        String::from_utf8(output)
+            .unwrap()
+            .lines()
+            .map(|l| serde_json::from_str::<JsonValue>(l).unwrap())
+            .find(|r| r["id"] == json!(1))
+            .expect("Missing tools/call response")
+    };
 
-    call_tool(&mut session, &registry, "strata_kv_put", json!({"key": "search-key", "value": "searchable text content"}));
+    let timed = call(true);
+    assert!(timed["result"]["_meta"]["elapsed_us"].is_u64());
+    assert!(timed["result"]["content"].is_array());
 
-    let result = call_tool(
-        &mut session,
-        &registry,
-        "strata_search",
-        json!({"query": "searchable", "k": 5}),
-    );
-    // Result is an array of search hits
-    assert!(result.is_array());
+    let untimed = call(false);
+    assert!(untimed["result"].get("_meta").is_none());
 }
 
 #[test]
-fn test_search_empty_database() {
-    let mut session = test_session();
-    let registry = ToolRegistry::new();
+fn test_redact_errors_keeps_code() {
+    let conflict = |redact: bool| -> JsonValue {
+        let mut server = McpServer::new(test_session())
+            .with_signal_handling(false)
+            .with_redacted_errors(redact);
+        let mut input = initialize_line(0);
+        let create = json!({"collection": "secret_coll", "dimension": 2});
+        input.push_str(&tool_call_line(1, "strata_vector_create_collection", create));
+        let insert = json!({"collection": "secret_coll", "key": "k", "vector": [1.0, 0.0], "mode": "insert"});
+        input.push_str(&tool_call_line(2, "strata_vector_upsert", insert.clone()));
+        input.push_str(&tool_call_line(3, "strata_vector_upsert", insert));
+
+        let mut output = Vec::new();
+        server
+            .run_with(std::io::Cursor::new(input.into_bytes()), &mut output)
+            .expect("Server failed");
+        String::from_utf8(output)
+            .unwrap()
+            .lines()
+            .map(|l| serde_json::from_str::<JsonValue>(l).unwrap())
+            .find(|r| r["id"] == json!(3))
+            .expect("Missing conflict response")
+    };
 
-    let result = call_tool(
-        &mut session,
-        &registry,
-        "strata_search",
-        json!({"query": "nothing"}),
-    );
-    assert_eq!(result, json!([]));
+    let full = conflict(false);
+    assert!(full["error"]["message"].as_str().unwrap().contains("secret_coll"));
+
+    let redacted = conflict(true);
+    let message = redacted["error"]["message"].as_str().unwrap();
+    assert!(!message.contains("secret_coll"), "{}", message);
+    assert!(message.contains("KEY_EXISTS"), "{}", message);
+    assert_eq!(redacted["error"]["code"], full["error"]["code"]);
 }
 
 #[test]
-fn test_search_with_primitives_filter() {
-    let mut session = test_session();
-    let registry = ToolRegistry::new();
+fn test_tools_list_category_filter() {
+    let mut server = McpServer::new(test_session()).with_signal_handling(false);
+    let mut input = initialize_line(0);
+    input.push_str("{\"jsonrpc\":\"2.0\",\"id\":1,\"method\":\"tools/list\",\"params\":{\"category\":\"kv\"}}\n");
+    input.push_str("{\"jsonrpc\":\"2.0\",\"id\":2,\"method\":\"tools/list\",\"params\":{\"category\":\"nope\"}}\n");
+    input.push_str("{\"jsonrpc\":\"2.0\",\"id\":3,\"method\":\"tools/list\"}\n");
+
+    let mut output = Vec::new();
+    server
+        .run_with(std::io::Cursor::new(input.into_bytes()), &mut output)
+        .expect("Server failed");
+
+    let responses: std::collections::HashMap<u64, JsonValue> = String::from_utf8(output)
+        .unwrap()
+        .lines()
+        .map(|l| {
+            let response: JsonValue = serde_json::from_str(l).unwrap();
+            (response["id"].as_u64().unwrap(), response)
+        })
+        .collect();
+    let names = |id: u64| -> Vec<String> {
+        responses[&id]["result"]["tools"]
+            .as_array()
+            .expect("Expected tools")
+            .iter()
+            .map(|t| t["name"].as_str().unwrap().to_string())
+            .collect()
+    };
 
-    call_tool(&mut session, &registry, "strata_kv_put", json!({"key": "k1", "value": "data"}));
+    let kv = names(1);
+    assert!(!kv.is_empty());
+    assert!(kv.iter().all(|n| n.starts_with("strata_kv_")), "{:?}", kv);
+    assert_eq!(kv.len(), ToolRegistry::new().tools().iter().filter(|t| t.name.starts_with("strata_kv_")).count());
+    assert!(names(2).is_empty());
+    assert_eq!(names(3).len(), ToolRegistry::new().tools().len());
+}
 
-    let result = call_tool(
-        &mut session,
-        &registry,
-        "strata_search",
-        json!({"query": "data", "primitives": ["kv"]}),
-    );
-    assert!(result.is_array());
+#[test]
+fn test_tool_prefix() {
+    let mut server = McpServer::new(test_session())
+        .with_signal_handling(false)
+        .with_tool_prefix("acme_");
+    let mut input = initialize_line(0);
+    input.push_str("{\"jsonrpc\":\"2.0\",\"id\":1,\"method\":\"tools/list\",\"params\":{\"category\":\"kv\"}}\n");
+    input.push_str(&tool_call_line(2, "acme_strata_kv_put", json!({"key": "k", "value": 1})));
+    input.push_str(&tool_call_line(3, "acme_strata_kv_get", json!({"key": "k", "raw": true})));
+    input.push_str(&tool_call_line(4, "strata_kv_get", json!({"key": "k"})));
+
+    let mut output = Vec::new();
+    server
+        .run_with(std::io::Cursor::new(input.into_bytes()), &mut output)
+        .expect("Server failed");
+
+    let responses: std::collections::HashMap<u64, JsonValue> = String::from_utf8(output)
+        .unwrap()
+        .lines()
+        .map(|l| {
+            let response: JsonValue = serde_json::from_str(l).unwrap();
+            (response["id"].as_u64().unwrap(), response)
+        })
+        .collect();
+
+    let kv: Vec<&str> = responses[&1]["result"]["tools"]
+        .as_array()
+        .expect("Expected tools")
+        .iter()
+        .map(|t| t["name"].as_str().unwrap())
+        .collect();
+    assert!(!kv.is_empty());
+    assert!(kv.iter().all(|n| n.starts_with("acme_strata_kv_")), "{:?}", kv);
+
+    assert!(responses[&2].get("error").is_none(), "{}", responses[&2]);
+    let text = responses[&3]["result"]["content"][0]["text"].as_str().expect("Expected text");
+    assert_eq!(serde_json::from_str::<JsonValue>(text).unwrap(), json!(1));
+
+    // The unprefixed name is not a tool on a prefixed server
+    assert!(responses[&4]["error"].is_object(), "{}", responses[&4]);
 }
 
 #[test]
-fn test_search_with_mode() {
+fn test_tool_prefix_batch_and_deprecation() {
     let mut session = test_session();
-    let registry = ToolRegistry::new();
+    let registry = ToolRegistry::new().with_prefix("acme_");
 
-    let result = call_tool(
-        &mut session,
-        &registry,
-        "strata_search",
-        json!({"query": "test", "mode": "keyword"}),
-    );
-    assert!(result.is_array());
+    let deprecated = registry
+        .tools()
+        .iter()
+        .find(|t| t.name == "acme_strata_event_len")
+        .expect("Expected prefixed deprecated tool");
+    assert_eq!(deprecated.replacement.as_deref(), Some("acme_strata_event_count"));
+
+    let ops = json!({"operations": [
+        {"tool": "acme_strata_kv_put", "arguments": {"key": "a", "value": 1}},
+        {"tool": "acme_strata_kv_get", "arguments": {"key": "a", "raw": true}}
+    ], "atomic": true});
+    let result = call_tool(&mut session, &registry, "acme_strata_batch", ops);
+    assert_eq!(result[1]["result"], json!(1));
+
+    let nested = json!({"operations": [{"tool": "acme_strata_batch", "arguments": {}}]});
+    let err = call_tool_err(&mut session, &registry, "acme_strata_batch", nested);
+    assert!(err.to_string().contains("Nested"), "{}", err);
 }
 
 #[test]
-fn test_search_with_expand_rerank_disabled() {
-    let mut session = test_session();
-    let registry = ToolRegistry::new();
+fn test_deprecated_tool_in_listing() {
+    let mut server = McpServer::new(test_session()).with_signal_handling(false);
+    let mut input = initialize_line(0);
+    input.push_str("{\"jsonrpc\":\"2.0\",\"id\":1,\"method\":\"tools/list\"}\n");
+
+    let mut output = Vec::new();
+    server
+        .run_with(std::io::Cursor::new(input.into_bytes()), &mut output)
+        .expect("Server failed");
+
+    let response: JsonValue = String::from_utf8(output)
+        .unwrap()
+        .lines()
+        .map(|l| serde_json::from_str::<JsonValue>(l).unwrap())
+        .find(|r| r["id"] == json!(1))
+        .expect("Missing tools/list response");
+    let tools = response["result"]["tools"].as_array().expect("Expected tools");
+    let tool = |name: &str| tools.iter().find(|t| t["name"] == json!(name)).expect("Missing tool").clone();
+
+    let len = tool("strata_event_len");
+    assert_eq!(len["deprecated"], json!(true));
+    assert_eq!(len["replacement"], json!("strata_event_count"));
+    let count = tool("strata_event_count");
+    assert!(count.get("deprecated").is_none() && count.get("replacement").is_none());
+
+    // The replacement of every deprecated tool exists
+    for t in ToolRegistry::new().tools().iter().filter(|t| t.deprecated) {
+        let replacement = t.replacement.as_deref().expect("Deprecated tool without replacement");
+        assert!(tools.iter().any(|l| l["name"] == json!(replacement)), "{} -> {}", t.name, replacement);
+    }
+}
 
-    let result = call_tool(
-        &mut session,
-        &registry,
-        "strata_search",
-        json!({"query": "test", "expand": false, "rerank": false}),
-    );
-    assert!(result.is_array());
+#[test]
+fn test_tools_call_requires_initialize() {
+    let mut server = McpServer::new(test_session()).with_signal_handling(false);
+
+    let mut input = String::new();
+    input.push_str(&tool_call_line(1, "strata_kv_put", json!({"key": "early", "value": 1})));
+    input.push_str("{\"jsonrpc\":\"2.0\",\"id\":2,\"method\":\"tools/list\"}\n");
+    input.push_str("{\"jsonrpc\":\"2.0\",\"id\":3,\"method\":\"ping\"}\n");
+    input.push_str(&initialize_line(4));
+    input.push_str(&tool_call_line(5, "strata_kv_put", json!({"key": "late", "value": 1})));
+
+    let mut output = Vec::new();
+    server
+        .run_with(std::io::Cursor::new(input.into_bytes()), &mut output)
+        .expect("Server failed");
+
+    let responses: std::collections::HashMap<u64, JsonValue> = String::from_utf8(output)
+        .unwrap()
+        .lines()
+        .map(|l| {
+            let response: JsonValue = serde_json::from_str(l).unwrap();
+            (response["id"].as_u64().unwrap(), response)
+        })
+        .collect();
+    for id in [1, 2] {
+        assert_eq!(responses[&id]["error"]["code"], json!(-32600));
+        let message = responses[&id]["error"]["message"].as_str().unwrap();
+        assert!(message.contains("not initialized"), "{}", message);
+    }
+    for id in [3, 4, 5] {
+        assert!(responses[&id].get("result").is_some(), "Unexpected error: {}", responses[&id]);
+    }
 }
 
 #[test]
-fn test_search_with_time_range() {
+fn test_overlapping_requests_all_complete() {
+    let mut server = McpServer::new(test_session()).with_signal_handling(false);
+
+    let mut input = initialize_line(0);
+    input.push_str(&tool_call_line(1, "strata_kv_put", json!({"key": "k", "value": 1})));
+    input.push_str("{\"jsonrpc\":\"2.0\",\"id\":2,\"method\":\"ping\"}\n");
+    input.push_str(&tool_call_line(3, "strata_search", json!({"query": "k"})));
+    input.push_str("{\"jsonrpc\":\"2.0\",\"id\":4,\"method\":\"ping\"}\n");
+    input.push_str(&tool_call_line(5, "strata_kv_get", json!({"key": "k", "raw": true})));
+
+    let mut output = Vec::new();
+    server
+        .run_with(std::io::Cursor::new(input.into_bytes()), &mut output)
+        .expect("Server failed");
+
+    // Responses may arrive out of order; correlate by id
+    let mut ids: Vec<u64> = String::from_utf8(output)
+        .unwrap()
+        .lines()
+        .map(|l| {
+            let response: JsonValue = serde_json::from_str(l).unwrap();
+            assert!(response.get("result").is_some(), "Unexpected error: {}", l);
+            response["id"].as_u64().unwrap()
+        })
+        .collect();
+    ids.sort();
+    assert_eq!(ids, vec![0, 1, 2, 3, 4, 5]);
+}
+
+// =============================================================================
+// Completion
+// =============================================================================
+
+#[test]
+fn test_complete_branch_argument() {
     let mut session = test_session();
     let registry = ToolRegistry::new();
 
-    let result = call_tool(
-        &mut session,
-        &registry,
-        "strata_search",
-        json!({
-            "query": "test",
-            "time_range": {"start": "2020-01-01T00:00:00Z", "end": "2030-01-01T00:00:00Z"}
-        }),
-    );
-    assert!(result.is_array());
+    call_tool(&mut session, &registry, "strata_branch_create", json!({"branch_id": "feature"}));
+
+    let values = registry
+        .complete(&mut session, "strata_branch_switch", "branch", "def")
+        .expect("Completion failed");
+    assert_eq!(values, vec!["default".to_string()]);
+
+    let values = registry
+        .complete(&mut session, "strata_branch_switch", "branch", "")
+        .expect("Completion failed");
+    assert!(values.contains(&"feature".to_string()));
 }
 
 #[test]
-fn test_search_with_all_options() {
+fn test_complete_keys_and_enums() {
     let mut session = test_session();
     let registry = ToolRegistry::new();
 
-    let result = call_tool(
-        &mut session,
-        &registry,
-        "strata_search",
-        json!({
-            "query": "hello",
-            "k": 5,
-            "primitives": ["kv"],
-            "mode": "hybrid",
-            "expand": false,
-            "rerank": false
-        }),
-    );
-    assert!(result.is_array());
+    call_tool(&mut session, &registry, "strata_kv_put", json!({"key": "user:1", "value": 1}));
+    call_tool(&mut session, &registry, "strata_kv_put", json!({"key": "user:2", "value": 2}));
+    call_tool(&mut session, &registry, "strata_kv_put", json!({"key": "order:1", "value": 3}));
+
+    let values = registry
+        .complete(&mut session, "strata_kv_get", "key", "user:")
+        .expect("Completion failed");
+    assert_eq!(values, vec!["user:1".to_string(), "user:2".to_string()]);
+
+    let values = registry
+        .complete(&mut session, "strata_search", "mode", "hy")
+        .expect("Completion failed");
+    assert_eq!(values, vec!["hybrid".to_string()]);
+
+    // Arguments without a provider complete to nothing
+    let values = registry
+        .complete(&mut session, "strata_kv_put", "value", "x")
+        .expect("Completion failed");
+    assert!(values.is_empty());
+}
+
+#[test]
+fn test_completion_request() {
+    let mut server = McpServer::new(test_session()).with_signal_handling(false);
+    let request = json!({
+        "jsonrpc": "2.0",
+        "id": 1,
+        "method": "completion/complete",
+        "params": {
+            "ref": {"type": "ref/tool", "name": "strata_branch_switch"},
+            "argument": {"name": "branch", "value": "def"}
+        }
+    });
+    let input = format!("{}{}\n", initialize_line(0), request);
+
+    let mut output = Vec::new();
+    server
+        .run_with(std::io::Cursor::new(input.into_bytes()), &mut output)
+        .expect("Server failed");
+
+    let response: JsonValue = String::from_utf8(output)
+        .unwrap()
+        .lines()
+        .map(|l| serde_json::from_str::<JsonValue>(l).unwrap())
+        .find(|r| r["id"] == json!(1))
+        .expect("Missing completion response");
+    assert_eq!(response["result"]["completion"]["values"], json!(["default"]));
+    assert_eq!(response["result"]["completion"]["hasMore"], json!(false));
 }
 
 // =============================================================================
@@ -1134,6 +4752,165 @@ fn test_read_only_rejects_state_write() {
     assert!(err_str.contains("read-only") || err_str.contains("ACCESS_DENIED"));
 }
 
+#[test]
+fn test_db_config_reports_read_only() {
+    let mut session = read_only_session();
+    let registry = ToolRegistry::new();
+
+    let result = call_tool(&mut session, &registry, "strata_db_config", json!({}));
+    assert_eq!(result["read_only"], json!(true));
+
+    let mut session = test_session();
+    let result = call_tool(&mut session, &registry, "strata_db_config", json!({}));
+    assert_eq!(result["read_only"], json!(false));
+}
+
+fn assert_latency_fields(summary: &serde_json::Value) {
+    for field in ["min_us", "p50_us", "p95_us", "p99_us", "max_us", "mean_us"] {
+        let value = summary[field].as_f64().unwrap_or_else(|| panic!("missing {}", field));
+        assert!(value >= 0.0, "{} is negative", field);
+    }
+}
+
+#[test]
+fn test_db_benchmark_reports_latencies() {
+    let mut session = test_session();
+    let registry = ToolRegistry::new();
+
+    let result = call_tool(&mut session, &registry, "strata_db_benchmark", json!({"iterations": 5}));
+    assert_eq!(result["iterations"], json!(5));
+    assert_eq!(result["writes"], json!(true));
+    assert_latency_fields(&result["ping"]);
+    assert_latency_fields(&result["put"]);
+    assert_latency_fields(&result["get"]);
+
+    // The scratch branch is cleaned up
+    let branches = call_tool(&mut session, &registry, "strata_branch_list", json!({}));
+    assert!(!branches.to_string().contains("mcp-benchmark"));
+
+    call_tool_err(&mut session, &registry, "strata_db_benchmark", json!({"iterations": 1000}));
+}
+
+#[test]
+fn test_db_benchmark_read_only_skips_writes() {
+    let mut session = read_only_session();
+    let registry = ToolRegistry::new();
+
+    let result = call_tool(&mut session, &registry, "strata_db_benchmark", json!({}));
+    assert_eq!(result["iterations"], json!(20));
+    assert_eq!(result["writes"], json!(false));
+    assert_eq!(result["put"], json!(null));
+    assert_latency_fields(&result["ping"]);
+    assert_latency_fields(&result["get"]);
+}
+
+#[test]
+fn test_db_health_reports_checks() {
+    let mut session = test_session();
+    let registry = ToolRegistry::new();
+
+    let result = call_tool(&mut session, &registry, "strata_db_health", json!({}));
+    assert_eq!(result["healthy"], json!(true));
+    for check in ["ping", "storage", "write"] {
+        assert_eq!(result["checks"][check]["status"], json!("ok"), "{}", check);
+        assert!(result["checks"][check]["latency_us"].is_u64(), "{}", check);
+    }
+    assert_eq!(result["checks"]["model"]["status"], json!("skipped"));
+
+    // The write probe branch is cleaned up
+    let branches = call_tool(&mut session, &registry, "strata_branch_list", json!({}));
+    assert!(branch_ids(&branches).iter().all(|id| !id.starts_with("mcp-health")));
+}
+
+#[test]
+fn test_db_health_read_only_skips_write() {
+    let mut session = read_only_session();
+    let registry = ToolRegistry::new();
+
+    let result = call_tool(&mut session, &registry, "strata_db_health", json!({}));
+    assert_eq!(result["healthy"], json!(true));
+    assert_eq!(result["checks"]["ping"]["status"], json!("ok"));
+    assert_eq!(result["checks"]["storage"]["status"], json!("ok"));
+    assert_eq!(result["checks"]["write"]["status"], json!("skipped"));
+    assert_eq!(result["checks"]["write"]["reason"], json!("database is read-only"));
+}
+
+#[test]
+fn test_db_branches_summary() {
+    let mut session = test_session();
+    let registry = ToolRegistry::new();
+
+    call_tool(&mut session, &registry, "strata_kv_put", json!({"key": "d1", "value": 1}));
+    call_tool(&mut session, &registry, "strata_branch_create", json!({"branch_id": "alpha"}));
+    call_tool(&mut session, &registry, "strata_branch_create", json!({"branch_id": "beta"}));
+
+    call_tool(&mut session, &registry, "strata_branch_switch", json!({"branch": "alpha"}));
+    call_tool(&mut session, &registry, "strata_kv_put", json!({"key": "a1", "value": 1}));
+    call_tool(&mut session, &registry, "strata_kv_put", json!({"key": "a2", "value": 2}));
+    call_tool(&mut session, &registry, "strata_state_set", json!({"cell": "a3", "value": 3}));
+    call_tool(&mut session, &registry, "strata_space_create", json!({"space": "other"}));
+    call_tool(&mut session, &registry, "strata_space_switch", json!({"space": "other"}));
+    call_tool(&mut session, &registry, "strata_json_set", json!({"key": "a4", "path": "$", "value": {}}));
+    call_tool(&mut session, &registry, "strata_branch_switch", json!({"branch": "default"}));
+
+    let result = call_tool(&mut session, &registry, "strata_db_branches_summary", json!({"limit": 2}));
+    assert_eq!(result["total_branches"], json!(3));
+    let status = call_tool(&mut session, &registry, "strata_branch_get", json!({"branch": "beta"}))["status"].clone();
+    let status = status.as_str().expect("Expected status");
+    assert_eq!(result["by_status"], json!({status: 3}));
+    assert_eq!(
+        result["branches"],
+        json!([{"id": "alpha", "key_count": 4}, {"id": "beta", "key_count": 0}])
+    );
+    assert_eq!(result["cursor"], json!("beta"));
+
+    let result = call_tool(&mut session, &registry, "strata_db_branches_summary", json!({"limit": 2, "cursor": "beta"}));
+    assert_eq!(result["branches"], json!([{"id": "default", "key_count": 1}]));
+    assert_eq!(result["cursor"], json!(null));
+}
+
+#[test]
+fn test_db_config_redacts_path() {
+    let mut session = test_session();
+    let registry = ToolRegistry::new();
+
+    session.set_runtime_config(RuntimeConfig {
+        cache: false,
+        db_path: Some("/data/strata".to_string()),
+        auto_embed: true,
+        redact_path: false,
+    });
+    let result = call_tool(&mut session, &registry, "strata_db_config", json!({}));
+    assert_eq!(result["storage"], json!("persistent"));
+    assert_eq!(result["db_path"], json!("/data/strata"));
+    assert_eq!(result["auto_embed"], json!(true));
+
+    session.set_runtime_config(RuntimeConfig {
+        cache: false,
+        db_path: Some("/data/strata".to_string()),
+        auto_embed: true,
+        redact_path: true,
+    });
+    let result = call_tool(&mut session, &registry, "strata_db_config", json!({}));
+    assert_eq!(result["db_path"], json!(null));
+    assert_eq!(result["path_redacted"], json!(true));
+}
+
+#[test]
+fn test_read_only_rejects_restore() {
+    let mut session = read_only_session();
+    let registry = ToolRegistry::new();
+
+    let err = call_tool_err(
+        &mut session,
+        &registry,
+        "strata_db_restore",
+        json!({"path": "/nonexistent"}),
+    );
+    let err_str = format!("{}", err);
+    assert!(err_str.contains("read-only") || err_str.contains("ACCESS_DENIED"));
+}
+
 #[test]
 fn test_read_only_rejects_event_append() {
     let mut session = read_only_session();
@@ -1173,6 +4950,46 @@ fn test_missing_required_arg() {
     assert!(err_str.contains("key") || err_str.contains("missing"));
 }
 
+#[test]
+fn test_unknown_property_rejected() {
+    let mut session = test_session();
+    let registry = ToolRegistry::new();
+
+    let err = call_tool_err(
+        &mut session,
+        &registry,
+        "strata_kv_put",
+        json!({"key": "k", "value": 1, "ttl": 60}),
+    );
+    assert_eq!(err.rpc_code(), -32602);
+    assert_eq!(format!("{}", err), "invalid argument 'ttl': Unknown property");
+}
+
+#[test]
+fn test_argument_type_mismatch() {
+    let mut session = test_session();
+    let registry = ToolRegistry::new();
+
+    let err = call_tool_err(&mut session, &registry, "strata_kv_get", json!({"key": 42}));
+    assert_eq!(format!("{}", err), "invalid argument 'key': Expected string, got integer");
+
+    let err = call_tool_err(
+        &mut session,
+        &registry,
+        "strata_search",
+        json!({"query": "q", "primitives": ["kv", 1]}),
+    );
+    assert!(format!("{}", err).contains("'primitives[1]'"));
+
+    let err = call_tool_err(
+        &mut session,
+        &registry,
+        "strata_search",
+        json!({"query": "q", "mode": "fuzzy"}),
+    );
+    assert!(format!("{}", err).contains("'mode'"));
+}
+
 #[test]
 fn test_branch_not_found() {
     let mut session = test_session();
@@ -1197,11 +5014,10 @@ fn test_tool_count() {
     let registry = ToolRegistry::new();
     let tools = registry.tools();
 
-    // After adding time_range + configure_model: 63 total
     assert_eq!(
         tools.len(),
-        63,
-        "Expected 63 tools, got {}. Tools: {:?}",
+        109,
+        "Expected 109 tools, got {}. Tools: {:?}",
         tools.len(),
         tools.iter().map(|t| &t.name).collect::<Vec<_>>()
     );